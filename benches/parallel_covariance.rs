@@ -0,0 +1,40 @@
+//! Compares the construction of the covariance matrices while fitting a process on one thread and on the whole
+//! rayon thread pool (the `rayon` feature builds them in parallel).
+//!
+//! Run it with `cargo bench --bench parallel_covariance --features rayon`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use friedrich::gaussian_process::{FitConfig, GaussianProcess};
+use friedrich::kernel::SquaredExp;
+use friedrich::prior::ZeroPrior;
+
+fn fit_benchmark(c: &mut Criterion)
+{
+    let nb_samples = 1000;
+    let inputs: Vec<Vec<f64>> =
+        (0..nb_samples).map(|i| vec![(i as f64 * 0.37).sin() * 5., (i as f64 * 0.11).cos() * 5.]).collect();
+    let outputs: Vec<f64> = inputs.iter().map(|x| x[0].sin() * x[1].cos()).collect();
+    // Training computes the covariance matrix and its Cholesky decomposition.
+    let make_gp =
+        || GaussianProcess::new(ZeroPrior {}, SquaredExp::new(1., 1.), 0.1, None, inputs.clone(), outputs.clone());
+
+    // Each iteration of the fit computes the gradient matrices then retrains the process.
+    let config = FitConfig { max_iter: 3, convergence_fraction: 0., ..FitConfig::default() };
+    let mut group = c.benchmark_group("fit");
+    group.sample_size(10);
+    let single_thread = rayon::ThreadPoolBuilder::new().num_threads(1).build().expect("a single thread pool");
+    group.bench_function("single thread", |b| {
+             b.iter_batched(make_gp,
+                            |mut gp| single_thread.install(|| gp.fit_parameters_with_config(false, true, &config)),
+                            BatchSize::LargeInput)
+         });
+    group.bench_function("thread pool", |b| {
+             b.iter_batched(make_gp,
+                            |mut gp| gp.fit_parameters_with_config(false, true, &config),
+                            BatchSize::LargeInput)
+         });
+    group.finish();
+}
+
+criterion_group!(benches, fit_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,32 @@
+//! Compares the prediction of the mean and variance of a trained process on 10 000 inputs on one thread
+//! and on the whole rayon thread pool (the `rayon` feature predicts large batches by chunks in parallel).
+//!
+//! Run it with `cargo bench --bench parallel_prediction --features rayon`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use friedrich::gaussian_process::GaussianProcess;
+use friedrich::kernel::SquaredExp;
+use friedrich::prior::ZeroPrior;
+
+fn prediction_benchmark(c: &mut Criterion)
+{
+    let nb_samples = 500;
+    let training_inputs: Vec<Vec<f64>> =
+        (0..nb_samples).map(|i| vec![(i as f64 * 0.37).sin() * 5., (i as f64 * 0.11).cos() * 5.]).collect();
+    let training_outputs: Vec<f64> = training_inputs.iter().map(|x| x[0].sin() * x[1].cos()).collect();
+    let gp = GaussianProcess::new(ZeroPrior {}, SquaredExp::new(1., 1.), 0.1, None, training_inputs, training_outputs);
+
+    let nb_inputs = 10_000;
+    let inputs: Vec<Vec<f64>> =
+        (0..nb_inputs).map(|i| vec![(i as f64 * 0.013).sin() * 5., (i as f64 * 0.007).cos() * 5.]).collect();
+
+    let mut group = c.benchmark_group("predict_mean_variance");
+    group.sample_size(10);
+    let single_thread = rayon::ThreadPoolBuilder::new().num_threads(1).build().expect("a single thread pool");
+    group.bench_function("single thread", |b| b.iter(|| single_thread.install(|| gp.predict_mean_variance(&inputs))));
+    group.bench_function("thread pool", |b| b.iter(|| gp.predict_mean_variance(&inputs)));
+    group.finish();
+}
+
+criterion_group!(benches, prediction_benchmark);
+criterion_main!(benches);
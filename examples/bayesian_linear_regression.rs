@@ -0,0 +1,53 @@
+//! Shows that a gaussian process with a linear kernel and a zero prior is a Bayesian linear regression.
+//!
+//! Run with `cargo run --example bayesian_linear_regression`.
+
+use friedrich::gaussian_process::GaussianProcess;
+use friedrich::kernel::Linear;
+use friedrich::prior::ZeroPrior;
+use nalgebra::{DMatrix, DVector};
+
+fn main()
+{
+    // Noisy samples of a line, one row per sample.
+    let training_inputs = DMatrix::from_column_slice(6, 1, &[0.1, 0.7, 1.3, 2.2, 2.9, 3.6]);
+    let training_outputs = DVector::from_column_slice(&[1.1, 2.0, 3.4, 4.9, 6.6, 7.8]);
+    let (sigma_b, sigma_v, offset, noise) = (2., 1.5, 1., 0.3);
+
+    // Gaussian process with a linear kernel: k(x,y) = σ_b² + σ_v²(x-c)(y-c).
+    let kernel = Linear::new(sigma_b, sigma_v, DVector::from_element(1, offset));
+    let gp = GaussianProcess::new(ZeroPrior {}, kernel, noise, None, training_inputs.clone(), training_outputs.clone());
+
+    // Bayesian linear regression on the features [1, x-c] with the prior weights ~ N(0, diag(σ_b², σ_v²)):
+    // the posterior of the weights has precision A = ΦᵀΦ/σ² + diag(1/σ_b², 1/σ_v²) and mean A⁻¹Φᵀy/σ².
+    let features = |inputs: &DMatrix<f64>| DMatrix::from_fn(inputs.nrows(), 2, |r, c| {
+        if c == 0 { 1. } else { inputs[(r, 0)] - offset }
+    });
+    let training_features = features(&training_inputs);
+    let noise_variance = noise * noise;
+    let mut precision = training_features.tr_mul(&training_features) / noise_variance;
+    precision[(0, 0)] += 1. / (sigma_b * sigma_b);
+    precision[(1, 1)] += 1. / (sigma_v * sigma_v);
+    let covariance = precision.try_inverse().expect("the precision matrix is positive definite");
+    let weights = &covariance * training_features.tr_mul(&training_outputs) / noise_variance;
+    println!("posterior weights: intercept {:.3} slope {:.3}", weights[0] - weights[1] * offset, weights[1]);
+
+    // Both models give the same predictions and uncertainty.
+    let inputs = DMatrix::from_column_slice(4, 1, &[-1., 0.5, 2., 5.]);
+    let (means, variances) = gp.predict_mean_variance(&inputs);
+    let inputs_features = features(&inputs);
+    for (i, (mean, variance)) in means.iter().zip(variances.iter()).enumerate()
+    {
+        let x = inputs_features.row(i);
+        let regression_mean = (x * &weights)[0];
+        let regression_variance = (x * &covariance * x.transpose())[0];
+        println!("prediction at {}: gp {:.4} ± {:.4}, regression {:.4} ± {:.4}",
+                 inputs[(i, 0)],
+                 mean,
+                 variance.sqrt(),
+                 regression_mean,
+                 regression_variance.sqrt());
+        assert!((mean - regression_mean).abs() < 1e-9);
+        assert!((variance - regression_variance).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,56 @@
+//! Compares adding samples to a trained process (which extends the Cholesky factor of its covariance matrix
+//! in `O(n²)` operations per sample) with retraining a process from scratch (an `O(n³)` decomposition).
+//!
+//! Run with `cargo run --release --example incremental_update_benchmark`.
+
+use friedrich::gaussian_process::GaussianProcess;
+use friedrich::kernel::SquaredExp;
+use friedrich::prior::ZeroPrior;
+use std::time::Instant;
+
+fn main()
+{
+    // A process trained on 1000 samples, to which 10 samples are then added one at a time.
+    let nb_samples = 1000;
+    let nb_new_samples = 10;
+    let inputs: Vec<Vec<f64>> = (0..(nb_samples + nb_new_samples)).map(|i| vec![i as f64 * 0.01]).collect();
+    let outputs: Vec<f64> = inputs.iter().map(|x| x[0].sin()).collect();
+    let train = |nb_samples: usize| {
+        GaussianProcess::new(ZeroPrior {},
+                             SquaredExp::new(0.5, 1.),
+                             0.1,
+                             None,
+                             inputs[..nb_samples].to_vec(),
+                             outputs[..nb_samples].to_vec())
+    };
+
+    // Incremental update of the trained process.
+    let mut gp = train(nb_samples);
+    let start = Instant::now();
+    for i in nb_samples..(nb_samples + nb_new_samples)
+    {
+        gp.add_samples(&vec![inputs[i].clone()], &vec![outputs[i]]);
+    }
+    let incremental_time = start.elapsed();
+
+    // Retraining from scratch after each new sample.
+    let start = Instant::now();
+    let mut retrained_gp = train(nb_samples);
+    for size in (nb_samples + 1)..=(nb_samples + nb_new_samples)
+    {
+        retrained_gp = train(size);
+    }
+    let retraining_time = start.elapsed();
+
+    println!("adding {} samples to a process trained on {} samples:", nb_new_samples, nb_samples);
+    println!("incremental update: {:?}", incremental_time);
+    println!("retraining from scratch: {:?}", retraining_time);
+    println!("speedup: {:.1}x", retraining_time.as_secs_f64() / incremental_time.as_secs_f64());
+
+    // Both processes make the same predictions.
+    let test_inputs = vec![vec![0.05], vec![3.33], vec![9.99]];
+    for (a, b) in gp.predict(&test_inputs).iter().zip(retrained_gp.predict(&test_inputs))
+    {
+        assert!((a - b).abs() < 1e-8, "{} and {}", a, b);
+    }
+}
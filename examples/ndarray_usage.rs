@@ -0,0 +1,29 @@
+//! Trains a gaussian process on data stored in ndarray arrays.
+//!
+//! Run with `cargo run --example ndarray_usage --features friedrich_ndarray`.
+
+use friedrich::gaussian_process::GaussianProcess;
+use ndarray::{array, Array1, Array2};
+
+fn main()
+{
+    // Training data, one row per sample.
+    let training_inputs: Array2<f64> = array![[0.8], [1.2], [3.8], [4.2]];
+    let training_outputs: Array1<f64> = array![3.0, 4.0, -2.0, -2.0];
+
+    // Defines and trains a model directly on the arrays.
+    let gp = GaussianProcess::default(training_inputs, training_outputs);
+
+    // Predicts the mean and variance of several points.
+    let inputs: Array2<f64> = array![[1.0], [2.0], [3.0]];
+    let means: Array1<f64> = gp.predict(&inputs);
+    let variances: Array1<f64> = gp.predict_variance(&inputs);
+    for ((input, mean), variance) in inputs.outer_iter().zip(means.iter()).zip(variances.iter())
+    {
+        println!("prediction at {}: {} ± {}", input[0], mean, variance.sqrt());
+    }
+
+    // Predicts the value of a single point.
+    let input: Array1<f64> = array![5.];
+    println!("prediction at 5: {}", gp.predict(&input));
+}
@@ -0,0 +1,57 @@
+//! Compares a sparse process using 50 inducing points (an `O(n*m²)` training)
+//! with a dense process (an `O(n³)` training) on 1000 samples.
+//!
+//! Run with `cargo run --release --example sparse_benchmark`.
+
+use friedrich::gaussian_process::{GaussianProcess, SparseGaussianProcess};
+use friedrich::kernel::SquaredExp;
+use friedrich::prior::ZeroPrior;
+use std::time::Instant;
+
+fn main()
+{
+    let nb_samples = 1000;
+    let nb_inducing_points = 50;
+    let training_inputs: Vec<Vec<f64>> = (0..nb_samples).map(|i| vec![i as f64 * 0.01]).collect();
+    let training_outputs: Vec<f64> = training_inputs.iter().map(|x| x[0].sin()).collect();
+    let inputs: Vec<Vec<f64>> = (0..nb_samples).map(|i| vec![i as f64 * 0.01 + 0.005]).collect();
+
+    // Dense process.
+    let start = Instant::now();
+    let gp = GaussianProcess::new(ZeroPrior {},
+                                  SquaredExp::new(0.5, 1.),
+                                  0.1,
+                                  None,
+                                  training_inputs.clone(),
+                                  training_outputs.clone());
+    let dense_training_time = start.elapsed();
+    let start = Instant::now();
+    let dense_means = gp.predict(&inputs);
+    let dense_prediction_time = start.elapsed();
+
+    // Sparse process, the inducing points being placed by k-means.
+    let start = Instant::now();
+    let sparse_gp = SparseGaussianProcess::with_kmeans(ZeroPrior {},
+                                                       SquaredExp::new(0.5, 1.),
+                                                       0.1,
+                                                       None,
+                                                       training_inputs,
+                                                       training_outputs,
+                                                       nb_inducing_points);
+    let sparse_training_time = start.elapsed();
+    let start = Instant::now();
+    let sparse_means = sparse_gp.predict(&inputs);
+    let sparse_prediction_time = start.elapsed();
+
+    println!("training on {} samples then predicting {} inputs:", nb_samples, inputs.len());
+    println!("dense process: training {:?}, prediction {:?}", dense_training_time, dense_prediction_time);
+    println!("sparse process ({} inducing points): training {:?}, prediction {:?}",
+             nb_inducing_points, sparse_training_time, sparse_prediction_time);
+    println!("training speedup: {:.1}x", dense_training_time.as_secs_f64() / sparse_training_time.as_secs_f64());
+
+    // The sparse process approximates the dense one.
+    let max_difference =
+        dense_means.iter().zip(sparse_means.iter()).map(|(dense, sparse)| (dense - sparse).abs()).fold(0., f64::max);
+    println!("largest difference between the predicted means: {:.2e}", max_difference);
+    assert!(max_difference < 0.05, "the sparse process does not approximate the dense one");
+}
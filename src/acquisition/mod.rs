@@ -0,0 +1,510 @@
+//! Acquisition functions
+//!
+//! An acquisition function scores candidate inputs given a trained gaussian process,
+//! it is the building block of [Bayesian Optimization](https://en.wikipedia.org/wiki/Bayesian_optimization) where the next point to evaluate is the one maximizing it.
+//!
+//! ```rust
+//! # use friedrich::gaussian_process::GaussianProcess;
+//! # use friedrich::acquisition::upper_confidence_bound;
+//! # use nalgebra::DMatrix;
+//! let training_inputs = DMatrix::from_column_slice(4, 1, &[0.8, 1.2, 3.8, 4.2]);
+//! let training_outputs = nalgebra::DVector::from_column_slice(&[3.0, 4.0, -2.0, -2.0]);
+//! let gp = GaussianProcess::default(training_inputs, training_outputs);
+//!
+//! // Scores candidate inputs and picks the best one.
+//! let candidates = DMatrix::from_fn(50, 1, |i, _| i as f64 * 0.1);
+//! let scores = upper_confidence_bound(&gp, &candidates, 2.);
+//! let best_candidate = candidates.row(scores.imax());
+//! ```
+//!
+//! All acquisition functions are expressed for a maximization problem.
+
+use crate::algebra::{normal_cdf, normal_pdf, CovarianceBuilder, DefaultBuilder};
+use crate::gaussian_process::{GaussianProcess, MultivariateNormal};
+use crate::parameters::{kernel::Kernel, kernel::SquaredExp, prior::Prior};
+use nalgebra::{DMatrix, DVector};
+use rand::Rng;
+use rand_distr::StandardNormal;
+use std::ops::AddAssign;
+
+//---------------------------------------------------------------------------------------
+// GRADIENTS
+
+/// Computes the gradient of the predicted mean and variance with respect to each input (one row per input).
+///
+/// The kernels do not expose their derivatives with respect to their inputs,
+/// the gradients are thus estimated with central finite differences.
+fn mean_variance_gradients<KernelType: Kernel, PriorType: Prior>(gp: &GaussianProcess<KernelType, PriorType>,
+                                                                  inputs: &DMatrix<f64>)
+                                                                  -> (DMatrix<f64>, DMatrix<f64>)
+    where DefaultBuilder: CovarianceBuilder<KernelType>
+{
+    let (nb_inputs, nb_dimensions) = inputs.shape();
+    // Builds all the shifted inputs, the shifts along dimension d being stored in rows [2 d n, 2 (d+1) n[.
+    let steps = inputs.map(|x| 1e-6 * x.abs().max(1.));
+    let mut shifted_inputs = DMatrix::zeros(2 * nb_dimensions * nb_inputs, nb_dimensions);
+    for d in 0..nb_dimensions
+    {
+        for i in 0..nb_inputs
+        {
+            let row_plus = 2 * d * nb_inputs + i;
+            let row_minus = row_plus + nb_inputs;
+            shifted_inputs.set_row(row_plus, &inputs.row(i));
+            shifted_inputs.set_row(row_minus, &inputs.row(i));
+            shifted_inputs[(row_plus, d)] += steps[(i, d)];
+            shifted_inputs[(row_minus, d)] -= steps[(i, d)];
+        }
+    }
+
+    let (means, variances) = gp.predict_mean_variance(&shifted_inputs);
+    let mut mean_gradients = DMatrix::zeros(nb_inputs, nb_dimensions);
+    let mut variance_gradients = DMatrix::zeros(nb_inputs, nb_dimensions);
+    for d in 0..nb_dimensions
+    {
+        for i in 0..nb_inputs
+        {
+            let row_plus = 2 * d * nb_inputs + i;
+            let row_minus = row_plus + nb_inputs;
+            let step = 2. * steps[(i, d)];
+            mean_gradients[(i, d)] = (means[row_plus] - means[row_minus]) / step;
+            variance_gradients[(i, d)] = (variances[row_plus] - variances[row_minus]) / step;
+        }
+    }
+    (mean_gradients, variance_gradients)
+}
+
+//---------------------------------------------------------------------------------------
+// UPPER CONFIDENCE BOUND
+
+/// Computes the upper confidence bound `μ(x) + κσ(x)` for each row of the inputs.
+///
+/// `kappa` controls the trade-off between exploitation (`kappa = 0` scores the inputs by their predicted mean)
+/// and exploration (a large `kappa` favours the inputs with the largest uncertainty).
+/// See `kappa_schedule` for a theoretically grounded value.
+pub fn upper_confidence_bound<KernelType: Kernel, PriorType: Prior>(gp: &GaussianProcess<KernelType, PriorType>,
+                                                                     inputs: &DMatrix<f64>,
+                                                                     kappa: f64)
+                                                                     -> DVector<f64>
+    where DefaultBuilder: CovarianceBuilder<KernelType>
+{
+    let (means, variances) = gp.predict_mean_variance(inputs);
+    means.zip_map(&variances, |mean, variance| mean + kappa * variance.max(0.).sqrt())
+}
+
+/// Computes the gradient of the upper confidence bound with respect to each row of the inputs
+/// (the output has one row per input).
+///
+/// The gradient is given by the chain rule `∇μ + κ∇σ²/2σ`,
+/// the gradients of the mean and variance being estimated with central finite differences.
+pub fn upper_confidence_bound_gradient<KernelType: Kernel, PriorType: Prior>(gp: &GaussianProcess<KernelType,
+                                                                                                  PriorType>,
+                                                                              inputs: &DMatrix<f64>,
+                                                                              kappa: f64)
+                                                                              -> DMatrix<f64>
+    where DefaultBuilder: CovarianceBuilder<KernelType>
+{
+    let variances = gp.predict_variance(inputs);
+    let (mean_gradients, variance_gradients) = mean_variance_gradients(gp, inputs);
+    let mut gradients = mean_gradients;
+    for (i, variance) in variances.iter().enumerate()
+    {
+        // The standard deviation is kept away from zero as its gradient diverges otherwise.
+        let std = variance.max(f64::EPSILON).sqrt();
+        let variance_gradient = variance_gradients.row(i) * (kappa / (2. * std));
+        gradients.row_mut(i).add_assign(&variance_gradient);
+    }
+    gradients
+}
+
+/// Returns the value of `kappa` suggested by the GP-UCB theory at iteration `t` (starting at 1)
+/// for inputs of dimension `d` and a confidence level `1 - delta`.
+///
+/// This is `κ_t = sqrt(2 log(t^(d/2+2) π² / 3δ))`, following [Gaussian Process Optimization in the Bandit Setting: No Regret and Experimental Design](https://arxiv.org/abs/0912.3995)
+/// (Srinivas et al.), it grows slowly with the iterations to ensure that the exploration never stops.
+pub fn kappa_schedule(t: usize, delta: f64, d: usize) -> f64
+{
+    assert!(t >= 1, "kappa_schedule: the iterations start at 1.");
+    assert!(delta > 0. && delta < 1., "kappa_schedule: delta should be in (0, 1).");
+    let t = t as f64;
+    let exponent = d as f64 / 2. + 2.;
+    let beta = 2. * (exponent * t.ln() + (std::f64::consts::PI.powi(2) / (3. * delta)).ln());
+    beta.sqrt()
+}
+
+//---------------------------------------------------------------------------------------
+// PROBABILITY OF IMPROVEMENT
+
+/// Computes the probability of improvement `Φ((μ(x) - η - ξ)/σ(x))` for each row of the inputs.
+///
+/// `eta` is the value to improve upon (usually the best output observed so far)
+/// and `xi` is an exploration bonus: the larger it is, the larger the improvement needed to score an input.
+/// Where the predicted variance is zero, the probability of improvement is a step function of the predicted mean.
+pub fn probability_of_improvement<KernelType: Kernel, PriorType: Prior>(gp: &GaussianProcess<KernelType,
+                                                                                             PriorType>,
+                                                                         inputs: &DMatrix<f64>,
+                                                                         eta: f64,
+                                                                         xi: f64)
+                                                                         -> DVector<f64>
+    where DefaultBuilder: CovarianceBuilder<KernelType>
+{
+    let (means, variances) = gp.predict_mean_variance(inputs);
+    means.zip_map(&variances, |mean, variance| {
+             let improvement = mean - eta - xi;
+             if variance > 0.
+             {
+                 normal_cdf(improvement / variance.sqrt())
+             }
+             else if improvement > 0.
+             {
+                 1.
+             }
+             else
+             {
+                 0.
+             }
+         })
+}
+
+/// Computes the gradient of the probability of improvement with respect to each row of the inputs
+/// (the output has one row per input).
+///
+/// The gradient is given by the chain rule `φ(z) (∇μ - z∇σ²/2σ) / σ` where `z = (μ - η - ξ)/σ`,
+/// the gradients of the mean and variance being estimated with central finite differences.
+/// It is zero where the predicted variance is zero.
+pub fn probability_of_improvement_gradient<KernelType: Kernel, PriorType: Prior>(gp: &GaussianProcess<KernelType,
+                                                                                                      PriorType>,
+                                                                                  inputs: &DMatrix<f64>,
+                                                                                  eta: f64,
+                                                                                  xi: f64)
+                                                                                  -> DMatrix<f64>
+    where DefaultBuilder: CovarianceBuilder<KernelType>
+{
+    let (means, variances) = gp.predict_mean_variance(inputs);
+    let (mean_gradients, variance_gradients) = mean_variance_gradients(gp, inputs);
+    let mut gradients = DMatrix::zeros(inputs.nrows(), inputs.ncols());
+    for (i, (mean, variance)) in means.iter().zip(variances.iter()).enumerate()
+    {
+        if *variance > 0.
+        {
+            let std = variance.sqrt();
+            let z = (mean - eta - xi) / std;
+            let density = normal_pdf(z);
+            let mean_gradient = mean_gradients.row(i) * (density / std);
+            let variance_gradient = variance_gradients.row(i) * (-density * z / (2. * variance));
+            gradients.row_mut(i).add_assign(&mean_gradient);
+            gradients.row_mut(i).add_assign(&variance_gradient);
+        }
+    }
+    gradients
+}
+
+//---------------------------------------------------------------------------------------
+// THOMPSON SAMPLING
+
+/// Draws a function from the posterior of the process and returns the candidate (a row of the input) maximizing it.
+///
+/// The function is sampled jointly at all candidates,
+/// which requires a Cholesky decomposition of their posterior covariance (`O(n^3)` in the number of candidates). For large candidate sets with a squared exponential kernel,
+/// see `thompson_sample_argmax_fourier`.
+pub fn thompson_sample_argmax<KernelType: Kernel, PriorType: Prior, RNG: Rng>(gp: &GaussianProcess<KernelType,
+                                                                                                   PriorType>,
+                                                                               candidates: &DMatrix<f64>,
+                                                                               rng: &mut RNG)
+                                                                               -> DVector<f64>
+{
+    // The posterior covariance of close candidates is near-singular,
+    // a small jitter is added to its diagonal to keep its Cholesky decomposition well-conditioned.
+    let (mean, mut covariance) = gp.predict_joint(candidates);
+    let jitter = 1e-10 * covariance.diagonal().amax().max(1.);
+    let diagonal = covariance.diagonal().add_scalar(jitter);
+    covariance.set_diagonal(&diagonal);
+    let distribution = MultivariateNormal::<DMatrix<f64>>::with_cholesky_epsilon(mean, covariance, Some(jitter));
+    let sample = distribution.sample(rng);
+    candidates.row(sample.imax()).transpose()
+}
+
+/// Draws an approximate function from the posterior of a process with a squared exponential kernel
+/// and returns the candidate (a row of the input) maximizing it.
+///
+/// The kernel is approximated with `nb_features` [random Fourier features](https://people.eecs.berkeley.edu/~brecht/papers/07.rah.rec.nips.pdf),
+/// turning the process into a bayesian linear regression whose weights are sampled.
+/// The cost is linear in the number of candidates (`O(nb_features^3)` for the weights),
+/// making this function suitable for large candidate sets where `thompson_sample_argmax` would be too slow.
+pub fn thompson_sample_argmax_fourier<PriorType: Prior, RNG: Rng>(gp: &GaussianProcess<SquaredExp, PriorType>,
+                                                                  candidates: &DMatrix<f64>,
+                                                                  nb_features: usize,
+                                                                  rng: &mut RNG)
+                                                                  -> DVector<f64>
+{
+    let sample = fourier_posterior_sample(gp, candidates, nb_features, rng);
+    candidates.row(sample.imax()).transpose()
+}
+
+/// Draws an approximate function from the posterior of a process with a squared exponential kernel
+/// (see `thompson_sample_argmax_fourier`) and evaluates it on the candidates.
+///
+/// The values are expressed in the scale seen by the process (before restoring the output standardization, if any).
+fn fourier_posterior_sample<PriorType: Prior, RNG: Rng>(gp: &GaussianProcess<SquaredExp, PriorType>,
+                                                        candidates: &DMatrix<f64>,
+                                                        nb_features: usize,
+                                                        rng: &mut RNG)
+                                                        -> DVector<f64>
+{
+    assert!(nb_features > 0, "thompson_sample_argmax_fourier: at least one feature is needed.");
+    let training_inputs = gp.training_inputs.as_matrix();
+    let nb_dimensions = training_inputs.ncols();
+    assert_eq!(candidates.ncols(), nb_dimensions);
+
+    // Random features such that k(x,y) ≈ φ(x)·φ(y),
+    // the frequencies being drawn from the spectral density of the kernel.
+    let ls = gp.kernel.ls.abs();
+    let frequencies = DMatrix::from_fn(nb_dimensions, nb_features, |_, _| rng.sample::<f64, _>(StandardNormal) / ls);
+    let phases = DVector::from_fn(nb_features, |_, _| rng.gen_range(0. ..2. * std::f64::consts::PI));
+    let scale = (2. * gp.kernel.ampl.abs() / nb_features as f64).sqrt();
+    let features = |inputs: DMatrix<f64>| {
+        let mut features = inputs * &frequencies;
+        for mut row in features.row_iter_mut()
+        {
+            row.zip_apply(&phases.transpose(), |f, phase| *f = scale * (*f + phase).cos());
+        }
+        features
+    };
+
+    // Posterior of the weights: N(P⁻¹Φᵀy/σ², P⁻¹) with P = ΦᵀΦ/σ² + I.
+    let noise_variance = (gp.noise * gp.noise).max(f64::EPSILON);
+    let training_features = features(training_inputs.into_owned());
+    // (the training outputs are stored minus the prior)
+    let residuals = gp.training_outputs.as_vector();
+    let mut precision = training_features.tr_mul(&training_features) / noise_variance;
+    precision += DMatrix::identity(nb_features, nb_features);
+    let precision = precision.cholesky().expect("thompson_sample_argmax_fourier: Cholesky decomposition failed!");
+    let mean = precision.solve(&(training_features.tr_mul(&residuals) / noise_variance));
+    let normal = DVector::from_fn(nb_features, |_, _| rng.sample(StandardNormal));
+    let deviation = precision.l()
+                             .tr_solve_upper_triangular(&normal)
+                             .expect("thompson_sample_argmax_fourier: triangular solve failed!");
+    let weights = mean + deviation;
+
+    // Evaluates the sampled function on the candidates.
+    // (the features use the inputs as seen by the process, the returned row is the raw candidate)
+    let inputs = gp.convert_inputs(candidates);
+    features(inputs.clone()) * weights + gp.prior.prior(&inputs)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::parameters::prior::{ConstantPrior, ZeroPrior};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn objective(x: f64) -> f64
+    {
+        -(x - 2.3).powi(2) + 0.3 * (3. * x).sin()
+    }
+
+    fn make_gp(inputs: &[f64]) -> GaussianProcess<SquaredExp, ZeroPrior>
+    {
+        let training_inputs = DMatrix::from_column_slice(inputs.len(), 1, inputs);
+        let training_outputs = DVector::from_iterator(inputs.len(), inputs.iter().map(|&x| objective(x)));
+        let kernel = SquaredExp::new(1., 4.);
+        GaussianProcess::new(ZeroPrior {}, kernel, 1e-3, Some(1e-10), training_inputs, training_outputs)
+    }
+
+    fn candidates() -> DMatrix<f64>
+    {
+        DMatrix::from_fn(101, 1, |i, _| i as f64 * 0.05)
+    }
+
+    #[test]
+    fn argmax_moves_toward_the_incumbent_as_kappa_vanishes()
+    {
+        let inputs = [0.2, 0.9, 2.5, 4.5];
+        let gp = make_gp(&inputs);
+        let candidates = candidates();
+        // The incumbent is the best training input.
+        let incumbent = inputs.iter().copied().max_by(|a, b| objective(*a).total_cmp(&objective(*b))).unwrap();
+
+        let distances: Vec<f64> = [8., 4., 2., 1., 0.5, 0.1, 0.]
+            .iter()
+            .map(|&kappa| {
+                let scores = upper_confidence_bound(&gp, &candidates, kappa);
+                (candidates[scores.imax()] - incumbent).abs()
+            })
+            .collect();
+        assert!(distances.windows(2).all(|w| w[1] <= w[0]), "distances {:?}", distances);
+        assert!(distances[distances.len() - 1] < distances[0], "distances {:?}", distances);
+        // Without exploration, the score is the predicted mean.
+        let scores = upper_confidence_bound(&gp, &candidates, 0.);
+        let means = gp.predict(&candidates);
+        assert!((&scores - &means).amax() < 1e-12);
+        assert_eq!(scores.imax(), means.imax());
+    }
+
+    #[test]
+    fn large_kappa_explores_the_most_uncertain_input()
+    {
+        let gp = make_gp(&[0.2, 0.5, 1., 4.5]);
+        let candidates = candidates();
+        let scores = upper_confidence_bound(&gp, &candidates, 1e6);
+        let variances = gp.predict_variance(&candidates);
+        assert_eq!(scores.imax(), variances.imax());
+        // The most uncertain input is far from the data.
+        let chosen = candidates[scores.imax()];
+        assert!(chosen > 2. && chosen < 4., "chosen input {}", chosen);
+    }
+
+    #[test]
+    fn upper_confidence_bound_gradient_matches_finite_differences()
+    {
+        let gp = make_gp(&[0.2, 1.1, 2.5, 4.5]);
+        let inputs = DMatrix::from_column_slice(3, 1, &[0.7, 1.8, 3.3]);
+        let kappa = 2.;
+        let gradients = upper_confidence_bound_gradient(&gp, &inputs, kappa);
+        let h = 1e-4;
+        let scores_plus = upper_confidence_bound(&gp, &inputs.add_scalar(h), kappa);
+        let scores_minus = upper_confidence_bound(&gp, &inputs.add_scalar(-h), kappa);
+        for i in 0..inputs.nrows()
+        {
+            let numeric = (scores_plus[i] - scores_minus[i]) / (2. * h);
+            assert!((gradients[(i, 0)] - numeric).abs() < 1e-5 * (1. + numeric.abs()),
+                    "input {}: gradient {} but finite difference {}",
+                    i,
+                    gradients[(i, 0)],
+                    numeric);
+        }
+    }
+
+    #[test]
+    fn kappa_grows_slowly_with_the_iterations()
+    {
+        let kappas: Vec<f64> = (1..=100).map(|t| kappa_schedule(t, 0.1, 2)).collect();
+        assert!(kappas.windows(2).all(|w| w[1] > w[0]));
+        // β_1 = 2 log(π²/0.3)
+        assert!((kappas[0] - (2. * (std::f64::consts::PI.powi(2) / 0.3).ln()).sqrt()).abs() < 1e-12);
+        assert!(kappas[99] < 3. * kappas[0]);
+    }
+
+    #[test]
+    fn probability_of_improvement_is_a_probability()
+    {
+        let gp = make_gp(&[0.2, 1.1, 2.5, 4.5]);
+        let candidates = DMatrix::from_fn(1001, 1, |i, _| i as f64 * 0.005);
+        let eta = objective(2.5);
+        let probabilities = probability_of_improvement(&gp, &candidates, eta, 0.01);
+        assert!(probabilities.iter().all(|&p| (0. ..=1.).contains(&p)));
+        // Integrated over the grid, and normalized by the length of the domain, the probability stays in [0, 1].
+        let integral = probabilities.sum() * 0.005 / 5.;
+        assert!(integral > 0. && integral < 1., "integral {}", integral);
+        // A larger exploration bonus requires a larger improvement.
+        let cautious = probability_of_improvement(&gp, &candidates, eta, 0.5);
+        assert!(cautious.iter().zip(probabilities.iter()).all(|(c, p)| c <= p));
+    }
+
+    #[test]
+    fn probability_of_improvement_becomes_a_step_function_without_uncertainty()
+    {
+        let inputs = [0.2, 1.1, 2.5, 4.5];
+        let training_inputs = DMatrix::from_column_slice(inputs.len(), 1, &inputs);
+        let training_outputs = DVector::from_iterator(inputs.len(), inputs.iter().map(|&x| objective(x)));
+        // The predicted mean at 1.1 is about -1.49, close to the threshold.
+        let eta = -1.45;
+        let mut previous_error = f64::INFINITY;
+        for noise in [1e-1, 1e-2, 1e-4, 1e-6, 1e-8]
+        {
+            let gp = GaussianProcess::new(ZeroPrior {},
+                                          SquaredExp::new(1., 4.),
+                                          noise,
+                                          Some(1e-10),
+                                          training_inputs.clone(),
+                                          training_outputs.clone());
+            // At the training inputs, the variance vanishes with the noise.
+            let probabilities = probability_of_improvement(&gp, &training_inputs, eta, 0.);
+            let steps = gp.predict(&training_inputs).map(|mean| if mean > eta { 1. } else { 0. });
+            let error = (probabilities - steps).amax();
+            assert!(error <= previous_error, "error {} increased with noise {}", error, noise);
+            previous_error = error;
+        }
+        assert!(previous_error < 1e-12, "error {}", previous_error);
+    }
+
+    #[test]
+    fn probability_of_improvement_gradient_matches_finite_differences()
+    {
+        let gp = make_gp(&[0.2, 1.1, 2.5, 4.5]);
+        let inputs = DMatrix::from_column_slice(3, 1, &[0.7, 1.8, 3.3]);
+        let (eta, xi) = (objective(2.5), 0.05);
+        let gradients = probability_of_improvement_gradient(&gp, &inputs, eta, xi);
+        let h = 1e-4;
+        let probabilities_plus = probability_of_improvement(&gp, &inputs.add_scalar(h), eta, xi);
+        let probabilities_minus = probability_of_improvement(&gp, &inputs.add_scalar(-h), eta, xi);
+        for i in 0..inputs.nrows()
+        {
+            let numeric = (probabilities_plus[i] - probabilities_minus[i]) / (2. * h);
+            assert!((gradients[(i, 0)] - numeric).abs() < 1e-5 * (1. + numeric.abs()),
+                    "input {}: gradient {} but finite difference {}",
+                    i,
+                    gradients[(i, 0)],
+                    numeric);
+        }
+    }
+
+    /// Returns the average location of the maximum of `nb_draws` thompson samples and the true location of the maximum.
+    fn average_thompson_argmax<F>(draw: F, nb_draws: usize) -> (f64, f64)
+        where F: Fn(&GaussianProcess<SquaredExp, ZeroPrior>, &DMatrix<f64>, &mut StdRng) -> DVector<f64>
+    {
+        let gp = make_gp(&[0., 0.5, 1., 1.5, 2., 2.5, 3., 3.5, 4., 4.5, 5.]);
+        let candidates = DMatrix::from_fn(26, 1, |i, _| i as f64 * 0.2);
+        let true_argmax = candidates[candidates.column(0).map(objective).imax()];
+        let mut rng = StdRng::seed_from_u64(13);
+        let average_argmax = (0..nb_draws).map(|_| draw(&gp, &candidates, &mut rng)[0]).sum::<f64>() / nb_draws as f64;
+        (average_argmax, true_argmax)
+    }
+
+    #[test]
+    fn thompson_sampling_recovers_the_maximum_in_expectation()
+    {
+        let (average_argmax, true_argmax) = average_thompson_argmax(thompson_sample_argmax, 1000);
+        assert!((average_argmax - true_argmax).abs() < 0.1,
+                "average argmax {} but true argmax {}",
+                average_argmax,
+                true_argmax);
+    }
+
+    #[test]
+    fn fourier_thompson_sampling_recovers_the_maximum_in_expectation()
+    {
+        let draw = |gp: &_, candidates: &_, rng: &mut _| thompson_sample_argmax_fourier(gp, candidates, 100, rng);
+        let (average_argmax, true_argmax) = average_thompson_argmax(draw, 200);
+        assert!((average_argmax - true_argmax).abs() < 0.1,
+                "average argmax {} but true argmax {}",
+                average_argmax,
+                true_argmax);
+    }
+
+    #[test]
+    fn fourier_samples_are_centered_on_the_prediction_with_a_non_zero_prior()
+    {
+        let inputs = [0., 0.5, 1., 1.5, 2., 2.5, 3., 3.5, 4., 4.5, 5.];
+        let training_inputs = DMatrix::from_column_slice(inputs.len(), 1, &inputs);
+        let training_outputs = DVector::from_iterator(inputs.len(), inputs.iter().map(|&x| objective(x) + 5.));
+        let gp = GaussianProcess::new(ConstantPrior::new(5.),
+                                      SquaredExp::new(1., 4.),
+                                      1e-3,
+                                      Some(1e-10),
+                                      training_inputs,
+                                      training_outputs);
+        let candidates = candidates();
+        let mut rng = StdRng::seed_from_u64(17);
+        let nb_draws = 100;
+        let mut average_sample = DVector::zeros(candidates.nrows());
+        for _ in 0..nb_draws
+        {
+            average_sample += fourier_posterior_sample(&gp, &candidates, 100, &mut rng) / nb_draws as f64;
+        }
+        let prediction = gp.predict(&candidates);
+        assert!((average_sample - &prediction).amax() < 0.2,
+                "average sample differs from the prediction {}",
+                prediction);
+    }
+}
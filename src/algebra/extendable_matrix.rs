@@ -7,6 +7,21 @@ use crate::algebra::{SMatrix, SVector};
 use nalgebra::*;
 use nalgebra::{storage::Storage, Dynamic, U1};
 
+//-----------------------------------------------------------------------------
+// REMOVAL
+
+/// Returns a vector with one boolean per row, true if the row is in the indices to be removed.
+fn removal_mask(indices: &[usize], nrows: usize) -> Vec<bool>
+{
+    let mut is_removed = vec![false; nrows];
+    for &index in indices
+    {
+        assert!(index < nrows, "row {} is out of range for {} rows.", index, nrows);
+        is_removed[index] = true;
+    }
+    is_removed
+}
+
 //-----------------------------------------------------------------------------
 // MATRIX
 
@@ -38,7 +53,7 @@ impl EMatrix
             let growed_capacity = (3 * capacity) / 2; // capacity increased by a factor 1.5
             let new_capacity = std::cmp::max(required_size, growed_capacity);
             // Builds new matrix with more rows.
-            let mut new_data = DMatrix::from_element(new_capacity, self.data.ncols(), std::f64::NAN);
+            let mut new_data = DMatrix::from_element(new_capacity, self.data.ncols(), f64::NAN);
             new_data.index_mut((..self.nrows, ..)).copy_from(&self.as_matrix());
             self.data = new_data;
         }
@@ -48,11 +63,42 @@ impl EMatrix
         self.nrows += rows.nrows();
     }
 
+    /// Removes several rows from the matrix, keeping the remaining rows in order.
+    ///
+    /// The capacity of the matrix is unchanged.
+    pub fn remove_rows(&mut self, indices: &[usize])
+    {
+        let is_removed = removal_mask(indices, self.nrows);
+        // Moves the rows that are kept to the top of the matrix, in order.
+        let mut nb_kept_rows = 0;
+        for row in (0..self.nrows).filter(|&row| !is_removed[row])
+        {
+            self.data.swap_rows(nb_kept_rows, row);
+            nb_kept_rows += 1;
+        }
+        self.nrows = nb_kept_rows;
+    }
+
     /// returns a slice to the data inside the extendable matrix
-    pub fn as_matrix(&self) -> MatrixSlice
+    pub fn as_matrix(&self) -> MatrixSlice<'_>
     {
         self.data.index((..self.nrows, ..))
     }
+
+    /// Returns the number of rows the matrix can hold without reallocating.
+    pub fn capacity(&self) -> usize
+    {
+        self.data.nrows()
+    }
+
+    /// Reallocates the underlying matrix such that its capacity matches its number of rows.
+    pub fn shrink_to_fit(&mut self)
+    {
+        if self.capacity() > self.nrows
+        {
+            self.data = self.as_matrix().into_owned();
+        }
+    }
 }
 
 //-----------------------------------------------------------------------------
@@ -86,7 +132,7 @@ impl EVector
             let growed_capacity = (3 * capacity) / 2; // capacity increased by a factor 1.5
             let new_capacity = std::cmp::max(required_size, growed_capacity);
             // Builds new matrix with more rows.
-            let mut new_data = DVector::from_element(new_capacity, std::f64::NAN);
+            let mut new_data = DVector::from_element(new_capacity, f64::NAN);
             new_data.index_mut((..self.nrows, ..)).copy_from(&self.as_vector());
             self.data = new_data;
         }
@@ -97,11 +143,42 @@ impl EVector
     }
 
     /// Returns a slice to the data inside the extendable matrix.
-    pub fn as_vector(&self) -> VectorSlice
+    pub fn as_vector(&self) -> VectorSlice<'_>
     {
         self.data.index((..self.nrows, ..))
     }
 
+    /// Returns the number of entries the vector can hold without reallocating.
+    pub fn capacity(&self) -> usize
+    {
+        self.data.nrows()
+    }
+
+    /// Reallocates the underlying vector such that its capacity matches its number of entries.
+    pub fn shrink_to_fit(&mut self)
+    {
+        if self.capacity() > self.nrows
+        {
+            self.data = self.as_vector().into_owned();
+        }
+    }
+
+    /// Removes several entries from the vector, keeping the remaining entries in order.
+    ///
+    /// The capacity of the vector is unchanged.
+    pub fn remove_entries(&mut self, indices: &[usize])
+    {
+        let is_removed = removal_mask(indices, self.nrows);
+        // Moves the entries that are kept to the top of the vector, in order.
+        let mut nb_kept_rows = 0;
+        for row in (0..self.nrows).filter(|&row| !is_removed[row])
+        {
+            self.data.swap_rows(nb_kept_rows, row);
+            nb_kept_rows += 1;
+        }
+        self.nrows = nb_kept_rows;
+    }
+
     /// assigns new content to the vector
     /// the new vector must be of the same size as the old vector
     pub fn assign<S: Storage<f64, Dynamic, U1>>(&mut self, rows: &SVector<S>)
@@ -127,4 +204,69 @@ mod tests
             e.add_rows(&x);
         }
     }
+
+    #[test]
+    fn shrink_to_fit_reclaims_the_extra_capacity()
+    {
+        let x = Input::into_dmatrix(vec![vec![1.0f64, 2.0f64], vec![3.0f64, 4.0f64]]);
+        let y = DVector::from_column_slice(&[5.0f64, 6.0f64]);
+        let mut e = EMatrix::new(x.clone());
+        let mut v = EVector::new(y.clone());
+        for _ in 0..5
+        {
+            e.add_rows(&x);
+            v.add_rows(&y);
+        }
+        assert!(e.capacity() > 12 && v.capacity() > 12);
+        let (matrix, vector) = (e.as_matrix().into_owned(), v.as_vector().into_owned());
+
+        e.shrink_to_fit();
+        v.shrink_to_fit();
+        assert_eq!(e.capacity(), 12);
+        assert_eq!(v.capacity(), 12);
+        assert_eq!(e.as_matrix(), matrix);
+        assert_eq!(v.as_vector(), vector);
+    }
+
+    #[test]
+    fn removing_a_row_gives_the_matrix_without_that_row()
+    {
+        let rows: Vec<Vec<f64>> = (0..6).map(|i| vec![i as f64, 10. * i as f64]).collect();
+        let entries: Vec<f64> = (0..6).map(|i| i as f64).collect();
+        let mut e = EMatrix::new(Input::into_dmatrix(rows[..2].to_vec()));
+        let mut v = EVector::new(DVector::from_column_slice(&entries[..2]));
+        e.add_rows(&Input::into_dmatrix(rows[2..].to_vec()));
+        v.add_rows(&DVector::from_column_slice(&entries[2..]));
+
+        e.remove_rows(&[2]);
+        v.remove_entries(&[2]);
+        let remaining = [0, 1, 3, 4, 5];
+        let expected_rows: Vec<Vec<f64>> = remaining.iter().map(|&i| rows[i].clone()).collect();
+        let expected_entries: Vec<f64> = remaining.iter().map(|&i| entries[i]).collect();
+        assert_eq!(e.as_matrix(), Input::into_dmatrix(expected_rows));
+        assert_eq!(v.as_vector(), DVector::from_column_slice(&expected_entries));
+
+        // Removing the last row.
+        e.remove_rows(&[4]);
+        v.remove_entries(&[4]);
+        assert_eq!(e.as_matrix().nrows(), 4);
+        assert_eq!(v.as_vector(), DVector::from_column_slice(&expected_entries[..4]));
+    }
+
+    #[test]
+    fn removing_rows_keeps_the_remaining_rows_in_order()
+    {
+        let rows: Vec<Vec<f64>> = (0..8).map(|i| vec![i as f64, -(i as f64)]).collect();
+        let entries: Vec<f64> = (0..8).map(|i| i as f64).collect();
+        let mut e = EMatrix::new(Input::into_dmatrix(rows.clone()));
+        let mut v = EVector::new(DVector::from_column_slice(&entries));
+        e.remove_rows(&[6, 0, 3, 6]);
+        v.remove_entries(&[6, 0, 3, 6]);
+        let remaining = [1, 2, 4, 5, 7];
+        let expected_rows: Vec<Vec<f64>> = remaining.iter().map(|&i| rows[i].clone()).collect();
+        let expected_entries: Vec<f64> = remaining.iter().map(|&i| entries[i]).collect();
+        assert_eq!(e.as_matrix(), Input::into_dmatrix(expected_rows));
+        assert_eq!(v.as_vector(), DVector::from_column_slice(&expected_entries));
+        assert_eq!(e.capacity(), 8);
+    }
 }
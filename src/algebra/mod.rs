@@ -3,8 +3,23 @@
 //! Various internal operations
 
 mod extendable_matrix;
+mod normal;
+#[cfg(feature = "rayon")]
+mod parallel;
 pub use extendable_matrix::{EMatrix, EVector};
+pub use normal::{normal_cdf, normal_pdf, normal_quantile};
+#[cfg(feature = "rayon")]
+pub use parallel::Parallel;
 
+/// The builder used to compute the covariance matrices: `Parallel` with the `rayon` feature, `Serial` otherwise.
+#[cfg(feature = "rayon")]
+pub type DefaultBuilder = Parallel;
+
+/// The builder used to compute the covariance matrices: `Parallel` with the `rayon` feature, `Serial` otherwise.
+#[cfg(not(feature = "rayon"))]
+pub type DefaultBuilder = Serial;
+
+use crate::error::FriedrichError;
 use crate::parameters::kernel::Kernel;
 use nalgebra::{storage::Storage, Cholesky, DMatrix, DVector, Dynamic, Matrix, SliceStorage, U1};
 
@@ -36,6 +51,34 @@ pub type VectorSlice<'a> = Matrix<f64, Dynamic, U1, SliceStorage<'a, f64, Dynami
 //-----------------------------------------------------------------------------
 // COVARIANCE MATRIX
 
+/// Adaptive jitter added to the diagonal of the covariance matrix when its Cholesky decomposition fails.
+///
+/// The decomposition is first attempted without jitter.
+/// On failure, `initial` is added to the diagonal and the decomposition retried,
+/// the jitter being multiplied by ten at each of the (at most) `max_retries` retries.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct CholeskyJitter
+{
+    /// Jitter used for the first retry.
+    pub initial: f64,
+    /// Maximum number of retries.
+    pub max_retries: usize
+}
+
+/// The default jitter.
+///
+/// The defaults are:
+/// - initial = 1e-10
+/// - max_retries = 6 (the last retry uses a jitter of 1e-5)
+impl Default for CholeskyJitter
+{
+    fn default() -> Self
+    {
+        CholeskyJitter { initial: 1e-10, max_retries: 6 }
+    }
+}
+
 /// computes a covariance matrix using a given kernel and two matrices
 /// the output has one row per row in m1 and one column per row in m2
 pub fn make_covariance_matrix<S1: Storage<f64, Dynamic, Dynamic>,
@@ -56,100 +99,504 @@ pub fn make_covariance_matrix<S1: Storage<f64, Dynamic, Dynamic>,
 /// Computes the cholesky decomposition of the covariance matrix of some inputs.
 /// Adds a given diagonal noise.
 /// Relies on the fact that only the lower triangular part of the matrix is needed for the decomposition.
-pub fn make_cholesky_cov_matrix<S: Storage<f64, Dynamic, Dynamic>, K: Kernel>(inputs: &SMatrix<S>,
-                                                                              kernel: &K,
-                                                                              diagonal_noise: f64,
-                                                                              cholesky_epsilon: Option<f64>)
-                                                                              -> Cholesky<f64, Dynamic>
+/// The columns of the matrix are computed in parallel with the `rayon` feature (see `DefaultBuilder`).
+///
+/// Also returns the jitter that was added to the diagonal (zero if the decomposition succeeded without jitter).
+pub fn make_cholesky_cov_matrix<S: Storage<f64, Dynamic, Dynamic> + Sync, K: Kernel>(
+    inputs: &SMatrix<S>,
+    kernel: &K,
+    diagonal_noise: f64,
+    cholesky_epsilon: Option<f64>,
+    cholesky_jitter: Option<CholeskyJitter>)
+    -> (Cholesky<f64, Dynamic>, f64)
+    where DefaultBuilder: CovarianceBuilder<K>
 {
-    // Empty covariance matrix
-    // TODO It would be faster to start with an an uninitialized matrix but it would require unsafe.
-    let mut covmatix = DMatrix::<f64>::from_element(inputs.nrows(), inputs.nrows(), std::f64::NAN);
-
-    // computes the covariance for all the lower triangular matrix
-    for (col_index, x) in inputs.row_iter().enumerate()
+    match (try_make_cholesky_cov_matrix(inputs, kernel, diagonal_noise, cholesky_epsilon, cholesky_jitter),
+           cholesky_epsilon)
     {
-        for (row_index, y) in inputs.row_iter().enumerate().skip(col_index)
+        (Ok(result), _) => result,
+        (Err(error @ FriedrichError::SingularMatrixDespiteJitter { .. }), _) => panic!("{}", error),
+        (Err(_), Some(cholesky_epsilon)) =>
+        {
+            panic!("Cholesky decomposition failed even though we used `cholesky_epsilon` value of {cholesky_epsilon}")
+        }
+        (Err(_), None) =>
         {
-            covmatix[(row_index, col_index)] = kernel.kernel(&x, &y);
+            panic!("Cholesky decomposition failed, consider setting `cholesky_epsilon` via `GaussianProcessBuilder`")
         }
+    }
+}
+
+/// Computes the cholesky decomposition of the covariance matrix of some inputs,
+/// returns an error if the matrix is not positive definite.
+/// Adds a given diagonal noise and, if the decomposition fails, an increasing jitter (see `CholeskyJitter`).
+/// The columns of the matrix are computed in parallel with the `rayon` feature (see `DefaultBuilder`).
+///
+/// Also returns the jitter that was added to the diagonal (zero if the decomposition succeeded without jitter).
+pub fn try_make_cholesky_cov_matrix<S: Storage<f64, Dynamic, Dynamic> + Sync, K: Kernel>(
+    inputs: &SMatrix<S>,
+    kernel: &K,
+    diagonal_noise: f64,
+    cholesky_epsilon: Option<f64>,
+    cholesky_jitter: Option<CholeskyJitter>)
+    -> Result<(Cholesky<f64, Dynamic>, f64), FriedrichError>
+    where DefaultBuilder: CovarianceBuilder<K>
+{
+    DefaultBuilder::try_make_cholesky_cov_matrix(inputs, kernel, diagonal_noise, cholesky_epsilon, cholesky_jitter)
+}
 
-        // adds diagonal noise
-        covmatix[(col_index, col_index)] += diagonal_noise * diagonal_noise;
+/// Fills the lower triangular part of the `col_index` column of the covariance matrix of some inputs
+/// (the rows below, and including, the diagonal), adding the diagonal noise.
+fn fill_covariance_column<S: Storage<f64, Dynamic, Dynamic>, K: Kernel>(inputs: &SMatrix<S>,
+                                                                        kernel: &K,
+                                                                        diagonal_noise: f64,
+                                                                        col_index: usize,
+                                                                        column: &mut [f64])
+{
+    let x = inputs.row(col_index);
+    for (row_index, covariance) in column.iter_mut().enumerate().skip(col_index)
+    {
+        *covariance = kernel.kernel(&x, &inputs.row(row_index));
     }
 
-    if let Some(cholesky_epsilon) = cholesky_epsilon
+    // adds diagonal noise
+    column[col_index] += diagonal_noise * diagonal_noise;
+}
+
+/// Computes the cholesky decomposition of a covariance matrix of which only the lower triangular part is filled,
+/// returns an error if the matrix is not positive definite.
+/// If the decomposition fails, an increasing jitter is added to the diagonal (see `CholeskyJitter`).
+///
+/// Also returns the jitter that was added to the diagonal (zero if the decomposition succeeded without jitter).
+fn decompose_with_jitter(covmatix: DMatrix<f64>,
+                         cholesky_epsilon: Option<f64>,
+                         cholesky_jitter: Option<CholeskyJitter>)
+                         -> Result<(Cholesky<f64, Dynamic>, f64), FriedrichError>
+{
+    let decompose = |covmatix: DMatrix<f64>| match cholesky_epsilon
     {
-        match Cholesky::new_with_substitute(covmatix, cholesky_epsilon) {
-            Some(v) => v,
-            None => panic!("Cholesky decomposition failed even though we used `cholesky_epsilon` value of {cholesky_epsilon}"),
-        }
+        Some(cholesky_epsilon) => Cholesky::new_with_substitute(covmatix, cholesky_epsilon),
+        None => covmatix.cholesky()
+    };
+    let cholesky_jitter = match cholesky_jitter
+    {
+        Some(cholesky_jitter) if cholesky_jitter.max_retries > 0 => cholesky_jitter,
+        _ => return decompose(covmatix).map(|cholesky| (cholesky, 0.)).ok_or(FriedrichError::SingularMatrix)
+    };
+    if let Some(cholesky) = decompose(covmatix.clone())
+    {
+        return Ok((cholesky, 0.));
     }
-    else
+
+    // retries with an increasing jitter on the diagonal
+    let mut jitter = cholesky_jitter.initial;
+    for retry in 0..cholesky_jitter.max_retries
     {
-        covmatix.cholesky().expect("Cholesky decomposition failed, consider setting `cholesky_epsilon` via `GaussianProcessBuilder`")
+        if retry > 0
+        {
+            jitter *= 10.;
+        }
+        let mut jittered_covmatrix = covmatix.clone();
+        for index in 0..jittered_covmatrix.nrows()
+        {
+            jittered_covmatrix[(index, index)] += jitter;
+        }
+        if let Some(cholesky) = decompose(jittered_covmatrix)
+        {
+            return Ok((cholesky, jitter));
+        }
     }
+    Err(FriedrichError::SingularMatrixDespiteJitter { jitter })
 }
 
 /// Add rows to the covariance matrix by updating its Cholesky decomposition in place.
 /// This is a O(n²*c) operation where n is the number of rows of the covariance matrix and c the number of new rows.
 /// `all_inputs` is a matrix with one row per input, the `nb_new_inputs` last rows are the one we want to add.
-pub fn add_rows_cholesky_cov_matrix<S: Storage<f64, Dynamic, Dynamic>, K: Kernel>(covmat_cholesky: &mut Cholesky<f64, Dynamic>,
-                                                                                  all_inputs: &SMatrix<S>,
-                                                                                  nb_new_inputs: usize,
-                                                                                  kernel: &K,
-                                                                                  diagonal_noise: f64)
+///
+/// `applied_jitter` is the jitter that was added to the diagonal of the current decomposition,
+/// it is also added to the diagonal of the new rows such that the factor stays the one of a single matrix.
+///
+/// Returns `false`, leaving the decomposition untouched, if numerical drift is detected
+/// (a new diagonal element of the factor that is not finite or lost to cancellation),
+/// in which case the decomposition should be recomputed from scratch.
+pub fn add_rows_cholesky_cov_matrix<S: Storage<f64, Dynamic, Dynamic>, K: Kernel>(
+    covmat_cholesky: &mut Cholesky<f64, Dynamic>,
+    all_inputs: &SMatrix<S>,
+    nb_new_inputs: usize,
+    kernel: &K,
+    diagonal_noise: f64,
+    applied_jitter: f64)
+    -> bool
 {
     // Extracts the number of old inputs and new inputs from full inputs.
     let nb_old_inputs = all_inputs.nrows() - nb_new_inputs;
     let new_inputs = all_inputs.rows(nb_old_inputs, nb_new_inputs);
 
     // Add samples one row at a time.
+    let mut covmat_l = covmat_cholesky.l();
     for (row_index, row) in new_inputs.row_iter().enumerate()
     {
         // Index where the column will be added in the Cholesky decomposition.
         let col_index = nb_old_inputs + row_index;
 
-        // Computes the column, the covariance between the new row and previous rows.
+        // Computes the column, the covariance between the new row and previous rows (including itself).
         let column_size = col_index + 1;
         let mut new_column = DVector::<f64>::from_fn(column_size, |training_row_index, _| {
             let training_row = all_inputs.row(training_row_index);
             kernel.kernel(&training_row, &row)
         });
-
-        // Add diagonal noise.
-        new_column[col_index] += diagonal_noise * diagonal_noise;
+        new_column[col_index] += applied_jitter;
 
         // Updates the cholesky decomposition with O(n²) operation.
-        *covmat_cholesky = covmat_cholesky.insert_column(col_index, new_column);
+        if !cholesky_update(&mut covmat_l, &new_column, diagonal_noise)
+        {
+            return false;
+        }
+    }
+    *covmat_cholesky = Cholesky::pack_dirty(covmat_l);
+    true
+}
+
+/// Adds a row (and the matching column) to a covariance matrix by extending its Cholesky factor `l` in place.
+/// This is a O(n²) operation where n is the number of rows of the covariance matrix.
+///
+/// `new_column` contains the covariance between the new input and all previous inputs followed by its own variance,
+/// to which the square of the `diagonal_noise` is added.
+/// The factor is bordered with the row `r = l⁻¹*new_column` and the diagonal element `sqrt(variance - |r|²)`.
+/// Only the lower triangular part of `l` is read.
+///
+/// Returns `false` if the updated factor is not numerically valid
+/// (a new diagonal element that is not finite or lost to cancellation),
+/// in which case it should be recomputed from scratch.
+pub fn cholesky_update(l: &mut DMatrix<f64>, new_column: &DVector<f64>, diagonal_noise: f64) -> bool
+{
+    let size = l.nrows();
+    assert_eq!(new_column.nrows(),
+               size + 1,
+               "cholesky_update: the new column should have one element per row plus one.");
+    let variance = new_column[size] + diagonal_noise * diagonal_noise;
+
+    // Solves the triangular system giving the new row of the factor.
+    let mut new_row = new_column.rows(0, size).into_owned();
+    let is_solved = l.solve_lower_triangular_mut(&mut new_row);
+    let diagonal_squared = variance - new_row.norm_squared();
+
+    // Borders the factor.
+    l.resize_mut(size + 1, size + 1, 0.);
+    l.row_mut(size).columns_mut(0, size).tr_copy_from(&new_row);
+    l[(size, size)] = diagonal_squared.max(0.).sqrt();
+
+    // The squared diagonal element is the variance minus the part explained by the previous rows,
+    // it is meaningless if it is not larger than the rounding error on the variance.
+    is_solved && diagonal_squared > f64::EPSILON * variance.abs()
+}
+
+/// Removes a row (and the matching column) from a covariance matrix by updating its Cholesky factor `l` in place.
+/// This is a O(n²) operation where n is the number of rows of the covariance matrix.
+///
+/// The rows above the removed row are unchanged while the trailing block absorbs the removed column
+/// through a rank-1 update, computed with a sequence of Givens rotations.
+/// Only the lower triangular part of `l` is read and written.
+///
+/// Returns `false` if the updated factor is not numerically valid (a diagonal element that is not strictly positive),
+/// in which case it should be recomputed from scratch.
+pub fn cholesky_downdate(l: &mut DMatrix<f64>, row: usize) -> bool
+{
+    let size = l.nrows();
+    assert!(row < size, "cholesky_downdate: row {} is out of range for a matrix of {} rows.", row, size);
+
+    // The part of the removed column below the diagonal.
+    let mut x = DVector::<f64>::zeros(size);
+    for i in (row + 1)..size
+    {
+        x[i] = l[(i, row)];
+    }
+
+    // Rank-1 update of the trailing block, each rotation cancels one element of `x`.
+    for j in (row + 1)..size
+    {
+        let diagonal = l[(j, j)];
+        let r = diagonal.hypot(x[j]);
+        let cos = r / diagonal;
+        let sin = x[j] / diagonal;
+        l[(j, j)] = r;
+        for i in (j + 1)..size
+        {
+            l[(i, j)] = (l[(i, j)] + sin * x[i]) / cos;
+            x[i] = cos * x[i] - sin * l[(i, j)];
+        }
     }
+
+    // Drops the removed row and column.
+    let matrix = std::mem::replace(l, DMatrix::zeros(0, 0));
+    *l = matrix.remove_row(row).remove_column(row);
+    l.diagonal().iter().all(|&d| d.is_finite() && d > 0.)
 }
 
 /// Returns a vector with the gradient of the covariance matrix (which is a matrix) for each kernel parameter.
-pub fn make_gradient_covariance_matrices<S: Storage<f64, Dynamic, Dynamic>, K: Kernel>(inputs: &SMatrix<S>,
-                                                                                       kernel: &K)
-                                                                                       -> Vec<DMatrix<f64>>
-{
-    // Empty covariance matrices.
-    let mut covmatrices: Vec<_> = (0..kernel.nb_parameters()).map(|_| {
-                                                                 DMatrix::<f64>::from_element(inputs.nrows(),
-                                                                                              inputs.nrows(),
-                                                                                              std::f64::NAN)
-                                                             })
-                                                             .collect();
-
-    // Computes the covariance for all the lower triangular matrix.
-    for (col_index, x) in inputs.row_iter().enumerate()
-    {
-        for (row_index, y) in inputs.row_iter().enumerate().skip(col_index)
+/// The columns of the matrices are computed in parallel with the `rayon` feature (see `DefaultBuilder`).
+pub fn make_gradient_covariance_matrices<S: Storage<f64, Dynamic, Dynamic> + Sync, K: Kernel>(
+    inputs: &SMatrix<S>,
+    kernel: &K)
+    -> Vec<DMatrix<f64>>
+    where DefaultBuilder: CovarianceBuilder<K>
+{
+    DefaultBuilder::make_gradient_covariance_matrices(inputs, kernel)
+}
+
+/// Returns one square matrix, filled with NaN, per kernel parameter.
+fn empty_gradient_matrices<K: Kernel>(nb_inputs: usize, kernel: &K) -> Vec<DMatrix<f64>>
+{
+    (0..kernel.nb_parameters()).map(|_| DMatrix::<f64>::from_element(nb_inputs, nb_inputs, f64::NAN)).collect()
+}
+
+/// Gathers, for each column index, the matching column of all the (square) gradient matrices
+/// (they are contiguous as the matrices are stored column-major).
+fn gradient_columns(covmatrices: &mut [DMatrix<f64>]) -> Vec<Vec<&mut [f64]>>
+{
+    let nb_inputs = covmatrices.first().map_or(0, |matrix| matrix.nrows());
+    let mut columns: Vec<Vec<&mut [f64]>> =
+        (0..nb_inputs).map(|_| Vec::with_capacity(covmatrices.len())).collect();
+    for matrix in covmatrices.iter_mut()
+    {
+        for (column, matrix_column) in columns.iter_mut().zip(matrix.as_mut_slice().chunks_mut(nb_inputs.max(1)))
+        {
+            column.push(matrix_column);
+        }
+    }
+    columns
+}
+
+/// Fills the lower triangular part of the `col_index` column of each gradient matrix
+/// (`columns` containing the column of the matrix of each parameter).
+fn fill_gradient_columns<S: Storage<f64, Dynamic, Dynamic>, K: Kernel>(inputs: &SMatrix<S>,
+                                                                       kernel: &K,
+                                                                       col_index: usize,
+                                                                       columns: &mut [&mut [f64]])
+{
+    let x = inputs.row(col_index);
+    for row_index in col_index..inputs.nrows()
+    {
+        for (&grad, column) in kernel.gradient(&x, &inputs.row(row_index)).iter().zip(columns.iter_mut())
+        {
+            column[row_index] = grad;
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------
+// BUILDERS
+
+/// Builds the covariance matrices required to fit a process, predicts batches of inputs
+/// and runs the restarts of the optimizer, either serially or in parallel.
+///
+/// The kernel only has to be `Sync` when the matrices are built in parallel (see `Parallel`, with the `rayon` feature),
+/// the free functions of this module use the `DefaultBuilder`.
+pub trait CovarianceBuilder<K: Kernel>
+{
+    /// See `try_make_cholesky_cov_matrix`.
+    fn try_make_cholesky_cov_matrix<S: Storage<f64, Dynamic, Dynamic> + Sync>(
+        inputs: &SMatrix<S>,
+        kernel: &K,
+        diagonal_noise: f64,
+        cholesky_epsilon: Option<f64>,
+        cholesky_jitter: Option<CholeskyJitter>)
+        -> Result<(Cholesky<f64, Dynamic>, f64), FriedrichError>;
+
+    /// See `make_gradient_covariance_matrices`.
+    fn make_gradient_covariance_matrices<S: Storage<f64, Dynamic, Dynamic> + Sync>(inputs: &SMatrix<S>,
+                                                                                 kernel: &K)
+                                                                                 -> Vec<DMatrix<f64>>;
+
+    /// Splits the rows of `inputs` into contiguous chunks, calls `f` on the kernel and each chunk
+    /// and returns the results in order.
+    fn map_row_chunks<T, F>(inputs: &DMatrix<f64>, kernel: &K, f: F) -> Vec<T>
+        where T: Send,
+              F: Fn(&K, MatrixSlice<'_>) -> T + Sync;
+
+    /// Calls `f` on the kernel and each index in `0..nb_tasks` and returns the results in order.
+    fn map_tasks<T, F>(nb_tasks: usize, kernel: &K, f: F) -> Vec<T>
+        where T: Send,
+              F: Fn(&K, usize) -> T + Sync;
+}
+
+/// Builds the covariance matrices on the calling thread.
+pub struct Serial;
+
+impl<K: Kernel> CovarianceBuilder<K> for Serial
+{
+    fn try_make_cholesky_cov_matrix<S: Storage<f64, Dynamic, Dynamic> + Sync>(
+        inputs: &SMatrix<S>,
+        kernel: &K,
+        diagonal_noise: f64,
+        cholesky_epsilon: Option<f64>,
+        cholesky_jitter: Option<CholeskyJitter>)
+        -> Result<(Cholesky<f64, Dynamic>, f64), FriedrichError>
+    {
+        // Empty covariance matrix
+        // TODO It would be faster to start with an an uninitialized matrix but it would require unsafe.
+        let nb_inputs = inputs.nrows();
+        let mut covmatix = DMatrix::<f64>::from_element(nb_inputs, nb_inputs, f64::NAN);
+
+        // computes the covariance for all the lower triangular matrix, one column at a time
+        // (the columns are contiguous as the matrix is stored column-major)
+        for (col_index, column) in covmatix.as_mut_slice().chunks_mut(nb_inputs.max(1)).enumerate()
+        {
+            fill_covariance_column(inputs, kernel, diagonal_noise, col_index, column);
+        }
+
+        decompose_with_jitter(covmatix, cholesky_epsilon, cholesky_jitter)
+    }
+
+    fn make_gradient_covariance_matrices<S: Storage<f64, Dynamic, Dynamic> + Sync>(inputs: &SMatrix<S>,
+                                                                                 kernel: &K)
+                                                                                 -> Vec<DMatrix<f64>>
+    {
+        // Empty covariance matrices.
+        let mut covmatrices = empty_gradient_matrices(inputs.nrows(), kernel);
+
+        // Computes the covariance for all the lower triangular matrix, one column at a time.
+        for (col_index, mut columns) in gradient_columns(&mut covmatrices).into_iter().enumerate()
+        {
+            fill_gradient_columns(inputs, kernel, col_index, &mut columns);
+        }
+
+        // Mirrors the lower triangular part.
+        for mat in covmatrices.iter_mut()
+        {
+            mat.fill_upper_triangle_with_lower_triangle();
+        }
+        covmatrices
+    }
+
+    /// Processes all the rows as a single chunk.
+    fn map_row_chunks<T, F>(inputs: &DMatrix<f64>, kernel: &K, f: F) -> Vec<T>
+        where T: Send,
+              F: Fn(&K, MatrixSlice<'_>) -> T + Sync
+    {
+        vec![f(kernel, inputs.rows(0, inputs.nrows()))]
+    }
+
+    /// Runs the tasks one after the other.
+    fn map_tasks<T, F>(nb_tasks: usize, kernel: &K, f: F) -> Vec<T>
+        where T: Send,
+              F: Fn(&K, usize) -> T + Sync
+    {
+        (0..nb_tasks).map(|index| f(kernel, index)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    /// A well conditioned 50x50 covariance matrix.
+    fn covariance_matrix() -> DMatrix<f64>
+    {
+        let b = DMatrix::<f64>::from_fn(50, 50, |r, c| ((r * 7 + c * 13) as f64).sin());
+        &b * b.transpose() + DMatrix::<f64>::identity(50, 50)
+    }
+
+    #[test]
+    fn covariance_matrices_match_the_kernel()
+    {
+        use crate::parameters::kernel::SquaredExp;
+        let inputs = DMatrix::<f64>::from_fn(40, 2, |r, c| ((r * 3 + c * 5) as f64).sin());
+        let kernel = SquaredExp::new(0.7, 1.3);
+        let noise = 0.1;
+
+        let covariance =
+            make_covariance_matrix(&inputs, &inputs, &kernel) + DMatrix::identity(40, 40) * (noise * noise);
+        let l = make_cholesky_cov_matrix(&inputs, &kernel, noise, None, None).0.l();
+        assert!((&l * l.transpose() - covariance).amax() < 1e-10);
+
+        let gradients = make_gradient_covariance_matrices(&inputs, &kernel);
+        assert_eq!(gradients.len(), kernel.nb_parameters());
+        for row in 0..40
         {
-            for (&grad, mat) in kernel.gradient(&x, &y).iter().zip(covmatrices.iter_mut())
+            for col in 0..40
             {
-                mat[(row_index, col_index)] = grad;
-                mat[(col_index, row_index)] = grad;
+                let expected = kernel.gradient(&inputs.row(row), &inputs.row(col));
+                for (gradient, expected) in gradients.iter().zip(expected)
+                {
+                    assert_eq!(gradient[(row, col)], expected);
+                }
             }
         }
     }
 
-    covmatrices
+    #[test]
+    fn cholesky_update_matches_the_full_decomposition()
+    {
+        let noise = 0.3;
+        let covariance = covariance_matrix();
+        let noisy_covariance = &covariance + DMatrix::<f64>::identity(50, 50) * (noise * noise);
+        let mut l = noisy_covariance.slice((0, 0), (10, 10)).into_owned().cholesky().unwrap().l();
+        for size in 10..50
+        {
+            let new_column = covariance.column(size).rows(0, size + 1).into_owned();
+            assert!(cholesky_update(&mut l, &new_column, noise));
+            let expected = noisy_covariance.slice((0, 0), (size + 1, size + 1)).into_owned().cholesky().unwrap().l();
+            assert!((&l - expected).amax() < 1e-10, "adding row {}", size);
+        }
+    }
+
+    #[test]
+    fn cholesky_update_reports_invalid_factors()
+    {
+        // Adding a duplicate of the first row makes the matrix singular.
+        let covariance = covariance_matrix();
+        let mut l = covariance.slice((0, 0), (10, 10)).into_owned().cholesky().unwrap().l();
+        let mut new_column = covariance.column(0).rows(0, 11).into_owned();
+        new_column[10] = covariance[(0, 0)];
+        assert!(!cholesky_update(&mut l, &new_column, 0.));
+        assert_eq!(l.shape(), (11, 11));
+    }
+
+    #[test]
+    fn cholesky_downdate_matches_the_full_decomposition()
+    {
+        let covariance = covariance_matrix();
+        for row in [0, 17, 49]
+        {
+            let mut l = covariance.clone().cholesky().unwrap().l();
+            assert!(cholesky_downdate(&mut l, row));
+            let expected = covariance.clone().remove_row(row).remove_column(row).cholesky().unwrap().l();
+            assert!((l.lower_triangle() - expected).amax() < 1e-10, "removing row {}", row);
+        }
+    }
+
+    #[test]
+    fn cholesky_downdate_reports_invalid_factors()
+    {
+        let mut l = covariance_matrix().cholesky().unwrap().l();
+        l[(20, 20)] = 0.;
+        assert!(!cholesky_downdate(&mut l, 3));
+        assert_eq!(l.shape(), (49, 49));
+    }
+
+    #[test]
+    fn cholesky_jitter_is_increased_until_the_decomposition_succeeds()
+    {
+        use crate::parameters::kernel::Precomputed;
+        // indefinite matrix with eigenvalues 3 and -1, a jitter strictly above 1 is needed
+        let kernel = Precomputed::new(DMatrix::from_row_slice(2, 2, &[1., 2., 2., 1.]));
+        let inputs = DMatrix::from_column_slice(2, 1, &[0., 1.]);
+
+        let result = try_make_cholesky_cov_matrix(&inputs, &kernel, 0., None, None);
+        assert_eq!(result.err(), Some(FriedrichError::SingularMatrix));
+        let jitter = CholeskyJitter { initial: 0.05, max_retries: 2 };
+        let result = try_make_cholesky_cov_matrix(&inputs, &kernel, 0., None, Some(jitter));
+        assert_eq!(result.err(), Some(FriedrichError::SingularMatrixDespiteJitter { jitter: 0.5 }));
+
+        // the third retry uses a jitter of 5
+        let jitter = CholeskyJitter { initial: 0.05, max_retries: 3 };
+        let (cholesky, applied_jitter) =
+            try_make_cholesky_cov_matrix(&inputs, &kernel, 0., None, Some(jitter)).unwrap();
+        assert!((applied_jitter - 5.).abs() < 1e-12);
+        let l = cholesky.l();
+        let expected = DMatrix::from_row_slice(2, 2, &[6., 2., 2., 6.]);
+        assert!((&l * l.transpose() - expected).amax() < 1e-12);
+    }
 }
@@ -0,0 +1,152 @@
+//! Standard normal distribution
+//!
+//! Density, cumulative distribution and quantile functions of the standard normal distribution,
+//! used to turn predicted means and variances into probabilities and confidence intervals.
+
+use std::f64::consts::{PI, SQRT_2};
+
+//-----------------------------------------------------------------------------
+// ERROR FUNCTION
+
+/// Computes the complementary error function using its series expansion for small inputs
+/// and its continued fraction expansion for large inputs (accurate to about machine precision).
+fn erfc(x: f64) -> f64
+{
+    if x < -3.
+    {
+        2. - erfc(-x)
+    }
+    else if x < 3.
+    {
+        // erf(x) = 2/√π exp(-x²) Σ 2ⁿ x^(2n+1) / (1·3·…·(2n+1)), all terms being positive.
+        let x2 = x * x;
+        let mut term = x;
+        let mut sum = x;
+        let mut n = 0.;
+        while term.abs() > 1e-17 * sum.abs()
+        {
+            n += 1.;
+            term *= 2. * x2 / (2. * n + 1.);
+            sum += term;
+        }
+        1. - 2. / PI.sqrt() * (-x2).exp() * sum
+    }
+    else
+    {
+        // erfc(x) = exp(-x²)/√π / (x + (1/2) / (x + 1 / (x + (3/2) / (x + …)))), evaluated from its tail.
+        let mut fraction = x;
+        for n in (1..=60).rev()
+        {
+            fraction = x + (n as f64 / 2.) / fraction;
+        }
+        (-x * x).exp() / (PI.sqrt() * fraction)
+    }
+}
+
+//-----------------------------------------------------------------------------
+// DISTRIBUTION
+
+/// Probability density function of the standard normal distribution.
+pub fn normal_pdf(z: f64) -> f64
+{
+    (-z * z / 2.).exp() / (2. * PI).sqrt()
+}
+
+/// Cumulative distribution function of the standard normal distribution.
+pub fn normal_cdf(z: f64) -> f64
+{
+    erfc(-z / SQRT_2) / 2.
+}
+
+/// Quantile function (inverse of the cumulative distribution function) of the standard normal distribution.
+///
+/// Uses the rational approximation of Peter Acklam as a starting point
+/// and refines it with Halley's method to reach about machine precision.
+pub fn normal_quantile(p: f64) -> f64
+{
+    assert!(p > 0. && p < 1., "normal_quantile: the probability should be in (0, 1), got {}.", p);
+    const A: [f64; 6] = [-3.969683028665376e+01,
+                         2.209460984245205e+02,
+                         -2.759285104469687e+02,
+                         1.38357751867269e+02,
+                         -3.066479806614716e+01,
+                         2.506628277459239e+00];
+    const B: [f64; 5] = [-5.447609879822406e+01,
+                         1.615858368580409e+02,
+                         -1.556989798598866e+02,
+                         6.680131188771972e+01,
+                         -1.328068155288572e+01];
+    const C: [f64; 6] = [-7.784894002430293e-03,
+                         -3.223964580411365e-01,
+                         -2.400758277161838e+00,
+                         -2.549732539343734e+00,
+                         4.374664141464968e+00,
+                         2.938163982698783e+00];
+    const D: [f64; 4] = [7.784695709041462e-03, 3.224671290700398e-01, 2.445134137142996e+00, 3.754408661907416e+00];
+    const P_LOW: f64 = 0.02425;
+
+    // Initial approximation, using the tail formula on both sides of the distribution.
+    let tail = |q: f64| {
+        let r = (-2. * q.ln()).sqrt();
+        (((((C[0] * r + C[1]) * r + C[2]) * r + C[3]) * r + C[4]) * r + C[5])
+        / ((((D[0] * r + D[1]) * r + D[2]) * r + D[3]) * r + 1.)
+    };
+    let mut z = if p < P_LOW
+    {
+        tail(p)
+    }
+    else if p > 1. - P_LOW
+    {
+        -tail(1. - p)
+    }
+    else
+    {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+        / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.)
+    };
+
+    // Refinement.
+    for _ in 0..2
+    {
+        let error = normal_cdf(z) - p;
+        let u = error / normal_pdf(z);
+        z -= u / (1. + z * u / 2.);
+    }
+    z
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn normal_cdf_matches_known_values()
+    {
+        assert!((normal_cdf(0.) - 0.5).abs() < 1e-15);
+        assert!((normal_cdf(1.) - 0.841_344_746_068_542_9).abs() < 1e-14);
+        assert!((normal_cdf(-2.5) - 0.006_209_665_325_776_132).abs() < 1e-15);
+        assert!((normal_cdf(5.) - 0.999_999_713_348_428_1).abs() < 1e-15);
+        assert!((normal_cdf(-6.) - 9.865_876_450_376_98e-10).abs() < 1e-22);
+        // The derivative of the cumulative distribution function is the density.
+        for z in [-4.5, -1.2, 0.3, 2.9, 4.2]
+        {
+            let numeric = (normal_cdf(z + 1e-5) - normal_cdf(z - 1e-5)) / 2e-5;
+            assert!((numeric - normal_pdf(z)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn normal_quantile_inverts_the_cdf()
+    {
+        assert!((normal_quantile(0.975) - 1.959_963_984_540_054).abs() < 1e-12);
+        assert!((normal_quantile(0.5)).abs() < 1e-15);
+        for z in [-7., -3.2, -0.9, 0.01, 1.5, 4.]
+        {
+            let p = normal_cdf(z);
+            assert!((normal_quantile(p) - z).abs() < 1e-9 * (1. + z.abs()), "z {} p {}", z, p);
+        }
+    }
+}
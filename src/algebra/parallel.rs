@@ -0,0 +1,143 @@
+//! Parallel computations
+//!
+//! With the `rayon` feature, the columns of the covariance matrices, the rows of large batches of inputs
+//! and the restarts of the optimizer can be processed concurrently, using the [rayon](https://crates.io/crates/rayon)
+//! thread pool.
+//! Those functions require the kernel to be `Sync`, the `Serial` builder does not.
+
+use super::{decompose_with_jitter, empty_gradient_matrices, fill_covariance_column, fill_gradient_columns,
+            gradient_columns, CholeskyJitter, CovarianceBuilder, MatrixSlice, SMatrix, Serial};
+use crate::error::FriedrichError;
+use crate::parameters::kernel::Kernel;
+use nalgebra::{storage::Storage, Cholesky, DMatrix, Dynamic};
+use rayon::prelude::*;
+
+//-----------------------------------------------------------------------------
+// BUILDER
+
+/// Minimum number of inputs for the covariance matrices to be built in parallel,
+/// smaller matrices are built on the calling thread (see `Serial`).
+const MIN_PARALLEL_INPUTS: usize = 64;
+
+/// Minimum number of rows in a chunk, smaller batches are not worth a task.
+const MIN_CHUNK_SIZE: usize = 64;
+
+/// Builds the covariance matrices on the rayon thread pool, the kernel being shared between threads.
+///
+/// The columns of the lower triangular part of the matrices, the chunks of rows of large batches of inputs
+/// and the restarts of the optimizer are processed concurrently.
+pub struct Parallel;
+
+impl<K: Kernel + Sync> CovarianceBuilder<K> for Parallel
+{
+    fn try_make_cholesky_cov_matrix<S: Storage<f64, Dynamic, Dynamic> + Sync>(
+        inputs: &SMatrix<S>,
+        kernel: &K,
+        diagonal_noise: f64,
+        cholesky_epsilon: Option<f64>,
+        cholesky_jitter: Option<CholeskyJitter>)
+        -> Result<(Cholesky<f64, Dynamic>, f64), FriedrichError>
+    {
+        let nb_inputs = inputs.nrows();
+        if nb_inputs < MIN_PARALLEL_INPUTS
+        {
+            return Serial::try_make_cholesky_cov_matrix(inputs,
+                                                        kernel,
+                                                        diagonal_noise,
+                                                        cholesky_epsilon,
+                                                        cholesky_jitter);
+        }
+
+        // Empty covariance matrix
+        let mut covmatix = DMatrix::<f64>::from_element(nb_inputs, nb_inputs, f64::NAN);
+
+        // computes the covariance for all the lower triangular matrix, the columns being shared between threads
+        covmatix.as_mut_slice().par_chunks_mut(nb_inputs.max(1)).enumerate().for_each(|(col_index, column)| {
+            fill_covariance_column(inputs, kernel, diagonal_noise, col_index, column);
+        });
+
+        decompose_with_jitter(covmatix, cholesky_epsilon, cholesky_jitter)
+    }
+
+    fn make_gradient_covariance_matrices<S: Storage<f64, Dynamic, Dynamic> + Sync>(inputs: &SMatrix<S>,
+                                                                                 kernel: &K)
+                                                                                 -> Vec<DMatrix<f64>>
+    {
+        if inputs.nrows() < MIN_PARALLEL_INPUTS
+        {
+            return Serial::make_gradient_covariance_matrices(inputs, kernel);
+        }
+
+        // Empty covariance matrices.
+        let mut covmatrices = empty_gradient_matrices(inputs.nrows(), kernel);
+
+        // Computes the covariance for all the lower triangular matrix, the columns being shared between threads.
+        gradient_columns(&mut covmatrices).into_par_iter().enumerate().for_each(|(col_index, mut columns)| {
+            fill_gradient_columns(inputs, kernel, col_index, &mut columns);
+        });
+
+        // Mirrors the lower triangular part.
+        covmatrices.par_iter_mut().for_each(|mat| mat.fill_upper_triangle_with_lower_triangle());
+        covmatrices
+    }
+
+    /// Processes the chunks concurrently, on the rayon thread pool.
+    fn map_row_chunks<T, F>(inputs: &DMatrix<f64>, kernel: &K, f: F) -> Vec<T>
+        where T: Send,
+              F: Fn(&K, MatrixSlice<'_>) -> T + Sync
+    {
+        let nb_rows = inputs.nrows();
+        let nb_chunks = (nb_rows / MIN_CHUNK_SIZE).clamp(1, rayon::current_num_threads());
+        let chunk_size = nb_rows.div_ceil(nb_chunks).max(1);
+        (0..nb_rows).step_by(chunk_size)
+                    .collect::<Vec<_>>()
+                    .into_par_iter()
+                    .map(|start| f(kernel, inputs.rows(start, chunk_size.min(nb_rows - start))))
+                    .collect()
+    }
+
+    /// Runs the tasks concurrently, on the rayon thread pool.
+    fn map_tasks<T, F>(nb_tasks: usize, kernel: &K, f: F) -> Vec<T>
+        where T: Send,
+              F: Fn(&K, usize) -> T + Sync
+    {
+        (0..nb_tasks).into_par_iter().map(|index| f(kernel, index)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::parameters::kernel::SquaredExp;
+
+    #[test]
+    fn parallel_covariance_matrices_match_the_serial_ones()
+    {
+        let inputs = DMatrix::<f64>::from_fn(150, 3, |r, c| ((r * 3 + c) as f64 * 0.37).sin());
+        let kernel = SquaredExp::new(0.8, 1.5);
+
+        let (serial, serial_jitter) = Serial::try_make_cholesky_cov_matrix(&inputs, &kernel, 0.1, None, None).unwrap();
+        let (parallel, parallel_jitter) =
+            Parallel::try_make_cholesky_cov_matrix(&inputs, &kernel, 0.1, None, None).unwrap();
+        assert_eq!(serial.l(), parallel.l());
+        assert_eq!(serial_jitter, parallel_jitter);
+
+        let serial = Serial::make_gradient_covariance_matrices(&inputs, &kernel);
+        let parallel = Parallel::make_gradient_covariance_matrices(&inputs, &kernel);
+        assert_eq!(serial, parallel);
+    }
+
+    #[test]
+    fn chunks_are_returned_in_order()
+    {
+        for nb_rows in [0, 1, 63, 64, 1000]
+        {
+            let inputs = DMatrix::<f64>::from_fn(nb_rows, 2, |r, c| (r * 2 + c) as f64);
+            let chunks =
+                Parallel::map_row_chunks(&inputs, &SquaredExp::default(), |_, chunk| chunk.column(0).into_owned());
+            let rows: Vec<f64> = chunks.iter().flat_map(|chunk| chunk.iter().copied()).collect();
+            assert_eq!(rows, inputs.column(0).iter().copied().collect::<Vec<_>>());
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use nalgebra::{DMatrix, DVector};
 
 #[cfg(feature = "friedrich_ndarray")]
-use ndarray::{Array1, ArrayBase, Data, Ix1, Ix2};
+use ndarray::{Array1, ArrayBase, Data, Dimension, Ix1, Ix2};
 
 //-----------------------------------------------------------------------------
 // TRAITS
@@ -124,11 +124,17 @@ impl Input for Vec<Vec<f64>>
     type OutVector = Vec<f64>;
 
     /// Converts an input matrix to a DMatrix.
+    ///
+    /// Panics with an explicit message if the inner vectors do not all have the same length.
     fn to_dmatrix(m: &Self) -> DMatrix<f64>
     {
         let nb_rows = m.len();
         assert_ne!(nb_rows, 0);
         let nb_cols = m[0].len();
+        if let Some((r, row)) = m.iter().enumerate().find(|(_, row)| row.len() != nb_cols)
+        {
+            panic!("Vec<Vec<f64>>: sample {} has {} elements but sample 0 has {}.", r, row.len(), nb_cols);
+        }
         DMatrix::from_fn(nb_rows, nb_cols, |r, c| m[r][c])
     }
 
@@ -153,11 +159,26 @@ impl<D: Data<Elem = f64>> Input for ArrayBase<D, Ix2>
     type OutVector = Array1<f64>;
 
     /// Converts an input matrix to a DMatrix.
+    ///
+    /// This is a one-time copy which is done as a plain memory copy if the array is contiguous (in either layout).
     fn to_dmatrix(m: &Self) -> DMatrix<f64>
     {
         assert_ne!(m.nrows(), 0);
-        // Use `.t()` to get from row-major to col-major.
-        DMatrix::from_iterator(m.nrows(), m.ncols(), m.t().iter().cloned())
+        if let Some(column_major) = m.t().as_slice()
+        {
+            // Column-major (fortran) layout, the layout used by nalgebra.
+            DMatrix::from_column_slice(m.nrows(), m.ncols(), column_major)
+        }
+        else if let Some(row_major) = m.as_slice()
+        {
+            // Row-major (C) layout, the default layout of ndarray.
+            DMatrix::from_row_slice(m.nrows(), m.ncols(), row_major)
+        }
+        else
+        {
+            // Use `.t()` to get from row-major to col-major.
+            DMatrix::from_iterator(m.nrows(), m.ncols(), m.t().iter().cloned())
+        }
     }
 
     /// Converts an input vector to a DVector.
@@ -166,6 +187,32 @@ impl<D: Data<Elem = f64>> Input for ArrayBase<D, Ix2>
         DVector::from_iterator(v.len(), v.iter().cloned())
     }
 
+    /// Converts an owned input matrix to a DMatrix.
+    ///
+    /// Owned arrays in column-major (fortran) layout are moved without copying their elements,
+    /// other arrays are copied once.
+    fn into_dmatrix(m: Self) -> DMatrix<f64>
+    {
+        if m.nrows() == 0 || m.t().as_slice().is_none()
+        {
+            return Self::to_dmatrix(&m);
+        }
+        let (nrows, ncols) = m.dim();
+        DMatrix::from_vec(nrows, ncols, into_contiguous_vec(m))
+    }
+
+    /// Converts an owned input vector to a DVector.
+    ///
+    /// Owned contiguous arrays are moved without copying their elements, other arrays are copied once.
+    fn into_dvector(v: Self::InVector) -> DVector<f64>
+    {
+        if v.as_slice().is_none()
+        {
+            return Self::to_dvector(&v);
+        }
+        DVector::from_vec(into_contiguous_vec(v))
+    }
+
     /// Converts a DVector to an output vector.
     fn from_dvector(v: &DVector<f64>) -> Self::OutVector
     {
@@ -173,6 +220,23 @@ impl<D: Data<Elem = f64>> Input for ArrayBase<D, Ix2>
     }
 }
 
+/// Extracts the elements of an array that is contiguous in memory, in memory order.
+///
+/// This is a move for owned arrays (and a single copy for views).
+/// The array might be a contiguous slice of a larger buffer, in which case the extra elements are dropped.
+#[cfg(feature = "friedrich_ndarray")]
+fn into_contiguous_vec<D: Data<Elem = f64>, Dim: Dimension>(array: ArrayBase<D, Dim>) -> Vec<f64>
+{
+    let len = array.len();
+    let array = array.into_owned();
+    let first_element = array.as_ptr() as usize;
+    let mut data = array.into_raw_vec();
+    let offset = (first_element - data.as_ptr() as usize) / std::mem::size_of::<f64>();
+    data.drain(..offset);
+    data.truncate(len);
+    data
+}
+
 /// Single row, ndarray array type.
 #[cfg(feature = "friedrich_ndarray")]
 impl<D: Data<Elem = f64>> Input for ArrayBase<D, Ix1>
@@ -0,0 +1,156 @@
+//! # Errors
+//!
+//! Errors that can be returned by the library.
+
+use std::fmt;
+
+/// The error type of the library.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FriedrichError
+{
+    /// A lower bound is not strictly smaller than its associated upper bound.
+    InvalidBounds
+    {
+        /// Index of the parameter.
+        index: usize,
+        /// The lower bound given for the parameter.
+        lower: f64,
+        /// The upper bound given for the parameter.
+        upper: f64
+    },
+    /// A vector does not have the expected number of elements.
+    DimensionMismatch
+    {
+        /// The expected number of elements.
+        expected: usize,
+        /// The number of elements found.
+        found: usize
+    },
+    /// An index does not refer to an element of a vector.
+    IndexOutOfBounds
+    {
+        /// The index given.
+        index: usize,
+        /// The number of elements of the vector.
+        len: usize
+    },
+    /// A matrix that should be symmetric is not.
+    NonSymmetricMatrix
+    {
+        /// Row of the first element that differs from its transposed element.
+        row: usize,
+        /// Column of the first element that differs from its transposed element.
+        column: usize
+    },
+    /// The data contains a non-finite value (NaN or infinite).
+    NonFiniteValue
+    {
+        /// Name of the data (such as the training inputs).
+        data: &'static str,
+        /// Index of the first row containing a non-finite value.
+        row: usize
+    },
+    /// A linear system (such as the least squares fit of a prior) could not be solved.
+    LinearSolveFailure,
+    /// The covariance matrix is not positive definite, its Cholesky decomposition failed.
+    SingularMatrix,
+    /// The covariance matrix is not positive definite even with the largest jitter added to its diagonal.
+    SingularMatrixDespiteJitter
+    {
+        /// The last jitter added to the diagonal.
+        jitter: f64
+    },
+    /// The bounds of a fitted parameter only contain values of the opposite sign
+    /// (the optimizers never change the sign of a parameter).
+    BoundsExcludeSign
+    {
+        /// Index of the parameter.
+        index: usize,
+        /// The value of the parameter.
+        value: f64
+    },
+    /// A parameter has an invalid value.
+    InvalidParameter
+    {
+        /// Name of the parameter.
+        name: &'static str,
+        /// The value given for the parameter.
+        value: f64
+    },
+    /// An input is outside of the domain of the kernel (such as a negative input for the `Brownian` kernel).
+    InvalidInput
+    {
+        /// Name of the kernel.
+        kernel: &'static str,
+        /// Index of the first row containing an invalid value.
+        row: usize,
+        /// The invalid value.
+        value: f64
+    }
+}
+
+impl fmt::Display for FriedrichError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            FriedrichError::InvalidBounds { index, lower, upper } =>
+            {
+                write!(f, "the lower bound ({}) of parameter {} should be smaller than its upper bound ({})", lower, index, upper)
+            }
+            FriedrichError::DimensionMismatch { expected, found } =>
+            {
+                write!(f, "expected {} elements but found {}", expected, found)
+            }
+            FriedrichError::IndexOutOfBounds { index, len } =>
+            {
+                write!(f, "the index {} is out of bounds for {} elements", index, len)
+            }
+            FriedrichError::NonSymmetricMatrix { row, column } =>
+            {
+                write!(f,
+                       "the matrix is not symmetric, element ({}, {}) differs from element ({}, {})",
+                       row, column, column, row)
+            }
+            FriedrichError::NonFiniteValue { data, row } =>
+            {
+                write!(f, "the {} contain a non-finite value (NaN or infinite) at row {}", data, row)
+            }
+            FriedrichError::LinearSolveFailure =>
+            {
+                write!(f, "the linear system could not be solved (the data might contain non-finite values)")
+            }
+            FriedrichError::SingularMatrix =>
+            {
+                write!(f,
+                       "the Cholesky decomposition of the covariance matrix failed, \
+                        consider setting `cholesky_epsilon` via `GaussianProcessBuilder`")
+            }
+            FriedrichError::SingularMatrixDespiteJitter { jitter } =>
+            {
+                write!(f,
+                       "the Cholesky decomposition of the covariance matrix failed even with a jitter of {} \
+                        added to its diagonal, consider allowing more retries in the `CholeskyJitter`",
+                       jitter)
+            }
+            FriedrichError::BoundsExcludeSign { index, value } =>
+            {
+                write!(f,
+                       "the bounds of parameter {} exclude the sign of its value ({}), \
+                        the optimizers never change the sign of a parameter",
+                       index, value)
+            }
+            FriedrichError::InvalidParameter { name, value } =>
+            {
+                write!(f, "the parameter `{}` cannot take the value {}", name, value)
+            }
+            FriedrichError::InvalidInput { kernel, row, value } =>
+            {
+                write!(f, "the {} kernel is not defined for the value {} at row {} of the inputs", kernel, value, row)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FriedrichError {}
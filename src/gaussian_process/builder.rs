@@ -1,9 +1,13 @@
-use super::GaussianProcess;
+use super::{AdamConfig, CholeskyJitter, FitConfig, GaussianProcess, InputStandardization, ObjectiveFunction, Optimizer,
+            OutputStandardization, ParameterBounds, validate_training_data};
+use crate::algebra::{CovarianceBuilder, DefaultBuilder};
 use crate::conversion::Input;
+use crate::error::FriedrichError;
+use crate::parameters::hyperprior::HyperPrior;
 use crate::parameters::kernel::Kernel;
 use crate::parameters::prior::Prior;
-use chrono::Duration;
 use nalgebra::{DMatrix, DVector};
+use std::sync::Arc;
 
 /// Builder to set the parameters of a gaussian process.
 ///
@@ -41,13 +45,17 @@ pub struct GaussianProcessBuilder<KernelType: Kernel, PriorType: Prior>
     /// Amplitude of the noise of the data.
     noise: f64,
     cholesky_epsilon: Option<f64>,
+    /// Adaptive jitter used if the Cholesky decomposition fails.
+    cholesky_jitter: Option<CholeskyJitter>,
     /// Type of fit to be applied.
     should_fit_kernel: bool,
     should_fit_prior: bool,
+    /// Should the inputs be standardized.
+    should_standardize_inputs: bool,
+    /// Should the outputs be standardized.
+    should_standardize_outputs: bool,
     /// Fit parameters.
-    max_iter: usize,
-    convergence_fraction: f64,
-    max_time: Duration,
+    fit_config: FitConfig,
     /// Data use for training.
     training_inputs: DMatrix<f64>,
     training_outputs: DVector<f64>
@@ -73,23 +81,25 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcessBuilder<KernelType, Pr
         let noise = 0.1 * training_outputs.row_variance()[0].sqrt(); // 10% of output std by default
         let should_fit_kernel = false;
         let should_fit_prior = false;
-        let max_iter = 100;
-        let convergence_fraction = 0.05;
-        let max_time = Duration::seconds(3600);
+        let should_standardize_inputs = false;
+        let should_standardize_outputs = false;
+        let fit_config = FitConfig::default();
         // In most cases no Cholesky epsilon is needed, especially if user has
         // has some noise set which is also the default. If some epsilon value
         // turns out to be needed, we point the in the right direction via a
         // runtime error message.
         let cholesky_epsilon = None;
+        let cholesky_jitter = None;
         GaussianProcessBuilder { prior,
                                  kernel,
                                  noise,
                                  cholesky_epsilon,
+                                 cholesky_jitter,
                                  should_fit_kernel,
                                  should_fit_prior,
-                                 max_iter,
-                                 convergence_fraction,
-                                 max_time,
+                                 should_standardize_inputs,
+                                 should_standardize_outputs,
+                                 fit_config,
                                  training_inputs,
                                  training_outputs }
     }
@@ -107,11 +117,12 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcessBuilder<KernelType, Pr
                                  kernel: self.kernel,
                                  noise: self.noise,
                                  cholesky_epsilon: self.cholesky_epsilon,
+                                 cholesky_jitter: self.cholesky_jitter,
                                  should_fit_kernel: self.should_fit_kernel,
                                  should_fit_prior: self.should_fit_prior,
-                                 max_iter: self.max_iter,
-                                 convergence_fraction: self.convergence_fraction,
-                                 max_time: self.max_time,
+                                 fit_config: self.fit_config,
+                                 should_standardize_inputs: self.should_standardize_inputs,
+                                 should_standardize_outputs: self.should_standardize_outputs,
                                  training_inputs: self.training_inputs,
                                  training_outputs: self.training_outputs }
     }
@@ -134,11 +145,12 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcessBuilder<KernelType, Pr
                                  kernel,
                                  noise: self.noise,
                                  cholesky_epsilon: self.cholesky_epsilon,
+                                 cholesky_jitter: self.cholesky_jitter,
                                  should_fit_kernel: self.should_fit_kernel,
                                  should_fit_prior: self.should_fit_prior,
-                                 max_iter: self.max_iter,
-                                 convergence_fraction: self.convergence_fraction,
-                                 max_time: self.max_time,
+                                 fit_config: self.fit_config,
+                                 should_standardize_inputs: self.should_standardize_inputs,
+                                 should_standardize_outputs: self.should_standardize_outputs,
                                  training_inputs: self.training_inputs,
                                  training_outputs: self.training_outputs }
     }
@@ -158,13 +170,103 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcessBuilder<KernelType, Pr
         GaussianProcessBuilder { cholesky_epsilon, ..self }
     }
 
+    /// Asks for an increasing jitter to be added to the diagonal of the covariance matrix
+    /// whenever its Cholesky decomposition fails (such as with near-duplicate inputs),
+    /// rather than failing immediately.
+    ///
+    /// The jitter starts at `cholesky_jitter.initial` and is multiplied by ten at each retry,
+    /// if the decomposition still fails after `cholesky_jitter.max_retries` retries
+    /// the training returns an error reporting the last jitter used.
+    pub fn set_cholesky_jitter(self, cholesky_jitter: CholeskyJitter) -> Self
+    {
+        GaussianProcessBuilder { cholesky_jitter: Some(cholesky_jitter), ..self }
+    }
+
     /// Modifies the stopping criteria of the gradient descent used to fit the noise and kernel parameters.
     ///
     /// The optimizer runs for a maximum of `max_iter` iterations and stops prematurely if all gradients are below `convergence_fraction` time their associated parameter
     /// or if it runs for more than `max_time`.
     pub fn set_fit_parameters(self, max_iter: usize, convergence_fraction: f64) -> Self
     {
-        GaussianProcessBuilder { max_iter, convergence_fraction, ..self }
+        let fit_config = FitConfig { max_iter, convergence_fraction, ..self.fit_config };
+        GaussianProcessBuilder { fit_config, ..self }
+    }
+
+    /// Asks for the optimizer to also stop when the objective changes by less than `likelihood_tolerance`
+    /// times its magnitude during an iteration (see `FitConfig::likelihood_tolerance`).
+    pub fn set_likelihood_tolerance(self, likelihood_tolerance: f64) -> Self
+    {
+        let fit_config = FitConfig { likelihood_tolerance: Some(likelihood_tolerance), ..self.fit_config };
+        GaussianProcessBuilder { fit_config, ..self }
+    }
+
+    /// Sets the algorithm used to fit the noise and kernel parameters (ADAM by default).
+    pub fn set_optimizer(self, optimizer: Optimizer) -> Self
+    {
+        let fit_config = FitConfig { optimizer, ..self.fit_config };
+        GaussianProcessBuilder { fit_config, ..self }
+    }
+
+    /// Sets the parameters of the ADAM algorithm used to fit the noise and kernel parameters.
+    pub fn set_adam_config(self, adam: AdamConfig) -> Self
+    {
+        let fit_config = FitConfig { adam, ..self.fit_config };
+        GaussianProcessBuilder { fit_config, ..self }
+    }
+
+    /// Sets the quantity maximized when fitting the noise and kernel parameters (the marginal likelihood by default).
+    pub fn set_objective(self, objective: ObjectiveFunction) -> Self
+    {
+        let fit_config = FitConfig { objective, ..self.fit_config };
+        GaussianProcessBuilder { fit_config, ..self }
+    }
+
+    /// Sets lower and upper bounds on the kernel parameters followed by the noise.
+    /// The parameters are projected back into the bounds after each step of the optimizer.
+    ///
+    /// `try_train` returns an error if the bounds do not cover all kernel parameters followed by the noise.
+    pub fn set_parameter_bounds(self, bounds: ParameterBounds) -> Self
+    {
+        let fit_config = FitConfig { bounds: Some(bounds), ..self.fit_config };
+        GaussianProcessBuilder { fit_config, ..self }
+    }
+
+    /// Sets the smallest magnitude of the noise reachable by the optimizer (see `FitConfig::noise_floor`).
+    pub fn set_noise_floor(self, noise_floor: f64) -> Self
+    {
+        let fit_config = FitConfig { noise_floor, ..self.fit_config };
+        GaussianProcessBuilder { fit_config, ..self }
+    }
+
+    /// Keeps the given parameters (indices of the kernel parameters followed by the noise) at their current value
+    /// during the fit (see `FitConfig::fixed_parameters`).
+    pub fn fix_parameters(self, fixed_parameters: &[usize]) -> Self
+    {
+        let fit_config = FitConfig { fixed_parameters: fixed_parameters.to_vec(), ..self.fit_config };
+        GaussianProcessBuilder { fit_config, ..self }
+    }
+
+    /// Asks for the optimizer to be restarted `nb_restarts` times from random initial parameters
+    /// (drawn deterministically from the `seed`), keeping the best parameters found.
+    pub fn set_restarts(self, nb_restarts: usize, seed: u64) -> Self
+    {
+        let fit_config = FitConfig { nb_restarts, seed, ..self.fit_config };
+        GaussianProcessBuilder { fit_config, ..self }
+    }
+
+    /// Sets hyperpriors on the kernel parameters followed by the noise (`None` for a parameter without hyperprior).
+    /// The fit then maximizes the objective plus the log density of the hyperpriors.
+    pub fn set_hyperpriors(self, hyperpriors: Vec<Option<Arc<dyn HyperPrior>>>) -> Self
+    {
+        let fit_config = FitConfig { hyperpriors, ..self.fit_config };
+        GaussianProcessBuilder { fit_config, ..self }
+    }
+
+    /// Sets all the parameters of the fit of the noise and kernel parameters at once.
+    /// See `FitConfig` for the available options.
+    pub fn set_fit_config(self, fit_config: FitConfig) -> Self
+    {
+        GaussianProcessBuilder { fit_config, ..self }
     }
 
     /// Asks for the parameters of the kernel to be fitted on the training data.
@@ -181,13 +283,78 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcessBuilder<KernelType, Pr
         GaussianProcessBuilder { should_fit_prior: true, ..self }
     }
 
+    /// Asks for the inputs to be standardized to zero mean and unit variance along each dimension.
+    /// The transformation is fitted on the training inputs and applied to all later inputs,
+    /// the parameters of the kernel are thus expressed in standardized units.
+    /// This is opt-in, by default the inputs are used as given.
+    pub fn standardize_inputs(self) -> Self
+    {
+        GaussianProcessBuilder { should_standardize_inputs: true, ..self }
+    }
+
+    /// Asks for the outputs to be standardized to zero mean and unit variance.
+    /// The process is trained on standardized outputs and its predictions are brought back to the scale of the outputs,
+    /// such that the `cholesky_epsilon` and the optimizer behave consistently whatever the magnitude of the outputs.
+    /// The noise given to the builder is converted to the standardized space
+    /// and all parameters of the trained process are expressed in that space.
+    /// This is opt-in, by default the outputs are used as given.
+    pub fn standardize_outputs(self) -> Self
+    {
+        GaussianProcessBuilder { should_standardize_outputs: true, ..self }
+    }
+
     //----------------------------------------------------------------------------------------------
     // TRAIN
 
     /// Trains the gaussian process.
     /// Fits the parameters if requested.
-    pub fn train(mut self) -> GaussianProcess<KernelType, PriorType>
+    pub fn train(self) -> GaussianProcess<KernelType, PriorType>
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        self.try_train().unwrap_or_else(|error| panic!("GaussianProcessBuilder::train: {}", error))
+    }
+
+    /// Fallible version of `train`, returns an error rather than panicking
+    /// if the parameters or the data are invalid (see `GaussianProcess::try_new`)
+    /// or if the fit of the parameters fails (see `GaussianProcess::try_fit_parameters_with_config`).
+    pub fn try_train(mut self) -> Result<GaussianProcess<KernelType, PriorType>, FriedrichError>
+        where DefaultBuilder: CovarianceBuilder<KernelType>
     {
+        // checks the data first as non-finite outputs also produce a non-finite default noise
+        validate_training_data(&self.training_inputs, &self.training_outputs)?;
+
+        // standardizes the data, if requested, before any parameter sees it
+        let input_standardization = if self.should_standardize_inputs
+        {
+            let standardization = InputStandardization::fit(&self.training_inputs);
+            self.training_inputs = standardization.apply(self.training_inputs);
+            Some(standardization)
+        }
+        else
+        {
+            None
+        };
+
+        let output_standardization = if self.should_standardize_outputs
+        {
+            let standardization = OutputStandardization::fit(&self.training_outputs);
+            self.training_outputs = standardization.apply(self.training_outputs);
+            self.noise /= standardization.std();
+            Some(standardization)
+        }
+        else
+        {
+            None
+        };
+
+        // checks the fit configuration against the kernel given by the user, before any costly computation
+        if self.should_fit_kernel
+        {
+            let mut parameters = self.kernel.get_parameters();
+            parameters.push(self.noise);
+            self.fit_config.validate(&parameters)?;
+        }
+
         // prepare kernel and noise values using heuristics
         // TODO how to detect if values have been entered by the user meaning that he does not want an heuristic ?
         if self.should_fit_kernel
@@ -196,20 +363,19 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcessBuilder<KernelType, Pr
         }
 
         // Builds a gp.
-        let mut gp = GaussianProcess::<KernelType, PriorType>::new(self.prior,
-                                                                   self.kernel,
-                                                                   self.noise,
-                                                                   self.cholesky_epsilon,
-                                                                   self.training_inputs,
-                                                                   self.training_outputs);
+        let mut gp = GaussianProcess::<KernelType, PriorType>::try_from_data(self.prior,
+                                                                             self.kernel,
+                                                                             self.noise,
+                                                                             self.cholesky_epsilon,
+                                                                             self.cholesky_jitter,
+                                                                             self.training_inputs,
+                                                                             self.training_outputs)?;
+        gp.input_standardization = input_standardization;
+        gp.output_standardization = output_standardization;
 
         // Fits the model, if requested, on the training data.
-        gp.fit_parameters(self.should_fit_prior,
-                          self.should_fit_kernel,
-                          self.max_iter,
-                          self.convergence_fraction,
-                          self.max_time);
+        gp.try_fit_parameters_with_config(self.should_fit_prior, self.should_fit_kernel, &self.fit_config)?;
 
-        gp
+        Ok(gp)
     }
 }
@@ -0,0 +1,272 @@
+//! K-fold cross-validation
+//!
+//! Estimates the predictive performance of a model by splitting the data into `k` folds,
+//! refitting the process on all folds but one and evaluating its predictions on the held-out fold.
+//!
+//! This is `k` times more expensive than the closed-form leave-one-out predictions (see `loo_predictions`)
+//! but the parameters are refitted on each split, which makes it a fair comparison between kernels and priors.
+//!
+//! ```rust
+//! # use friedrich::gaussian_process::{FitConfig, GaussianProcess};
+//! let training_inputs: Vec<Vec<f64>> = (0..20).map(|i| vec![i as f64 * 0.3]).collect();
+//! let training_outputs: Vec<f64> = training_inputs.iter().map(|x| x[0].sin()).collect();
+//! let gp = GaussianProcess::default(training_inputs.clone(), training_outputs.clone());
+//!
+//! // Five folds, the prior and kernel being refitted on each training split.
+//! let report = gp.cross_validate(training_inputs, training_outputs, 5, true, true, &FitConfig::default()).unwrap();
+//! assert_eq!(report.fold_mean_squared_error.len(), 5);
+//! assert!(report.mean_squared_error < 0.1);
+//! ```
+
+use super::{FitConfig, GaussianProcess, InputStandardization, OutputStandardization, validate_training_data};
+use crate::algebra::{CovarianceBuilder, DefaultBuilder};
+use crate::conversion::Input;
+use crate::error::FriedrichError;
+use crate::parameters::{kernel::Kernel, prior::Prior};
+use nalgebra::{DMatrix, DVector};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use std::f64::consts::PI;
+
+/// Performance of a model on the held-out folds of a k-fold cross-validation (see `cross_validate`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct CvReport
+{
+    /// Mean squared error of the predicted means, over all samples.
+    pub mean_squared_error: f64,
+    /// Mean negative log predictive density of the outputs (including the noise), over all samples.
+    ///
+    /// Unlike the squared error, it penalizes over and under confident predictions.
+    pub negative_log_predictive_density: f64,
+    /// Mean squared error on each held-out fold.
+    pub fold_mean_squared_error: Vec<f64>,
+    /// Mean negative log predictive density on each held-out fold.
+    pub fold_negative_log_predictive_density: Vec<f64>
+}
+
+impl<KernelType: Kernel, PriorType: Prior + Clone> GaussianProcess<KernelType, PriorType>
+{
+    /// Performs a k-fold cross-validation of the model on the given data.
+    ///
+    /// The current process is only used as a template: its prior, kernel, noise, Cholesky settings
+    /// and standardizations are the starting point of each fold while its own training data is ignored,
+    /// such that a process can be evaluated on its training data as well as on any other data.
+    ///
+    /// The samples are shuffled then split into `k` folds of equal size (up to one sample),
+    /// the assignment being deterministic given `fit_config.seed`.
+    /// For each fold, a process is built on the other folds, starting from the template
+    /// (its standardizations being refitted on the split),
+    /// its prior (if `fit_prior`) and its kernel and noise (if `fit_kernel`) are fitted with `fit_config`
+    /// (see `fit_parameters_with_config`) and it predicts the held-out fold.
+    /// Without any fit, this evaluates the current parameters.
+    ///
+    /// Returns an error if `k` is not between 2 and the number of samples,
+    /// if the data is invalid (see `try_new`) or if a fit fails (see `try_fit_parameters_with_config`).
+    pub fn cross_validate<T: Input>(&self,
+                                    inputs: T,
+                                    outputs: T::InVector,
+                                    k: usize,
+                                    fit_prior: bool,
+                                    fit_kernel: bool,
+                                    fit_config: &FitConfig)
+                                    -> Result<CvReport, FriedrichError>
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        let inputs = T::into_dmatrix(inputs);
+        let outputs = T::into_dvector(outputs);
+        if inputs.nrows() != outputs.nrows()
+        {
+            return Err(FriedrichError::DimensionMismatch { expected: inputs.nrows(), found: outputs.nrows() });
+        }
+        validate_training_data(&inputs, &outputs)?;
+        let nb_samples = inputs.nrows();
+        if k < 2 || k > nb_samples
+        {
+            return Err(FriedrichError::InvalidParameter { name: "k", value: k as f64 });
+        }
+
+        // Assigns the shuffled samples to the folds in turn.
+        let mut order: Vec<usize> = (0..nb_samples).collect();
+        order.shuffle(&mut StdRng::seed_from_u64(fit_config.seed));
+
+        // The noise is brought back to the scale of the outputs.
+        let noise = self.noise * self.variance_scale().sqrt();
+        let mut fold_mean_squared_error = Vec::with_capacity(k);
+        let mut fold_negative_log_predictive_density = Vec::with_capacity(k);
+        let (mut total_squared_error, mut total_negative_log_density) = (0., 0.);
+        for fold in 0..k
+        {
+            let is_in_fold = |position: &usize| position % k == fold;
+            let test: Vec<usize> = (0..nb_samples).filter(is_in_fold).map(|position| order[position]).collect();
+            let train: Vec<usize> =
+                (0..nb_samples).filter(|position| !is_in_fold(position)).map(|position| order[position]).collect();
+
+            let gp = self.fit_split(inputs.select_rows(&train),
+                                    outputs.select_rows(&train),
+                                    noise,
+                                    fit_prior,
+                                    fit_kernel,
+                                    fit_config)?;
+            let test_inputs = inputs.select_rows(&test);
+            let (means, variances) = gp.predict_mean_variance(&test_inputs);
+            let noise_variance = gp.noise * gp.noise * gp.variance_scale();
+
+            let (mut squared_error, mut negative_log_density) = (0., 0.);
+            for ((&sample, mean), variance) in test.iter().zip(means.iter()).zip(variances.iter())
+            {
+                let error = (outputs[sample] - mean).powi(2);
+                let variance = variance + noise_variance;
+                squared_error += error;
+                negative_log_density += 0.5 * ((2. * PI * variance).ln() + error / variance);
+            }
+            total_squared_error += squared_error;
+            total_negative_log_density += negative_log_density;
+            fold_mean_squared_error.push(squared_error / test.len() as f64);
+            fold_negative_log_predictive_density.push(negative_log_density / test.len() as f64);
+        }
+
+        Ok(CvReport { mean_squared_error: total_squared_error / nb_samples as f64,
+                      negative_log_predictive_density: total_negative_log_density / nb_samples as f64,
+                      fold_mean_squared_error,
+                      fold_negative_log_predictive_density })
+    }
+
+    /// Builds a process on a training split, starting from the parameters of the current process,
+    /// then fits its prior and kernel as requested.
+    ///
+    /// The `noise` is expressed in the scale of the outputs.
+    fn fit_split(&self,
+                 mut inputs: DMatrix<f64>,
+                 mut outputs: DVector<f64>,
+                 mut noise: f64,
+                 fit_prior: bool,
+                 fit_kernel: bool,
+                 fit_config: &FitConfig)
+                 -> Result<Self, FriedrichError>
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        let input_standardization = if self.input_standardization.is_some()
+        {
+            let standardization = InputStandardization::fit(&inputs);
+            inputs = standardization.apply(inputs);
+            Some(standardization)
+        }
+        else
+        {
+            None
+        };
+
+        let output_standardization = if self.output_standardization.is_some()
+        {
+            let standardization = OutputStandardization::fit(&outputs);
+            outputs = standardization.apply(outputs);
+            noise /= standardization.std();
+            Some(standardization)
+        }
+        else
+        {
+            None
+        };
+
+        let mut gp = Self::try_from_data(self.prior.clone(),
+                                         self.kernel.clone(),
+                                         noise,
+                                         self.cholesky_epsilon,
+                                         self.cholesky_jitter,
+                                         inputs,
+                                         outputs)?;
+        gp.input_standardization = input_standardization;
+        gp.output_standardization = output_standardization;
+        gp.try_fit_parameters_with_config(fit_prior, fit_kernel, fit_config)?;
+        Ok(gp)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::parameters::{kernel, prior};
+
+    fn data() -> (Vec<Vec<f64>>, Vec<f64>)
+    {
+        let inputs: Vec<Vec<f64>> = (0..12).map(|i| vec![i as f64 * 0.4]).collect();
+        let outputs: Vec<f64> = inputs.iter().map(|x| x[0].sin() + 0.1 * (7. * x[0]).cos()).collect();
+        (inputs, outputs)
+    }
+
+    #[test]
+    fn leave_one_out_folds_match_the_closed_form_predictions()
+    {
+        let (inputs, outputs) = data();
+        let gp = GaussianProcess::new(prior::ZeroPrior {},
+                                      kernel::SquaredExp::new(1.2, 1.),
+                                      0.2,
+                                      None,
+                                      inputs.clone(),
+                                      outputs.clone());
+        // Without fit, the parameters are kept and the folds match the closed-form formula.
+        let report = gp.cross_validate(inputs, outputs.clone(), outputs.len(), false, false, &FitConfig::default())
+                       .unwrap();
+
+        let (means, variances) = gp.loo_predictions();
+        let nb_samples = outputs.len() as f64;
+        let mean_squared_error: f64 =
+            outputs.iter().zip(means.iter()).map(|(y, mean)| (y - mean).powi(2)).sum::<f64>() / nb_samples;
+        let negative_log_predictive_density: f64 =
+            outputs.iter()
+                   .zip(means.iter().zip(variances.iter()))
+                   .map(|(y, (mean, variance))| 0.5 * ((2. * PI * variance).ln() + (y - mean).powi(2) / variance))
+                   .sum::<f64>()
+            / nb_samples;
+        assert!((report.mean_squared_error - mean_squared_error).abs() < 1e-9);
+        assert!((report.negative_log_predictive_density - negative_log_predictive_density).abs() < 1e-9);
+        assert_eq!(report.fold_mean_squared_error.len(), outputs.len());
+    }
+
+    #[test]
+    fn fold_assignment_is_deterministic_given_the_seed()
+    {
+        let (inputs, outputs) = data();
+        let gp = GaussianProcess::builder(inputs.clone(), outputs.clone()).fit_kernel().train();
+        let cross_validate = |seed: u64| {
+            let config = FitConfig { seed, ..FitConfig::default() };
+            gp.cross_validate(inputs.clone(), outputs.clone(), 3, true, true, &config).unwrap()
+        };
+
+        let report = cross_validate(1);
+        assert_eq!(report, cross_validate(1));
+        assert_ne!(report.fold_mean_squared_error, cross_validate(2).fold_mean_squared_error);
+        // The global means are the means of the folds as they have the same size.
+        let fold_mean = report.fold_mean_squared_error.iter().sum::<f64>() / 3.;
+        assert!((report.mean_squared_error - fold_mean).abs() < 1e-12);
+    }
+
+    #[test]
+    fn fit_flags_select_the_refitted_parameters()
+    {
+        let (inputs, outputs) = data();
+        // A length scale too short to interpolate between the samples.
+        let gp = GaussianProcess::new(prior::ZeroPrior {},
+                                      kernel::SquaredExp::new(0.1, 1.),
+                                      0.2,
+                                      None,
+                                      inputs.clone(),
+                                      outputs.clone());
+        let cross_validate = |fit_kernel: bool| {
+            gp.cross_validate(inputs.clone(), outputs.clone(), 4, false, fit_kernel, &FitConfig::default()).unwrap()
+        };
+        assert!(cross_validate(true).mean_squared_error < cross_validate(false).mean_squared_error);
+    }
+
+    #[test]
+    fn invalid_number_of_folds_is_rejected()
+    {
+        let (inputs, outputs) = data();
+        let gp = GaussianProcess::default(inputs.clone(), outputs.clone());
+        for k in [0, 1, outputs.len() + 1]
+        {
+            assert_eq!(gp.cross_validate(inputs.clone(), outputs.clone(), k, true, true, &FitConfig::default()),
+                       Err(FriedrichError::InvalidParameter { name: "k", value: k as f64 }));
+        }
+    }
+}
@@ -2,7 +2,7 @@
 //!
 //! ```rust
 //! # use friedrich::gaussian_process::GaussianProcess;
-//! # use chrono::Duration;
+//! # use std::time::Duration;
 //! // Trains a gaussian process on a dataset of one dimension vectors.
 //! let training_inputs = vec![vec![0.8], vec![1.2], vec![3.8], vec![4.2]];
 //! let training_outputs = vec![3.0, 4.0, -2.0, -2.0];
@@ -26,7 +26,7 @@
 //! let fit_kernel = true;
 //! let max_iter = 100;
 //! let convergence_fraction = 0.05;
-//! let max_time = Duration::seconds(3600);
+//! let max_time = Duration::from_secs(3600);
 //! gp.add_samples(&additional_inputs, &additional_outputs);
 //! gp.fit_parameters(fit_prior, fit_kernel, max_iter, convergence_fraction, max_time);
 //!
@@ -39,12 +39,19 @@
 //! }
 //! ```
 
-use crate::algebra::{add_rows_cholesky_cov_matrix, make_cholesky_cov_matrix, make_covariance_matrix, EMatrix,
-                 EVector};
+use crate::algebra::{add_rows_cholesky_cov_matrix, cholesky_downdate, make_cholesky_cov_matrix, make_covariance_matrix,
+                     normal_quantile, try_make_cholesky_cov_matrix, CovarianceBuilder, DefaultBuilder, EMatrix,
+                     EVector};
+pub use crate::algebra::CholeskyJitter;
 use crate::conversion::Input;
-use crate::parameters::{kernel, kernel::Kernel, prior, prior::Prior};
-use chrono::Duration;
+use crate::error::FriedrichError;
+use crate::parameters::{kernel, kernel::Kernel, prior, prior::ConstantValuedPrior, prior::Prior};
 use nalgebra::{Cholesky, DMatrix, DVector, Dynamic};
+use rand::Rng;
+use std::time::Duration;
+
+mod cross_validation;
+pub use cross_validation::CvReport;
 
 mod multivariate_normal;
 pub use multivariate_normal::MultivariateNormal;
@@ -53,8 +60,25 @@ mod builder;
 pub use builder::GaussianProcessBuilder;
 
 mod optimizer;
+pub use optimizer::{AdamConfig, FitConfig, ObjectiveFunction, OptimStep, Optimizer, ParameterBounds};
+
+#[cfg(feature = "friedrich_serde")]
+mod persistence;
+
+mod sparse;
+pub use sparse::SparseGaussianProcess;
+
+mod standardization;
+pub use standardization::{InputStandardization, OutputStandardization};
 
 /// A Gaussian process that can be used to make predictions based on its training data
+///
+/// With the `friedrich_serde` feature (enabled by default),
+/// the process implements `serde::Serialize` and `serde::Deserialize`.
+/// The training data, prior, kernel parameters, noise, standardizations, Cholesky settings and the Cholesky factor
+/// of the covariance matrix (with the jitter it required) are all stored such that a deserialized process
+/// predicts, and is updated by `add_samples`, exactly as the original one, without being retrained.
+/// The `save` and `load` methods write and read a process to and from a file in a compact binary format.
 #[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct GaussianProcess<KernelType: Kernel, PriorType: Prior>
 {
@@ -71,11 +95,23 @@ pub struct GaussianProcess<KernelType: Kernel, PriorType: Prior>
     /// fail. See <https://github.com/nestordemeure/friedrich/issues/43> for
     /// details.
     pub cholesky_epsilon: Option<f64>,
+    /// Optional jitter added to the diagonal of the covariance matrix, with increasing values,
+    /// if and only if its Cholesky decomposition fails (see `CholeskyJitter`).
+    #[cfg_attr(feature = "friedrich_serde", serde(default))]
+    pub cholesky_jitter: Option<CholeskyJitter>,
+    /// Optional standardization applied to all inputs (the training inputs are stored standardized).
+    input_standardization: Option<InputStandardization>,
+    /// Optional standardization applied to the outputs (the training outputs are stored standardized).
+    output_standardization: Option<OutputStandardization>,
     /// Data used for fit
-    training_inputs: EMatrix,
-    training_outputs: EVector,
+    pub(crate) training_inputs: EMatrix,
+    pub(crate) training_outputs: EVector,
     /// Cholesky decomposition of the covariance matrix trained on the current data points.
-    covmat_cholesky: Cholesky<f64, Dynamic>
+    covmat_cholesky: Cholesky<f64, Dynamic>,
+    /// Jitter added to the diagonal of the covariance matrix by its last full decomposition (zero if none),
+    /// also added to the samples appended by `add_samples`.
+    #[cfg_attr(feature = "friedrich_serde", serde(default))]
+    applied_jitter: f64
 }
 
 impl GaussianProcess<kernel::Gaussian, prior::ConstantPrior>
@@ -101,6 +137,26 @@ impl GaussianProcess<kernel::Gaussian, prior::ConstantPrior>
                                                                                                .train()
     }
 
+    /// Returns a gaussian process with a Gaussian kernel and a constant prior, both fitted to the data,
+    /// for one-dimensional inputs given as a plain vector of scalars.
+    ///
+    /// ```rust
+    /// # use friedrich::gaussian_process::GaussianProcess;
+    /// # fn main() {
+    /// let training_inputs = vec![0.8, 1.2, 3.8, 4.2];
+    /// let training_outputs = vec![3.0, 4.0, -2.0, -2.0];
+    /// let gp = GaussianProcess::default_one_dimensional(training_inputs, training_outputs);
+    ///
+    /// // predictions take one vector per sample
+    /// let prediction = gp.predict(&vec![vec![1.], vec![2.]]);
+    /// # }
+    /// ```
+    pub fn default_one_dimensional(training_inputs: Vec<f64>, training_outputs: Vec<f64>) -> Self
+    {
+        let training_inputs: Vec<Vec<f64>> = training_inputs.into_iter().map(|x| vec![x]).collect();
+        Self::default(training_inputs, training_outputs)
+    }
+
     /// Returns a builder to define specific parameters of the gaussian process.
     ///
     /// ```rust
@@ -135,10 +191,27 @@ impl GaussianProcess<kernel::Gaussian, prior::ConstantPrior>
     }
 }
 
+/// Checks that the training data only contains finite values,
+/// returns an error naming the first row containing a NaN or an infinite value otherwise.
+fn validate_training_data(training_inputs: &DMatrix<f64>, training_outputs: &DVector<f64>) -> Result<(), FriedrichError>
+{
+    if let Some(row) = training_inputs.row_iter().position(|row| row.iter().any(|x| !x.is_finite()))
+    {
+        return Err(FriedrichError::NonFiniteValue { data: "training inputs", row });
+    }
+    if let Some(row) = training_outputs.iter().position(|y| !y.is_finite())
+    {
+        return Err(FriedrichError::NonFiniteValue { data: "training outputs", row });
+    }
+    Ok(())
+}
+
 impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType>
 {
     /// Raw method to create a new gaussian process with the given parameters / data.
     /// We recommend that you use either the default parameters or the builder to simplify the definition process.
+    ///
+    /// Panics if the parameters or the data are invalid, see `try_new` for a version returning an error instead.
     pub fn new<T: Input>(prior: PriorType,
                          kernel: KernelType,
                          noise: f64,
@@ -146,47 +219,306 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType
                          training_inputs: T,
                          training_outputs: T::InVector)
                          -> Self
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        Self::try_new(prior, kernel, noise, cholesky_epsilon, training_inputs, training_outputs)
+            .unwrap_or_else(|error| panic!("GaussianProcess::new: {}", error))
+    }
+
+    /// Fallible version of `new`, returns an error rather than panicking
+    /// if the noise is invalid, if the number of inputs and outputs differ,
+    /// if the training data contains non-finite values (NaN or infinite),
+    /// if the kernel is not defined for the training inputs (see `Kernel::validate_inputs`)
+    /// or if the covariance matrix is not positive definite.
+    pub fn try_new<T: Input>(prior: PriorType,
+                             kernel: KernelType,
+                             noise: f64,
+                             cholesky_epsilon: Option<f64>,
+                             training_inputs: T,
+                             training_outputs: T::InVector)
+                             -> Result<Self, FriedrichError>
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        Self::try_from_data(prior,
+                            kernel,
+                            noise,
+                            cholesky_epsilon,
+                            None,
+                            T::into_dmatrix(training_inputs),
+                            T::into_dvector(training_outputs))
+    }
+
+    /// Validates the parameters and the training data before building the process.
+    fn try_from_data(prior: PriorType,
+                     kernel: KernelType,
+                     noise: f64,
+                     cholesky_epsilon: Option<f64>,
+                     cholesky_jitter: Option<CholeskyJitter>,
+                     training_inputs: DMatrix<f64>,
+                     training_outputs: DVector<f64>)
+                     -> Result<Self, FriedrichError>
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        if noise.is_nan() || noise < 0.
+        {
+            return Err(FriedrichError::InvalidParameter { name: "noise", value: noise });
+        }
+        if training_inputs.nrows() != training_outputs.nrows()
+        {
+            return Err(FriedrichError::DimensionMismatch { expected: training_inputs.nrows(),
+                                                           found: training_outputs.nrows() });
+        }
+        validate_training_data(&training_inputs, &training_outputs)?;
+        kernel.validate_inputs(&training_inputs)?;
+        Self::try_from_matrices(prior,
+                                kernel,
+                                noise,
+                                cholesky_epsilon,
+                                cholesky_jitter,
+                                training_inputs,
+                                training_outputs)
+    }
+
+    /// Version of `new` that does not check the training data, for data that is already known to be valid
+    /// (such as when building many processes in a hot loop).
+    ///
+    /// Non-finite values in the training data are not detected and will lead to non-finite predictions.
+    /// Panics if the covariance matrix is not positive definite.
+    pub fn new_unchecked<T: Input>(prior: PriorType,
+                                   kernel: KernelType,
+                                   noise: f64,
+                                   cholesky_epsilon: Option<f64>,
+                                   training_inputs: T,
+                                   training_outputs: T::InVector)
+                                   -> Self
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        Self::try_from_matrices(prior,
+                                kernel,
+                                noise,
+                                cholesky_epsilon,
+                                None,
+                                T::into_dmatrix(training_inputs),
+                                T::into_dvector(training_outputs))
+            .unwrap_or_else(|error| panic!("GaussianProcess::new_unchecked: {}", error))
+    }
+
+    /// Builds the process from training data that has already been validated.
+    fn try_from_matrices(prior: PriorType,
+                         kernel: KernelType,
+                         noise: f64,
+                         cholesky_epsilon: Option<f64>,
+                         cholesky_jitter: Option<CholeskyJitter>,
+                         training_inputs: DMatrix<f64>,
+                         training_outputs: DVector<f64>)
+                         -> Result<Self, FriedrichError>
+        where DefaultBuilder: CovarianceBuilder<KernelType>
     {
-        assert!(noise >= 0., "The noise parameter should non-negative but we tried to set it to {}", noise);
-        let training_inputs = T::into_dmatrix(training_inputs);
-        let training_outputs = T::into_dvector(training_outputs);
-        assert_eq!(training_inputs.nrows(), training_outputs.nrows());
         // converts training data into extendable matrix
         let training_inputs = EMatrix::new(training_inputs);
         let training_outputs = EVector::new(training_outputs - prior.prior(&training_inputs.as_matrix()));
         // computes cholesky decomposition
-        let covmat_cholesky =
-            make_cholesky_cov_matrix(&training_inputs.as_matrix(), &kernel, noise, cholesky_epsilon);
-        GaussianProcess { prior,
-                          kernel,
-                          noise,
-                          cholesky_epsilon,
-                          training_inputs,
-                          training_outputs,
-                          covmat_cholesky }
+        let (covmat_cholesky, applied_jitter) = try_make_cholesky_cov_matrix(&training_inputs.as_matrix(),
+                                                                             &kernel,
+                                                                             noise,
+                                                                             cholesky_epsilon,
+                                                                             cholesky_jitter)?;
+        Ok(GaussianProcess { prior,
+                             kernel,
+                             noise,
+                             cholesky_epsilon,
+                             cholesky_jitter,
+                             input_standardization: None,
+                             output_standardization: None,
+                             training_inputs,
+                             training_outputs,
+                             covmat_cholesky,
+                             applied_jitter })
     }
 
     /// Adds new samples to the model.
     ///
     /// Updates the model (which is faster than a retraining from scratch)
     /// but does not refit the parameters.
+    /// The Cholesky decomposition of the covariance matrix is extended in O(n²) operations per new sample,
+    /// falling back to a full decomposition (using `cholesky_epsilon` and `cholesky_jitter`)
+    /// if numerical drift is detected.
+    /// If the last full decomposition needed a jitter, the same jitter is added to the variance of the new samples.
+    ///
+    /// Panics if the samples are invalid, see `try_add_samples` for a version returning an error instead.
     pub fn add_samples<T: Input>(&mut self, inputs: &T, outputs: &T::InVector)
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        self.try_add_samples(inputs, outputs)
+            .unwrap_or_else(|error| panic!("GaussianProcess::add_samples: {}", error))
+    }
+
+    /// Fallible version of `add_samples`, returns an error rather than panicking
+    /// if the inputs do not have the dimension of the training inputs, if the number of inputs and outputs differ,
+    /// if the new samples contain non-finite values (NaN or infinite)
+    /// or if the kernel is not defined for the new inputs (see `Kernel::validate_inputs`), the model being left untouched.
+    pub fn try_add_samples<T: Input>(&mut self, inputs: &T, outputs: &T::InVector) -> Result<(), FriedrichError>
+        where DefaultBuilder: CovarianceBuilder<KernelType>
     {
         let inputs = T::to_dmatrix(inputs);
         let outputs = T::to_dvector(outputs);
-        assert_eq!(inputs.nrows(), outputs.nrows());
-        assert_eq!(inputs.ncols(), self.training_inputs.as_matrix().ncols());
+        let nb_dimensions = self.training_inputs.as_matrix().ncols();
+        if inputs.ncols() != nb_dimensions
+        {
+            return Err(FriedrichError::DimensionMismatch { expected: nb_dimensions, found: inputs.ncols() });
+        }
+        if inputs.nrows() != outputs.nrows()
+        {
+            return Err(FriedrichError::DimensionMismatch { expected: inputs.nrows(), found: outputs.nrows() });
+        }
+        validate_training_data(&inputs, &outputs)?;
+
+        let inputs = self.convert_inputs(&inputs);
+        self.kernel.validate_inputs(&inputs)?;
+        let outputs = match &self.output_standardization
+        {
+            Some(standardization) => standardization.apply(outputs),
+            None => outputs
+        };
         // grows the training matrix
         let outputs = outputs - self.prior.prior(&inputs);
         self.training_inputs.add_rows(&inputs);
         self.training_outputs.add_rows(&outputs);
         // add new rows to cholesky matrix
         let nb_new_inputs = inputs.nrows();
-        add_rows_cholesky_cov_matrix(&mut self.covmat_cholesky,
-                                     &self.training_inputs.as_matrix(),
-                                     nb_new_inputs,
+        if !add_rows_cholesky_cov_matrix(&mut self.covmat_cholesky,
+                                         &self.training_inputs.as_matrix(),
+                                         nb_new_inputs,
+                                         &self.kernel,
+                                         self.noise,
+                                         self.applied_jitter)
+        {
+            // The result is not numerically valid.
+            self.refactorize();
+        }
+        Ok(())
+    }
+
+    /// Removes a training sample from the model (such as a corrupted measurement).
+    ///
+    /// The samples are indexed in the order in which they were given to the model.
+    /// The Cholesky decomposition of the covariance matrix is downdated in O(n²) operations
+    /// (falling back to a full decomposition if the result is not numerically valid)
+    /// and the parameters are not refitted.
+    ///
+    /// Panics if the index is out of range or if the sample is the last one of the model.
+    pub fn remove_training_point(&mut self, index: usize)
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        self.remove_samples(&[index]);
+    }
+
+    /// Removes several training samples from the model (such as the oldest samples of a sliding window).
+    ///
+    /// The samples are indexed in the order in which they were given to the model, repeated indices are ignored.
+    /// The Cholesky decomposition of the covariance matrix is downdated in O(n²) operations per removed sample
+    /// (falling back to a full decomposition if the result is not numerically valid)
+    /// and the parameters are not refitted.
+    ///
+    /// Note that each downdate accumulates a small rounding error into the decomposition:
+    /// when removing samples over long periods of time,
+    /// call `refactorize` once in a while to start again from an exact decomposition.
+    ///
+    /// Panics if an index is out of range or if all samples would be removed.
+    pub fn remove_samples(&mut self, indices: &[usize])
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        let nb_samples = self.training_inputs.as_matrix().nrows();
+        if let Some(&index) = indices.iter().find(|&&i| i >= nb_samples)
+        {
+            panic!("GaussianProcess::remove_samples: index {} is out of range for {} samples.", index, nb_samples);
+        }
+        let mut indices = indices.to_vec();
+        indices.sort_unstable();
+        indices.dedup();
+        assert!(indices.len() < nb_samples, "GaussianProcess::remove_samples: cannot remove all samples.");
+
+        self.training_inputs.remove_rows(&indices);
+        self.training_outputs.remove_entries(&indices);
+        // Downdates the Cholesky decomposition, from the last index such that the other indices are not shifted.
+        let mut covmat_l = self.covmat_cholesky.l();
+        if indices.iter().rev().all(|&index| cholesky_downdate(&mut covmat_l, index))
+        {
+            self.covmat_cholesky = Cholesky::pack_dirty(covmat_l);
+        }
+        else
+        {
+            // The result is not numerically valid.
+            self.refactorize();
+        }
+    }
+
+    /// Recomputes the Cholesky decomposition of the covariance matrix from scratch.
+    ///
+    /// This discards the rounding errors accumulated by the incremental updates of `add_samples` and `remove_samples`,
+    /// at the cost of a O(n³) operation.
+    pub fn refactorize(&mut self)
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        (self.covmat_cholesky, self.applied_jitter) =
+            make_cholesky_cov_matrix(&self.training_inputs.as_matrix(),
                                      &self.kernel,
-                                     self.noise);
+                                     self.noise,
+                                     self.cholesky_epsilon,
+                                     self.cholesky_jitter);
+    }
+
+    /// Releases the memory reserved for future samples.
+    ///
+    /// The training data grows geometrically when samples are added (see `add_samples`),
+    /// this reallocates it to its exact size which is useful once a long online learning session is over.
+    pub fn shrink_to_fit(&mut self)
+    {
+        self.training_inputs.shrink_to_fit();
+        self.training_outputs.shrink_to_fit();
+    }
+
+    /// Returns the standardization applied to the inputs, if any (see `GaussianProcessBuilder::standardize_inputs`).
+    ///
+    /// The kernel parameters (such as length scales) are expressed in the standardized space.
+    pub fn input_standardization(&self) -> Option<&InputStandardization>
+    {
+        self.input_standardization.as_ref()
+    }
+
+    /// Returns the standardization applied to the outputs, if any (see `GaussianProcessBuilder::standardize_outputs`).
+    ///
+    /// The noise, kernel and prior parameters as well as the likelihoods are expressed in the standardized space.
+    pub fn output_standardization(&self) -> Option<&OutputStandardization>
+    {
+        self.output_standardization.as_ref()
+    }
+
+    /// Brings predicted means back to the scale of the outputs, undoing the output standardization if any.
+    fn restore_means(&self, means: DVector<f64>) -> DVector<f64>
+    {
+        match &self.output_standardization
+        {
+            Some(standardization) => standardization.restore(means),
+            None => means
+        }
+    }
+
+    /// Factor bringing predicted (co)variances back to the scale of the outputs.
+    fn variance_scale(&self) -> f64
+    {
+        self.output_standardization.as_ref().map_or(1., |standardization| standardization.std().powi(2))
+    }
+
+    /// Converts inputs into a matrix, applying the input standardization if any.
+    pub(crate) fn convert_inputs<T: Input>(&self, inputs: &T) -> DMatrix<f64>
+    {
+        let inputs = T::to_dmatrix(inputs);
+        match &self.input_standardization
+        {
+            Some(standardization) => standardization.apply(inputs),
+            None => inputs
+        }
     }
 
     /// Computes the log likelihood of the current model given the training data.
@@ -219,55 +551,179 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType
         -(data_fit + complexity_penalty + normalization_constant) / 2.
     }
 
+    /// Computes the marginal log likelihood of the training data given the current model.
+    ///
+    /// Unlike `likelihood`, the log-determinant of the covariance matrix is computed exactly
+    /// (from its Cholesky decomposition) making this the quantity maximized when fitting the parameters and the standard metric to compare kernels.
+    pub fn log_marginal_likelihood(&self) -> f64
+    {
+        // formula : -1/2 (transpose(output)*cov(train,train)^-1*output + log|cov(train,train)| + size(train)*log(2*pi))
+
+        // transpose(ol)*ol = transpose(output)*cov(train,train)^-1*output
+        let output = self.training_outputs.as_vector();
+        let ol = self.covmat_cholesky
+                     .l_dirty()
+                     .solve_lower_triangular(&output)
+                     .expect("log_marginal_likelihood : solve failed");
+        let data_fit: f64 = ol.norm_squared();
+
+        // log|cov(train,train)| = 2*sum(log(diag(l)))
+        let log_determinant: f64 = 2. * self.covmat_cholesky.l_dirty().diagonal().iter().map(|d| d.ln()).sum::<f64>();
+
+        let n = output.nrows();
+        let normalization_constant = (n as f64) * (2. * std::f64::consts::PI).ln();
+
+        -(data_fit + log_determinant + normalization_constant) / 2.
+    }
+
+    /// Computes the leave-one-out cross-validation predictions: for each training point,
+    /// the mean and variance of the prediction of the model trained on all other points.
+    ///
+    /// They are computed in closed form from the current model (without any refitting) which makes them
+    /// a cheap diagnostic of the model, `loo_log_probability` being the sum of the associated log probabilities.
+    /// The variances are those of a noisy observation, they include the variance of the noise.
+    pub fn loo_predictions(&self) -> (DVector<f64>, DVector<f64>)
+    {
+        // formula (see Rasmussen & Williams, Gaussian Processes for Machine Learning, section 5.4.2):
+        // mean_i = prior_i + output_i - alpha_i / K^-1_ii
+        // variance_i = 1 / K^-1_ii
+
+        let cov_inv = self.covmat_cholesky.inverse();
+        let outputs = self.training_outputs.as_vector();
+        let alpha = &cov_inv * outputs;
+        let mut means = self.prior.prior(&self.training_inputs.as_matrix());
+        let variances = cov_inv.diagonal().map(|cov_inv_diag| 1. / cov_inv_diag);
+        for i in 0..means.nrows()
+        {
+            means[i] += outputs[i] - alpha[i] * variances[i];
+        }
+        (self.restore_means(means), variances * self.variance_scale())
+    }
+
+    /// Computes the leave-one-out cross-validation log likelihood of the training data given the current model.
+    ///
+    /// This is the sum, over all training points, of the log probability of the point
+    /// under the model trained on all other points (see `loo_predictions` for the associated predictions).
+    /// It is an alternative to the marginal likelihood for model selection
+    /// that is more robust to a misspecified kernel.
+    pub fn loo_log_likelihood(&self) -> f64
+    {
+        // formula (see Rasmussen & Williams, Gaussian Processes for Machine Learning, section 5.4.2):
+        // mean_i = output_i - alpha_i / K^-1_ii
+        // variance_i = 1 / K^-1_ii
+        // sum_i -1/2 log(variance_i) - (output_i - mean_i)² / (2 variance_i) - 1/2 log(2 pi)
+        // K = cov(train,train)
+        // alpha = K^-1 * output
+
+        let cov_inv = self.covmat_cholesky.inverse();
+        let alpha = &cov_inv * self.training_outputs.as_vector();
+        let log_2_pi = (2. * std::f64::consts::PI).ln();
+        alpha.iter()
+             .zip(cov_inv.diagonal().iter())
+             .map(|(alpha, cov_inv_diag)| (cov_inv_diag.ln() - alpha * alpha / cov_inv_diag - log_2_pi) / 2.)
+             .sum()
+    }
+
+    /// Computes the sum, over all training points, of the log density of the leave-one-out prediction
+    /// (see `loo_predictions`) at the training output.
+    ///
+    /// It is equal to `loo_log_likelihood` unless the outputs are standardized,
+    /// in which case the densities are expressed at the scale of the outputs (rather than the standardized scale)
+    /// such that they can be compared between models.
+    pub fn loo_log_probability(&self) -> f64
+    {
+        let nb_samples = self.training_outputs.as_vector().nrows() as f64;
+        self.loo_log_likelihood() - nb_samples * self.variance_scale().ln() / 2.
+    }
+
     //----------------------------------------------------------------------------------------------
     // PREDICT
 
     /// Makes a prediction (the mean of the gaussian process) for each row of the input.
+    ///
+    /// With the `rayon` feature, large batches of inputs are predicted by chunks on the rayon thread pool.
     pub fn predict<T: Input>(&self, inputs: &T) -> T::OutVector
+        where DefaultBuilder: CovarianceBuilder<KernelType>
     {
         // formula : prior + cov(input,train)*cov(train,train)^-1 * output
 
-        let inputs = T::to_dmatrix(inputs);
+        let inputs = self.convert_inputs(inputs);
         assert_eq!(inputs.ncols(), self.training_inputs.as_matrix().ncols());
+        let training_inputs = self.training_inputs.as_matrix();
+        let training_outputs = self.training_outputs.as_vector();
+        let covmat_cholesky = &self.covmat_cholesky;
 
-        // computes weights to give each training sample
-        let mut weights = make_covariance_matrix(&self.training_inputs.as_matrix(), &inputs, &self.kernel);
-        self.covmat_cholesky.solve_mut(&mut weights);
+        // weights.transpose() * &self.training_outputs, for each chunk of inputs
+        let deviations = DefaultBuilder::map_row_chunks(&inputs, &self.kernel, |kernel, chunk| {
+            // computes weights to give each training sample
+            let mut weights = make_covariance_matrix(&training_inputs, &chunk, kernel);
+            covmat_cholesky.solve_mut(&mut weights);
+            weights.tr_mul(&training_outputs)
+        });
 
-        // computes prior for the given inputs
-        let mut prior = self.prior.prior(&inputs);
+        // adds the prior for the given inputs
+        let means = self.prior.prior(&inputs) + concat_vectors(&deviations);
 
-        // weights.transpose() * &self.training_outputs + prior
-        prior.gemm_tr(1f64, &weights, &self.training_outputs.as_vector(), 1f64);
+        T::from_dvector(&self.restore_means(means))
+    }
+
+    /// Predicts the prior of the gaussian process (its mean in the absence of training data) for each row of the input.
+    ///
+    /// When the outputs are standardized, it is expressed at the scale of the outputs and includes their mean.
+    /// This is the constant term of the decompositions of the mean given by `predict_dimension` and `predict_order`.
+    pub fn predict_prior<T: Input>(&self, inputs: &T) -> T::OutVector
+    {
+        let inputs = self.convert_inputs(inputs);
+        assert_eq!(inputs.ncols(), self.training_inputs.as_matrix().ncols());
+        T::from_dvector(&self.restore_means(self.prior.prior(&inputs)))
+    }
 
-        T::from_dvector(&prior)
+    /// Fallible version of `predict`, returns an error rather than panicking
+    /// if the inputs do not have the dimension of the training inputs
+    /// or if the kernel is not defined for the inputs (see `Kernel::validate_inputs`).
+    pub fn try_predict<T: Input>(&self, inputs: &T) -> Result<T::OutVector, FriedrichError>
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        let inputs = T::to_dmatrix(inputs);
+        let nb_dimensions = self.training_inputs.as_matrix().ncols();
+        if inputs.ncols() != nb_dimensions
+        {
+            return Err(FriedrichError::DimensionMismatch { expected: nb_dimensions, found: inputs.ncols() });
+        }
+        self.kernel.validate_inputs(&self.convert_inputs(&inputs))?;
+        let prediction = self.predict(&inputs);
+        Ok(T::from_dvector(&prediction))
     }
 
     /// Predicts the variance of the gaussian process for each row of the input.
     /// This quantity (and its square root) can be used as a proxy for the uncertainty of the prediction.
+    ///
+    /// With the `rayon` feature, large batches of inputs are predicted by chunks on the rayon thread pool.
     pub fn predict_variance<T: Input>(&self, inputs: &T) -> T::OutVector
+        where DefaultBuilder: CovarianceBuilder<KernelType>
     {
         // formula, diagonal of : cov(input,input) - cov(input,train)*cov(train,train)^-1*cov(train,input)
 
-        let inputs = T::to_dmatrix(inputs);
+        let inputs = self.convert_inputs(inputs);
         assert_eq!(inputs.ncols(), self.training_inputs.as_matrix().ncols());
+        let training_inputs = self.training_inputs.as_matrix();
+        let covmat_l = self.covmat_cholesky.l();
 
-        // compute the covariances
-        let cov_train_inputs =
-            make_covariance_matrix(&self.training_inputs.as_matrix(), &inputs, &self.kernel);
+        let variances = DefaultBuilder::map_row_chunks(&inputs, &self.kernel, |kernel, chunk| {
+            // compute the covariances
+            let cov_train_inputs = make_covariance_matrix(&training_inputs, &chunk, kernel);
 
-        // solve linear system
-        let kl = self.covmat_cholesky
-                     .l()
-                     .solve_lower_triangular(&cov_train_inputs)
-                     .expect("predict_covariance : solve failed");
+            // solve linear system
+            let kl = covmat_l.solve_lower_triangular(&cov_train_inputs).expect("predict_covariance : solve failed");
 
-        // (cov_inputs_inputs - (kl.transpose() * kl)).diagonal()
-        let variances = inputs.row_iter()
-                              .map(|row| self.kernel.kernel(&row, &row)) // variance of input points with themselves
-                              .zip(kl.column_iter().map(|col| col.norm_squared())) // diag(kl^T * kl)
-                              .map(|(base_cov, predicted_cov)| base_cov - predicted_cov);
-        let variances = DVector::<f64>::from_iterator(inputs.nrows(), variances);
+            // (cov_inputs_inputs - (kl.transpose() * kl)).diagonal()
+            let variances = chunk.row_iter()
+                                 .map(|row| kernel.kernel(&row, &row)) // variance of input points with themselves
+                                 .zip(kl.column_iter().map(|col| col.norm_squared())) // diag(kl^T * kl)
+                                 .map(|(base_cov, predicted_cov)| base_cov - predicted_cov);
+            DVector::<f64>::from_iterator(chunk.nrows(), variances)
+        });
+        let variances = concat_vectors(&variances) * self.variance_scale();
 
         T::from_dvector(&variances)
     }
@@ -288,49 +744,98 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType
     /// # }
     /// ```
     pub fn predict_mean_variance<T: Input>(&self, inputs: &T) -> (T::OutVector, T::OutVector)
+        where DefaultBuilder: CovarianceBuilder<KernelType>
     {
-        let inputs = T::to_dmatrix(inputs);
+        let inputs = self.convert_inputs(inputs);
         assert_eq!(inputs.ncols(), self.training_inputs.as_matrix().ncols());
+        let training_inputs = self.training_inputs.as_matrix();
+        let training_outputs = self.training_outputs.as_vector();
+        let covmat_cholesky = &self.covmat_cholesky;
 
-        // computes weights to give each training sample
-        let cov_train_inputs =
-            make_covariance_matrix(&self.training_inputs.as_matrix(), &inputs, &self.kernel);
-        let weights = self.covmat_cholesky.solve(&cov_train_inputs);
+        let (deviations, variances): (Vec<_>, Vec<_>) =
+            DefaultBuilder::map_row_chunks(&inputs, &self.kernel, |kernel, chunk| {
+                // computes weights to give each training sample
+                let cov_train_inputs = make_covariance_matrix(&training_inputs, &chunk, kernel);
+                let weights = covmat_cholesky.solve(&cov_train_inputs);
 
-        // ----- mean -----
+                // weights.transpose() * &self.training_outputs
+                let deviations = weights.tr_mul(&training_outputs);
 
-        // computes prior for the given inputs
-        let mut prior = self.prior.prior(&inputs);
+                // (cov_inputs_inputs - cov_train_inputs.transpose() * weights).diagonal()
+                let mut variances = DVector::<f64>::zeros(chunk.nrows());
+                for (i, input) in chunk.row_iter().enumerate()
+                {
+                    let base_cov = kernel.kernel(&input, &input);
+                    let predicted_cov = cov_train_inputs.column(i).dot(&weights.column(i));
+                    variances[i] = base_cov - predicted_cov;
+                }
+                (deviations, variances)
+            }).into_iter()
+              .unzip();
 
-        // weights.transpose() * &self.training_outputs + prior
-        prior.gemm_tr(1f64, &weights, &self.training_outputs.as_vector(), 1f64);
+        // ----- mean -----
 
-        let mean = T::from_dvector(&prior);
+        // adds the prior for the given inputs
+        let means = self.prior.prior(&inputs) + concat_vectors(&deviations);
+        let mean = T::from_dvector(&self.restore_means(means));
 
         // ----- variance -----
 
-        // (cov_inputs_inputs - cov_train_inputs.transpose() * weights).diagonal()
-        let mut variances = DVector::<f64>::zeros(inputs.nrows());
-        for (i, input) in inputs.row_iter().enumerate()
-        {
-            let base_cov = self.kernel.kernel(&input, &input);
-            let predicted_cov = cov_train_inputs.column(i).dot(&weights.column(i));
-            variances[i] = base_cov - predicted_cov;
-        }
-
-        let variance = T::from_dvector(&variances);
+        let variance = T::from_dvector(&(concat_vectors(&variances) * self.variance_scale()));
 
         // ----- result -----
 
         (mean, variance)
     }
 
+    /// Predicts the standard deviation of the gaussian process for each row of the input.
+    ///
+    /// Numerical errors can produce tiny negative variances close to the training samples,
+    /// those are clamped to zero before taking the square root.
+    pub fn predict_std<T: Input>(&self, inputs: &T) -> T::OutVector
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        let variances = self.predict_variance(&T::to_dmatrix(inputs));
+        let stds = variances.map(|variance| variance.max(0.).sqrt());
+        T::from_dvector(&stds)
+    }
+
+    /// Predicts a confidence interval for each row of the input,
+    /// the `level` (such as `0.95`) being the probability that the process falls between the lower and upper bounds.
+    ///
+    /// The interval is centered on the mean and its bounds are placed using the quantiles of the normal distribution.
+    ///
+    /// ```rust
+    /// # use friedrich::gaussian_process::GaussianProcess;
+    /// # fn main() {
+    /// # let training_inputs = vec![vec![0.8], vec![1.2], vec![3.8], vec![4.2]];
+    /// # let training_outputs = vec![3.0, 4.0, -2.0, -2.0];
+    /// let gp = GaussianProcess::default(training_inputs, training_outputs);
+    /// let input = vec![1.];
+    /// let (lower, upper) = gp.predict_interval(&input, 0.95);
+    /// println!("prediction in [{}, {}] with 95% probability", lower, upper);
+    /// # }
+    /// ```
+    pub fn predict_interval<T: Input>(&self, inputs: &T, level: f64) -> (T::OutVector, T::OutVector)
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        assert!(level > 0. && level < 1., "predict_interval: the level should be in (0, 1), got {}.", level);
+        let quantile = normal_quantile((1. + level) / 2.);
+        let (means, variances) = self.predict_mean_variance(&T::to_dmatrix(inputs));
+        let half_widths = variances.map(|variance| quantile * variance.max(0.).sqrt());
+        (T::from_dvector(&(&means - &half_widths)), T::from_dvector(&(means + half_widths)))
+    }
+
     /// Returns the covariance matrix for the rows of the input.
+    ///
+    /// This is the full posterior covariance `K** - K*^T K^-1 K*` between all inputs,
+    /// computed with the Cholesky decomposition of the training covariance matrix rather than its inverse.
+    /// Its diagonal is the output of `predict_variance`.
     pub fn predict_covariance<T: Input>(&self, inputs: &T) -> DMatrix<f64>
     {
         // formula : cov(input,input) - cov(input,train)*cov(train,train)^-1*cov(train,input)
 
-        let inputs = T::to_dmatrix(inputs);
+        let inputs = self.convert_inputs(inputs);
         assert_eq!(inputs.ncols(), self.training_inputs.as_matrix().ncols());
 
         // compute the covariances
@@ -346,7 +851,38 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType
 
         // cov_inputs_inputs - (kl.transpose() * kl)
         cov_inputs_inputs.gemm_tr(-1f64, &kl, &kl, 1f64);
-        cov_inputs_inputs
+        cov_inputs_inputs * self.variance_scale()
+    }
+
+    /// Predicts both the mean and the full covariance matrix of the gaussian process for the rows of the input.
+    ///
+    /// Unlike `predict_mean_variance`, the covariance between the inputs is kept which is needed to produce joint samples
+    /// or to integrate over the inputs.
+    /// Faster than calling `predict` and `predict_covariance` separately.
+    pub fn predict_joint<T: Input>(&self, inputs: &T) -> (T::OutVector, DMatrix<f64>)
+    {
+        // formula :
+        // mean = prior + cov(input,train)*cov(train,train)^-1 * output
+        // cov = cov(input,input) - cov(input,train)*cov(train,train)^-1*cov(train,input)
+
+        let inputs = self.convert_inputs(inputs);
+        assert_eq!(inputs.ncols(), self.training_inputs.as_matrix().ncols());
+
+        // computes weights to give each training sample
+        let cov_train_inputs =
+            make_covariance_matrix(&self.training_inputs.as_matrix(), &inputs, &self.kernel);
+        let weights = self.covmat_cholesky.solve(&cov_train_inputs);
+
+        // computes the mean
+        let mut prior = self.prior.prior(&inputs);
+        prior.gemm_tr(1f64, &weights, &self.training_outputs.as_vector(), 1f64);
+        let mean = T::from_dvector(&self.restore_means(prior));
+
+        // computes the covariance
+        let mut cov_inputs_inputs = make_covariance_matrix(&inputs, &inputs, &self.kernel);
+        cov_inputs_inputs.gemm_tr(-1f64, &cov_train_inputs, &weights, 1f64);
+
+        (mean, cov_inputs_inputs * self.variance_scale())
     }
 
     /// Produces a multivariate gaussian that can be used to sample at the input points.
@@ -370,7 +906,7 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType
     /// ```
     pub fn sample_at<T: Input>(&self, inputs: &T) -> MultivariateNormal<T>
     {
-        let inputs = T::to_dmatrix(inputs);
+        let inputs = self.convert_inputs(inputs);
         assert_eq!(inputs.ncols(), self.training_inputs.as_matrix().ncols());
 
         // compute the weights
@@ -381,16 +917,49 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType
         // computes covariance
         let mut cov_inputs_inputs = make_covariance_matrix(&inputs, &inputs, &self.kernel);
         cov_inputs_inputs.gemm_tr(-1f64, &cov_train_inputs, &weights, 1f64);
-        let cov = cov_inputs_inputs;
+        let cov = cov_inputs_inputs * self.variance_scale();
 
         // computes the mean
         let mut prior = self.prior.prior(&inputs);
         prior.gemm_tr(1f64, &weights, &self.training_outputs.as_vector(), 1f64);
-        let mean = prior;
+        let mean = self.restore_means(prior);
 
         MultivariateNormal::new(mean, cov)
     }
 
+    /// Draws `nb_samples` joint samples from the posterior distribution of the process at the rows of the input.
+    ///
+    /// Returns a matrix with one column per sample (and one row per input), usable for Monte Carlo integration.
+    /// When the posterior covariance is near-singular (for example, when inputs are very close to each other),
+    /// `cholesky_epsilon` plays the same role as the `cholesky_epsilon` of the process: it is used in place
+    /// of the diagonal term during the Cholesky decomposition if and only if the decomposition would otherwise fail.
+    ///
+    /// ```rust
+    /// # use friedrich::gaussian_process::GaussianProcess;
+    /// # fn main() {
+    /// # let training_inputs = vec![vec![0.8], vec![1.2], vec![3.8], vec![4.2]];
+    /// # let training_outputs = vec![3.0, 4.0, -2.0, -2.0];
+    /// # let gp = GaussianProcess::default(training_inputs, training_outputs);
+    /// let new_inputs = vec![vec![1.], vec![2.], vec![3.]];
+    /// let mut rng = rand::thread_rng();
+    /// let samples = gp.sample_posterior(&new_inputs, 100, Some(1e-10), &mut rng);
+    /// assert_eq!(samples.shape(), (3, 100));
+    /// # }
+    /// ```
+    pub fn sample_posterior<T: Input, RNG: Rng>(&self,
+                                                inputs: &T,
+                                                nb_samples: usize,
+                                                cholesky_epsilon: Option<f64>,
+                                                rng: &mut RNG)
+                                                -> DMatrix<f64>
+    {
+        let inputs = T::to_dmatrix(inputs);
+        let (mean, covariance) = self.predict_joint(&inputs);
+        let distribution =
+            MultivariateNormal::<DMatrix<f64>>::with_cholesky_epsilon(mean, covariance, cholesky_epsilon);
+        distribution.sample_many(nb_samples, rng)
+    }
+
     //----------------------------------------------------------------------------------------------
     // FIT
 
@@ -400,47 +969,2461 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType
     /// It runs for a maximum of `max_iter` iterations and stops prematurely if all gradients are below `convergence_fraction` time their associated parameter
     /// or if it runs for more than `max_time`.
     ///
-    /// Good default values for `max_iter`, `convergence_fraction` and `max_time` are `100`, `0.05` and `chrono::Duration::seconds(3600)` (one hour)
+    /// Good default values for `max_iter`, `convergence_fraction` and `max_time` are `100`, `0.05` and `std::time::Duration::from_secs(3600)` (one hour)
     ///
     /// Note that, if the `noise` parameter ends up unnaturally large after the fit, it is a good sign that the kernel is unadapted to the data.
-    pub fn fit_parameters(
-        &mut self,
-        fit_prior: bool,
-        fit_kernel: bool,
-        max_iter: usize,
-        convergence_fraction: f64,
-        max_time: Duration,
-    ) {
-        if fit_prior {
-            // Gets the original data back in order to update the prior.
-            let training_outputs =
-                self.training_outputs.as_vector() + self.prior.prior(&self.training_inputs.as_matrix());
-            self.prior.fit(&self.training_inputs.as_matrix(), &training_outputs);
-            let training_outputs = training_outputs - self.prior.prior(&self.training_inputs.as_matrix());
-            self.training_outputs.assign(&training_outputs);
-            // NOTE: Adding and subtracting each time we fit a prior might be numerically unwise.
+    /// If the kernel contains a `WhiteNoise` component, it competes with the `noise` parameter
+    /// (only their sum matters) and the fitted noise is split between the two.
+    pub fn fit_parameters(&mut self,
+                          fit_prior: bool,
+                          fit_kernel: bool,
+                          max_iter: usize,
+                          convergence_fraction: f64,
+                          max_time: Duration)
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        let config = FitConfig { max_iter, convergence_fraction, max_time, ..FitConfig::default() };
+        self.fit_parameters_with_config(fit_prior, fit_kernel, &config);
+    }
 
-            if !fit_kernel
-            {
-                // Retrains model from scratch.
-                self.covmat_cholesky = make_cholesky_cov_matrix(&self.training_inputs.as_matrix(),
-                                                                &self.kernel,
-                                                                self.noise,
-                                                                self.cholesky_epsilon);
-            }
-        }
+    /// Fits the kernel and noise parameters and retrains the model, using the L-BFGS algorithm rather than ADAM.
+    ///
+    /// L-BFGS builds an approximation of the curvature of the objective which makes it converge in far fewer
+    /// iterations than ADAM on ill-conditioned problems (see `Optimizer::Lbfgs`).
+    /// It runs for a maximum of `max_iter` iterations and stops prematurely if all parameters change by less than
+    /// `convergence_fraction` time their value during an iteration.
+    /// Use `Optimizer::Lbfgs` with `fit_parameters_with_config` to also fit the prior or limit the runtime.
+    pub fn optimize_parameters_lbfgs(&mut self, max_iter: usize, convergence_fraction: f64)
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        let config = FitConfig { max_iter, convergence_fraction, optimizer: Optimizer::Lbfgs, ..FitConfig::default() };
+        self.fit_parameters_with_config(false, true, &config);
+    }
 
-        // Fit kernel and retrains model from scratch.
-        if fit_kernel
-        {
-            if self.kernel.is_scalable()
-            {
-                self.scaled_optimize_parameters(max_iter, convergence_fraction, max_time);
-            }
-            else
-            {
-                self.optimize_parameters(max_iter, convergence_fraction, max_time);
-            }
-        }
+    /// Fits the kernel and noise parameters and retrains the model, using the Nelder-Mead simplex algorithm.
+    ///
+    /// The algorithm maximizes the marginal log likelihood using only evaluations of the likelihood
+    /// (see `Optimizer::NelderMead`), it is thus usable with kernels whose gradient is expensive or imprecise.
+    /// It runs for a maximum of `max_iter` iterations and stops prematurely if all vertices of the simplex are within
+    /// `convergence_fraction` time the value of the best parameters.
+    /// The prior is not refitted (as with `fit_parameters(false, true)`),
+    /// use `Optimizer::NelderMead` with `fit_parameters_with_config` to also fit the prior or limit the runtime.
+    pub fn optimize_parameters_simplex(&mut self, max_iter: usize, convergence_fraction: f64)
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        let config =
+            FitConfig { max_iter, convergence_fraction, optimizer: Optimizer::NelderMead, ..FitConfig::default() };
+        self.fit_parameters_with_config(false, true, &config);
+    }
+
+    /// Fits the kernel and noise parameters and retrains the model, restarting the optimizer `nb_restarts` times.
+    ///
+    /// After a first run from the current parameters, each restart starts from random parameters
+    /// (drawn log-uniformly between a tenth and ten times the current parameters, see `FitConfig::nb_restarts`)
+    /// and the parameters with the highest marginal log likelihood are kept.
+    /// The random parameters are deterministic given the `seed`,
+    /// restarts for which the covariance matrix stops being positive definite are skipped.
+    ///
+    /// `max_iter`, `convergence_fraction` and `max_time` apply to each run of the optimizer.
+    pub fn fit_with_restarts(&mut self,
+                             nb_restarts: usize,
+                             seed: u64,
+                             max_iter: usize,
+                             convergence_fraction: f64,
+                             max_time: Duration)
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        let config = FitConfig { max_iter, convergence_fraction, max_time, nb_restarts, seed, ..FitConfig::default() };
+        self.fit_parameters_with_config(false, true, &config);
+    }
+
+    /// Fits the requested parameters and retrains the model, running the optimizer from `nb_starts` initial parameters.
+    ///
+    /// The first run starts from the current parameters while the others start from random parameters
+    /// (drawn log-uniformly between a tenth and ten times the current parameters, see `FitConfig::nb_restarts`),
+    /// the parameters with the highest marginal log likelihood are kept.
+    /// This makes the fit robust to the local optima of the likelihood, at the price of a longer runtime.
+    ///
+    /// `max_iter`, `convergence_fraction` and `max_time` apply to each run of the optimizer.
+    /// With the `rayon` feature, the runs starting from random parameters are done concurrently.
+    pub fn fit_parameters_multistart(&mut self,
+                                     nb_starts: usize,
+                                     max_iter: usize,
+                                     convergence_fraction: f64,
+                                     max_time: Duration)
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        assert!(nb_starts > 0, "fit_parameters_multistart: at least one start is needed.");
+        let config = FitConfig { max_iter,
+                                 convergence_fraction,
+                                 max_time,
+                                 nb_restarts: nb_starts - 1,
+                                 ..FitConfig::default() };
+        self.fit_parameters_with_config(false, true, &config);
+    }
+
+    /// Fits the requested parameters and retrains the model, keeping the kernel parameters and noise within bounds.
+    ///
+    /// `lower` and `upper` contain the bounds of each kernel parameter (in the order of `kernel.get_parameters()`)
+    /// followed by the bounds of the noise, in the natural space of the parameters.
+    /// Parameters are projected back into the bounds after each step of the optimizer
+    /// which can be used, for example, to keep the noise high enough for the covariance matrix to stay well conditioned.
+    ///
+    /// The other parameters of the optimizer take their default values (see `FitConfig`),
+    /// use `ParameterBounds` with `fit_parameters_with_config` to modify them.
+    ///
+    /// Returns an error, without fitting anything, if the bounds do not have one element per parameter
+    /// or if a lower bound is not strictly smaller than its upper bound
+    /// (and an error if the fit fails, see `try_fit_parameters_with_config`).
+    pub fn fit_parameters_with_bounds(&mut self,
+                                      fit_prior: bool,
+                                      fit_kernel: bool,
+                                      lower: &[f64],
+                                      upper: &[f64])
+                                      -> Result<(), FriedrichError>
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        let bounds = ParameterBounds::new(lower.to_vec(), upper.to_vec())?;
+        let nb_parameters = self.kernel.nb_parameters() + 1;
+        if bounds.len() != nb_parameters
+        {
+            return Err(FriedrichError::DimensionMismatch { expected: nb_parameters, found: bounds.len() });
+        }
+        let config = FitConfig { bounds: Some(bounds), ..FitConfig::default() };
+        self.try_fit_parameters_with_config(fit_prior, fit_kernel, &config)?;
+        Ok(())
+    }
+
+    /// Fits the requested parameters and retrains the model, using the given configuration for the optimizer.
+    ///
+    /// See `FitConfig` for the available options (such as the use of the L-BFGS algorithm instead of ADAM):
+    ///
+    /// ```rust
+    /// # use friedrich::gaussian_process::{FitConfig, GaussianProcess, Optimizer};
+    /// # let training_inputs = vec![vec![0.8], vec![1.2], vec![3.8], vec![4.2]];
+    /// # let training_outputs = vec![3.0, 4.0, -2.0, -2.0];
+    /// let mut gp = GaussianProcess::default(training_inputs, training_outputs);
+    /// let config = FitConfig { optimizer: Optimizer::Lbfgs, ..FitConfig::default() };
+    /// gp.fit_parameters_with_config(false, true, &config);
+    /// ```
+    ///
+    /// Returns the trace of the optimizer, one `OptimStep` per iteration, if `config.record_trace` is true
+    /// (the trace is empty otherwise, or if the kernel is not fitted).
+    pub fn fit_parameters_with_config(&mut self,
+                                      fit_prior: bool,
+                                      fit_kernel: bool,
+                                      config: &FitConfig)
+                                      -> Vec<OptimStep>
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        self.try_fit_parameters_with_config(fit_prior, fit_kernel, config)
+            .unwrap_or_else(|error| panic!("fit_parameters_with_config: {}", error))
+    }
+
+    /// Fallible version of `fit_parameters_with_config`, returns an error rather than panicking
+    /// if the bounds or hyperpriors do not cover all kernel parameters followed by the noise
+    /// (or if a fixed parameter is not one of them), if the bounds exclude the sign of a fitted parameter,
+    /// if the fit of the prior fails, if the covariance matrix is not positive definite once the prior is fitted
+    /// or if the covariance matrix stops being positive definite during the fit of the kernel
+    /// (which can be prevented with a `cholesky_epsilon` or a lower bound on the noise).
+    ///
+    /// The model is left unchanged when the configuration is invalid or when the fit of the prior fails.
+    /// When the covariance matrix stops being positive definite, the kernel and noise go back to their initial values.
+    pub fn try_fit_parameters_with_config(&mut self,
+                                          fit_prior: bool,
+                                          fit_kernel: bool,
+                                          config: &FitConfig)
+                                          -> Result<Vec<OptimStep>, FriedrichError>
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        self.fit_parameters_using::<DefaultBuilder>(fit_prior, fit_kernel, config)
+    }
+
+    /// Fits the requested parameters, building the covariance matrices with the given `CovarianceBuilder`.
+    fn fit_parameters_using<B: CovarianceBuilder<KernelType>>(&mut self,
+                                                              fit_prior: bool,
+                                                              fit_kernel: bool,
+                                                              config: &FitConfig)
+                                                              -> Result<Vec<OptimStep>, FriedrichError>
+    {
+        // Checks the configuration before modifying the model.
+        if fit_kernel
+        {
+            let mut parameters = self.kernel.get_parameters();
+            parameters.push(self.noise);
+            config.validate(&parameters)?;
+        }
+
+        if fit_prior
+        {
+            // Gets the original data back in order to update the prior.
+            let training_outputs =
+                self.training_outputs.as_vector() + self.prior.prior(&self.training_inputs.as_matrix());
+            self.prior.try_fit(&self.training_inputs.as_matrix(), &training_outputs)?;
+            let training_outputs = training_outputs - self.prior.prior(&self.training_inputs.as_matrix());
+            self.training_outputs.assign(&training_outputs);
+            // NOTE: Adding and subtracting each time we fit a prior might be numerically unwise.
+
+            if !fit_kernel
+            {
+                // Retrains model from scratch.
+                self.retrain::<B>()?;
+            }
+        }
+
+        // Fit kernel and retrains model from scratch.
+        if fit_kernel
+        {
+            self.optimize_with_restarts::<B>(config)
+        }
+        else
+        {
+            Ok(Vec::new())
+        }
+    }
+}
+
+/// Concatenates the predictions of consecutive chunks of inputs.
+fn concat_vectors(vectors: &[DVector<f64>]) -> DVector<f64>
+{
+    DVector::from_iterator(vectors.iter().map(|v| v.nrows()).sum(), vectors.iter().flat_map(|v| v.iter().copied()))
+}
+
+impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<kernel::Additive<KernelType>, PriorType>
+{
+    /// Predicts the contribution of a single input dimension to the mean of the process, for each row of the input.
+    ///
+    /// With an additive kernel, the mean of the process is the prior plus a sum of one-dimensional functions,
+    /// this returns the function associated with the given dimension which is useful to interpret the model.
+    /// Summing the contributions of all dimensions and the prior (see `predict_prior`)
+    /// gives back the output of `predict`.
+    /// When the outputs are standardized, the contributions are expressed at the scale of the outputs
+    /// (the mean of the outputs being part of the prior).
+    pub fn predict_dimension<T: Input>(&self, inputs: &T, dimension: usize) -> T::OutVector
+    {
+        let inputs = self.convert_inputs(inputs);
+        let nb_dimensions = self.training_inputs.as_matrix().ncols();
+        assert_eq!(inputs.ncols(), nb_dimensions);
+        assert!(dimension < nb_dimensions,
+                "predict_dimension: dimension {} is out of range for inputs of dimension {}.",
+                dimension,
+                nb_dimensions);
+
+        // covariance, along the given dimension, between the training samples and the inputs
+        let kernel = self.kernel.kernel_of_dimension(dimension, nb_dimensions);
+        let cov_train_inputs = make_covariance_matrix(&self.training_inputs.as_matrix().columns(dimension, 1),
+                                                      &inputs.columns(dimension, 1),
+                                                      kernel);
+
+        // cov(input,train)*cov(train,train)^-1 * output
+        let weights = self.covmat_cholesky.solve(&self.training_outputs.as_vector());
+        let contribution = cov_train_inputs.tr_mul(&weights) * self.variance_scale().sqrt();
+        T::from_dvector(&contribution)
+    }
+}
+
+impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<kernel::Anova<KernelType>, PriorType>
+{
+    /// Predicts the contribution of an order of the ANOVA kernel to the mean of the process, for each row of the input:
+    /// 1 for the main effects and 2 for the two-way interactions.
+    ///
+    /// Comparing the magnitude of both contributions tells how much of the signal is explained by interactions.
+    /// Summing the contributions of both orders and the prior (see `predict_prior`) gives back the output of `predict`.
+    /// When the outputs are standardized, the contributions are expressed at the scale of the outputs
+    /// (the mean of the outputs being part of the prior).
+    pub fn predict_order<T: Input>(&self, inputs: &T, order: usize) -> T::OutVector
+    {
+        let inputs = self.convert_inputs(inputs);
+        let training_inputs = self.training_inputs.as_matrix();
+        assert_eq!(inputs.ncols(), training_inputs.ncols());
+
+        // covariance, restricted to the given order, between the training samples and the inputs
+        let cov_train_inputs = DMatrix::<f64>::from_fn(training_inputs.nrows(), inputs.nrows(), |r, c| {
+            self.kernel.order_kernel(order, &training_inputs.row(r), &inputs.row(c))
+        });
+
+        // cov(input,train)*cov(train,train)^-1 * output
+        let weights = self.covmat_cholesky.solve(&self.training_outputs.as_vector());
+        let contribution = cov_train_inputs.tr_mul(&weights) * self.variance_scale().sqrt();
+        T::from_dvector(&contribution)
+    }
+}
+
+impl<PriorType: Prior> GaussianProcess<kernel::Precomputed, PriorType>
+{
+    /// Computes the covariance between the training samples and new points
+    /// given the similarities of the new points with the points of the Gram matrix (one row per new point).
+    fn covariance_from_similarities(&self, similarities: &DMatrix<f64>) -> DMatrix<f64>
+    {
+        assert_eq!(similarities.ncols(),
+                   self.kernel.nb_points(),
+                   "Precomputed: the similarities should have one column per point of the Gram matrix.");
+        let training_inputs = self.training_inputs.as_matrix();
+        DMatrix::<f64>::from_fn(training_inputs.nrows(), similarities.nrows(), |r, c| {
+            similarities[(c, training_inputs[(r, 0)] as usize)]
+        })
+    }
+
+    /// Predicts the variance of the gaussian process for points that are not in the Gram matrix
+    /// given their `similarities` with the points of the Gram matrix (one row per new point)
+    /// and their `self_similarities` (the similarity of each new point with itself).
+    pub fn predict_variance_with_similarities(&self,
+                                              similarities: &DMatrix<f64>,
+                                              self_similarities: &DVector<f64>)
+                                              -> DVector<f64>
+    {
+        // formula, diagonal of : cov(input,input) - cov(input,train)*cov(train,train)^-1*cov(train,input)
+        assert_eq!(self_similarities.nrows(), similarities.nrows());
+        let kl = self.covmat_cholesky
+                     .l()
+                     .solve_lower_triangular(&self.covariance_from_similarities(similarities))
+                     .expect("predict_variance_with_similarities : solve failed");
+        DVector::<f64>::from_fn(similarities.nrows(), |r, _| self_similarities[r] - kl.column(r).norm_squared())
+            * self.variance_scale()
+    }
+}
+
+impl<PriorType: ConstantValuedPrior> GaussianProcess<kernel::Precomputed, PriorType>
+{
+    /// Makes a prediction (the mean of the gaussian process) for points that are not in the Gram matrix
+    /// given their `similarities` with the points of the Gram matrix (one row per new point).
+    ///
+    /// New points have no input the prior could be evaluated on, the prior thus has to be constant.
+    pub fn predict_with_similarities(&self, similarities: &DMatrix<f64>) -> DVector<f64>
+    {
+        // formula : prior + cov(input,train)*cov(train,train)^-1 * output
+        let mut weights = self.covariance_from_similarities(similarities);
+        self.covmat_cholesky.solve_mut(&mut weights);
+
+        let mut prior = DVector::from_element(similarities.nrows(), self.prior.value());
+        prior.gemm_tr(1f64, &weights, &self.training_outputs.as_vector(), 1f64);
+        self.restore_means(prior)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    #[cfg(feature = "rayon")]
+    use crate::algebra::Parallel;
+    use crate::algebra::Serial;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn one_dimensional_constructor_matches_nested_vectors()
+    {
+        let training_inputs = vec![0.8, 1.2, 3.8, 4.2, 5.1];
+        let training_outputs = vec![3.0, 4.0, -2.0, -2.0, 0.5];
+        let gp = GaussianProcess::default_one_dimensional(training_inputs.clone(), training_outputs.clone());
+        let nested_inputs: Vec<Vec<f64>> = training_inputs.iter().map(|&x| vec![x]).collect();
+        let expected_gp = GaussianProcess::default(nested_inputs, training_outputs);
+        let inputs = vec![vec![1.], vec![2.5], vec![4.7]];
+        assert_eq!(gp.predict(&inputs), expected_gp.predict(&inputs));
+    }
+
+    #[test]
+    #[should_panic(expected = "Vec<Vec<f64>>: sample 2 has 1 elements but sample 0 has 2.")]
+    fn inconsistent_sample_lengths_are_rejected()
+    {
+        let training_inputs = vec![vec![0.8, 1.], vec![1.2, 0.], vec![3.8]];
+        GaussianProcess::default(training_inputs, vec![3.0, 4.0, -2.0]);
+    }
+
+    #[test]
+    fn precomputed_kernel_matches_the_kernel_it_was_computed_with()
+    {
+        let kernel = kernel::SquaredExp::new(0.7, 1.3);
+        let training_inputs = DMatrix::from_fn(12, 1, |i, _| i as f64 * 0.4);
+        let training_outputs = training_inputs.column(0).map(|x| x.sin());
+        let inputs = DMatrix::from_row_slice(3, 1, &[0.5, 2.1, 5.3]);
+        let gp = GaussianProcess::new(prior::ZeroPrior {},
+                                      kernel,
+                                      0.1,
+                                      None,
+                                      training_inputs.clone(),
+                                      training_outputs.clone());
+
+        // The inputs of the precomputed kernel are the indices of the training points in the Gram matrix.
+        let gram = make_covariance_matrix(&training_inputs, &training_inputs, &kernel);
+        let indices = DMatrix::from_fn(12, 1, |i, _| i as f64);
+        let precomputed_gp = GaussianProcess::new(prior::ZeroPrior {},
+                                                  kernel::Precomputed::new(gram.clone()),
+                                                  0.1,
+                                                  None,
+                                                  indices.clone(),
+                                                  training_outputs.clone());
+        let similarities = make_covariance_matrix(&inputs, &training_inputs, &kernel);
+        let self_similarities = DVector::from_element(3, 1.3);
+        let mean = precomputed_gp.predict_with_similarities(&similarities);
+        let variance = precomputed_gp.predict_variance_with_similarities(&similarities, &self_similarities);
+        assert!((mean - gp.predict(&inputs)).amax() < 1e-12);
+        assert!((variance - gp.predict_variance(&inputs)).amax() < 1e-12);
+
+        // The constant of the prior applies to the new points.
+        let gp = GaussianProcess::new(prior::ConstantPrior::new(0.7),
+                                      kernel,
+                                      0.1,
+                                      None,
+                                      training_inputs.clone(),
+                                      training_outputs.clone());
+        let precomputed_gp = GaussianProcess::new(prior::ConstantPrior::new(0.7),
+                                                  kernel::Precomputed::new(gram),
+                                                  0.1,
+                                                  None,
+                                                  indices.clone(),
+                                                  training_outputs.clone());
+        let mean = precomputed_gp.predict_with_similarities(&similarities);
+        assert!((mean - gp.predict(&inputs)).amax() < 1e-12);
+
+        // Without kernel parameters, the fit only changes the noise.
+        let gram = make_covariance_matrix(&training_inputs, &training_inputs, &kernel);
+        let fitted_gp = GaussianProcess::builder(indices, training_outputs)
+            .set_kernel(kernel::Precomputed::new(gram))
+            .set_prior(prior::ZeroPrior {})
+            .set_noise(0.5)
+            .fit_kernel()
+            .train();
+        assert!(fitted_gp.noise < 0.5 && fitted_gp.noise > 0.);
+    }
+
+    #[test]
+    fn standardized_inputs_match_manually_standardized_data()
+    {
+        // dimensions on wildly different scales
+        let training_inputs =
+            DMatrix::from_fn(30, 2, |i, j| if j == 0 { 1000. * (i as f64 * 0.7).sin() } else { 1e-3 * i as f64 });
+        let training_outputs = DVector::from_fn(30, |i, _| (i as f64 * 0.2).cos());
+        let gp = GaussianProcess::builder(training_inputs.clone(), training_outputs.clone()).standardize_inputs()
+                                                                                            .train();
+
+        let standardization = gp.input_standardization().expect("the inputs should be standardized");
+        let column = training_inputs.column(1);
+        assert!((standardization.mean()[1] - column.mean()).abs() < 1e-12);
+        assert!((standardization.std()[1] - column.variance().sqrt()).abs() < 1e-12);
+
+        let standardized_inputs = standardization.apply(training_inputs.clone());
+        for column in standardized_inputs.column_iter()
+        {
+            assert!(column.mean().abs() < 1e-10);
+            assert!((column.variance() - 1.).abs() < 1e-10);
+        }
+        let manual_gp = GaussianProcess::builder(standardized_inputs, training_outputs).train();
+
+        // predictions take raw inputs and standardize them internally
+        let test_inputs = DMatrix::from_fn(5, 2, |i, j| if j == 0 { 200. * i as f64 - 400. } else { 5e-3 * i as f64 });
+        let prediction = gp.predict(&test_inputs);
+        let manual_prediction = manual_gp.predict(&standardization.apply(test_inputs));
+        assert!((prediction - manual_prediction).norm() < 1e-10);
+    }
+
+    #[test]
+    fn standardized_outputs_match_manually_standardized_data()
+    {
+        // outputs of the order of 1e6
+        let training_inputs = DMatrix::from_fn(20, 1, |i, _| i as f64 * 0.3);
+        let training_outputs = training_inputs.column(0).map(|x| 3e6 + 1e6 * x.sin());
+        let mut gp = GaussianProcess::builder(training_inputs.clone(), training_outputs.clone()).standardize_outputs()
+                                                                                                .train();
+
+        let standardization = gp.output_standardization().expect("the outputs should be standardized").clone();
+        assert!((standardization.mean() - training_outputs.mean()).abs() < 1e-6);
+        assert!((standardization.std() - training_outputs.variance().sqrt()).abs() < 1e-6);
+        let standardized_outputs = standardization.apply(training_outputs.clone());
+        assert!(standardized_outputs.mean().abs() < 1e-10);
+        assert!((standardized_outputs.variance() - 1.).abs() < 1e-10);
+        let mut manual_gp = GaussianProcess::builder(training_inputs, standardized_outputs).train();
+        assert!((gp.noise - manual_gp.noise).abs() < 1e-12);
+
+        // the mean is scaled and shifted back, the variance is scaled by the square of the standard deviation
+        let test_inputs = DMatrix::from_fn(4, 1, |i, _| i as f64 * 1.7 + 0.1);
+        let (mean, variance) = gp.predict_mean_variance(&test_inputs);
+        let (manual_mean, manual_variance) = manual_gp.predict_mean_variance(&test_inputs);
+        let std = standardization.std();
+        assert!((mean - standardization.restore(manual_mean)).norm() < 1e-6 * std);
+        assert!((variance - manual_variance * std * std).norm() < 1e-6 * std * std);
+        assert!((gp.predict_covariance(&test_inputs).diagonal() - gp.predict_variance(&test_inputs)).norm()
+                < 1e-6 * std * std);
+
+        // new samples are given at the scale of the outputs
+        let new_inputs = DMatrix::from_element(1, 1, 6.5);
+        let new_outputs = DVector::from_element(1, 3e6 + 1e6 * 6.5f64.sin());
+        gp.add_samples(&new_inputs, &new_outputs);
+        manual_gp.add_samples(&new_inputs, &standardization.apply(new_outputs));
+        let manual_prediction = standardization.restore(manual_gp.predict(&test_inputs));
+        assert!((gp.predict(&test_inputs) - manual_prediction).norm() < 1e-6 * std);
+    }
+
+    #[test]
+    fn function_prior_lets_the_process_learn_the_residual()
+    {
+        // a strong known trend plus a small residual
+        let function = |x: f64| 10. * x + 0.3 * (3. * x).sin();
+        let training_inputs: Vec<Vec<f64>> = (0..15).map(|i| vec![i as f64 * 0.2]).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| function(x[0])).collect();
+        let residual_gp = GaussianProcess::builder(training_inputs.clone(), training_outputs.clone())
+            .set_prior(prior::FnPrior::new(|x| 10. * x[0]))
+            .fit_kernel()
+            .fit_prior()
+            .train();
+        let zero_gp = GaussianProcess::builder(training_inputs, training_outputs).set_prior(prior::ZeroPrior {})
+                                                                                 .fit_kernel()
+                                                                                 .train();
+
+        // away from the data, the zero prior goes back to zero while the residual process follows the trend
+        let test_inputs: Vec<Vec<f64>> = (0..10).map(|i| vec![1.5 + i as f64 * 0.3]).collect();
+        let error = |gp_predictions: Vec<f64>| -> f64 {
+            gp_predictions.iter().zip(&test_inputs).map(|(prediction, x)| (prediction - function(x[0])).abs()).sum()
+        };
+        let residual_error = error(residual_gp.predict(&test_inputs));
+        let zero_error = error(zero_gp.predict(&test_inputs));
+        assert!(residual_error < 0.1 * zero_error, "residual error {} against {}", residual_error, zero_error);
+        assert!(residual_error / (test_inputs.len() as f64) < 0.3, "residual error {}", residual_error);
+    }
+
+    #[test]
+    fn matern32_fits_non_smooth_function()
+    {
+        // |x| is continuous but not differentiable at 0.
+        let training_inputs: Vec<Vec<f64>> = (-8..=8).map(|i| vec![i as f64 * 0.25]).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| x[0].abs()).collect();
+        let gp = GaussianProcess::builder(training_inputs, training_outputs).set_kernel(kernel::Matern32::default())
+                                                                            .fit_kernel()
+                                                                            .fit_prior()
+                                                                            .train();
+
+        for x in [-1.3, -0.6, 0.1, 0.4, 1.7]
+        {
+            let prediction = gp.predict(&vec![x]);
+            assert!((prediction - f64::abs(x)).abs() < 0.1, "prediction {} at {}", prediction, x);
+        }
+    }
+
+    #[test]
+    fn matern52_fits_noisy_sinusoid()
+    {
+        let mut rng = StdRng::seed_from_u64(42);
+        let training_inputs: Vec<Vec<f64>> = (0..40).map(|i| vec![i as f64 * 0.15]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| x[0].sin() + rng.gen_range(-0.05..0.05)).collect();
+        let mut gp = GaussianProcess::new(prior::ZeroPrior {},
+                                          kernel::Matern52::default(),
+                                          0.1,
+                                          None,
+                                          training_inputs,
+                                          training_outputs);
+        gp.fit_parameters(false, true, 100, 0.05, Duration::from_secs(3600));
+
+        for x in [0.5, 1.7, 3.2, 4.6]
+        {
+            let prediction = gp.predict(&vec![x]);
+            assert!((prediction - f64::sin(x)).abs() < 0.1, "prediction {} at {}", prediction, x);
+        }
+    }
+
+    #[test]
+    fn periodic_recovers_period_of_noisy_sine()
+    {
+        let period = 2.;
+        let mut rng = StdRng::seed_from_u64(42);
+        let training_inputs: Vec<Vec<f64>> = (0..40).map(|i| vec![i as f64 * 0.2]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter()
+                           .map(|x| (2. * std::f64::consts::PI * x[0] / period).sin() + rng.gen_range(-0.1..0.1))
+                           .collect();
+        let kernel = kernel::Periodic::new(1., 1.8, 1.);
+        let gp = GaussianProcess::builder(training_inputs, training_outputs).set_kernel(kernel)
+                                                                            .fit_kernel()
+                                                                            .train();
+
+        let fitted_period = gp.kernel.period.abs();
+        assert!((fitted_period - period).abs() < 0.05 * period, "fitted period {}", fitted_period);
+    }
+
+    #[test]
+    fn rational_quadratic_parameters_stay_valid_during_fit()
+    {
+        // Small scale wiggles on top of a large scale trend.
+        let training_inputs: Vec<Vec<f64>> = (0..30).map(|i| vec![i as f64 * 0.2]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| (0.5 * x[0]).sin() + 0.2 * (5. * x[0]).sin()).collect();
+        let gp = GaussianProcess::builder(training_inputs, training_outputs).set_kernel(kernel::RationalQuadratic::default())
+                                                                            .fit_kernel()
+                                                                            .train();
+
+        assert!(gp.kernel.alpha > 0.);
+        assert!(gp.kernel.get_parameters().iter().all(|p| p.is_finite()));
+        assert!(gp.predict(&vec![2.5]).is_finite());
+    }
+
+    #[test]
+    fn periodic_recovers_period_of_pure_sinusoid()
+    {
+        let period = 3.;
+        let training_inputs: Vec<Vec<f64>> = (0..30).map(|i| vec![i as f64 * 0.3]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| (2. * std::f64::consts::PI * x[0] / period).sin()).collect();
+        let kernel = kernel::Periodic::new(1., 2.8, 1.);
+        let gp = GaussianProcess::builder(training_inputs, training_outputs).set_kernel(kernel)
+                                                                            .set_noise(0.01)
+                                                                            .fit_kernel()
+                                                                            .train();
+
+        assert!((gp.kernel.period - period).abs() < 0.05 * period, "fitted period {}", gp.kernel.period);
+    }
+
+    #[test]
+    fn rational_quadratic_fit_improves_likelihood()
+    {
+        // Smooth on the left and rough on the right.
+        let training_inputs: Vec<Vec<f64>> = (0..40).map(|i| vec![i as f64 * 0.1]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter()
+                           .map(|x| if x[0] < 2. { x[0].sin() } else { x[0].sin() + 0.3 * (8. * x[0]).sin() })
+                           .collect();
+        let mut gp = GaussianProcess::new(prior::ZeroPrior {},
+                                          kernel::RationalQuadratic::default(),
+                                          0.1,
+                                          None,
+                                          training_inputs,
+                                          training_outputs);
+
+        let likelihood_before = gp.likelihood();
+        gp.fit_parameters(false, true, 100, 0.05, Duration::from_secs(3600));
+        let likelihood_after = gp.likelihood();
+        assert!(likelihood_after > likelihood_before,
+                "likelihood went from {} to {}",
+                likelihood_before,
+                likelihood_after);
+    }
+
+    #[test]
+    fn kernel_sum_fit_converges()
+    {
+        // Uniform noise on [-0.3, 0.3] has a variance of 0.03.
+        let mut rng = StdRng::seed_from_u64(5);
+        let training_inputs: Vec<Vec<f64>> = (0..60).map(|i| vec![i as f64 * 0.1]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| x[0].sin() + rng.gen_range(-0.3..0.3)).collect();
+        let kernel = kernel::KernelArith(kernel::SquaredExp::default())
+                     + kernel::KernelArith(kernel::WhiteNoise::default());
+        // The global noise is fixed at zero such that the white noise kernel carries all the noise.
+        let mut gp = GaussianProcess::new(prior::ZeroPrior {}, kernel, 0., None, training_inputs, training_outputs);
+        let config = FitConfig { optimizer: Optimizer::Lbfgs,
+                                 max_iter: 200,
+                                 convergence_fraction: 1e-4,
+                                 fixed_parameters: vec![3],
+                                 record_trace: true,
+                                 ..FitConfig::default() };
+        let trace = gp.fit_parameters_with_config(false, true, &config);
+
+        // The gradient optimizer converges to a stationary point before its iteration budget is exhausted.
+        assert!(trace.len() < config.max_iter, "{} iterations", trace.len());
+        let (first, last) = (&trace[0], &trace[trace.len() - 1]);
+        assert!(last.log_likelihood > first.log_likelihood);
+        assert!(last.gradient_norm < 1e-3 * first.gradient_norm, "gradient norm {}", last.gradient_norm);
+        assert_eq!(gp.noise, 0.);
+        let variance = gp.kernel.k2.variance;
+        assert!((0.015..0.06).contains(&variance), "white noise variance {}", variance);
+        let prediction = gp.predict(&vec![2.05]);
+        assert!((prediction - f64::sin(2.05)).abs() < 0.15, "prediction {}", prediction);
+    }
+
+    #[test]
+    fn kernel_product_fits_locally_periodic_signal()
+    {
+        // A periodic signal whose shape slowly drifts.
+        let f = |x: f64| (2. * std::f64::consts::PI * x).sin() * (0.2 * x).cos();
+        let training_inputs: Vec<Vec<f64>> = (0..50).map(|i| vec![i as f64 * 0.1]).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| f(x[0])).collect();
+        let kernel = kernel::KernelArith(kernel::Periodic::new(1., 1., 1.))
+                     * kernel::KernelArith(kernel::SquaredExp::new(3., 1.));
+        let gp = GaussianProcess::builder(training_inputs, training_outputs).set_kernel(kernel)
+                                                                            .set_noise(0.01)
+                                                                            .fit_kernel()
+                                                                            .train();
+
+        for x in [0.55, 2.25, 4.05]
+        {
+            let prediction = gp.predict(&vec![x]);
+            assert!((prediction - f(x)).abs() < 0.1, "prediction {} at {}", prediction, x);
+        }
+    }
+
+    #[test]
+    fn locally_periodic_kernel_fits_seasonal_signal()
+    {
+        // A seasonal signal whose amplitude slowly drifts.
+        let f = |x: f64| (2. * std::f64::consts::PI * x).sin() * (0.2 * x).cos();
+        let training_inputs: Vec<Vec<f64>> = (0..50).map(|i| vec![i as f64 * 0.1]).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| f(x[0])).collect();
+        let kernel = kernel::LocallyPeriodic::new(1., 1.1, 3., 1.);
+        let gp = GaussianProcess::builder(training_inputs, training_outputs).set_kernel(kernel)
+                                                                            .set_noise(0.01)
+                                                                            .fit_kernel()
+                                                                            .train();
+
+        // All four parameters are fitted in a single call.
+        assert!(gp.kernel.get_parameters().iter().all(|p| p.is_finite()));
+        assert!((gp.kernel.period.abs() - 1.).abs() < 0.05, "period {}", gp.kernel.period);
+        for x in [0.55, 2.25, 4.05]
+        {
+            let prediction = gp.predict(&vec![x]);
+            assert!((prediction - f(x)).abs() < 0.1, "prediction {} at {}", prediction, x);
+        }
+    }
+
+    #[test]
+    fn anova_orders_sum_to_the_prediction()
+    {
+        // A main effect plus an interaction between the first two dimensions.
+        let mut rng = StdRng::seed_from_u64(5);
+        let f = |x: &[f64]| x[0].sin() + 0.5 * x[0] * x[1];
+        let training_inputs: Vec<Vec<f64>> =
+            (0..80).map(|_| (0..3).map(|_| rng.gen_range(-2. ..2.)).collect()).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| f(x)).collect();
+        let gp = GaussianProcess::builder(training_inputs, training_outputs)
+            .set_kernel(kernel::Anova::<kernel::SquaredExp>::default())
+            .set_noise(0.01)
+            .fit_kernel()
+            .fit_prior()
+            .train();
+
+        let inputs = DMatrix::from_fn(15, 3, |i, d| -1.4 + 0.2 * i as f64 + 0.1 * d as f64);
+        let main = gp.predict_order(&inputs, 1);
+        let interaction = gp.predict_order(&inputs, 2);
+        let prediction = gp.predict(&inputs);
+        let prior = gp.predict_prior(&inputs);
+        assert!((&main + &interaction + prior - &prediction).amax() < 1e-8);
+        let expected = DVector::from_fn(15, |i, _| f(&[inputs[(i, 0)], inputs[(i, 1)]]));
+        assert!((prediction - expected).amax() < 0.1);
+    }
+
+    #[test]
+    fn contributions_sum_to_the_prediction_with_standardized_outputs()
+    {
+        // Outputs far from zero, such that the mean removed by the standardization matters.
+        let mut rng = StdRng::seed_from_u64(8);
+        let training_inputs: Vec<Vec<f64>> =
+            (0..40).map(|_| (0..2).map(|_| rng.gen_range(-2. ..2.)).collect()).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| 100. + 5. * x[0].sin() + x[1]).collect();
+        let inputs = DMatrix::from_fn(10, 2, |i, d| -1.4 + 0.3 * i as f64 - 0.2 * d as f64);
+
+        let gp = GaussianProcess::builder(training_inputs.clone(), training_outputs.clone())
+            .set_kernel(kernel::Additive::with_dimension(kernel::SquaredExp::default(), 2))
+            .set_prior(prior::ConstantPrior::new(0.5))
+            .standardize_outputs()
+            .train();
+        let total = gp.predict_prior(&inputs) + gp.predict_dimension(&inputs, 0) + gp.predict_dimension(&inputs, 1);
+        assert!((total - gp.predict(&inputs)).amax() < 1e-8);
+
+        let gp = GaussianProcess::builder(training_inputs, training_outputs)
+            .set_kernel(kernel::Anova::<kernel::SquaredExp>::default())
+            .set_prior(prior::ConstantPrior::new(0.5))
+            .standardize_outputs()
+            .train();
+        let total = gp.predict_prior(&inputs) + gp.predict_order(&inputs, 1) + gp.predict_order(&inputs, 2);
+        assert!((total - gp.predict(&inputs)).amax() < 1e-8);
+    }
+
+    #[test]
+    fn additive_kernel_recovers_the_contribution_of_each_dimension()
+    {
+        // An additive function of three dimensions, the last one being irrelevant.
+        let mut rng = StdRng::seed_from_u64(21);
+        let f0 = |x: f64| (2. * x).sin();
+        let f1 = |x: f64| 0.5 * x * x;
+        let training_inputs: Vec<Vec<f64>> =
+            (0..60).map(|_| (0..3).map(|_| rng.gen_range(-2. ..2.)).collect()).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| f0(x[0]) + f1(x[1])).collect();
+        let kernel = kernel::Additive::with_dimension(kernel::SquaredExp::default(), 3);
+        let gp = GaussianProcess::builder(training_inputs, training_outputs).set_kernel(kernel)
+                                                                            .set_prior(prior::ZeroPrior {})
+                                                                            .set_noise(0.01)
+                                                                            .fit_kernel()
+                                                                            .train();
+        assert_eq!(gp.kernel.kernels().len(), 3);
+
+        // The contributions are recovered up to a constant.
+        let inputs = DMatrix::from_fn(21, 3, |i, _| -1.5 + 0.15 * i as f64);
+        let centered = |v: DVector<f64>| v.add_scalar(-v.mean());
+        let contribution0 = centered(gp.predict_dimension(&inputs, 0));
+        let contribution1 = centered(gp.predict_dimension(&inputs, 1));
+        let contribution2 = gp.predict_dimension(&inputs, 2);
+        assert!((contribution0 - centered(inputs.column(0).map(f0))).amax() < 0.1);
+        assert!((contribution1 - centered(inputs.column(1).map(f1))).amax() < 0.1);
+        assert!((contribution2.add_scalar(-contribution2.mean())).amax() < 0.1);
+
+        // Summed, the contributions give back the prediction.
+        let total = (0..3).map(|d| gp.predict_dimension(&inputs, d)).fold(DVector::zeros(21), |acc, c| acc + c);
+        assert!((total - gp.predict(&inputs)).amax() < 1e-9);
+    }
+
+    #[test]
+    fn fn_kernel_reproduces_the_fit_of_the_native_kernel()
+    {
+        let training_inputs: Vec<Vec<f64>> = (0..30).map(|i| vec![i as f64 * 0.2]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| x[0].sin() + 0.1 * (7.3 * x[0]).cos()).collect();
+        let squared_exp = |x: &[f64], y: &[f64], p: &[f64]| {
+            let distance_squared: f64 = x.iter().zip(y).map(|(a, b)| (a - b) * (a - b)).sum();
+            p[1].abs() * (-distance_squared / (2. * p[0] * p[0])).exp()
+        };
+        let gp_fn = GaussianProcess::builder(training_inputs.clone(), training_outputs.clone())
+            .set_kernel(kernel::FnKernel::new(squared_exp, vec![1., 1.]))
+            .set_noise(0.01)
+            .set_optimizer(Optimizer::Lbfgs)
+            .set_fit_parameters(200, 1e-6)
+            .fit_kernel()
+            .train();
+        let gp_native = GaussianProcess::builder(training_inputs, training_outputs)
+            .set_kernel(kernel::SquaredExp::new(1., 1.))
+            .set_noise(0.01)
+            .set_optimizer(Optimizer::Lbfgs)
+            .set_fit_parameters(200, 1e-6)
+            .fit_kernel()
+            .train();
+
+        // With the same optimizer, both fits converge to the same parameters
+        // (the length scale is only defined up to its sign).
+        let parameters = gp_fn.kernel.get_parameters();
+        assert!((parameters[0].abs() - gp_native.kernel.ls.abs()).abs() < 1e-2 * gp_native.kernel.ls.abs(),
+                "length scales {} and {}",
+                parameters[0],
+                gp_native.kernel.ls);
+        assert!((parameters[1].abs() - gp_native.kernel.ampl.abs()).abs() < 1e-2 * gp_native.kernel.ampl.abs(),
+                "amplitudes {} and {}",
+                parameters[1],
+                gp_native.kernel.ampl);
+        for x in [0.5, 2.3, 5.1]
+        {
+            let (fn_prediction, native_prediction) = (gp_fn.predict(&vec![x]), gp_native.predict(&vec![x]));
+            assert!((fn_prediction - native_prediction).abs() < 1e-3, "{} and {}", fn_prediction, native_prediction);
+        }
+    }
+
+    #[test]
+    fn shrink_to_fit_leaves_predictions_unchanged()
+    {
+        let mut gp = GaussianProcess::default(vec![vec![0.], vec![1.]], vec![0., 1.]);
+        for i in 2..40
+        {
+            let x = i as f64 * 0.5;
+            gp.add_samples(&vec![vec![x]], &vec![x.sin()]);
+        }
+        assert!(gp.training_inputs.capacity() > 40);
+        let inputs = vec![vec![0.3], vec![7.7], vec![25.]];
+        let (mean, variance) = (gp.predict(&inputs), gp.predict_variance(&inputs));
+
+        gp.shrink_to_fit();
+        assert_eq!(gp.training_inputs.capacity(), 40);
+        assert_eq!(gp.training_outputs.capacity(), 40);
+        assert_eq!(gp.predict(&inputs), mean);
+        assert_eq!(gp.predict_variance(&inputs), variance);
+    }
+
+    #[test]
+    fn removing_a_training_point_matches_a_model_trained_without_it()
+    {
+        let training_inputs: Vec<Vec<f64>> = (0..10).map(|i| vec![i as f64 * 0.4]).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| x[0].sin()).collect();
+        let mut gp = GaussianProcess::default(training_inputs.clone(), training_outputs.clone());
+        // A corrupted measurement added online.
+        gp.add_samples(&vec![vec![1.7]], &vec![100.]);
+        gp.remove_training_point(10);
+        gp.remove_training_point(3);
+
+        let mut remaining_inputs = training_inputs;
+        let mut remaining_outputs = training_outputs;
+        remaining_inputs.remove(3);
+        remaining_outputs.remove(3);
+        let expected = GaussianProcess::builder(remaining_inputs, remaining_outputs).set_prior(gp.prior.clone())
+                                                                                   .set_kernel(gp.kernel)
+                                                                                   .set_noise(gp.noise)
+                                                                                   .train();
+        let inputs = vec![vec![0.3], vec![1.2], vec![1.7], vec![5.]];
+        for (a, b) in gp.predict(&inputs).iter().zip(expected.predict(&inputs))
+        {
+            assert!((a - b).abs() < 1e-10, "{} and {}", a, b);
+        }
+        for (a, b) in gp.predict_variance(&inputs).iter().zip(expected.predict_variance(&inputs))
+        {
+            assert!((a - b).abs() < 1e-10, "{} and {}", a, b);
+        }
+    }
+
+    #[test]
+    fn adding_samples_matches_a_model_trained_on_all_samples()
+    {
+        let training_inputs: Vec<Vec<f64>> = (0..20).map(|i| vec![i as f64 * 0.3]).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| x[0].cos()).collect();
+        let inputs = vec![vec![0.1], vec![2.9], vec![4.4], vec![7.]];
+        let build = |nb_samples: usize, noise: f64| {
+            GaussianProcess::builder(training_inputs[..nb_samples].to_vec(), training_outputs[..nb_samples].to_vec())
+                .set_prior(prior::ZeroPrior {})
+                .set_kernel(kernel::SquaredExp::new(0.8, 1.))
+                .set_noise(noise)
+                .set_cholesky_epsilon(Some(1e-10))
+                .train()
+        };
+
+        // The factor is extended incrementally.
+        let mut gp = build(12, 0.1);
+        gp.add_samples(&training_inputs[12..].to_vec(), &training_outputs[12..].to_vec());
+        let expected = build(20, 0.1);
+        assert!((gp.covmat_cholesky.l() - expected.covmat_cholesky.l()).amax() < 1e-10);
+        for (a, b) in gp.predict(&inputs).iter().zip(expected.predict(&inputs))
+        {
+            assert!((a - b).abs() < 1e-10, "{} and {}", a, b);
+        }
+
+        // Without noise, a duplicated input makes the matrix singular which forces a full decomposition.
+        let mut gp = build(20, 0.);
+        gp.add_samples(&vec![training_inputs[5].clone()], &vec![training_outputs[5]]);
+        assert!(gp.covmat_cholesky.l().iter().all(|x| x.is_finite()));
+        for (x, y) in training_inputs.iter().zip(training_outputs.iter())
+        {
+            assert!((gp.predict(x) - y).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn noise_floor_and_kernel_bounds_keep_the_fit_valid()
+    {
+        // Noiseless linear data pushes the length scale up and the noise toward zero,
+        // the covariance matrix used to become singular during the fit.
+        let training_inputs: Vec<Vec<f64>> = (0..30).map(|i| vec![i as f64 * 0.1]).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| 2. * x[0] + 1.).collect();
+        for optimizer in [Optimizer::Adam, Optimizer::Lbfgs, Optimizer::NelderMead]
+        {
+            let gp = GaussianProcess::builder(training_inputs.clone(), training_outputs.clone())
+                .set_kernel(kernel::SquaredExp::new(1., 1.))
+                .set_noise(0.1)
+                .set_optimizer(optimizer)
+                .set_noise_floor(1e-3)
+                .set_fit_parameters(500, 0.)
+                .fit_kernel()
+                .train();
+            assert!(gp.noise >= 1e-3 * (1. - 1e-12), "{:?}: noise {}", optimizer, gp.noise);
+            assert!(gp.kernel.ls.is_finite() && gp.kernel.ls.abs() <= 1e10);
+            assert!(gp.log_marginal_likelihood().is_finite());
+        }
+
+        // A length scale outside of the bounds of the kernel is projected into them.
+        let mut gp = GaussianProcess::builder(training_inputs, training_outputs)
+            .set_kernel(kernel::SquaredExp::new(-1e20, 1.))
+            .set_noise(0.1)
+            .train();
+        let config = FitConfig { max_iter: 1, nb_restarts: 0, ..FitConfig::default() };
+        gp.fit_parameters_with_config(false, true, &config);
+        assert!(gp.kernel.ls >= -1e10 && gp.kernel.ls < 0.);
+    }
+
+    #[test]
+    fn sliding_window_matches_a_model_trained_on_the_window()
+    {
+        let f = |x: f64| (1.5 * x).sin();
+        let window = 15;
+        let inputs: Vec<Vec<f64>> = (0..60).map(|i| vec![i as f64 * 0.25]).collect();
+        let outputs: Vec<f64> = inputs.iter().map(|x| f(x[0])).collect();
+        let build = |start: usize| {
+            GaussianProcess::builder(inputs[start..start + window].to_vec(), outputs[start..start + window].to_vec())
+                .set_prior(prior::ZeroPrior {})
+                .set_kernel(kernel::SquaredExp::new(0.7, 1.))
+                .set_noise(0.05)
+                .train()
+        };
+
+        // Slides the window by batches of five samples.
+        let mut gp = build(0);
+        for start in (5..=45).step_by(5)
+        {
+            let new_samples = start + window - 5..start + window;
+            gp.add_samples(&inputs[new_samples.clone()].to_vec(), &outputs[new_samples].to_vec());
+            gp.remove_samples(&[3, 0, 4, 1, 2, 0]);
+        }
+        let expected = build(45);
+        let test_inputs: Vec<Vec<f64>> = (0..10).map(|i| vec![11. + i as f64 * 0.3]).collect();
+        for (a, b) in gp.predict(&test_inputs).iter().zip(expected.predict(&test_inputs))
+        {
+            assert!((a - b).abs() < 1e-8, "{} and {}", a, b);
+        }
+
+        // Refactorizing gives back the exact decomposition.
+        gp.refactorize();
+        assert!((gp.covmat_cholesky.l() - expected.covmat_cholesky.l()).amax() < 1e-12);
+    }
+
+    #[test]
+    fn ornstein_uhlenbeck_optimization_converges()
+    {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut value = 0.;
+        let training_inputs: Vec<Vec<f64>> = (0..40).map(|i| vec![i as f64 * 0.1]).collect();
+        let training_outputs: Vec<f64> = (0..40).map(|_| {
+                                                    value += rng.gen_range(-0.3..0.3);
+                                                    value
+                                                })
+                                                .collect();
+        let mut gp = GaussianProcess::new(prior::ZeroPrior {},
+                                          kernel::OrnsteinUhlenbeck::default(),
+                                          0.1,
+                                          None,
+                                          training_inputs,
+                                          training_outputs);
+
+        // Exercises both the unscaled and the scaled optimizer.
+        gp.optimize_parameters::<Serial>(&FitConfig::default()).unwrap();
+        assert!(gp.noise.is_finite() && gp.noise > 0.);
+        gp.scaled_optimize_parameters::<Serial>(&FitConfig::default()).unwrap();
+        assert!(gp.noise.is_finite() && gp.noise > 0.);
+        assert!(gp.kernel.get_parameters().iter().all(|p| p.is_finite()));
+    }
+
+    #[test]
+    fn polynomial_kernel_extrapolates_polynomials()
+    {
+        fn assert_extrapolates(degree: i32, f: fn(f64) -> f64)
+        {
+            let training_inputs: Vec<Vec<f64>> = (-10..=10).map(|i| vec![i as f64 * 0.2]).collect();
+            let training_outputs: Vec<f64> = training_inputs.iter().map(|x| f(x[0])).collect();
+            let kernel = kernel::Polynomial::new(1., 1., degree);
+            let gp = GaussianProcess::builder(training_inputs, training_outputs).set_kernel(kernel)
+                                                                                .set_prior(prior::ZeroPrior {})
+                                                                                .set_noise(1e-3)
+                                                                                .train();
+
+            // Far away from the training data, a stationary kernel would revert to the prior.
+            for x in [-4., 4.]
+            {
+                let prediction = gp.predict(&vec![x]);
+                assert!((prediction - f(x)).abs() < 0.05 * (1. + f(x).abs()),
+                        "degree {}: prediction {} at {} instead of {}",
+                        degree,
+                        prediction,
+                        x,
+                        f(x));
+            }
+        }
+
+        assert_extrapolates(2, |x| 1. + 2. * x - 0.5 * x * x);
+        assert_extrapolates(3, |x| 0.5 - x + 0.2 * x * x * x);
+    }
+
+    #[test]
+    fn squared_exp_ard_detects_irrelevant_dimension()
+    {
+        let mut rng = StdRng::seed_from_u64(42);
+        let training_inputs: Vec<Vec<f64>> =
+            (0..60).map(|_| (0..3).map(|_| rng.gen_range(0. ..3.)).collect()).collect();
+        // The last dimension has no influence on the output.
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| x[0].sin() + 0.5 * x[1].cos()).collect();
+        let kernel = kernel::SquaredExpArd::default();
+        let gp = GaussianProcess::builder(training_inputs, training_outputs).set_kernel(kernel)
+                                                                            .fit_kernel()
+                                                                            .train();
+
+        let ls: Vec<f64> = gp.kernel.length_scales().iter().map(|l| l.abs()).collect();
+        assert_eq!(ls.len(), 3);
+        assert!(ls[2] > 3. * ls[0].max(ls[1]), "length scales {:?}", ls);
+    }
+
+    #[test]
+    fn linear_plus_squared_exp_fits_line_plus_wiggle()
+    {
+        let f = |x: f64| 2. * x - 1. + 0.3 * (4. * x).sin();
+        let training_inputs: Vec<Vec<f64>> = (0..40).map(|i| vec![i as f64 * 0.1]).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| f(x[0])).collect();
+        let kernel = kernel::KernelArith(kernel::Linear::default()) + kernel::KernelArith(kernel::SquaredExp::default());
+        let gp = GaussianProcess::builder(training_inputs, training_outputs).set_kernel(kernel)
+                                                                            .set_prior(prior::ZeroPrior {})
+                                                                            .fit_kernel()
+                                                                            .train();
+
+        for x in [0.35, 1.85, 3.05]
+        {
+            let prediction = gp.predict(&vec![x]);
+            assert!((prediction - f(x)).abs() < 0.05, "prediction {} at {}", prediction, x);
+        }
+    }
+
+    /// Serializes then deserializes a value, checking that it is written back to the same bytes.
+    #[cfg(feature = "friedrich_serde")]
+    fn round_trip<T: serde::Serialize + serde::de::DeserializeOwned>(value: &T) -> T
+    {
+        let bytes = bincode::serialize(value).unwrap();
+        let decoded: T = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(bincode::serialize(&decoded).unwrap(), bytes);
+        decoded
+    }
+
+    #[cfg(feature = "friedrich_serde")]
+    #[test]
+    fn deserialized_process_predicts_as_the_original()
+    {
+        let mut rng = StdRng::seed_from_u64(9);
+        let training_inputs = DMatrix::<f64>::from_fn(30, 2, |_, _| rng.gen_range(0.0..5.));
+        let training_outputs =
+            DVector::from_fn(30, |r, _| training_inputs[(r, 0)].sin() + 0.3 * training_inputs[(r, 1)]);
+        let test_inputs = DMatrix::from_fn(10, 2, |_, _| rng.gen_range(0.0..5.));
+
+        // The Cholesky factor is stored, the deserialized process is not retrained.
+        let gp = GaussianProcess::builder(training_inputs.clone(), training_outputs.clone())
+            .set_kernel(kernel::SquaredExpArd::default())
+            .set_prior(prior::LinearPrior::default(2))
+            .fit_kernel()
+            .fit_prior()
+            .train();
+        let decoded = round_trip(&gp);
+        assert_eq!(decoded.covmat_cholesky.l(), gp.covmat_cholesky.l());
+        assert!((decoded.predict(&test_inputs) - gp.predict(&test_inputs)).amax() < 1e-12);
+        assert!((decoded.predict_variance(&test_inputs) - gp.predict_variance(&test_inputs)).amax() < 1e-12);
+
+        let sparse = SparseGaussianProcess::new(prior::ConstantPrior::default(2),
+                                                kernel::SquaredExp::default(),
+                                                0.1,
+                                                None,
+                                                training_inputs.clone(),
+                                                training_outputs.clone(),
+                                                training_inputs.rows(0, 10).into_owned());
+        let decoded = round_trip(&sparse);
+        assert!((decoded.predict(&test_inputs) - sparse.predict(&test_inputs)).amax() < 1e-12);
+
+        // Every built-in kernel and prior round-trips.
+        let kernel =
+            kernel::KernelArith(kernel::Periodic::default()) * kernel::KernelArith(kernel::Matern52::default());
+        let gp = GaussianProcess::new(prior::QuadraticPrior::default(2),
+                                      kernel,
+                                      0.1,
+                                      None,
+                                      training_inputs.clone(),
+                                      training_outputs.clone());
+        assert!((round_trip(&gp).predict(&test_inputs) - gp.predict(&test_inputs)).amax() < 1e-12);
+        round_trip(&(kernel::KernelArith(kernel::Linear::default())
+                     + kernel::KernelArith(kernel::WhiteNoise::default())));
+        round_trip(&prior::ZeroPrior::default(2));
+        round_trip(&prior::ConstantPrior::default(2));
+        round_trip(&prior::PolynomialPrior::default(2));
+        round_trip(&kernel::Linear::default());
+        round_trip(&kernel::Polynomial::default());
+        round_trip(&kernel::SquaredExp::default());
+        round_trip(&kernel::Exponential::default());
+        round_trip(&kernel::GammaExponential::default());
+        round_trip(&kernel::PiecewisePolynomial::default());
+        round_trip(&kernel::Matern32::default());
+        round_trip(&kernel::LocallyPeriodic::default());
+        round_trip(&kernel::Cosine::default());
+        round_trip(&kernel::HyperTan::default());
+        round_trip(&kernel::ArcSine::default());
+        round_trip(&kernel::Multiquadric::default());
+        round_trip(&kernel::RationalQuadratic::default());
+        round_trip(&kernel::Constant::default());
+        round_trip(&kernel::Brownian::default());
+        round_trip(&kernel::Hamming::default());
+        round_trip(&kernel::SpectralMixture::default());
+        round_trip(&kernel::Precomputed::default());
+        round_trip(&kernel::Scaled::<kernel::SquaredExp>::default());
+        round_trip(&kernel::Masked::<kernel::SquaredExp>::default());
+        round_trip(&kernel::Additive::<kernel::Matern52>::default());
+        round_trip(&kernel::Anova::<kernel::SquaredExp>::default());
+        round_trip(&kernel::Warped::<kernel::SquaredExp>::default());
+    }
+
+    #[cfg(feature = "friedrich_serde")]
+    #[test]
+    fn deserialized_process_keeps_its_settings_and_can_be_updated()
+    {
+        let training_inputs = DMatrix::from_column_slice(6, 1, &[10., 12., 12., 15., 17., 20.]);
+        let training_outputs = DVector::from_column_slice(&[300., 340., 340., 310., 280., 330.]);
+        let new_inputs = DMatrix::from_column_slice(2, 1, &[13., 22.]);
+        let new_outputs = DVector::from_column_slice(&[335., 320.]);
+        let test_inputs = DMatrix::from_column_slice(3, 1, &[11., 16., 25.]);
+
+        // Standardizations, Cholesky settings and the jitter applied to the duplicated input are all stored.
+        let mut gp = GaussianProcess::builder(training_inputs, training_outputs)
+            .set_noise(0.)
+            .set_cholesky_jitter(CholeskyJitter { initial: 1e-6, max_retries: 3 })
+            .standardize_inputs()
+            .standardize_outputs()
+            .train();
+        gp.cholesky_epsilon = Some(1e-12);
+        let mut decoded = round_trip(&gp);
+        assert_eq!(decoded.cholesky_epsilon, Some(1e-12));
+        assert_eq!(decoded.cholesky_jitter, gp.cholesky_jitter);
+        assert!(gp.applied_jitter > 0.);
+        assert_eq!(decoded.applied_jitter, gp.applied_jitter);
+        assert_eq!(decoded.predict(&test_inputs), gp.predict(&test_inputs));
+        assert_eq!(decoded.predict_variance(&test_inputs), gp.predict_variance(&test_inputs));
+
+        // The deserialized process is updated exactly as the original one.
+        gp.add_samples(&new_inputs, &new_outputs);
+        decoded.add_samples(&new_inputs, &new_outputs);
+        assert_eq!(decoded.predict(&test_inputs), gp.predict(&test_inputs));
+        assert_eq!(decoded.predict_variance(&test_inputs), gp.predict_variance(&test_inputs));
+
+        round_trip(&crate::parameters::hyperprior::LogNormal::new(0., 1.));
+        round_trip(&crate::parameters::hyperprior::Gamma::new(2., 0.5));
+    }
+
+    #[test]
+    fn white_noise_kernel_fits_the_noise_when_global_noise_is_fixed()
+    {
+        // Uniform noise on [-0.3, 0.3] has a variance of 0.03.
+        let mut rng = StdRng::seed_from_u64(7);
+        let training_inputs: Vec<Vec<f64>> = (0..80).map(|i| vec![i as f64 * 0.1]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| x[0].sin() + rng.gen_range(-0.3..0.3)).collect();
+        let kernel = kernel::KernelArith(kernel::SquaredExp::default()) + kernel::KernelArith(kernel::WhiteNoise::default());
+        // The kernel is scalable so the optimizer only rescales the (null) global noise.
+        let mut gp = GaussianProcess::new(prior::ZeroPrior {}, kernel, 0., None, training_inputs, training_outputs);
+        gp.fit_parameters(false, true, 100, 0.05, Duration::from_secs(3600));
+
+        assert_eq!(gp.noise, 0.);
+        let variance = gp.kernel.k2.variance;
+        assert!((0.015..0.06).contains(&variance), "white noise variance {}", variance);
+        let prediction = gp.predict(&vec![2.05]);
+        assert!((prediction - f64::sin(2.05)).abs() < 0.15, "prediction {}", prediction);
+    }
+
+    #[test]
+    fn white_noise_kernel_matches_the_fitted_global_noise()
+    {
+        let mut rng = StdRng::seed_from_u64(11);
+        let training_inputs: Vec<Vec<f64>> = (0..80).map(|i| vec![i as f64 * 0.1]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| x[0].sin() + rng.gen_range(-0.3..0.3)).collect();
+
+        // noise carried by the global noise parameter
+        // (both fits are run to convergence such that they reach the same optimum)
+        let mut gp = GaussianProcess::new(prior::ZeroPrior {},
+                                          kernel::SquaredExp::default(),
+                                          0.1,
+                                          None,
+                                          training_inputs.clone(),
+                                          training_outputs.clone());
+        gp.fit_parameters(false, true, 1000, 1e-3, Duration::from_secs(3600));
+        let global_variance = gp.noise * gp.noise;
+
+        // noise carried by a white noise kernel
+        let kernel =
+            kernel::KernelArith(kernel::SquaredExp::default()) + kernel::KernelArith(kernel::WhiteNoise::default());
+        let mut white_gp =
+            GaussianProcess::new(prior::ZeroPrior {}, kernel, 0., None, training_inputs, training_outputs);
+        white_gp.fit_parameters(false, true, 1000, 1e-3, Duration::from_secs(3600));
+        let white_variance = white_gp.kernel.k2.variance.abs();
+
+        assert!((white_variance - global_variance).abs() < 0.2 * global_variance,
+                "white noise variance {} against global noise variance {}",
+                white_variance,
+                global_variance);
+        for x in [0.55, 2.05, 4.3]
+        {
+            assert!((white_gp.predict(&vec![x]) - gp.predict(&vec![x])).abs() < 0.05);
+        }
+    }
+
+    #[test]
+    fn constant_kernel_learns_the_offset_of_the_data()
+    {
+        // A constant offset plus noise, fitted with a zero prior.
+        let mut rng = StdRng::seed_from_u64(3);
+        let training_inputs: Vec<Vec<f64>> = (0..40).map(|i| vec![i as f64 * 0.25]).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|_| 5. + rng.gen_range(-0.1..0.1)).collect();
+        let kernel =
+            kernel::KernelArith(kernel::Constant::new(1.)) + kernel::KernelArith(kernel::SquaredExp::default());
+        let gp = GaussianProcess::builder(training_inputs.clone(), training_outputs.clone())
+            .set_prior(prior::ZeroPrior {})
+            .set_kernel(kernel)
+            .set_noise(0.1)
+            .fit_kernel()
+            .train();
+
+        // The offset is carried by the constant kernel, which explains most of the variance,
+        // such that predictions far away from the data stay at the offset rather than going back to the prior.
+        assert!(gp.kernel.k1.c.abs() > 10. * gp.kernel.k2.ampl.abs(), "kernel {:?}", gp.kernel);
+        let prediction = gp.predict(&vec![40.]);
+        assert!((prediction - 5.).abs() < 0.2, "prediction {}", prediction);
+
+        // Without the constant kernel, the prediction goes back to the (zero) prior.
+        let gp = GaussianProcess::builder(training_inputs, training_outputs).set_prior(prior::ZeroPrior {})
+                                                                            .set_noise(0.1)
+                                                                            .fit_kernel()
+                                                                            .train();
+        assert!(gp.predict(&vec![40.]).abs() < 0.5);
+    }
+
+    #[test]
+    fn joint_prediction_matches_pointwise_predictions()
+    {
+        let training_inputs = DMatrix::from_column_slice(5, 1, &[0.8, 1.2, 2.5, 3.8, 4.2]);
+        let training_outputs = DVector::from_column_slice(&[3.0, 4.0, 1.0, -2.0, -2.0]);
+        let gp = GaussianProcess::default(training_inputs, training_outputs);
+
+        let inputs = DMatrix::from_column_slice(4, 1, &[0.5, 1., 2.9, 5.]);
+        let (mean, covariance) = gp.predict_joint(&inputs);
+        let (expected_mean, expected_variance) = gp.predict_mean_variance(&inputs);
+        assert!((mean - expected_mean).amax() < 1e-10);
+        assert!((covariance.diagonal() - expected_variance).amax() < 1e-10);
+        assert!((&covariance - covariance.transpose()).amax() < 1e-10);
+        assert!((covariance - gp.predict_covariance(&inputs)).amax() < 1e-10);
+    }
+
+    #[test]
+    fn log_marginal_likelihood_matches_direct_computation()
+    {
+        let training_inputs = DMatrix::from_column_slice(5, 1, &[0.8, 1.2, 2.5, 3.8, 4.2]);
+        let training_outputs = DVector::from_column_slice(&[3.0, 4.0, 1.0, -2.0, -2.0]);
+        let gp = GaussianProcess::new(prior::ZeroPrior {},
+                                      kernel::SquaredExp::new(1.1, 4.),
+                                      0.3,
+                                      None,
+                                      training_inputs.clone(),
+                                      training_outputs.clone());
+
+        let mut covariance = crate::algebra::make_covariance_matrix(&training_inputs, &training_inputs, &gp.kernel);
+        covariance += DMatrix::identity(5, 5) * 0.3 * 0.3;
+        let data_fit = training_outputs.dot(&(covariance.clone().try_inverse().unwrap() * &training_outputs));
+        let expected = -(data_fit + covariance.determinant().ln() + 5. * (2. * std::f64::consts::PI).ln()) / 2.;
+        assert!((gp.log_marginal_likelihood() - expected).abs() < 1e-10);
+    }
+
+    #[test]
+    fn posterior_samples_converge_to_the_posterior()
+    {
+        let training_inputs = DMatrix::from_column_slice(5, 1, &[0.8, 1.2, 2.5, 3.8, 4.2]);
+        let training_outputs = DVector::from_column_slice(&[3.0, 4.0, 1.0, -2.0, -2.0]);
+        let gp = GaussianProcess::default(training_inputs, training_outputs);
+        let inputs = DMatrix::from_column_slice(3, 1, &[0.5, 1.9, 3.]);
+        let (mean, covariance) = gp.predict_joint(&inputs);
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut previous_error = f64::INFINITY;
+        for nb_samples in [100, 10_000, 200_000]
+        {
+            let samples = gp.sample_posterior(&inputs, nb_samples, None, &mut rng);
+            let sample_mean = samples.column_mean();
+            let centered = DMatrix::from_fn(3, nb_samples, |r, c| samples[(r, c)] - sample_mean[r]);
+            let sample_covariance = &centered * centered.transpose() / (nb_samples as f64 - 1.);
+            let error = (sample_mean - &mean).amax().max((sample_covariance - &covariance).amax());
+            assert!(error < previous_error, "error {} did not decrease with {} samples", error, nb_samples);
+            previous_error = error;
+        }
+        assert!(previous_error < 2e-2, "error {}", previous_error);
+    }
+
+    #[test]
+    fn posterior_samples_survive_a_singular_covariance()
+    {
+        let training_inputs = DMatrix::from_column_slice(5, 1, &[0.8, 1.2, 2.5, 3.8, 4.2]);
+        let training_outputs = DVector::from_column_slice(&[3.0, 4.0, 1.0, -2.0, -2.0]);
+        let gp = GaussianProcess::default(training_inputs, training_outputs);
+        // Identical inputs lead to a singular posterior covariance.
+        let inputs = DMatrix::from_column_slice(3, 1, &[2., 2., 2.]);
+
+        let mut rng = StdRng::seed_from_u64(5);
+        let samples = gp.sample_posterior(&inputs, 10, Some(1e-12), &mut rng);
+        for sample in samples.column_iter()
+        {
+            assert!((sample[0] - sample[1]).abs() < 1e-3 && (sample[0] - sample[2]).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn posterior_samples_are_reproducible_and_independent()
+    {
+        let training_inputs = DMatrix::from_column_slice(5, 1, &[0.8, 1.2, 2.5, 3.8, 4.2]);
+        let training_outputs = DVector::from_column_slice(&[3.0, 4.0, 1.0, -2.0, -2.0]);
+        let gp = GaussianProcess::default(training_inputs, training_outputs);
+        let inputs = DMatrix::from_column_slice(4, 1, &[0.5, 1.9, 3., 5.]);
+
+        // A seeded generator gives the same sample paths.
+        let samples = gp.sample_posterior(&inputs, 20, None, &mut StdRng::seed_from_u64(11));
+        let replay = gp.sample_posterior(&inputs, 20, None, &mut StdRng::seed_from_u64(11));
+        assert_eq!(samples, replay);
+
+        // Each column is a distinct sample path.
+        for i in 0..samples.ncols()
+        {
+            for j in (i + 1)..samples.ncols()
+            {
+                assert!((samples.column(i) - samples.column(j)).amax() > 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn prediction_intervals_use_the_normal_quantiles()
+    {
+        let training_inputs = DMatrix::from_column_slice(5, 1, &[0.8, 1.2, 2.5, 3.8, 4.2]);
+        let training_outputs = DVector::from_column_slice(&[3.0, 4.0, 1.0, -2.0, -2.0]);
+        let gp = GaussianProcess::default(training_inputs.clone(), training_outputs);
+        let inputs = DMatrix::from_column_slice(4, 1, &[0.5, 1.9, 3., 6.]);
+
+        let (mean, variance) = gp.predict_mean_variance(&inputs);
+        let std = gp.predict_std(&inputs);
+        assert!((std.map(|s| s * s) - &variance).amax() < 1e-12);
+        let (lower, upper) = gp.predict_interval(&inputs, 0.95);
+        assert!((&lower - (&mean - &std * 1.959_963_984_540_054)).amax() < 1e-9);
+        assert!((&upper - (&mean + &std * 1.959_963_984_540_054)).amax() < 1e-9);
+        let (narrow_lower, narrow_upper) = gp.predict_interval(&inputs, 0.5);
+        assert!(lower < narrow_lower && narrow_upper < upper);
+
+        // Standard deviations are never NaN, even where the variance is numerically zero.
+        assert!(gp.predict_std(&training_inputs).iter().all(|s| *s >= 0.));
+    }
+
+    #[test]
+    fn invalid_added_samples_are_rejected()
+    {
+        let training_inputs = DMatrix::from_column_slice(4, 2, &[0.8, 1.2, 3.8, 4.2, 0., 1., 2., 3.]);
+        let training_outputs = DVector::from_column_slice(&[3.0, 4.0, -2.0, -2.0]);
+        let mut gp = GaussianProcess::default(training_inputs.clone(), training_outputs);
+        let prediction = gp.predict(&training_inputs);
+
+        let new_inputs = DMatrix::from_row_slice(2, 2, &[2.5, 1.5, 0.3, f64::INFINITY]);
+        let new_outputs = DVector::from_column_slice(&[1.0, 0.5]);
+        assert_eq!(gp.try_add_samples(&new_inputs, &new_outputs),
+                   Err(FriedrichError::NonFiniteValue { data: "training inputs", row: 1 }));
+        let nan_outputs = DVector::from_column_slice(&[f64::NAN, 0.5]);
+        assert_eq!(gp.try_add_samples(&training_inputs.rows(0, 2).into_owned(), &nan_outputs),
+                   Err(FriedrichError::NonFiniteValue { data: "training outputs", row: 0 }));
+        assert_eq!(gp.try_add_samples(&DMatrix::zeros(2, 3), &new_outputs),
+                   Err(FriedrichError::DimensionMismatch { expected: 2, found: 3 }));
+        assert_eq!(gp.try_add_samples(&DMatrix::zeros(3, 2), &new_outputs),
+                   Err(FriedrichError::DimensionMismatch { expected: 3, found: 2 }));
+        // the model is left untouched
+        assert_eq!(gp.training_inputs.as_matrix().nrows(), 4);
+        assert_eq!(gp.predict(&training_inputs), prediction);
+
+        gp.try_add_samples(&training_inputs.rows(0, 2).into_owned(), &new_outputs).unwrap();
+        assert_eq!(gp.training_inputs.as_matrix().nrows(), 6);
+    }
+
+    #[test]
+    fn inputs_outside_the_domain_of_the_kernel_are_rejected()
+    {
+        let training_inputs = DMatrix::from_column_slice(4, 1, &[0.5, 1., 2., 3.]);
+        let training_outputs = DVector::from_column_slice(&[3.0, 4.0, -2.0, -2.0]);
+        let negative_inputs = DMatrix::from_column_slice(2, 1, &[1.5, -0.5]);
+        let expected = FriedrichError::InvalidInput { kernel: "Brownian", row: 1, value: -0.5 };
+
+        let result = GaussianProcess::try_new(prior::ZeroPrior::default(1),
+                                              kernel::Brownian::default(),
+                                              1e-3,
+                                              None,
+                                              negative_inputs.clone(),
+                                              DVector::from_column_slice(&[1.0, 0.5]));
+        assert_eq!(result.err(), Some(expected.clone()));
+
+        let mut gp = GaussianProcess::try_new(prior::ZeroPrior::default(1),
+                                              kernel::Brownian::default(),
+                                              1e-3,
+                                              None,
+                                              training_inputs.clone(),
+                                              training_outputs).unwrap();
+        let prediction = gp.predict(&training_inputs);
+        assert_eq!(gp.try_add_samples(&negative_inputs, &DVector::from_column_slice(&[1.0, 0.5])),
+                   Err(expected.clone()));
+        // the model is left untouched
+        assert_eq!(gp.training_inputs.as_matrix().nrows(), 4);
+        assert_eq!(gp.predict(&training_inputs), prediction);
+        assert_eq!(gp.try_predict(&negative_inputs).err(), Some(expected));
+    }
+
+    #[test]
+    #[should_panic(expected = "GaussianProcess::add_samples: the training outputs contain a non-finite value")]
+    fn add_samples_panics_on_non_finite_samples()
+    {
+        let training_inputs = DMatrix::from_column_slice(2, 1, &[0.8, 1.2]);
+        let mut gp = GaussianProcess::default(training_inputs.clone(), DVector::from_column_slice(&[3.0, 4.0]));
+        gp.add_samples(&training_inputs, &DVector::from_column_slice(&[1.0, f64::NAN]));
+    }
+
+    #[test]
+    fn non_finite_training_data_is_rejected()
+    {
+        let training_inputs = DMatrix::from_column_slice(4, 2, &[0.8, 1.2, 3.8, 4.2, 0., 1., 2., 3.]);
+        let training_outputs = DVector::from_column_slice(&[3.0, 4.0, -2.0, -2.0]);
+        let make_gp = |inputs: DMatrix<f64>, outputs: DVector<f64>| {
+            GaussianProcess::try_new(prior::ZeroPrior {}, kernel::SquaredExp::default(), 0.1, None, inputs, outputs)
+        };
+
+        let mut nan_inputs = training_inputs.clone();
+        nan_inputs[(3, 1)] = f64::NAN;
+        let error = make_gp(nan_inputs, training_outputs.clone()).err();
+        assert_eq!(error, Some(FriedrichError::NonFiniteValue { data: "training inputs", row: 3 }));
+        assert_eq!(error.unwrap().to_string(),
+                   "the training inputs contain a non-finite value (NaN or infinite) at row 3");
+
+        let mut infinite_outputs = training_outputs.clone();
+        infinite_outputs[1] = f64::INFINITY;
+        let error = GaussianProcess::builder(training_inputs.clone(), infinite_outputs.clone()).try_train().err();
+        assert_eq!(error, Some(FriedrichError::NonFiniteValue { data: "training outputs", row: 1 }));
+
+        // the unchecked constructor skips the validation and produces non-finite predictions
+        let gp = GaussianProcess::new_unchecked(prior::ZeroPrior {},
+                                                kernel::SquaredExp::default(),
+                                                0.1,
+                                                None,
+                                                training_inputs.clone(),
+                                                infinite_outputs);
+        assert!(!gp.predict(&training_inputs).iter().all(|y| y.is_finite()));
+        let gp = GaussianProcess::new_unchecked(prior::ZeroPrior {},
+                                                kernel::SquaredExp::default(),
+                                                0.1,
+                                                None,
+                                                training_inputs.clone(),
+                                                training_outputs.clone());
+        let checked_gp = make_gp(training_inputs.clone(), training_outputs).unwrap();
+        assert_eq!(gp.predict(&training_inputs), checked_gp.predict(&training_inputs));
+    }
+
+    #[test]
+    fn cholesky_jitter_handles_duplicated_inputs()
+    {
+        let training_inputs = DMatrix::from_column_slice(5, 1, &[0.8, 1.2, 1.2, 3.8, 4.2]);
+        let training_outputs = DVector::from_column_slice(&[3.0, 4.0, 4.0, -2.0, -2.0]);
+        let result = GaussianProcess::builder(training_inputs.clone(), training_outputs.clone()).set_noise(0.)
+                                                                                                .try_train();
+        assert_eq!(result.err(), Some(FriedrichError::SingularMatrix));
+
+        let gp = GaussianProcess::builder(training_inputs.clone(), training_outputs.clone())
+            .set_noise(0.)
+            .set_cholesky_jitter(CholeskyJitter::default())
+            .fit_kernel()
+            .train();
+        assert_eq!(gp.cholesky_jitter, Some(CholeskyJitter::default()));
+        assert!((gp.predict(&training_inputs) - training_outputs).amax() < 1e-3);
+    }
+
+    #[test]
+    fn added_samples_share_the_jitter_of_the_decomposition()
+    {
+        let training_inputs = DMatrix::from_column_slice(5, 1, &[0.8, 1.2, 1.2, 3.8, 4.2]);
+        let training_outputs = DVector::from_column_slice(&[3.0, 4.0, 4.0, -2.0, -2.0]);
+        let mut gp = GaussianProcess::builder(training_inputs, training_outputs)
+            .set_noise(0.)
+            .set_cholesky_jitter(CholeskyJitter { initial: 1e-6, max_retries: 3 })
+            .train();
+        assert_eq!(gp.applied_jitter, 1e-6);
+
+        let new_inputs = DMatrix::from_column_slice(2, 1, &[2.5, 5.]);
+        let new_outputs = DVector::from_column_slice(&[1.0, -1.0]);
+        gp.add_samples(&new_inputs, &new_outputs);
+        let inputs = gp.training_inputs.as_matrix();
+        let expected = make_covariance_matrix(&inputs, &inputs, &gp.kernel)
+                       + DMatrix::identity(7, 7) * (gp.applied_jitter + gp.noise * gp.noise);
+        let l = gp.covmat_cholesky.l();
+        assert!((&l * l.transpose() - expected).amax() < 1e-12);
+    }
+
+    #[test]
+    fn fallible_methods_return_errors_rather_than_panicking()
+    {
+        let training_inputs = DMatrix::from_column_slice(5, 1, &[0.8, 1.2, 2.5, 3.8, 4.2]);
+        let training_outputs = DVector::from_column_slice(&[3.0, 4.0, 1.0, -2.0, -2.0]);
+        let make_gp = |noise: f64, inputs: DMatrix<f64>, outputs: DVector<f64>| {
+            GaussianProcess::try_new(prior::ZeroPrior {}, kernel::SquaredExp::default(), noise, None, inputs, outputs)
+        };
+
+        // InvalidParameter
+        let result = make_gp(-0.1, training_inputs.clone(), training_outputs.clone());
+        assert_eq!(result.err(), Some(FriedrichError::InvalidParameter { name: "noise", value: -0.1 }));
+
+        // DimensionMismatch
+        let result = make_gp(0.1, training_inputs.clone(), training_outputs.rows(0, 4).into_owned());
+        assert_eq!(result.err(), Some(FriedrichError::DimensionMismatch { expected: 5, found: 4 }));
+        let gp = make_gp(0.1, training_inputs.clone(), training_outputs.clone()).unwrap();
+        assert_eq!(gp.try_predict(&DMatrix::zeros(3, 2)).err(),
+                   Some(FriedrichError::DimensionMismatch { expected: 1, found: 2 }));
+        assert_eq!(gp.try_predict(&training_inputs).unwrap(), gp.predict(&training_inputs));
+
+        // SingularMatrix
+        let duplicated_inputs = DMatrix::from_column_slice(5, 1, &[1., 1., 2., 3., 4.]);
+        let result = make_gp(0., duplicated_inputs, training_outputs.clone());
+        assert_eq!(result.err(), Some(FriedrichError::SingularMatrix));
+
+        // NonFiniteValue (which would otherwise make the fit of the prior fail with a LinearSolveFailure)
+        let mut nan_outputs = training_outputs.clone();
+        nan_outputs[2] = f64::NAN;
+        let linear_prior = prior::LinearPrior::default(1);
+        let result = GaussianProcess::builder(training_inputs.clone(), nan_outputs).set_prior(linear_prior)
+                                                                                   .set_noise(0.1)
+                                                                                   .fit_prior()
+                                                                                   .try_train();
+        assert_eq!(result.err(), Some(FriedrichError::NonFiniteValue { data: "training outputs", row: 2 }));
+
+        // The fit is not attempted with an invalid configuration.
+        let mut gp = make_gp(0.1, training_inputs, training_outputs).unwrap();
+        let config = FitConfig { hyperpriors: vec![None; 2], ..FitConfig::default() };
+        assert_eq!(gp.try_fit_parameters_with_config(false, true, &config).err(),
+                   Some(FriedrichError::DimensionMismatch { expected: 3, found: 2 }));
+    }
+
+    #[test]
+    fn optimizers_return_an_error_when_the_covariance_matrix_becomes_singular()
+    {
+        // Duplicated inputs make the covariance matrix singular once the noise is bounded to zero.
+        let training_inputs = DMatrix::from_column_slice(5, 1, &[1., 1., 2., 3., 4.]);
+        let training_outputs = DVector::from_column_slice(&[3.0, 4.0, 1.0, -2.0, -2.0]);
+        let bounds = ParameterBounds::new(vec![f64::NEG_INFINITY, f64::NEG_INFINITY, 0.],
+                                          vec![f64::INFINITY, f64::INFINITY, 1e-300]).unwrap();
+        for optimizer in [Optimizer::Adam, Optimizer::Lbfgs, Optimizer::NelderMead]
+        {
+            let mut gp = GaussianProcess::new(prior::ZeroPrior {},
+                                              kernel::SquaredExp::default(),
+                                              0.1,
+                                              None,
+                                              training_inputs.clone(),
+                                              training_outputs.clone());
+            let predictions = gp.predict(&training_inputs);
+            let config = FitConfig { optimizer, bounds: Some(bounds.clone()), ..FitConfig::default() };
+            let result = gp.try_fit_parameters_with_config(false, true, &config);
+            assert_eq!(result.err(), Some(FriedrichError::SingularMatrix), "{:?}", optimizer);
+
+            // The model goes back to its initial parameters.
+            assert_eq!(gp.kernel.get_parameters(), kernel::SquaredExp::default().get_parameters());
+            assert_eq!(gp.noise, 0.1);
+            assert_eq!(gp.predict(&training_inputs), predictions);
+        }
+    }
+
+    #[test]
+    fn log_marginal_likelihood_gradient_matches_finite_differences()
+    {
+        let training_inputs = DMatrix::from_column_slice(5, 1, &[0.8, 1.2, 2.5, 3.8, 4.2]);
+        let training_outputs = DVector::from_column_slice(&[3.0, 4.0, 1.0, -2.0, -2.0]);
+        let make_gp = |parameters: &[f64]| {
+            GaussianProcess::new(prior::ZeroPrior {},
+                                 kernel::SquaredExp::new(parameters[0], parameters[1]),
+                                 parameters[2],
+                                 None,
+                                 training_inputs.clone(),
+                                 training_outputs.clone())
+        };
+
+        let parameters = [1.1, 4., 0.3];
+        let gradient = make_gp(&parameters).log_marginal_likelihood_gradient();
+        assert_eq!(gradient.len(), parameters.len());
+        for (i, analytic) in gradient.into_iter().enumerate()
+        {
+            let h = 1e-6;
+            let mut upper = parameters;
+            upper[i] += h;
+            let mut lower = parameters;
+            lower[i] -= h;
+            let numeric =
+                (make_gp(&upper).log_marginal_likelihood() - make_gp(&lower).log_marginal_likelihood()) / (2. * h);
+            assert!((analytic - numeric).abs() < 1e-5 * (1. + numeric.abs()),
+                    "parameter {}: analytic {} numeric {}",
+                    i,
+                    analytic,
+                    numeric);
+        }
+    }
+
+    #[test]
+    fn lbfgs_reaches_a_better_likelihood_than_adam_in_fewer_iterations()
+    {
+        let mut rng = StdRng::seed_from_u64(11);
+        let training_inputs: Vec<Vec<f64>> = (0..50).map(|i| vec![i as f64 * 0.12]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| (2. * x[0]).sin() + rng.gen_range(-0.1..0.1)).collect();
+        let make_gp = || {
+            GaussianProcess::new(prior::ZeroPrior {},
+                                 kernel::Matern52::default(),
+                                 0.5,
+                                 None,
+                                 training_inputs.clone(),
+                                 training_outputs.clone())
+        };
+
+        let mut adam = make_gp();
+        adam.fit_parameters_with_config(false, true, &FitConfig { convergence_fraction: 1e-3, ..FitConfig::default() });
+        let lbfgs_config =
+            FitConfig { max_iter: 20, convergence_fraction: 1e-3, optimizer: Optimizer::Lbfgs, ..FitConfig::default() };
+        let mut lbfgs = make_gp();
+        lbfgs.fit_parameters_with_config(false, true, &lbfgs_config);
+
+        assert!(lbfgs.log_marginal_likelihood() > make_gp().log_marginal_likelihood());
+        assert!(lbfgs.log_marginal_likelihood() >= adam.log_marginal_likelihood() - 1e-6);
+    }
+
+    #[test]
+    fn adam_moves_the_noise_toward_the_noise_of_the_data()
+    {
+        // The noise is fitted in log-space where a noise below one has a negative logarithm:
+        // the step has to be added to the logarithm (multiplying it would move a small noise in the wrong direction
+        // and a noise of one would never move).
+        // The polynomial kernel is not scalable, the noise being fitted by its gradient.
+        let mut rng = StdRng::seed_from_u64(11);
+        let training_inputs: Vec<Vec<f64>> = (0..40).map(|i| vec![i as f64 * 0.2]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| 0.5 * x[0] + rng.gen_range(-0.5..0.5)).collect();
+        for initial_noise in [0.01, 1.]
+        {
+            let mut gp = GaussianProcess::new(prior::ZeroPrior {},
+                                              kernel::Polynomial::new(1., 1., 1),
+                                              initial_noise,
+                                              None,
+                                              training_inputs.clone(),
+                                              training_outputs.clone());
+            let config = FitConfig { max_iter: 300, convergence_fraction: 1e-3, ..FitConfig::default() };
+            gp.fit_parameters_with_config(false, true, &config);
+            assert!(gp.noise > 0.15 && gp.noise < 0.5, "noise {} starting from {}", gp.noise, initial_noise);
+        }
+    }
+
+    #[test]
+    fn nelder_mead_converges_without_gradients()
+    {
+        let training_inputs: Vec<Vec<f64>> = (0..30).map(|i| vec![i as f64 * 0.2]).collect();
+        let mut rng = StdRng::seed_from_u64(5);
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| x[0].sin() + rng.gen_range(-0.1..0.1)).collect();
+        fn check<K: Kernel + Clone>(kernel: K, training_inputs: &[Vec<f64>], training_outputs: &[f64])
+            where DefaultBuilder: CovarianceBuilder<K>
+        {
+            let make_gp = || {
+                GaussianProcess::new(prior::ZeroPrior {},
+                                     kernel.clone(),
+                                     0.1,
+                                     None,
+                                     training_inputs.to_vec(),
+                                     training_outputs.to_vec())
+            };
+            let fit = |optimizer: Optimizer| {
+                let mut gp = make_gp();
+                let config = FitConfig { max_iter: 500,
+                                         convergence_fraction: 1e-4,
+                                         optimizer,
+                                         record_trace: true,
+                                         ..FitConfig::default() };
+                let trace = gp.try_fit_parameters_with_config(false, true, &config).unwrap();
+                (trace, gp.log_marginal_likelihood())
+            };
+
+            let (trace, likelihood) = fit(Optimizer::NelderMead);
+            assert!(trace.len() < 500, "no convergence within {} iterations", trace.len());
+            assert!(trace.iter().all(|step| step.gradient_norm == 0.));
+            let (_, lbfgs_likelihood) = fit(Optimizer::Lbfgs);
+            assert!(likelihood > make_gp().log_marginal_likelihood());
+            assert!(likelihood >= lbfgs_likelihood - 1e-2, "{} against {}", likelihood, lbfgs_likelihood);
+
+            // the convenience method uses the same algorithm
+            let mut gp = make_gp();
+            gp.optimize_parameters_simplex(500, 1e-4);
+            assert!((gp.log_marginal_likelihood() - likelihood).abs() < 1e-9);
+        }
+        check(kernel::SquaredExp::default(), &training_inputs, &training_outputs);
+        check(kernel::Matern52::default(), &training_inputs, &training_outputs);
+        check(kernel::Exponential::default(), &training_inputs, &training_outputs);
+    }
+
+    #[test]
+    fn lbfgs_converges_in_fewer_iterations_than_adam()
+    {
+        let training_inputs: Vec<Vec<f64>> = (0..30).map(|i| vec![i as f64 * 0.2]).collect();
+        let mut rng = StdRng::seed_from_u64(3);
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| x[0].sin() + rng.gen_range(-0.1..0.1)).collect();
+        let make_gp = || {
+            GaussianProcess::new(prior::ZeroPrior {},
+                                 kernel::SquaredExp::default(),
+                                 0.1,
+                                 None,
+                                 training_inputs.clone(),
+                                 training_outputs.clone())
+        };
+        let iterations = |optimizer: Optimizer| {
+            let mut gp = make_gp();
+            let config = FitConfig { max_iter: 500,
+                                     convergence_fraction: 1e-4,
+                                     optimizer,
+                                     record_trace: true,
+                                     ..FitConfig::default() };
+            let trace = gp.try_fit_parameters_with_config(false, true, &config).unwrap();
+            (trace.len(), gp.log_marginal_likelihood())
+        };
+
+        let (adam_iterations, adam_likelihood) = iterations(Optimizer::Adam);
+        let (lbfgs_iterations, lbfgs_likelihood) = iterations(Optimizer::Lbfgs);
+        assert!(lbfgs_iterations < adam_iterations, "{} iterations against {}", lbfgs_iterations, adam_iterations);
+        assert!(lbfgs_likelihood >= adam_likelihood - 1e-3, "{} against {}", lbfgs_likelihood, adam_likelihood);
+
+        // the convenience method uses the same algorithm
+        let mut gp = make_gp();
+        gp.optimize_parameters_lbfgs(500, 1e-4);
+        assert!((gp.log_marginal_likelihood() - lbfgs_likelihood).abs() < 1e-9);
+    }
+
+    #[test]
+    fn loo_log_likelihood_matches_refitting_without_each_point()
+    {
+        let inputs = [0.8, 1.2, 2.5, 3.8, 4.2];
+        let outputs = [3.0, 4.0, 1.0, -2.0, -2.0];
+        let kernel = kernel::SquaredExp::new(1.1, 4.);
+        let noise = 0.3;
+        let gp = GaussianProcess::new(prior::ZeroPrior {},
+                                      kernel,
+                                      noise,
+                                      None,
+                                      inputs.iter().map(|&x| vec![x]).collect::<Vec<_>>(),
+                                      outputs.to_vec());
+
+        let mut expected = 0.;
+        for i in 0..inputs.len()
+        {
+            let others = |values: &[f64]| -> Vec<f64> {
+                values.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, &v)| v).collect()
+            };
+            let training_inputs: Vec<Vec<f64>> = others(&inputs).into_iter().map(|x| vec![x]).collect();
+            let loo_gp =
+                GaussianProcess::new(prior::ZeroPrior {}, kernel, noise, None, training_inputs, others(&outputs));
+            let (mean, variance) = loo_gp.predict_mean_variance(&vec![inputs[i]]);
+            let variance = variance + noise * noise;
+            expected += -0.5 * (2. * std::f64::consts::PI * variance).ln();
+            expected -= (outputs[i] - mean).powi(2) / (2. * variance);
+        }
+        assert!((gp.loo_log_likelihood() - expected).abs() < 1e-8);
+    }
+
+    #[test]
+    fn loo_predictions_match_refitting_without_each_point()
+    {
+        let inputs = [0.8, 1.2, 2.5, 3.8, 4.2];
+        let outputs = [3.0, 4.0, 1.0, -2.0, -2.0];
+        let kernel = kernel::SquaredExp::new(1.1, 4.);
+        let noise = 0.3;
+        let prior = prior::ConstantPrior::new(0.5);
+        let gp = GaussianProcess::new(prior.clone(),
+                                      kernel,
+                                      noise,
+                                      None,
+                                      inputs.iter().map(|&x| vec![x]).collect::<Vec<_>>(),
+                                      outputs.to_vec());
+
+        let (means, variances) = gp.loo_predictions();
+        let mut log_probability = 0.;
+        for i in 0..inputs.len()
+        {
+            let others = |values: &[f64]| -> Vec<f64> {
+                values.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, &v)| v).collect()
+            };
+            let training_inputs: Vec<Vec<f64>> = others(&inputs).into_iter().map(|x| vec![x]).collect();
+            let loo_gp = GaussianProcess::new(prior.clone(), kernel, noise, None, training_inputs, others(&outputs));
+            let (mean, variance) = loo_gp.predict_mean_variance(&vec![inputs[i]]);
+            assert!((means[i] - mean).abs() < 1e-8, "mean {} against {}", means[i], mean);
+            assert!((variances[i] - (variance + noise * noise)).abs() < 1e-8);
+            log_probability += -0.5 * (2. * std::f64::consts::PI * variances[i]).ln();
+            log_probability -= (outputs[i] - means[i]).powi(2) / (2. * variances[i]);
+        }
+        assert!((gp.loo_log_likelihood() - log_probability).abs() < 1e-8);
+        assert!((gp.loo_log_probability() - log_probability).abs() < 1e-8);
+
+        // With standardized outputs, the log probability is expressed at the scale of the outputs.
+        let gp = GaussianProcess::builder(inputs.iter().map(|&x| vec![x]).collect::<Vec<_>>(), outputs.to_vec())
+            .set_kernel(kernel)
+            .set_prior(prior::ConstantPrior::new(0.5))
+            .set_noise(noise)
+            .standardize_outputs()
+            .train();
+        let (means, variances) = gp.loo_predictions();
+        let log_probability: f64 = (0..inputs.len()).map(|i| {
+                                                        -0.5 * (2. * std::f64::consts::PI * variances[i]).ln()
+                                                        - (outputs[i] - means[i]).powi(2) / (2. * variances[i])
+                                                    })
+                                                    .sum();
+        assert!((gp.loo_log_probability() - log_probability).abs() < 1e-8);
+    }
+
+    #[test]
+    fn loo_log_likelihood_gradient_matches_finite_differences()
+    {
+        let training_inputs = DMatrix::from_column_slice(5, 1, &[0.8, 1.2, 2.5, 3.8, 4.2]);
+        let training_outputs = DVector::from_column_slice(&[3.0, 4.0, 1.0, -2.0, -2.0]);
+        let make_gp = |parameters: &[f64]| {
+            GaussianProcess::new(prior::ZeroPrior {},
+                                 kernel::SquaredExp::new(parameters[0], parameters[1]),
+                                 parameters[2],
+                                 None,
+                                 training_inputs.clone(),
+                                 training_outputs.clone())
+        };
+
+        let parameters = [1.1, 4., 0.3];
+        let gradient = make_gp(&parameters).loo_log_likelihood_gradient();
+        assert_eq!(gradient.len(), parameters.len());
+        for (i, analytic) in gradient.into_iter().enumerate()
+        {
+            let h = 1e-6;
+            let mut upper = parameters;
+            upper[i] += h;
+            let mut lower = parameters;
+            lower[i] -= h;
+            let numeric = (make_gp(&upper).loo_log_likelihood() - make_gp(&lower).loo_log_likelihood()) / (2. * h);
+            assert!((analytic - numeric).abs() < 1e-5 * (1. + numeric.abs()),
+                    "parameter {}: analytic {} numeric {}",
+                    i,
+                    analytic,
+                    numeric);
+        }
+    }
+
+    #[test]
+    fn both_objectives_generalize_to_held_out_data()
+    {
+        let mut rng = StdRng::seed_from_u64(13);
+        let training_inputs: Vec<Vec<f64>> = (0..40).map(|i| vec![i as f64 * 0.15]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| x[0].sin() + rng.gen_range(-0.1..0.1)).collect();
+
+        for objective in [ObjectiveFunction::MarginalLikelihood, ObjectiveFunction::LooLogLikelihood]
+        {
+            for optimizer in [Optimizer::Adam, Optimizer::Lbfgs, Optimizer::NelderMead]
+            {
+                let gp = GaussianProcess::builder(training_inputs.clone(), training_outputs.clone())
+                    .set_kernel(kernel::Matern52::default())
+                    .set_objective(objective)
+                    .set_optimizer(optimizer)
+                    .fit_kernel()
+                    .train();
+                for x in [0.4, 1.7, 3.3, 5.1]
+                {
+                    let prediction = gp.predict(&vec![x]);
+                    assert!((prediction - f64::sin(x)).abs() < 0.1,
+                            "{:?} {:?}: prediction {} at {}",
+                            objective,
+                            optimizer,
+                            prediction,
+                            x);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_bounds_are_rejected()
+    {
+        let training_inputs = vec![vec![0.8], vec![1.2], vec![3.8], vec![4.2]];
+        let training_outputs = vec![3.0, 4.0, -2.0, -2.0];
+        let mut gp = GaussianProcess::default(training_inputs, training_outputs);
+
+        let result = gp.fit_parameters_with_bounds(false, true, &[0., 0., 1.], &[1., 1., 1.]);
+        assert_eq!(result, Err(FriedrichError::InvalidBounds { index: 2, lower: 1., upper: 1. }));
+        let result = gp.fit_parameters_with_bounds(false, true, &[0., 0.], &[1., 1.]);
+        assert_eq!(result, Err(FriedrichError::DimensionMismatch { expected: 3, found: 2 }));
+
+        // The optimizers never change the sign of a parameter, bounds crossing zero are accepted.
+        let noise = gp.noise;
+        let result = gp.fit_parameters_with_bounds(false, true, &[0., 0., -2.], &[1., 1., -1.]);
+        assert_eq!(result, Err(FriedrichError::BoundsExcludeSign { index: 2, value: noise }));
+        let result = gp.fit_parameters_with_bounds(false, true, &[-10., -10., -1.], &[10., 10., 1.]);
+        assert_eq!(result, Ok(()));
+
+        // The builder checks the bounds before training.
+        let bounds = ParameterBounds::new(vec![0., 0.], vec![1., 1.]).unwrap();
+        let result = GaussianProcess::builder(vec![vec![0.8], vec![1.2]], vec![3.0, 4.0]).set_parameter_bounds(bounds)
+                                                                                         .fit_kernel()
+                                                                                         .try_train();
+        assert_eq!(result.err(), Some(FriedrichError::DimensionMismatch { expected: 3, found: 2 }));
+    }
+
+    #[test]
+    fn noise_lower_bound_keeps_the_covariance_well_conditioned()
+    {
+        // Dense, noiseless and smooth data pushes the noise toward zero.
+        let training_inputs: Vec<Vec<f64>> = (0..60).map(|i| vec![i as f64 * 0.05]).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| x[0].sin()).collect();
+        let lower = [1e-3, 1e-3, 1e-2];
+        let upper = [f64::INFINITY, f64::INFINITY, f64::INFINITY];
+
+        for optimizer in [Optimizer::Adam, Optimizer::Lbfgs, Optimizer::NelderMead]
+        {
+            let mut gp = GaussianProcess::new(prior::ZeroPrior {},
+                                              kernel::SquaredExp::default(),
+                                              0.1,
+                                              None,
+                                              training_inputs.clone(),
+                                              training_outputs.clone());
+            let bounds = ParameterBounds::new(lower.to_vec(), upper.to_vec()).unwrap();
+            let config = FitConfig { optimizer, bounds: Some(bounds), ..FitConfig::default() };
+            gp.fit_parameters_with_config(false, true, &config);
+            assert!(gp.noise >= 1e-2 * (1. - 1e-12), "{:?}: noise {}", optimizer, gp.noise);
+            assert!(gp.log_marginal_likelihood().is_finite());
+        }
+    }
+
+    #[test]
+    fn length_scale_stays_in_its_bounds_during_long_runs()
+    {
+        // Smooth data would lead to a length scale well above the upper bound.
+        let training_inputs: Vec<Vec<f64>> = (0..40).map(|i| vec![i as f64 * 0.2]).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| x[0].sin()).collect();
+        let lower = vec![0.1, 1e-3, 1e-3];
+        let upper = vec![0.5, 1e3, 1e3];
+
+        for optimizer in [Optimizer::Adam, Optimizer::Lbfgs, Optimizer::NelderMead]
+        {
+            let mut gp = GaussianProcess::new(prior::ZeroPrior {},
+                                              kernel::SquaredExp::new(1., 1.),
+                                              0.1,
+                                              None,
+                                              training_inputs.clone(),
+                                              training_outputs.clone());
+            let bounds = ParameterBounds::new(lower.clone(), upper.clone()).unwrap();
+            let config = FitConfig { optimizer, max_iter: 1000, bounds: Some(bounds), ..FitConfig::default() };
+            gp.fit_parameters_with_config(false, true, &config);
+            let ls = gp.kernel.get_parameters()[0];
+            assert!((0.1..=0.5).contains(&ls), "{:?}: length scale {}", optimizer, ls);
+            assert!((ls - 0.5).abs() < 1e-9, "{:?}: the upper bound should be active, length scale {}", optimizer, ls);
+        }
+    }
+
+    #[test]
+    fn scaled_kernel_uses_the_scaled_optimizer()
+    {
+        let mut rng = StdRng::seed_from_u64(17);
+        let training_inputs: Vec<Vec<f64>> = (0..30).map(|i| vec![i as f64 * 0.1 - 1.5]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| 5. * x[0] * x[0] - 2. * x[0] + rng.gen_range(-0.2..0.2)).collect();
+        let kernel = kernel::Scaled::new(kernel::Polynomial::new(1., 1., 2), 1.);
+        let mut gp = GaussianProcess::new(prior::ZeroPrior {}, kernel, 0.1, None, training_inputs, training_outputs);
+        let likelihood_before = gp.log_marginal_likelihood();
+        gp.scaled_optimize_parameters::<Serial>(&FitConfig::default()).unwrap();
+
+        assert!(gp.log_marginal_likelihood() > likelihood_before);
+        let prediction = gp.predict(&vec![2.]);
+        assert!((prediction - 16.).abs() < 0.5, "prediction {}", prediction);
+    }
+
+    #[test]
+    fn restarts_are_reproducible_and_never_worse()
+    {
+        let mut rng = StdRng::seed_from_u64(19);
+        let training_inputs: Vec<Vec<f64>> = (0..40).map(|i| vec![i as f64 * 0.15]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| (3. * x[0]).sin() + rng.gen_range(-0.1..0.1)).collect();
+        let fit = |nb_restarts: usize| {
+            let mut gp = GaussianProcess::new(prior::ZeroPrior {},
+                                              kernel::Matern52::new(0.05, 1.),
+                                              0.5,
+                                              None,
+                                              training_inputs.clone(),
+                                              training_outputs.clone());
+            gp.fit_parameters_with_config(false, true, &FitConfig { nb_restarts, seed: 3, ..FitConfig::default() });
+            gp
+        };
+
+        let single = fit(0);
+        let restarted = fit(5);
+        assert!(restarted.log_marginal_likelihood() >= single.log_marginal_likelihood() - 1e-9);
+        let again = fit(5);
+        assert_eq!(restarted.kernel.get_parameters(), again.kernel.get_parameters());
+        assert_eq!(restarted.noise, again.noise);
+
+        let mut with_restarts = fit(0);
+        with_restarts.fit_with_restarts(5, 3, 100, 0.05, Duration::from_secs(3600));
+        assert!(with_restarts.log_marginal_likelihood() >= single.log_marginal_likelihood() - 1e-9);
+    }
+
+    #[test]
+    fn multistart_escapes_a_poor_local_mode()
+    {
+        use std::f64::consts::PI;
+        let mut rng = StdRng::seed_from_u64(23);
+        let training_inputs: Vec<Vec<f64>> = (0..40).map(|i| vec![i as f64 * 0.25]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| (2. * PI * x[0] / 3.).sin() + rng.gen_range(-0.05..0.05)).collect();
+        let fit = |initial_period: f64, nb_starts: usize| {
+            let mut gp = GaussianProcess::new(prior::ZeroPrior {},
+                                              kernel::Periodic::new(1., initial_period, 1.),
+                                              0.1,
+                                              None,
+                                              training_inputs.clone(),
+                                              training_outputs.clone());
+            gp.fit_parameters_multistart(nb_starts, 100, 0.05, Duration::from_secs(3600));
+            gp.log_marginal_likelihood()
+        };
+
+        // The likelihood of the periodic kernel is highly multimodal in the period,
+        // starting from a poor period the single run stays stuck in a mode that the restarts escape.
+        let results: Vec<(f64, f64)> =
+            [1.3, 2.2, 4.5, 6.1].iter().map(|&period| (fit(period, 1), fit(period, 6))).collect();
+        let best = results.iter().map(|(single, multistart)| single.max(*multistart)).fold(f64::NEG_INFINITY, f64::max);
+        let nb_successes = |likelihoods: Vec<f64>| likelihoods.iter().filter(|&&l| l > best - 5.).count();
+        let nb_single_successes = nb_successes(results.iter().map(|(single, _)| *single).collect());
+        let nb_multistart_successes = nb_successes(results.iter().map(|(_, multistart)| *multistart).collect());
+        assert!(nb_multistart_successes > nb_single_successes, "results {:?}", results);
+        assert_eq!(nb_multistart_successes, results.len(), "results {:?}", results);
+
+        // The first start being the single run, the best of all starts is never worse.
+        for (single, multistart) in results
+        {
+            assert!(multistart >= single - 1e-9, "multistart {} single {}", multistart, single);
+        }
+    }
+
+    #[test]
+    fn adam_learning_rate_controls_the_step_size()
+    {
+        let training_inputs = vec![vec![0.8], vec![1.2], vec![2.5], vec![3.8], vec![4.2]];
+        let training_outputs = vec![3.0, 4.0, 1.0, -2.0, -2.0];
+        let single_step = |learning_rate: f64| {
+            let mut gp = GaussianProcess::new(prior::ZeroPrior {},
+                                              kernel::Matern52::new(1., 1.),
+                                              0.3,
+                                              None,
+                                              training_inputs.clone(),
+                                              training_outputs.clone());
+            let adam = AdamConfig { learning_rate, ..AdamConfig::default() };
+            gp.optimize_parameters::<Serial>(&FitConfig { max_iter: 1, adam, ..FitConfig::default() }).unwrap();
+            (gp.kernel.ls - 1.).abs()
+        };
+
+        // The first ADAM step changes each parameter by `learning_rate` time its value.
+        assert!((single_step(0.1) - 0.1).abs() < 1e-6);
+        assert!((single_step(0.01) - 0.01).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cosine_kernel_trains_on_a_pure_sinusoid()
+    {
+        let mut rng = StdRng::seed_from_u64(3);
+        let training_inputs: Vec<Vec<f64>> = (0..40).map(|i| vec![i as f64 * 0.15]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| 2. * (x[0] * 2.).cos() + rng.gen_range(-0.05..0.05)).collect();
+
+        for optimizer in [Optimizer::Adam, Optimizer::Lbfgs, Optimizer::NelderMead]
+        {
+            let kernel = kernel::Cosine::new(3., 1.);
+            let mut gp = GaussianProcess::new(prior::ZeroPrior {},
+                                              kernel,
+                                              0.1,
+                                              Some(1e-8),
+                                              training_inputs.clone(),
+                                              training_outputs.clone());
+            let config = FitConfig { optimizer, max_iter: 500, convergence_fraction: 1e-4, ..FitConfig::default() };
+            gp.fit_parameters_with_config(false, true, &config);
+            assert!(gp.log_marginal_likelihood().is_finite());
+            let period = gp.kernel.period;
+            assert!((period - std::f64::consts::PI).abs() < 0.05, "{:?}: period {}", optimizer, period);
+            // No decay between periods: the extrapolation stays accurate far from the data.
+            let prediction = gp.predict(&vec![20.]);
+            assert!((prediction - 2. * 40f64.cos()).abs() < 0.2, "{:?}: prediction {}", optimizer, prediction);
+        }
+    }
+
+    #[test]
+    fn fixed_parameters_are_not_fitted()
+    {
+        // The period of the signal is known exactly, only the length scale and amplitude are fitted.
+        let training_inputs: Vec<Vec<f64>> = (0..40).map(|i| vec![i as f64 * 0.25]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| (2. * std::f64::consts::PI * x[0] / 3.).sin()).collect();
+        for optimizer in [Optimizer::Adam, Optimizer::Lbfgs, Optimizer::NelderMead]
+        {
+            let gp = GaussianProcess::builder(training_inputs.clone(), training_outputs.clone())
+                .set_kernel(kernel::Periodic::new(2., 3., 0.5))
+                .set_noise(0.05)
+                .set_optimizer(optimizer)
+                .set_restarts(2, 0)
+                .fix_parameters(&[1, 3])
+                .fit_kernel()
+                .train();
+            assert_eq!(gp.kernel.period, 3., "{:?}", optimizer);
+            assert_eq!(gp.noise, 0.05, "{:?}", optimizer);
+            assert!(gp.kernel.ls != 2. && gp.kernel.ampl != 0.5, "{:?}: {:?}", optimizer, gp.kernel);
+        }
+
+        // The index of a fixed parameter must refer to a kernel parameter or the noise.
+        let mut gp = GaussianProcess::default(training_inputs, training_outputs);
+        let config = FitConfig { fixed_parameters: vec![3], ..FitConfig::default() };
+        let result = gp.try_fit_parameters_with_config(false, true, &config);
+        assert_eq!(result.unwrap_err(), FriedrichError::IndexOutOfBounds { index: 3, len: 3 });
+    }
+
+    #[test]
+    fn hyperpriors_regularize_the_length_scale()
+    {
+        use crate::parameters::hyperprior::{Gamma, HyperPrior, LogNormal};
+        use std::sync::Arc;
+
+        // A handful of noisy points gives little information on the length scale.
+        let mut rng = StdRng::seed_from_u64(11);
+        let training_inputs: Vec<Vec<f64>> = (0..6).map(|i| vec![i as f64 * 0.7]).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|_| rng.gen_range(-1.0..1.0)).collect();
+        let make_gp = || {
+            GaussianProcess::new(prior::ZeroPrior {},
+                                 kernel::SquaredExp::new(1., 1.),
+                                 0.1,
+                                 None,
+                                 training_inputs.clone(),
+                                 training_outputs.clone())
+        };
+
+        let config = FitConfig { optimizer: Optimizer::Lbfgs, ..FitConfig::default() };
+        let mut gp = make_gp();
+        gp.fit_parameters_with_config(false, true, &config);
+        let ml_likelihood = gp.log_marginal_likelihood();
+        let ml_ls = gp.kernel.ls;
+
+        // A tight log-normal hyperprior around a length scale of 2.
+        let ls_prior: Arc<dyn HyperPrior> = Arc::new(LogNormal::new(2f64.ln(), 0.05));
+        let noise_prior: Arc<dyn HyperPrior> = Arc::new(Gamma::new(2., 1.));
+        let hyperpriors = vec![Some(ls_prior), None, Some(noise_prior)];
+        for optimizer in [Optimizer::Adam, Optimizer::Lbfgs]
+        {
+            let config = FitConfig { optimizer, hyperpriors: hyperpriors.clone(), ..FitConfig::default() };
+            let mut gp = make_gp();
+            gp.fit_parameters_with_config(false, true, &config);
+            let ls = gp.kernel.ls;
+            assert!((ls - 2.).abs() < 0.5, "{:?}: length scale {} (maximum likelihood {})", optimizer, ls, ml_ls);
+            // The MAP estimate trades some likelihood for the prior.
+            assert!(gp.log_marginal_likelihood() <= ml_likelihood + 1e-6);
+        }
+    }
+
+    #[test]
+    fn gamma_exponential_exponent_distinguishes_rough_from_smooth_data()
+    {
+        let mut rng = StdRng::seed_from_u64(8);
+        let training_inputs: Vec<Vec<f64>> = (0..100).map(|i| vec![i as f64 * 0.1]).collect();
+        // A random walk is rough while a sinusoid is smooth.
+        let mut position = 0.;
+        let rough_outputs: Vec<f64> = training_inputs.iter()
+                                                     .map(|_| {
+                                                         position += rng.gen_range(-1.0..1.0);
+                                                         position
+                                                     })
+                                                     .collect();
+        let smooth_outputs: Vec<f64> = training_inputs.iter().map(|x| x[0].sin()).collect();
+
+        let fitted_gamma = |training_outputs: Vec<f64>| {
+            let mut gp = GaussianProcess::new(prior::ConstantPrior::new(0.),
+                                              kernel::GammaExponential::default(),
+                                              0.1,
+                                              Some(1e-8),
+                                              training_inputs.clone(),
+                                              training_outputs);
+            let config = FitConfig { optimizer: Optimizer::Lbfgs, ..FitConfig::default() };
+            gp.fit_parameters_with_config(true, true, &config);
+            gp.kernel.gamma
+        };
+        let rough_gamma = fitted_gamma(rough_outputs);
+        let smooth_gamma = fitted_gamma(smooth_outputs);
+        assert!(rough_gamma < 1.5, "rough gamma {}", rough_gamma);
+        assert!(smooth_gamma > 1.8, "smooth gamma {}", smooth_gamma);
+        // the exponent is kept within the bounds declared by the kernel
+        assert!(smooth_gamma <= 2., "smooth gamma {}", smooth_gamma);
+    }
+
+    #[test]
+    fn optimization_trace_records_each_iteration()
+    {
+        let mut rng = StdRng::seed_from_u64(4);
+        let training_inputs: Vec<Vec<f64>> = (0..30).map(|i| vec![i as f64 * 0.2]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| x[0].sin() + rng.gen_range(-0.1..0.1)).collect();
+        let make_gp = || {
+            GaussianProcess::new(prior::ZeroPrior {},
+                                 kernel::SquaredExp::new(3., 0.5),
+                                 0.5,
+                                 None,
+                                 training_inputs.clone(),
+                                 training_outputs.clone())
+        };
+
+        // No trace by default.
+        assert!(make_gp().fit_parameters_with_config(false, true, &FitConfig::default()).is_empty());
+
+        for optimizer in [Optimizer::Adam, Optimizer::Lbfgs]
+        {
+            let config = FitConfig { optimizer, record_trace: true, nb_restarts: 1, ..FitConfig::default() };
+            let mut gp = make_gp();
+            let trace = gp.fit_parameters_with_config(false, true, &config);
+            let first_run: Vec<&OptimStep> = trace.iter().filter(|step| step.restart == 0).collect();
+            assert!(first_run.len() > 1);
+            assert!(first_run.len() <= config.max_iter);
+            assert!(trace.iter().any(|step| step.restart == 1));
+            for (index, step) in first_run.iter().enumerate()
+            {
+                assert_eq!(step.iteration, index + 1);
+                assert_eq!(step.parameters.len(), 2);
+                assert!(step.gradient_norm.is_finite());
+            }
+            // The first step is the initial state and the fit improves the likelihood.
+            assert!((first_run[0].parameters[0] - 3.).abs() < 1e-12);
+            assert!((first_run[0].parameters[1] - 0.5).abs() < 1e-12);
+            assert!((first_run[0].noise - 0.5).abs() < 1e-12);
+            assert!(first_run.last().unwrap().log_likelihood > first_run[0].log_likelihood);
+        }
+
+        // The Nelder-Mead trace follows the best vertex, whose likelihood never decreases.
+        let config = FitConfig { optimizer: Optimizer::NelderMead, record_trace: true, ..FitConfig::default() };
+        let trace = make_gp().fit_parameters_with_config(false, true, &config);
+        assert!(trace.len() > 1);
+        assert!((trace[0].parameters[0] - 3.).abs() < 1e-12);
+        assert!((trace[0].noise - 0.5).abs() < 1e-12);
+        for (index, steps) in trace.windows(2).enumerate()
+        {
+            assert_eq!(steps[0].iteration, index + 1);
+            assert_eq!(steps[0].gradient_norm, 0.);
+            assert!(steps[1].log_likelihood >= steps[0].log_likelihood);
+        }
+    }
+
+    #[test]
+    fn likelihood_tolerance_stops_the_fit_once_the_likelihood_stalls()
+    {
+        let mut rng = StdRng::seed_from_u64(6);
+        let training_inputs: Vec<Vec<f64>> = (0..30).map(|i| vec![i as f64 * 0.2]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().map(|x| x[0].sin() + rng.gen_range(-0.1..0.1)).collect();
+        let make_gp = || {
+            GaussianProcess::new(prior::ZeroPrior {},
+                                 kernel::SquaredExp::new(3., 0.5),
+                                 0.5,
+                                 None,
+                                 training_inputs.clone(),
+                                 training_outputs.clone())
+        };
+
+        for optimizer in [Optimizer::Adam, Optimizer::Lbfgs, Optimizer::NelderMead]
+        {
+            // Without any convergence criterion, the optimizer runs for `max_iter` iterations.
+            let config = FitConfig { optimizer,
+                                     max_iter: 200,
+                                     convergence_fraction: 0.,
+                                     record_trace: true,
+                                     ..FitConfig::default() };
+            let full_trace = make_gp().fit_parameters_with_config(false, true, &config);
+
+            let config = FitConfig { likelihood_tolerance: Some(1e-4), ..config };
+            let mut gp = make_gp();
+            let trace = gp.fit_parameters_with_config(false, true, &config);
+            assert!(trace.len() < full_trace.len(), "{:?}: {} >= {}", optimizer, trace.len(), full_trace.len());
+            let last_likelihood = trace.last().unwrap().log_likelihood;
+            assert!((gp.log_marginal_likelihood() - last_likelihood).abs() <= 1e-4 * last_likelihood.abs().max(1.));
+        }
+    }
+
+    #[test]
+    fn arcsine_kernel_extrapolates_a_saturating_function()
+    {
+        // A smoothed step function which saturates at -1 and 1.
+        let training_inputs: Vec<Vec<f64>> = (0..40).map(|i| vec![i as f64 * 0.2 - 4.]).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| (3. * x[0]).tanh()).collect();
+        let mut gp = GaussianProcess::new(prior::ZeroPrior {},
+                                          kernel::ArcSine::default(),
+                                          0.05,
+                                          Some(1e-6),
+                                          training_inputs,
+                                          training_outputs);
+        let config = FitConfig { optimizer: Optimizer::Lbfgs, ..FitConfig::default() };
+        gp.fit_parameters_with_config(false, true, &config);
+
+        // Far from the data, a stationary kernel would go back to the prior (zero) while the arcsine kernel saturates.
+        let (left, right) = (gp.predict(&vec![-20.]), gp.predict(&vec![20.]));
+        assert!(left < -0.8 && right > 0.8, "predictions {} and {}", left, right);
+    }
+
+    #[test]
+    fn brownian_kernel_models_a_random_walk()
+    {
+        let mut rng = StdRng::seed_from_u64(12);
+        let training_inputs: Vec<Vec<f64>> = (1..=50).map(|i| vec![i as f64 * 0.1]).collect();
+        let mut position = 0.;
+        let training_outputs: Vec<f64> = training_inputs.iter()
+                                                        .map(|_| {
+                                                            position += rng.gen_range(-0.5..0.5);
+                                                            position
+                                                        })
+                                                        .collect();
+        let mut gp = GaussianProcess::new(prior::ZeroPrior {},
+                                          kernel::Brownian::default(),
+                                          0.01,
+                                          None,
+                                          training_inputs,
+                                          training_outputs);
+        let config = FitConfig { optimizer: Optimizer::Lbfgs, ..FitConfig::default() };
+        gp.fit_parameters_with_config(false, true, &config);
+        let variance = gp.kernel.variance;
+        // A step of variance 1/12 every 0.1 is a variance of 5/6 per unit of input.
+        assert!((variance - 5. / 6.).abs() < 0.4, "variance {}", variance);
+
+        // The process is exactly null at the origin.
+        assert_eq!(gp.predict(&vec![0.]), 0.);
+        assert_eq!(gp.predict_variance(&vec![0.]), 0.);
+        // Beyond the data, the variance grows linearly with the distance to the last point.
+        let growth = gp.predict_variance(&vec![10.]) - gp.predict_variance(&vec![7.]);
+        assert!((growth - 3. * variance).abs() < 1e-9, "growth {}", growth);
+    }
+
+    #[test]
+    fn predicted_covariance_matches_the_explicit_formula()
+    {
+        let training_inputs = vec![vec![0.8, 0.1], vec![1.2, -0.4], vec![2.5, 0.3], vec![3.8, 1.], vec![4.2, 0.]];
+        let training_outputs = vec![3.0, 4.0, 1.0, -2.0, -2.0];
+        let gp = GaussianProcess::new(prior::ZeroPrior {},
+                                      kernel::SquaredExp::new(1.1, 2.),
+                                      0.3,
+                                      None,
+                                      training_inputs.clone(),
+                                      training_outputs);
+        let inputs = vec![vec![0.5, 0.], vec![1., 0.2], vec![2.9, -1.], vec![5., 0.5]];
+        let covariance = gp.predict_covariance(&inputs);
+
+        let training_inputs = Vec::<Vec<f64>>::to_dmatrix(&training_inputs);
+        let test_inputs = Vec::<Vec<f64>>::to_dmatrix(&inputs);
+        let mut training_covariance = make_covariance_matrix(&training_inputs, &training_inputs, &gp.kernel);
+        training_covariance += DMatrix::identity(5, 5) * 0.3 * 0.3;
+        let cross_covariance = make_covariance_matrix(&training_inputs, &test_inputs, &gp.kernel);
+        let expected = make_covariance_matrix(&test_inputs, &test_inputs, &gp.kernel)
+                       - cross_covariance.transpose() * training_covariance.try_inverse().unwrap() * &cross_covariance;
+        assert!((&covariance - expected).amax() < 1e-10);
+
+        let variances = gp.predict_variance(&inputs);
+        for (i, variance) in variances.iter().enumerate()
+        {
+            assert!((covariance[(i, i)] - variance).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn batch_predictions_match_the_predictions_of_each_input()
+    {
+        let training_inputs: Vec<Vec<f64>> = (0..50).map(|i| vec![i as f64 * 0.2, (i as f64).cos()]).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| x[0].sin() + x[1]).collect();
+        let gp = GaussianProcess::builder(training_inputs, training_outputs).set_prior(prior::LinearPrior::default(2))
+                                                                            .fit_prior()
+                                                                            .train();
+
+        let inputs: Vec<Vec<f64>> = (0..1000).map(|i| vec![i as f64 * 0.01, (i as f64 * 0.3).sin()]).collect();
+        let means = gp.predict(&inputs);
+        let variances = gp.predict_variance(&inputs);
+        let means_variances = gp.predict_mean_variance(&inputs);
+        for (i, input) in inputs.iter().enumerate()
+        {
+            assert_eq!(gp.predict(input), means[i]);
+            assert_eq!(gp.predict_variance(input), variances[i]);
+            assert_eq!(gp.predict_mean_variance(input), (means_variances.0[i], means_variances.1[i]));
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_fit_matches_the_serial_fit()
+    {
+        let training_inputs: Vec<Vec<f64>> = (0..100).map(|i| vec![i as f64 * 0.1]).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| x[0].sin()).collect();
+        let mut serial_gp = GaussianProcess::default(training_inputs.clone(), training_outputs.clone());
+        let mut parallel_gp = GaussianProcess::default(training_inputs, training_outputs);
+
+        let config = FitConfig { max_iter: 20, nb_restarts: 3, ..FitConfig::default() };
+        serial_gp.fit_parameters_using::<Serial>(true, true, &config).unwrap();
+        parallel_gp.fit_parameters_using::<Parallel>(true, true, &config).unwrap();
+        assert_eq!(serial_gp.kernel.get_parameters(), parallel_gp.kernel.get_parameters());
+        assert_eq!(serial_gp.noise, parallel_gp.noise);
     }
 }
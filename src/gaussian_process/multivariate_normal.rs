@@ -1,5 +1,5 @@
 use crate::conversion::Input;
-use nalgebra::{DMatrix, DVector};
+use nalgebra::{Cholesky, DMatrix, DVector};
 use rand::Rng;
 use rand_distr::StandardNormal;
 use std::marker::PhantomData;
@@ -58,6 +58,25 @@ impl<T: Input> MultivariateNormal<T>
         MultivariateNormal { mean, cholesky_covariance, input_type: PhantomData }
     }
 
+    /// Produces a new multivariate gaussian with the given parameters.
+    ///
+    /// If given, `cholesky_epsilon` is used in place of the diagonal term during the Cholesky decomposition
+    /// if and only if the decomposition would otherwise fail (which happens when the covariance is near-singular).
+    pub fn with_cholesky_epsilon(mean: DVector<f64>, covariance: DMatrix<f64>, cholesky_epsilon: Option<f64>) -> Self
+    {
+        match cholesky_epsilon
+        {
+            None => MultivariateNormal::new(mean, covariance),
+            Some(cholesky_epsilon) =>
+            {
+                let cholesky_covariance = Cholesky::new_with_substitute(covariance, cholesky_epsilon)
+                    .expect("MultivariateNormal: Cholesky decomposition failed even though we used `cholesky_epsilon`!")
+                    .unpack();
+                MultivariateNormal { mean, cholesky_covariance, input_type: PhantomData }
+            }
+        }
+    }
+
     /// Outputs the mean of the distribution.
     pub fn mean(&self) -> T::OutVector
     {
@@ -71,4 +90,18 @@ impl<T: Input> MultivariateNormal<T>
         let sample = &self.mean + &self.cholesky_covariance * normal;
         T::from_dvector(&sample)
     }
+
+    /// Takes a random number generator and uses it to draw `nb_samples` samples from the distribution.
+    ///
+    /// Returns a matrix with one column per sample (and one row per dimension of the distribution).
+    pub fn sample_many<RNG: Rng>(&self, nb_samples: usize, rng: &mut RNG) -> DMatrix<f64>
+    {
+        let normal = DMatrix::from_fn(self.mean.nrows(), nb_samples, |_, _| rng.sample(StandardNormal));
+        let mut samples = &self.cholesky_covariance * normal;
+        for mut sample in samples.column_iter_mut()
+        {
+            sample += &self.mean;
+        }
+        samples
+    }
 }
@@ -7,21 +7,553 @@
 //! to rescale the kernel at each step with the optimal magnitude which has the effect of fitting the noise without computing its gradient.
 //!
 //! Otherwise we fit the noise in log-scale as its magnitude matters more than its precise value.
+//!
+//! Alternatively, the [L-BFGS](https://en.wikipedia.org/wiki/Limited-memory_BFGS) algorithm can be used on the logarithm of the parameters.
+//! It usually converges in far fewer iterations (and thus Cholesky decompositions) than ADAM.
+//!
+//! Finally, the [Nelder-Mead](https://en.wikipedia.org/wiki/Nelder%E2%80%93Mead_method) simplex algorithm
+//! only requires evaluations of the objective, which makes it usable with kernels that do not provide gradients.
+//!
+//! Hyperpriors on the parameters can be given to turn the fit into a maximum a posteriori estimate (see the `hyperprior` module).
 
-use chrono::{Duration, Utc};
+use nalgebra::DMatrix;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use super::GaussianProcess;
-use crate::algebra::{make_cholesky_cov_matrix, make_gradient_covariance_matrices};
-use crate::parameters::{kernel::Kernel, prior::Prior};
+use crate::algebra::{make_gradient_covariance_matrices, CovarianceBuilder, DefaultBuilder, EMatrix, EVector};
+use crate::error::FriedrichError;
+use crate::parameters::{hyperprior::HyperPrior, kernel::Kernel, prior::Prior, prior::ZeroPrior};
+
+/// Algorithm used to fit the kernel and noise parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Optimizer
+{
+    /// The ADAM gradient descent algorithm (using the rescaling of the kernel when it is scalable).
+    #[default]
+    Adam,
+    /// The L-BFGS quasi-Newton algorithm, applied to the logarithm of the parameters.
+    Lbfgs,
+    /// The Nelder-Mead simplex algorithm, applied to the logarithm of the parameters.
+    ///
+    /// It never computes the gradient of the objective but usually requires more iterations than the other algorithms.
+    NelderMead
+}
+
+/// Quantity maximized when fitting the kernel and noise parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ObjectiveFunction
+{
+    /// The marginal log likelihood of the training data (see `log_marginal_likelihood`).
+    #[default]
+    MarginalLikelihood,
+    /// The leave-one-out cross-validation log likelihood of the training data (see `loo_log_likelihood`).
+    ///
+    /// It is more robust to a misspecified kernel but cannot use the rescaling of scalable kernels.
+    LooLogLikelihood
+}
+
+/// Parameters of the ADAM gradient descent algorithm.
+///
+/// See [optimizing-gradient-descent](https://ruder.io/optimizing-gradient-descent/#adam) for their meaning.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AdamConfig
+{
+    /// Step size, as a fraction of the value of each parameter.
+    pub learning_rate: f64,
+    /// Decay rate of the mean of the gradient.
+    pub beta1: f64,
+    /// Decay rate of the variance of the gradient.
+    pub beta2: f64,
+    /// Small value avoiding divisions by zero (also used in place of null parameters).
+    pub epsilon: f64
+}
+
+/// The default ADAM parameters.
+///
+/// The defaults are:
+/// - learning_rate = 0.1
+/// - beta1 = 0.9
+/// - beta2 = 0.999
+/// - epsilon = 1e-8
+impl Default for AdamConfig
+{
+    fn default() -> AdamConfig
+    {
+        AdamConfig { learning_rate: 0.1, beta1: 0.9, beta2: 0.999, epsilon: 1e-8 }
+    }
+}
+
+/// Lower and upper bounds on the kernel parameters followed by the noise.
+///
+/// The bounds are expressed in the natural space of the parameters
+/// and the parameters are projected back into the bounds after each step of the optimizer.
+///
+/// The optimizers work on the logarithm of the magnitude of the parameters and never change their sign:
+/// bounds crossing zero (such as unbounded parameters) only constrain the parameter on the side of its sign
+/// while bounds excluding the sign of a fitted parameter are rejected by the fit
+/// (see `FriedrichError::BoundsExcludeSign`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParameterBounds
+{
+    lower: Vec<f64>,
+    upper: Vec<f64>
+}
+
+impl ParameterBounds
+{
+    /// Builds bounds from the lower and upper bounds of each kernel parameter, followed by the bounds of the noise.
+    ///
+    /// Returns an error if the vectors have different lengths or if a lower bound is not strictly smaller than its upper bound.
+    /// Use `f64::NEG_INFINITY` and `f64::INFINITY` for unbounded parameters.
+    pub fn new(lower: Vec<f64>, upper: Vec<f64>) -> Result<ParameterBounds, FriedrichError>
+    {
+        if lower.len() != upper.len()
+        {
+            return Err(FriedrichError::DimensionMismatch { expected: lower.len(), found: upper.len() });
+        }
+        if let Some(index) = lower.iter().zip(&upper).position(|(l, u)| l.partial_cmp(u) != Some(Ordering::Less))
+        {
+            return Err(FriedrichError::InvalidBounds { index, lower: lower[index], upper: upper[index] });
+        }
+        Ok(ParameterBounds { lower, upper })
+    }
+
+    /// Returns the lower bounds.
+    pub fn lower(&self) -> &[f64]
+    {
+        &self.lower
+    }
+
+    /// Returns the upper bounds.
+    pub fn upper(&self) -> &[f64]
+    {
+        &self.upper
+    }
+
+    /// Number of parameters (kernel parameters plus the noise) covered by the bounds.
+    pub fn len(&self) -> usize
+    {
+        self.lower.len()
+    }
+
+    /// Returns true if the bounds cover no parameter.
+    pub fn is_empty(&self) -> bool
+    {
+        self.lower.is_empty()
+    }
+
+    /// Projects the parameters (kernel parameters followed by the noise) into the bounds.
+    fn clamp(&self, parameters: &mut [f64])
+    {
+        for ((p, l), u) in parameters.iter_mut().zip(&self.lower).zip(&self.upper)
+        {
+            *p = p.max(*l).min(*u);
+        }
+    }
+}
+
+/// Parameters of the fit of the kernel and noise parameters.
+#[derive(Clone, Debug)]
+pub struct FitConfig
+{
+    /// Maximum number of iterations of the optimizer.
+    pub max_iter: usize,
+    /// The optimizer stops prematurely if all parameters change by less than this fraction of their value during an iteration.
+    ///
+    /// Set it to `0.` to rely only on the `likelihood_tolerance`.
+    pub convergence_fraction: f64,
+    /// If set, the optimizer also stops prematurely if the objective changes by less than this fraction
+    /// of its magnitude (or by less than this value when the magnitude is below one) during an iteration.
+    ///
+    /// The optimizer stops as soon as one of the criteria is met.
+    pub likelihood_tolerance: Option<f64>,
+    /// The optimizer stops prematurely if it runs for longer than this duration.
+    pub max_time: Duration,
+    /// Algorithm used for the fit.
+    pub optimizer: Optimizer,
+    /// Parameters of the ADAM algorithm, used when `optimizer` is `Optimizer::Adam`.
+    pub adam: AdamConfig,
+    /// Quantity maximized by the fit.
+    pub objective: ObjectiveFunction,
+    /// Optional bounds on the kernel parameters followed by the noise.
+    ///
+    /// They are applied on top of the bounds of the kernel (see the `parameter_bounds` function of the Kernel trait).
+    pub bounds: Option<ParameterBounds>,
+    /// Smallest magnitude of the noise reachable by the optimizer.
+    ///
+    /// A small positive floor (such as `1e-6` times the standard deviation of the outputs)
+    /// prevents the covariance matrix from becoming singular when the optimizer tries to interpolate the data.
+    pub noise_floor: f64,
+    /// Indices of the parameters (kernel parameters followed by the noise) that are kept at their current value.
+    ///
+    /// Frozen parameters are not modified by the optimizer (nor drawn randomly by the restarts)
+    /// and are ignored when checking for convergence.
+    /// Freezing a parameter disables the rescaling of scalable kernels as it would modify the amplitude and noise.
+    pub fixed_parameters: Vec<usize>,
+    /// Number of additional runs of the optimizer, each one starting from random initial parameters.
+    ///
+    /// The initial parameters are drawn log-uniformly between a tenth and ten times the original parameters
+    /// (and projected into the bounds if any), the parameters with the best objective are kept.
+    /// With the `rayon` feature, the restarts run concurrently on the rayon thread pool.
+    pub nb_restarts: usize,
+    /// Seed of the random number generator used to draw the initial parameters of the restarts.
+    pub seed: u64,
+    /// Optional hyperpriors on the kernel parameters followed by the noise (`None` for a parameter without hyperprior).
+    ///
+    /// If the vector is not empty, the fit maximizes the objective plus the log density of the hyperpriors
+    /// (a maximum a posteriori estimate) and the kernel is never rescaled.
+    pub hyperpriors: Vec<Option<Arc<dyn HyperPrior>>>,
+    /// If true, the state of the model is recorded at each iteration of the optimizer (see `OptimStep`).
+    pub record_trace: bool
+}
+
+impl FitConfig
+{
+    /// Checks that the configuration applies to the given kernel parameters followed by the noise:
+    /// the bounds and hyperpriors (if any) cover all parameters, the fixed parameters are among them
+    /// and the bounds of the fitted parameters contain values of the sign of the parameter.
+    pub(super) fn validate(&self, parameters: &[f64]) -> Result<(), FriedrichError>
+    {
+        let nb_parameters = parameters.len();
+        if let Some(bounds) = &self.bounds
+        {
+            if bounds.len() != nb_parameters
+            {
+                return Err(FriedrichError::DimensionMismatch { expected: nb_parameters, found: bounds.len() });
+            }
+            let excludes_sign = |(index, &value): (usize, &f64)| {
+                !self.fixed_parameters.contains(&index)
+                && if value < 0. { bounds.lower[index] >= 0. } else { bounds.upper[index] <= 0. }
+            };
+            if let Some((index, &value)) = parameters.iter().enumerate().find(|&parameter| excludes_sign(parameter))
+            {
+                return Err(FriedrichError::BoundsExcludeSign { index, value });
+            }
+        }
+        if !self.hyperpriors.is_empty() && self.hyperpriors.len() != nb_parameters
+        {
+            return Err(FriedrichError::DimensionMismatch { expected: nb_parameters, found: self.hyperpriors.len() });
+        }
+        if let Some(&index) = self.fixed_parameters.iter().find(|&&index| index >= nb_parameters)
+        {
+            return Err(FriedrichError::IndexOutOfBounds { index, len: nb_parameters });
+        }
+        Ok(())
+    }
+}
+
+/// The default fit configuration.
+///
+/// The defaults are:
+/// - max_iter = 100
+/// - convergence_fraction = 0.05
+/// - no likelihood_tolerance
+/// - max_time = one hour
+/// - optimizer = ADAM (with the default `AdamConfig`)
+/// - objective = marginal likelihood
+/// - no bounds
+/// - noise_floor = 0
+/// - no fixed parameters
+/// - no restarts (seed = 0)
+/// - no hyperpriors
+/// - no trace
+impl Default for FitConfig
+{
+    fn default() -> FitConfig
+    {
+        FitConfig { max_iter: 100,
+                    convergence_fraction: 0.05,
+                    likelihood_tolerance: None,
+                    max_time: Duration::from_secs(3600),
+                    optimizer: Optimizer::default(),
+                    adam: AdamConfig::default(),
+                    objective: ObjectiveFunction::default(),
+                    bounds: None,
+                    noise_floor: 0.,
+                    fixed_parameters: Vec::new(),
+                    nb_restarts: 0,
+                    seed: 0,
+                    hyperpriors: Vec::new(),
+                    record_trace: false }
+    }
+}
+
+/// State of the model at the beginning of an iteration of the optimizer.
+///
+/// Recorded when `FitConfig::record_trace` is true, it can be used to plot the convergence of a fit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OptimStep
+{
+    /// Index of the run of the optimizer (0 for the initial run, then one per restart).
+    pub restart: usize,
+    /// Index of the iteration within the run, starting at 1.
+    pub iteration: usize,
+    /// The kernel parameters.
+    pub parameters: Vec<f64>,
+    /// The noise parameter.
+    pub noise: f64,
+    /// Euclidean norm of the gradient followed by the optimizer
+    /// (which, depending on the algorithm, might be expressed in log-space or for a rescaled kernel).
+    ///
+    /// Set to zero for the Nelder-Mead algorithm which does not compute gradients.
+    pub gradient_norm: f64,
+    /// The marginal log-likelihood of the model (see `log_marginal_likelihood`).
+    pub log_likelihood: f64
+}
 
 impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType>
 {
+    //-------------------------------------------------------------------------------------------------
+    // RESTARTS
+
+    /// Records the current state of the model, given the gradient followed by the optimizer.
+    fn trace_step(&self, iteration: usize, gradient: &[f64]) -> OptimStep
+    {
+        OptimStep { restart: 0,
+                    iteration,
+                    parameters: self.kernel.get_parameters(),
+                    noise: self.noise,
+                    gradient_norm: dot(gradient, gradient).sqrt(),
+                    log_likelihood: self.log_marginal_likelihood() }
+    }
+
+    /// Sets the kernel parameters followed by the noise then retrains the model.
+    ///
+    /// Returns an error if the covariance matrix is not positive definite for the new parameters.
+    fn set_kernel_and_noise_parameters<B: CovarianceBuilder<KernelType>>(&mut self,
+                                                                         parameters: &[f64])
+                                                                         -> Result<(), FriedrichError>
+    {
+        let nb_kernel_parameters = parameters.len() - 1;
+        self.kernel.set_parameters(&parameters[..nb_kernel_parameters]);
+        self.noise = parameters[nb_kernel_parameters];
+        self.retrain::<B>()
+    }
+
+    /// Recomputes the Cholesky decomposition of the covariance matrix for the current parameters.
+    ///
+    /// Returns an error if the covariance matrix is not positive definite.
+    pub(super) fn retrain<B: CovarianceBuilder<KernelType>>(&mut self) -> Result<(), FriedrichError>
+    {
+        (self.covmat_cholesky, self.applied_jitter) =
+            B::try_make_cholesky_cov_matrix(&self.training_inputs.as_matrix(),
+                                            &self.kernel,
+                                            self.noise,
+                                            self.cholesky_epsilon,
+                                            self.cholesky_jitter)?;
+        Ok(())
+    }
+
+    /// Projects the kernel parameters followed by the noise into the `config.bounds` (if any),
+    /// into the bounds of the kernel and above the `config.noise_floor`.
+    ///
+    /// The bounds of the kernel and the noise floor apply to the magnitude of the parameters, keeping their signs.
+    /// Parameters listed in `config.fixed_parameters` are left unchanged.
+    /// Returns true if a parameter was modified.
+    fn project_parameters(&self, config: &FitConfig, parameters: &mut [f64]) -> bool
+    {
+        let original_parameters = parameters.to_vec();
+        if let Some(bounds) = &config.bounds
+        {
+            bounds.clamp(parameters);
+        }
+        let (noise, kernel_parameters) = parameters.split_last_mut().expect("the noise is always a parameter");
+        for (p, (lower, upper)) in kernel_parameters.iter_mut().zip(self.kernel.parameter_bounds())
+        {
+            *p = p.signum() * p.abs().max(lower).min(upper);
+        }
+        *noise = noise.signum() * noise.abs().max(config.noise_floor);
+        for &index in &config.fixed_parameters
+        {
+            parameters[index] = original_parameters[index];
+        }
+        parameters != original_parameters.as_slice()
+    }
+
+    /// Returns true if the objective changed by less than `config.likelihood_tolerance` since the `previous_objective`
+    /// (which is then updated), always returns false if there is no such tolerance.
+    fn objective_has_converged(&self, config: &FitConfig, previous_objective: &mut Option<f64>) -> bool
+    {
+        match (config.likelihood_tolerance, previous_objective.as_mut())
+        {
+            (Some(tolerance), Some(previous)) =>
+            {
+                let objective = self.objective_value(config);
+                let change = (objective - *previous).abs();
+                let magnitude = previous.abs().max(1.);
+                *previous = objective;
+                change <= tolerance * magnitude
+            }
+            _ => false
+        }
+    }
+
+    /// Runs the optimizer selected in the configuration, starting from the current parameters.
+    /// Returns the trace of the optimizer (empty unless `config.record_trace` is true).
+    fn run_optimizer<B: CovarianceBuilder<KernelType>>(&mut self,
+                                                       config: &FitConfig)
+                                                       -> Result<Vec<OptimStep>, FriedrichError>
+    {
+        match config.optimizer
+        {
+            // The rescaling is only valid for the marginal likelihood.
+            Optimizer::Adam
+                if self.kernel.is_scalable()
+                   && config.objective == ObjectiveFunction::MarginalLikelihood
+                   && config.hyperpriors.is_empty()
+                   && config.fixed_parameters.is_empty() =>
+            {
+                self.scaled_optimize_parameters::<B>(config)
+            }
+            Optimizer::Adam => self.optimize_parameters::<B>(config),
+            Optimizer::Lbfgs => self.lbfgs_optimize_parameters::<B>(config),
+            Optimizer::NelderMead => self.nelder_mead_optimize_parameters::<B>(config)
+        }
+    }
+
+    /// Runs the optimizer from the current parameters then from `config.nb_restarts` random initial parameters,
+    /// keeping the parameters with the best objective.
+    ///
+    /// The random initial parameters are deterministic given `config.seed`,
+    /// the runs starting from them are done concurrently with the `rayon` feature.
+    /// Returns the trace of all runs of the optimizer (empty unless `config.record_trace` is true).
+    ///
+    /// A restart for which the covariance matrix stops being positive definite is skipped
+    /// (it appears in the trace as a single step, of iteration 0, with a log-likelihood of minus infinity).
+    /// Returns an error if the covariance matrix stops being positive definite during the first run,
+    /// the kernel and noise then go back to their initial values.
+    pub(super) fn optimize_with_restarts<B: CovarianceBuilder<KernelType>>(&mut self,
+                                                                           config: &FitConfig)
+                                                                           -> Result<Vec<OptimStep>, FriedrichError>
+    {
+        let mut initial_parameters = self.kernel.get_parameters();
+        initial_parameters.push(self.noise);
+
+        let result = self.run_optimizer_with_restarts::<B>(config, &initial_parameters);
+        if result.is_err()
+        {
+            // The covariance matrix was positive definite for the initial parameters.
+            self.set_kernel_and_noise_parameters::<B>(&initial_parameters)?;
+        }
+        result
+    }
+
+    /// Runs the optimizer from the current parameters then from `config.nb_restarts` parameters
+    /// drawn around the `initial_parameters` (see `optimize_with_restarts`).
+    fn run_optimizer_with_restarts<B: CovarianceBuilder<KernelType>>(&mut self,
+                                                                     config: &FitConfig,
+                                                                     initial_parameters: &[f64])
+                                                                     -> Result<Vec<OptimStep>, FriedrichError>
+    {
+        let mut trace = self.run_optimizer::<B>(config)?;
+        if config.nb_restarts == 0
+        {
+            return Ok(trace);
+        }
+
+        let mut best_value = self.objective_value(config);
+        let mut best_parameters = self.kernel.get_parameters();
+        best_parameters.push(self.noise);
+
+        // Draws parameters log-uniformly between a tenth and ten times the initial parameters.
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let mut starts = Vec::with_capacity(config.nb_restarts);
+        for _ in 0..config.nb_restarts
+        {
+            let mut parameters: Vec<f64> =
+                initial_parameters.iter().map(|p| p * 10f64.powf(rng.gen_range(-1f64..=1f64))).collect();
+            for &index in &config.fixed_parameters
+            {
+                parameters[index] = initial_parameters[index];
+            }
+            self.project_parameters(config, &mut parameters);
+            starts.push(parameters);
+        }
+
+        // Runs each restart on a copy of the process (concurrently with the `rayon` feature),
+        // without the prior which is not used by the optimizers.
+        let training_inputs = self.training_inputs.as_matrix();
+        let training_outputs = self.training_outputs.as_vector();
+        let (input_standardization, output_standardization) =
+            (&self.input_standardization, &self.output_standardization);
+        let covmat_cholesky = &self.covmat_cholesky;
+        let (cholesky_epsilon, cholesky_jitter) = (self.cholesky_epsilon, self.cholesky_jitter);
+        let runs = B::map_tasks(starts.len(), &self.kernel, |kernel, index| -> Result<_, FriedrichError> {
+            let mut gp = GaussianProcess { prior: ZeroPrior {},
+                                           kernel: kernel.clone(),
+                                           noise: 0.,
+                                           cholesky_epsilon,
+                                           cholesky_jitter,
+                                           input_standardization: input_standardization.clone(),
+                                           output_standardization: output_standardization.clone(),
+                                           training_inputs: EMatrix::new(training_inputs.into_owned()),
+                                           training_outputs: EVector::new(training_outputs.into_owned()),
+                                           covmat_cholesky: covmat_cholesky.clone(),
+                                           applied_jitter: 0. };
+            gp.set_kernel_and_noise_parameters::<B>(&starts[index])?;
+            let steps = gp.run_optimizer::<B>(config)?;
+            let mut parameters = gp.kernel.get_parameters();
+            parameters.push(gp.noise);
+            Ok((steps, gp.objective_value(config), parameters))
+        });
+
+        for (restart, (start, run)) in (1..).zip(starts.iter().zip(runs))
+        {
+            match run
+            {
+                Ok((steps, value, parameters)) =>
+                {
+                    trace.extend(steps.into_iter().map(|step| OptimStep { restart, ..step }));
+                    if value > best_value
+                    {
+                        best_value = value;
+                        best_parameters = parameters;
+                    }
+                }
+                Err(_) =>
+                {
+                    // The restart reached a singular covariance matrix, it is skipped.
+                    if config.record_trace
+                    {
+                        let (noise, kernel_parameters) = start.split_last().expect("the noise is a parameter");
+                        trace.push(OptimStep { restart,
+                                               iteration: 0,
+                                               parameters: kernel_parameters.to_vec(),
+                                               noise: *noise,
+                                               gradient_norm: 0.,
+                                               log_likelihood: f64::NEG_INFINITY });
+                    }
+                }
+            }
+        }
+
+        self.set_kernel_and_noise_parameters::<B>(&best_parameters)?;
+        Ok(trace)
+    }
+
     //-------------------------------------------------------------------------------------------------
     // NON-SCALABLE KERNEL
 
-    /// Computes the gradient of the marginal likelihood for the current value of each parameter.
-    /// The produced vector contains the gradient per kernel parameter followed by the gradient for the noise parameter.
-    fn gradient_marginal_likelihood(&self) -> Vec<f64>
+    /// Computes the gradient of the marginal log likelihood (see `log_marginal_likelihood`)
+    /// for the current value of each parameter.
+    ///
+    /// The produced vector contains the gradient per kernel parameter, in the order of `kernel.get_parameters()`,
+    /// followed by the gradient for the `noise` parameter.
+    /// This can be used to fit the parameters with an external optimizer.
+    pub fn log_marginal_likelihood_gradient(&self) -> Vec<f64>
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        let cov_gradients = make_gradient_covariance_matrices(&self.training_inputs.as_matrix(), &self.kernel);
+        self.log_marginal_likelihood_gradient_from(cov_gradients)
+    }
+
+    /// Computes the gradient of the marginal log likelihood, see `log_marginal_likelihood_gradient`,
+    /// given the gradient of the covariance matrix for each kernel parameter.
+    fn log_marginal_likelihood_gradient_from(&self, cov_gradients: Vec<DMatrix<f64>>) -> Vec<f64>
     {
         // formula: 1/2 ( transpose(alpha) * dp * alpha - trace(K^-1 * dp) )
         // K = cov(train,train)
@@ -34,7 +566,7 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType
 
         // Loop over the gradient matrix for each parameter.
         let mut results = vec![];
-        for cov_gradient in make_gradient_covariance_matrices(&self.training_inputs.as_matrix(), &self.kernel)
+        for cov_gradient in cov_gradients
         {
             // transpose(alpha) * cov_gradient * alpha
             let data_fit: f64 = cov_gradient.column_iter()
@@ -59,33 +591,134 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType
         results
     }
 
-    /// Fit parameters using a gradient descent algorithm.
+    /// Computes the gradient of the leave-one-out log likelihood (see `loo_log_likelihood`)
+    /// for the current value of each parameter.
+    ///
+    /// The produced vector contains the gradient per kernel parameter, in the order of `kernel.get_parameters()`,
+    /// followed by the gradient for the `noise` parameter.
+    pub fn loo_log_likelihood_gradient(&self) -> Vec<f64>
+        where DefaultBuilder: CovarianceBuilder<KernelType>
+    {
+        let cov_gradients = make_gradient_covariance_matrices(&self.training_inputs.as_matrix(), &self.kernel);
+        self.loo_log_likelihood_gradient_from(cov_gradients)
+    }
+
+    /// Computes the gradient of the leave-one-out log likelihood, see `loo_log_likelihood_gradient`,
+    /// given the gradient of the covariance matrix for each kernel parameter.
+    fn loo_log_likelihood_gradient_from(&self, cov_gradients: Vec<DMatrix<f64>>) -> Vec<f64>
+    {
+        // formula (see Rasmussen & Williams, Gaussian Processes for Machine Learning, equation 5.13):
+        // sum_i ( alpha_i * (Z*alpha)_i - 1/2 (1 + alpha_i²/K^-1_ii) * (Z*K^-1)_ii ) / K^-1_ii
+        // K = cov(train,train)
+        // alpha = K^-1 * output
+        // Z = K^-1 * dp
+        // dp = gradient(K, parameter)
+
+        let cov_inv = self.covmat_cholesky.inverse();
+        let alpha = &cov_inv * self.training_outputs.as_vector();
+        let gradient = |z: DMatrix<f64>| -> f64 {
+            let z_alpha = &z * &alpha;
+            z.row_iter()
+             .zip(cov_inv.column_iter())
+             .enumerate()
+             .map(|(i, (z_row, cov_inv_col))| {
+                 let cov_inv_diag = cov_inv[(i, i)];
+                 let z_cov_inv_diag = z_row.tr_dot(&cov_inv_col);
+                 (alpha[i] * z_alpha[i] - 0.5 * (1. + alpha[i] * alpha[i] / cov_inv_diag) * z_cov_inv_diag)
+                 / cov_inv_diag
+             })
+             .sum()
+        };
+
+        // Loop over the gradient matrix for each parameter.
+        let mut results = vec![];
+        for cov_gradient in cov_gradients
+        {
+            results.push(gradient(&cov_inv * cov_gradient));
+        }
+
+        // Adds the noise parameter.
+        // gradient(K, noise) = 2*noise*Id
+        results.push(gradient(&cov_inv * (2. * self.noise)));
+
+        results
+    }
+
+    /// Computes the gradient of the objective function of the configuration (plus the log density of its hyperpriors)
+    /// for the current value of each parameter, followed by the gradient for the `noise` parameter.
+    ///
+    /// The gradient of the parameters listed in `config.fixed_parameters` is set to zero
+    /// such that the optimizers never move them.
+    fn objective_gradient<B: CovarianceBuilder<KernelType>>(&self, config: &FitConfig) -> Vec<f64>
+    {
+        let cov_gradients = B::make_gradient_covariance_matrices(&self.training_inputs.as_matrix(), &self.kernel);
+        let mut gradients = match config.objective
+        {
+            ObjectiveFunction::MarginalLikelihood => self.log_marginal_likelihood_gradient_from(cov_gradients),
+            ObjectiveFunction::LooLogLikelihood => self.loo_log_likelihood_gradient_from(cov_gradients)
+        };
+        let mut parameters = self.kernel.get_parameters();
+        parameters.push(self.noise);
+        for ((gradient, parameter), hyperprior) in gradients.iter_mut().zip(parameters).zip(&config.hyperpriors)
+        {
+            if let Some(hyperprior) = hyperprior
+            {
+                *gradient += parameter.signum() * hyperprior.log_density_gradient(parameter.abs());
+            }
+        }
+        for &index in &config.fixed_parameters
+        {
+            gradients[index] = 0.;
+        }
+        gradients
+    }
+
+    /// Computes the objective function of the configuration (plus the log density of its hyperpriors)
+    /// for the current parameters.
+    fn objective_value(&self, config: &FitConfig) -> f64
+    {
+        let value = match config.objective
+        {
+            ObjectiveFunction::MarginalLikelihood => self.log_marginal_likelihood(),
+            ObjectiveFunction::LooLogLikelihood => self.loo_log_likelihood()
+        };
+        let mut parameters = self.kernel.get_parameters();
+        parameters.push(self.noise);
+        let log_hyperprior: f64 = parameters.iter()
+                                            .zip(&config.hyperpriors)
+                                            .filter_map(|(p, hyperprior)| {
+                                                hyperprior.as_ref().map(|h| h.log_density(p.abs()))
+                                            })
+                                            .sum();
+        value + log_hyperprior
+    }
+
+    /// Fit parameters using a gradient descent algorithm on the `config.objective`.
     ///
-    /// Runs for a maximum of `max_iter` iterations (100 is a good default value).
-    /// Stops prematurely if all the components of the gradient go below `convergence_fraction` time the value of their respectively parameter (0.05 is a good default value).
-    /// Stops prematurely if the runtime exceeds `max_time`.
+    /// Runs for a maximum of `config.max_iter` iterations (100 is a good default value).
+    /// Stops prematurely if all the components of the gradient go below `config.convergence_fraction` time the value of their respectively parameter (0.05 is a good default value).
+    /// Stops prematurely if the runtime exceeds `config.max_time`.
+    /// Parameters are projected into the bounds (see `project_parameters`) after each step.
     ///
     /// The `noise` parameter is fitted in log-scale as its magnitude matters more than its precise value.
-    pub(super) fn optimize_parameters(&mut self,
-                                      max_iter: usize,
-                                      convergence_fraction: f64,
-                                      max_time: Duration)
+    /// Returns an error if the covariance matrix stops being positive definite.
+    pub(super) fn optimize_parameters<B: CovarianceBuilder<KernelType>>(&mut self,
+                                                                        config: &FitConfig)
+                                                                        -> Result<Vec<OptimStep>, FriedrichError>
     {
         // use the ADAM gradient descent algorithm
         // see [optimizing-gradient-descent](https://ruder.io/optimizing-gradient-descent/)
         // for a good point on current gradient descent algorithms
 
         // Constant parameters.
-        let beta1 = 0.9;
-        let beta2 = 0.999;
-        let epsilon = 1e-8;
-        let learning_rate = 0.1;
+        let AdamConfig { learning_rate, beta1, beta2, epsilon } = config.adam;
 
         let mut parameters: Vec<_> = self.kernel
                                          .get_parameters()
                                          .iter()
-                                         .map(|&p| {
-                                             if p == 0.
+                                         .enumerate()
+                                         .map(|(i, &p)| {
+                                             if p == 0. && !config.fixed_parameters.contains(&i)
                                              {
                                                  epsilon
                                              }
@@ -99,43 +732,69 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType
         let mut mean_grad = vec![0.; parameters.len()];
         let mut var_grad = vec![0.; parameters.len()];
 
-        let time_start = Utc::now();
-        for i in 1..=max_iter
+        let mut previous_objective = config.likelihood_tolerance.map(|_| self.objective_value(config));
+        let mut trace = Vec::new();
+        let time_start = Instant::now();
+        for i in 1..=config.max_iter
         {
-            let mut gradients = self.gradient_marginal_likelihood();
+            let mut gradients = self.objective_gradient::<B>(config);
             if let Some(noise_grad) = gradients.last_mut()
             {
                 // Corrects gradient of noise for log-space.
                 *noise_grad *= self.noise
             }
+            if config.record_trace
+            {
+                trace.push(self.trace_step(i, &gradients));
+            }
 
             let mut had_significant_progress = false;
             for p in 0..parameters.len()
             {
+                if config.fixed_parameters.contains(&p)
+                {
+                    continue;
+                }
                 mean_grad[p] = beta1 * mean_grad[p] + (1. - beta1) * gradients[p];
                 var_grad[p] = beta2 * var_grad[p] + (1. - beta2) * gradients[p].powi(2);
                 let bias_corrected_mean = mean_grad[p] / (1. - beta1.powi(i as i32));
                 let bias_corrected_variance = var_grad[p] / (1. - beta2.powi(i as i32));
                 let delta = learning_rate * bias_corrected_mean / (bias_corrected_variance.sqrt() + epsilon);
-                had_significant_progress |= delta.abs() > convergence_fraction;
-                parameters[p] *= 1. + delta;
+                had_significant_progress |= delta.abs() > config.convergence_fraction;
+                if p == parameters.len() - 1
+                {
+                    // The noise is in log-space, an additive step is a relative change of the noise.
+                    parameters[p] += delta;
+                }
+                else
+                {
+                    parameters[p] *= 1. + delta;
+                }
+            }
+
+            // Projects parameters into the bounds, in the natural space of the noise.
+            let mut natural_parameters = parameters.clone();
+            let nb_kernel_parameters = parameters.len() - 1;
+            natural_parameters[nb_kernel_parameters] = parameters[nb_kernel_parameters].exp();
+            if self.project_parameters(config, &mut natural_parameters)
+            {
+                natural_parameters[nb_kernel_parameters] = natural_parameters[nb_kernel_parameters].ln();
+                parameters = natural_parameters;
             }
 
             // Sets parameters.
             self.kernel.set_parameters(&parameters);
-            if let Some(noise) = parameters.last()
+            if !config.fixed_parameters.contains(&nb_kernel_parameters)
             {
                 // Gets out of log-space before setting noise.
-                self.noise = noise.exp()
+                self.noise = parameters[nb_kernel_parameters].exp()
             }
 
             // Fits model.
-            self.covmat_cholesky = make_cholesky_cov_matrix(&self.training_inputs.as_matrix(),
-                                                            &self.kernel,
-                                                            self.noise,
-                                                            self.cholesky_epsilon);
+            self.retrain::<B>()?;
 
-            if (!had_significant_progress) || (Utc::now().signed_duration_since(time_start) > max_time)
+            let has_converged = self.objective_has_converged(config, &mut previous_objective);
+            if (!had_significant_progress) || has_converged || (time_start.elapsed() > config.max_time)
             {
                 //println!("Iterations:{}", i);
                 break;
@@ -146,6 +805,7 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType
         self.likelihood(),
         parameters,
         self.noise);*/
+        Ok(trace)
     }
 
     //-------------------------------------------------------------------------------------------------
@@ -156,7 +816,7 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType
     ///
     /// See [Fast methods for training Gaussian processes on large datasets](https://arxiv.org/pdf/1604.01250.pdf)
     /// for the formula used to compute the scale and the modification to the gradient.
-    fn scaled_gradient_marginal_likelihood(&self) -> (f64, Vec<f64>)
+    fn scaled_gradient_marginal_likelihood<B: CovarianceBuilder<KernelType>>(&self) -> (f64, Vec<f64>)
     {
         // formula:
         // gradient = 1/2 ( transpose(alpha) * dp * alpha / scale - trace(K^-1 * dp) )
@@ -175,7 +835,7 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType
 
         // Loop on the gradient matrix for each parameter.
         let mut results = vec![];
-        for cov_gradient in make_gradient_covariance_matrices(&self.training_inputs.as_matrix(), &self.kernel)
+        for cov_gradient in B::make_gradient_covariance_matrices(&self.training_inputs.as_matrix(), &self.kernel)
         {
             // transpose(alpha) * cov_gradient * alpha / scale
             // NOTE: This quantity is divided by the scale which is not the case for the unscaled gradient.
@@ -205,23 +865,21 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType
     /// Fit parameters using a gradient descent algorithm.
     /// Additionally, at each step, the kernel and noise are rescaled using the optimal magnitude.
     ///
-    /// Runs for a maximum of `max_iter` iterations (100 is a good default value).
-    /// Stops prematurely if all the components of the gradient go below `convergence_fraction` time the value of their respectively parameter (0.05 is a good default value).
-    /// Stops prematurely if the runtime exceeds `max_time`.
-    pub(super) fn scaled_optimize_parameters(&mut self,
-                                             max_iter: usize,
-                                             convergence_fraction: f64,
-                                             max_time: Duration)
+    /// Runs for a maximum of `config.max_iter` iterations (100 is a good default value).
+    /// Stops prematurely if all the components of the gradient go below `config.convergence_fraction` time the value of their respectively parameter (0.05 is a good default value).
+    /// Stops prematurely if the runtime exceeds `config.max_time`.
+    /// Parameters are projected into the bounds (see `project_parameters`) after each step (and rescaling).
+    /// Returns an error if the covariance matrix stops being positive definite.
+    pub(super) fn scaled_optimize_parameters<B: CovarianceBuilder<KernelType>>(&mut self,
+                                                                               config: &FitConfig)
+                                                                               -> Result<Vec<OptimStep>, FriedrichError>
     {
         // use the ADAM gradient descent algorithm
         // see [optimizing-gradient-descent](https://ruder.io/optimizing-gradient-descent/)
         // for a good point on current gradient descent algorithms
 
         // Constant parameters.
-        let beta1 = 0.9;
-        let beta2 = 0.999;
-        let epsilon = 1e-8;
-        let learning_rate = 0.1;
+        let AdamConfig { learning_rate, beta1, beta2, epsilon } = config.adam;
 
         let mut parameters: Vec<_> = self.kernel
                                          .get_parameters()
@@ -240,10 +898,16 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType
         let mut mean_grad = vec![0.; parameters.len()];
         let mut var_grad = vec![0.; parameters.len()];
 
-        let time_start = Utc::now();
-        for i in 1..=max_iter
+        let mut previous_objective = config.likelihood_tolerance.map(|_| self.objective_value(config));
+        let mut trace = Vec::new();
+        let time_start = Instant::now();
+        for i in 1..=config.max_iter
         {
-            let (scale, gradients) = self.scaled_gradient_marginal_likelihood();
+            let (scale, gradients) = self.scaled_gradient_marginal_likelihood::<B>();
+            if config.record_trace
+            {
+                trace.push(self.trace_step(i, &gradients));
+            }
 
             let mut had_significant_progress = false;
             for p in 0..parameters.len()
@@ -253,7 +917,7 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType
                 let bias_corrected_mean = mean_grad[p] / (1. - beta1.powi(i as i32));
                 let bias_corrected_variance = var_grad[p] / (1. - beta2.powi(i as i32));
                 let delta = learning_rate * bias_corrected_mean / (bias_corrected_variance.sqrt() + epsilon);
-                had_significant_progress |= delta.abs() > convergence_fraction;
+                had_significant_progress |= delta.abs() > config.convergence_fraction;
                 parameters[p] *= 1. + delta;
             }
 
@@ -263,13 +927,19 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType
             self.noise *= scale;
             parameters = self.kernel.get_parameters(); // Get parameters back as they have been rescaled.
 
+            // Projects parameters into the bounds.
+            parameters.push(self.noise);
+            if self.project_parameters(config, &mut parameters)
+            {
+                self.kernel.set_parameters(&parameters);
+            }
+            self.noise = parameters.pop().expect("the noise was just pushed");
+
             // Fits model.
-            self.covmat_cholesky = make_cholesky_cov_matrix(&self.training_inputs.as_matrix(),
-                                                            &self.kernel,
-                                                            self.noise,
-                                                            self.cholesky_epsilon);
+            self.retrain::<B>()?;
 
-            if (!had_significant_progress) || (Utc::now().signed_duration_since(time_start) > max_time)
+            let has_converged = self.objective_has_converged(config, &mut previous_objective);
+            if (!had_significant_progress) || has_converged || (time_start.elapsed() > config.max_time)
             {
                 //println!("Iterations:{}", i);
                 break;
@@ -280,6 +950,669 @@ impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType
         self.likelihood(),
         parameters,
         self.noise);*/
+        Ok(trace)
+    }
+
+    //-------------------------------------------------------------------------------------------------
+    // L-BFGS
+
+    /// Sets the kernel parameters and noise from their logarithm (and sign) then retrains the model.
+    ///
+    /// The `fixed_parameters` (index and value pairs) are set to their exact value
+    /// rather than the exponential of their logarithm.
+    /// Returns an error if the covariance matrix is not positive definite for the new parameters.
+    fn set_log_parameters<B: CovarianceBuilder<KernelType>>(&mut self,
+                                                            log_parameters: &[f64],
+                                                            signs: &[f64],
+                                                            fixed_parameters: &[(usize, f64)])
+                                                            -> Result<(), FriedrichError>
+    {
+        let mut parameters: Vec<f64> = log_parameters.iter().zip(signs).map(|(l, s)| s * l.exp()).collect();
+        for &(index, value) in fixed_parameters
+        {
+            parameters[index] = value;
+        }
+        self.set_kernel_and_noise_parameters::<B>(&parameters)
+    }
+
+    /// Computes the gradient of the negated objective with respect to the logarithm of each parameter.
+    fn negative_log_space_gradient<B: CovarianceBuilder<KernelType>>(&self, config: &FitConfig) -> Vec<f64>
+    {
+        let mut parameters = self.kernel.get_parameters();
+        parameters.push(self.noise);
+        self.objective_gradient::<B>(config).iter().zip(parameters).map(|(g, p)| -g * p).collect()
+    }
+
+    /// Fit parameters using the L-BFGS algorithm on the logarithm of the parameters
+    /// (with a line search enforcing the strong Wolfe conditions, see `wolfe_line_search`)
+    /// in order to maximize the given `objective`.
+    ///
+    /// Runs for a maximum of `config.max_iter` iterations (each one usually requiring a single Cholesky decomposition).
+    /// Stops prematurely if all parameters change by less than `config.convergence_fraction` time their value during an iteration.
+    /// Stops prematurely if the runtime exceeds `config.max_time`.
+    /// Candidate parameters are projected into the bounds (see `project_parameters`).
+    /// Returns an error if the covariance matrix stops being positive definite.
+    pub(super) fn lbfgs_optimize_parameters<B: CovarianceBuilder<KernelType>>(&mut self,
+                                                                              config: &FitConfig)
+                                                                              -> Result<Vec<OptimStep>, FriedrichError>
+    {
+        // see Nocedal & Wright, Numerical Optimization, algorithms 7.4 and 7.5
+
+        // Constant parameters.
+        let history_size = 10;
+        let max_step = 1.; // Maximum change of a log-parameter per iteration.
+        let epsilon = 1e-8;
+
+        // Parameters are optimized in log-space, their signs are kept aside as some kernels accept negative parameters.
+        let mut parameters = self.kernel.get_parameters();
+        parameters.push(self.noise);
+        self.project_parameters(config, &mut parameters);
+        let signs: Vec<f64> = parameters.iter().map(|&p| if p < 0. { -1. } else { 1. }).collect();
+        // Insures no parameter is 0 (which would block the algorithm).
+        let mut log_parameters: Vec<f64> = parameters.iter().map(|p| p.abs().max(epsilon).ln()).collect();
+        let fixed_parameters: Vec<(usize, f64)> =
+            config.fixed_parameters.iter().map(|&index| (index, parameters[index])).collect();
+        self.set_log_parameters::<B>(&log_parameters, &signs, &fixed_parameters)?;
+
+        let mut value = -self.objective_value(config);
+        let mut gradient = self.negative_log_space_gradient::<B>(config);
+        // Stores the (step, gradient change, 1/dot(step, gradient change)) triplets of the last iterations.
+        let mut history: VecDeque<(Vec<f64>, Vec<f64>, f64)> = VecDeque::with_capacity(history_size);
+
+        let mut trace = Vec::new();
+        let time_start = Instant::now();
+        for i in 1..=config.max_iter
+        {
+            if config.record_trace
+            {
+                trace.push(self.trace_step(i, &gradient));
+            }
+
+            // Computes the descent direction with the two-loop recursion.
+            let mut direction = gradient.clone();
+            let mut alphas = Vec::with_capacity(history.len());
+            for (step, gradient_change, rho) in history.iter().rev()
+            {
+                let alpha = rho * dot(step, &direction);
+                axpy(-alpha, gradient_change, &mut direction);
+                alphas.push(alpha);
+            }
+            let gamma = history.back().map_or(1., |(step, gradient_change, _)| {
+                                          dot(step, gradient_change) / dot(gradient_change, gradient_change)
+                                      });
+            direction.iter_mut().for_each(|d| *d *= gamma);
+            for ((step, gradient_change, rho), alpha) in history.iter().zip(alphas.iter().rev())
+            {
+                let beta = rho * dot(gradient_change, &direction);
+                axpy(alpha - beta, step, &mut direction);
+            }
+            direction.iter_mut().for_each(|d| *d = -*d);
+
+            // Falls back to steepest descent if the direction is not a descent direction.
+            let mut slope = dot(&gradient, &direction);
+            if slope >= 0. || slope.is_nan()
+            {
+                direction = gradient.iter().map(|g| -g).collect();
+                slope = -dot(&gradient, &gradient);
+                history.clear();
+            }
+
+            // Line search, the step being capped to `max_step`.
+            let largest_direction = direction.iter().fold(0f64, |acc, d| acc.max(d.abs()));
+            let max_step_size = if largest_direction > max_step { max_step / largest_direction } else { 1. };
+            let line = LineSearch { origin: &log_parameters,
+                                    direction: &direction,
+                                    signs: &signs,
+                                    fixed_parameters: &fixed_parameters,
+                                    value,
+                                    slope };
+            let (candidate, candidate_value, candidate_gradient) =
+                match self.wolfe_line_search::<B>(config, &line, max_step_size)
+                {
+                    Some(point) => point,
+                    None =>
+                    {
+                        // The line search failed, we go back to the previous parameters.
+                        self.set_log_parameters::<B>(&log_parameters, &signs, &fixed_parameters)?;
+                        break;
+                    }
+                };
+
+            // Updates the history.
+            let step: Vec<f64> = candidate.iter().zip(&log_parameters).map(|(c, p)| c - p).collect();
+            let gradient_change: Vec<f64> = candidate_gradient.iter().zip(&gradient).map(|(c, g)| c - g).collect();
+            let curvature = dot(&step, &gradient_change);
+            if curvature > epsilon * dot(&gradient_change, &gradient_change)
+            {
+                if history.len() == history_size
+                {
+                    history.pop_front();
+                }
+                history.push_back((step.clone(), gradient_change, 1. / curvature));
+            }
+
+            // A change of `delta` of a log-parameter is a relative change of about `delta` of the parameter.
+            let had_significant_progress = step.iter().any(|s| s.abs() > config.convergence_fraction);
+            let has_converged = matches!(config.likelihood_tolerance,
+                                         Some(tolerance) if (candidate_value - value).abs()
+                                                            <= tolerance * value.abs().max(1.));
+            log_parameters = candidate;
+            value = candidate_value;
+            gradient = candidate_gradient;
+
+            if (!had_significant_progress) || has_converged || (time_start.elapsed() > config.max_time)
+            {
+                break;
+            };
+        }
+        Ok(trace)
+    }
+
+    /// Sets the parameters at `step_size` along the line (projected into the bounds)
+    /// and returns them with the associated value of the negated objective.
+    ///
+    /// If the covariance matrix is not positive definite at that point, the negated objective is infinite
+    /// (the line search then shrinks the step) and the model must be retrained before being used.
+    fn line_search_evaluate<B: CovarianceBuilder<KernelType>>(&mut self,
+                                                              config: &FitConfig,
+                                                              line: &LineSearch,
+                                                              step_size: f64)
+                                                              -> (Vec<f64>, f64)
+    {
+        let candidate: Vec<f64> = line.origin.iter().zip(line.direction).map(|(p, d)| p + step_size * d).collect();
+        self.log_space_evaluate::<B>(config, candidate.clone(), line.signs, line.fixed_parameters)
+            .unwrap_or((candidate, f64::INFINITY))
+    }
+
+    /// Sets the parameters at the given log-parameters (projected into the bounds)
+    /// and returns them with the associated value of the negated objective.
+    /// Returns an error if the covariance matrix is not positive definite for the candidate.
+    fn log_space_evaluate<B: CovarianceBuilder<KernelType>>(&mut self,
+                                                            config: &FitConfig,
+                                                            mut candidate: Vec<f64>,
+                                                            signs: &[f64],
+                                                            fixed_parameters: &[(usize, f64)])
+                                                            -> Result<(Vec<f64>, f64), FriedrichError>
+    {
+        let epsilon = 1e-8;
+        // Projects the candidate into the bounds, in the natural space of the parameters.
+        let mut parameters: Vec<f64> = candidate.iter().zip(signs).map(|(c, s)| s * c.exp()).collect();
+        if self.project_parameters(config, &mut parameters)
+        {
+            candidate = parameters.iter().map(|p| p.abs().max(epsilon).ln()).collect();
+        }
+        self.set_log_parameters::<B>(&candidate, signs, fixed_parameters)?;
+        let value = -self.objective_value(config);
+        Ok((candidate, value))
+    }
+
+    /// Searches, along the line, a step size satisfying the strong Wolfe conditions
+    /// (a sufficient decrease of the negated objective and a sufficient decrease of the magnitude of its slope)
+    /// which guarantees that the L-BFGS update keeps a positive definite approximation of the hessian.
+    ///
+    /// The largest step size allowed, `max_step_size` (the natural unit step of L-BFGS, capped), is tried first.
+    /// If it is not acceptable, the acceptable step sizes are bracketed between it and the origin
+    /// then refined by interpolation, see Nocedal & Wright, Numerical Optimization, algorithms 3.5 and 3.6
+    /// (the extrapolation of algorithm 3.5 is never needed as larger step sizes are not allowed).
+    /// Returns the log-parameters, negated objective and log-space gradient at the selected point
+    /// (the model being left at that point) or `None` if no step size decreases the objective
+    /// (the model then has to be retrained).
+    /// Points where the covariance matrix is not positive definite are treated as having an infinite negated objective.
+    fn wolfe_line_search<B: CovarianceBuilder<KernelType>>(&mut self,
+                                                           config: &FitConfig,
+                                                           line: &LineSearch,
+                                                           max_step_size: f64)
+                                                           -> Option<LinePoint>
+    {
+        // Constant parameters.
+        let armijo_factor = 1e-4;
+        let curvature_factor = 0.9;
+        let max_evaluations = 20;
+        let min_step_size = 1e-8 * max_step_size;
+
+        let is_sufficient_decrease = |step_size: f64, value: f64| {
+            value <= line.value + armijo_factor * step_size * line.slope
+        };
+        let directional_derivative = |gradient: &[f64]| dot(gradient, line.direction);
+
+        // Bracketing phase: tries the largest step size allowed.
+        // `(step size, value, slope)` of the lowest point found so far, starting at the origin.
+        let mut low = (0., line.value, line.slope);
+        let mut high;
+        let mut evaluation = 1;
+        let (candidate, value) = self.line_search_evaluate::<B>(config, line, max_step_size);
+        if !is_sufficient_decrease(max_step_size, value) || value >= low.1
+        {
+            high = (max_step_size, value);
+        }
+        else
+        {
+            let gradient = self.negative_log_space_gradient::<B>(config);
+            let slope = directional_derivative(&gradient);
+            if slope < 0. || slope.abs() <= -curvature_factor * line.slope
+            {
+                // Satisfies the strong Wolfe conditions or, the objective still decreasing,
+                // the sufficient decrease condition at the largest step size allowed.
+                return Some((candidate, value, gradient));
+            }
+            high = (low.0, low.1);
+            low = (max_step_size, value, slope);
+        }
+
+        // Zoom phase: the bracket is shrunk until an acceptable point is found.
+        while evaluation < max_evaluations && (high.0 - low.0).abs() > min_step_size
+        {
+            evaluation += 1;
+            // Minimizer of the quadratic interpolating the value and slope at `low` and the value at `high`,
+            // safeguarded to stay well inside the bracket.
+            let width = high.0 - low.0;
+            let curvature = (high.1 - low.1 - low.2 * width) / (width * width);
+            let interpolation = if curvature > 0. { low.0 - low.2 / (2. * curvature) } else { f64::NAN };
+            let (lower, upper) = (low.0.min(high.0), low.0.max(high.0));
+            let margin = 0.1 * (upper - lower);
+            let is_inside = interpolation > lower + margin && interpolation < upper - margin;
+            let step_size = if interpolation.is_finite() && is_inside
+            {
+                interpolation
+            }
+            else
+            {
+                low.0 + width / 2.
+            };
+
+            let (candidate, value) = self.line_search_evaluate::<B>(config, line, step_size);
+            if !is_sufficient_decrease(step_size, value) || value >= low.1
+            {
+                high = (step_size, value);
+                continue;
+            }
+            let gradient = self.negative_log_space_gradient::<B>(config);
+            let slope = directional_derivative(&gradient);
+            if slope.abs() <= -curvature_factor * line.slope
+            {
+                return Some((candidate, value, gradient));
+            }
+            if slope * (high.0 - low.0) >= 0.
+            {
+                high = (low.0, low.1);
+            }
+            low = (step_size, value, slope);
+        }
+
+        // No point satisfies the curvature condition, falls back on the lowest point found (if it is not the origin).
+        if low.0 > 0.
+        {
+            let (candidate, value) = self.line_search_evaluate::<B>(config, line, low.0);
+            let gradient = self.negative_log_space_gradient::<B>(config);
+            Some((candidate, value, gradient))
+        }
+        else
+        {
+            None
+        }
+    }
+}
+
+impl<KernelType: Kernel, PriorType: Prior> GaussianProcess<KernelType, PriorType>
+{
+    //-------------------------------------------------------------------------------------------------
+    // NELDER-MEAD
+
+    /// Evaluates a vertex of the simplex (see `log_space_evaluate`),
+    /// a NaN objective is replaced by an infinite negated objective such that the vertex is never kept.
+    /// The state of the model at the vertex is kept when the trace is recorded.
+    /// Returns an error if the covariance matrix is not positive definite at the vertex.
+    fn try_simplex_evaluate<B: CovarianceBuilder<KernelType>>(&mut self,
+                                                              config: &FitConfig,
+                                                              log_parameters: Vec<f64>,
+                                                              signs: &[f64],
+                                                              fixed_parameters: &[(usize, f64)])
+                                                              -> Result<Vertex, FriedrichError>
+    {
+        let (log_parameters, value) = self.log_space_evaluate::<B>(config, log_parameters, signs, fixed_parameters)?;
+        let value = if value.is_nan() { f64::INFINITY } else { value };
+        let step = if config.record_trace { Some(self.trace_step(0, &[])) } else { None };
+        Ok(Vertex { log_parameters, value, step })
+    }
+
+    /// Evaluates a vertex of the simplex (see `try_simplex_evaluate`).
+    ///
+    /// If the covariance matrix is not positive definite at the vertex, the negated objective is infinite
+    /// (the vertex is then never kept) and the model must be retrained before being used.
+    fn simplex_evaluate<B: CovarianceBuilder<KernelType>>(&mut self,
+                                                          config: &FitConfig,
+                                                          log_parameters: Vec<f64>,
+                                                          signs: &[f64],
+                                                          fixed_parameters: &[(usize, f64)])
+                                                          -> Vertex
+    {
+        self.try_simplex_evaluate::<B>(config, log_parameters.clone(), signs, fixed_parameters)
+            .unwrap_or(Vertex { log_parameters, value: f64::INFINITY, step: None })
+    }
+
+    /// Fit parameters using the Nelder-Mead simplex algorithm on the logarithm of the parameters
+    /// in order to maximize the `config.objective`, using only evaluations of the objective.
+    ///
+    /// The reflection, expansion, contraction and shrinkage coefficients adapt to the number n of free parameters
+    /// (1, 1+2/n, 0.75-1/2n and 1-1/n, with n at least 2 where they are the standard 1, 2, 0.5 and 0.5)
+    /// which keeps the algorithm efficient in higher dimensions,
+    /// see Gao & Han, Implementing the Nelder-Mead simplex algorithm with adaptive parameters.
+    ///
+    /// Runs for a maximum of `config.max_iter` iterations (each one requiring one or two Cholesky decompositions,
+    /// plus one per parameter when the simplex shrinks).
+    /// Stops prematurely if all vertices of the simplex are within `config.convergence_fraction` time the value of the
+    /// parameters of the best vertex (or if their objectives are within `config.likelihood_tolerance`).
+    /// Stops prematurely if the runtime exceeds `config.max_time`.
+    /// Vertices are projected into the bounds (see `project_parameters`).
+    /// Vertices where the covariance matrix is not positive definite are never kept,
+    /// returns an error only if it is not positive definite at the initial parameters.
+    pub(super) fn nelder_mead_optimize_parameters<B: CovarianceBuilder<KernelType>>(
+        &mut self,
+        config: &FitConfig)
+        -> Result<Vec<OptimStep>, FriedrichError>
+    {
+        let initial_step = 0.5; // Initial size of the simplex, in log-space.
+        let epsilon = 1e-8;
+
+        // Parameters are optimized in log-space, their signs are kept aside as some kernels accept negative parameters.
+        let mut parameters = self.kernel.get_parameters();
+        parameters.push(self.noise);
+        self.project_parameters(config, &mut parameters);
+        let signs: Vec<f64> = parameters.iter().map(|&p| if p < 0. { -1. } else { 1. }).collect();
+        // Insures no parameter is 0 (which would make its log-space step meaningless).
+        let log_parameters: Vec<f64> = parameters.iter().map(|p| p.abs().max(epsilon).ln()).collect();
+        let fixed_parameters: Vec<(usize, f64)> =
+            config.fixed_parameters.iter().map(|&index| (index, parameters[index])).collect();
+        let free_parameters: Vec<usize> =
+            (0..parameters.len()).filter(|index| !config.fixed_parameters.contains(index)).collect();
+        if free_parameters.is_empty()
+        {
+            return Ok(Vec::new());
+        }
+
+        // Adaptive coefficients (with a single free parameter, the shrinkage would collapse the simplex).
+        let dimension = free_parameters.len().max(2) as f64;
+        let (reflection, expansion, contraction, shrinkage) =
+            (1., 1. + 2. / dimension, 0.75 - 0.5 / dimension, 1. - 1. / dimension);
+
+        // Builds the initial simplex, one vertex per free parameter plus the initial parameters.
+        let initial_vertex = self.try_simplex_evaluate::<B>(config, log_parameters.clone(), &signs, &fixed_parameters)?;
+        let mut simplex = vec![initial_vertex];
+        for &index in &free_parameters
+        {
+            let mut vertex = log_parameters.clone();
+            vertex[index] += initial_step;
+            simplex.push(self.simplex_evaluate::<B>(config, vertex, &signs, &fixed_parameters));
+        }
+
+        // Computes `centroid + factor * (centroid - vertex)`.
+        let move_from = |centroid: &[f64], vertex: &[f64], factor: f64| -> Vec<f64> {
+            centroid.iter().zip(vertex).map(|(c, v)| c + factor * (c - v)).collect()
+        };
+
+        let mut trace = Vec::new();
+        let time_start = Instant::now();
+        for i in 1..=config.max_iter
+        {
+            // Sorts the vertices from best to worst.
+            simplex.sort_by(|a, b| a.value.total_cmp(&b.value));
+            if let Some(step) = &simplex[0].step
+            {
+                trace.push(OptimStep { iteration: i, ..step.clone() });
+            }
+
+            // A change of `delta` of a log-parameter is a relative change of about `delta` of the parameter.
+            let best = &simplex[0];
+            let is_far_from_best = |vertex: &Vertex| {
+                vertex.log_parameters
+                      .iter()
+                      .zip(&best.log_parameters)
+                      .any(|(v, b)| (v - b).abs() > config.convergence_fraction)
+            };
+            let had_significant_progress = simplex.iter().any(is_far_from_best);
+            let worst_value = simplex[simplex.len() - 1].value;
+            let has_converged = matches!(config.likelihood_tolerance,
+                                         Some(tolerance) if (worst_value - best.value).abs()
+                                                            <= tolerance * best.value.abs().max(1.));
+            if (!had_significant_progress) || has_converged || (time_start.elapsed() > config.max_time)
+            {
+                break;
+            }
+
+            // Centroid of all vertices but the worst.
+            let worst = simplex.pop().expect("the simplex has at least two vertices");
+            let mut centroid = vec![0.; worst.log_parameters.len()];
+            for vertex in &simplex
+            {
+                axpy(1. / simplex.len() as f64, &vertex.log_parameters, &mut centroid);
+            }
+
+            let reflected = self.simplex_evaluate::<B>(config,
+                                                  move_from(&centroid, &worst.log_parameters, reflection),
+                                                  &signs,
+                                                  &fixed_parameters);
+            if reflected.value < simplex[0].value
+            {
+                // The reflection is the new best vertex, tries to go further in that direction.
+                let expanded = self.simplex_evaluate::<B>(config,
+                                                     move_from(&centroid, &worst.log_parameters, expansion),
+                                                     &signs,
+                                                     &fixed_parameters);
+                simplex.push(if expanded.value < reflected.value { expanded } else { reflected });
+            }
+            else if reflected.value < simplex[simplex.len() - 1].value
+            {
+                simplex.push(reflected);
+            }
+            else
+            {
+                // Contracts toward the best of the reflected and worst vertices.
+                let (outside, contracted) = if reflected.value < worst.value
+                {
+                    (reflected.value, move_from(&centroid, &worst.log_parameters, contraction * reflection))
+                }
+                else
+                {
+                    (worst.value, move_from(&centroid, &worst.log_parameters, -contraction))
+                };
+                let contracted = self.simplex_evaluate::<B>(config, contracted, &signs, &fixed_parameters);
+                if contracted.value < outside
+                {
+                    simplex.push(contracted);
+                }
+                else
+                {
+                    // Shrinks all vertices toward the best one.
+                    simplex.push(worst);
+                    let best = simplex[0].log_parameters.clone();
+                    for vertex in simplex.iter_mut().skip(1)
+                    {
+                        let shrunk = move_from(&best, &vertex.log_parameters, -shrinkage);
+                        *vertex = self.simplex_evaluate::<B>(config, shrunk, &signs, &fixed_parameters);
+                    }
+                }
+            }
+        }
+
+        // Leaves the model at the best vertex.
+        let best = simplex.into_iter()
+                          .min_by(|a, b| a.value.total_cmp(&b.value))
+                          .expect("the simplex has at least two vertices");
+        self.try_simplex_evaluate::<B>(config, best.log_parameters, &signs, &fixed_parameters)?;
+        Ok(trace)
     }
 }
 
+/// Vertex of the simplex of the Nelder-Mead algorithm.
+struct Vertex
+{
+    /// Log-parameters of the vertex.
+    log_parameters: Vec<f64>,
+    /// Negated objective at the vertex.
+    value: f64,
+    /// State of the model at the vertex, kept only when the trace is recorded.
+    step: Option<OptimStep>
+}
+
+/// Line along which the L-BFGS algorithm searches its next point, in log-space.
+struct LineSearch<'a>
+{
+    /// Log-parameters at the start of the line.
+    origin: &'a [f64],
+    /// Direction of the line.
+    direction: &'a [f64],
+    /// Signs of the parameters.
+    signs: &'a [f64],
+    /// Parameters that are kept at a fixed value.
+    fixed_parameters: &'a [(usize, f64)],
+    /// Negated objective at the start of the line.
+    value: f64,
+    /// Derivative of the negated objective along the direction at the start of the line.
+    slope: f64
+}
+
+/// Log-parameters, negated objective and log-space gradient at a point of a line search.
+type LinePoint = (Vec<f64>, f64, Vec<f64>);
+
+/// Dot product between two slices.
+fn dot(x: &[f64], y: &[f64]) -> f64
+{
+    x.iter().zip(y).map(|(a, b)| a * b).sum()
+}
+
+/// Computes `y += a*x`.
+fn axpy(a: f64, x: &[f64], y: &mut [f64])
+{
+    y.iter_mut().zip(x).for_each(|(y, x)| *y += a * x);
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::algebra::{SRowVector, Serial};
+    use crate::parameters::{kernel, prior};
+    use nalgebra::{storage::Storage, DVector, Dynamic, U1};
+
+    #[test]
+    fn line_search_shrinks_steps_reaching_a_singular_covariance_matrix()
+    {
+        // Duplicated inputs make the covariance matrix singular once the noise reaches zero.
+        let training_inputs = DMatrix::from_column_slice(5, 1, &[1., 1., 2., 3., 4.]);
+        let training_outputs = DVector::from_column_slice(&[3.0, 3.0, 1.0, -2.0, -2.0]);
+        let mut gp = GaussianProcess::new(prior::ZeroPrior {},
+                                          kernel::SquaredExp::default(),
+                                          0.1,
+                                          None,
+                                          training_inputs,
+                                          training_outputs);
+        let config = FitConfig::default();
+
+        // The largest step moves the noise to zero.
+        let mut parameters = gp.kernel.get_parameters();
+        parameters.push(gp.noise);
+        let origin: Vec<f64> = parameters.iter().map(|p| p.ln()).collect();
+        let direction = [0., 0., -1000.];
+        let line = LineSearch { origin: &origin,
+                                direction: &direction,
+                                signs: &[1., 1., 1.],
+                                fixed_parameters: &[],
+                                value: -gp.objective_value(&config),
+                                slope: dot(&gp.negative_log_space_gradient::<Serial>(&config), &direction) };
+        assert!(line.slope < 0., "the noise should decrease as the duplicated inputs have the same output");
+
+        let (candidate, value, _) =
+            gp.wolfe_line_search::<Serial>(&config, &line, 1.).expect("a smaller step should decrease the noise");
+        assert!(value < line.value);
+        assert!(candidate[2] < origin[2] && candidate[2] > origin[2] - 1000.);
+    }
+
+    /// A squared exponential kernel whose covariance matrix is not positive definite for length scales above 2.
+    #[derive(Clone, Default)]
+    struct SingularAboveTwo(kernel::SquaredExp);
+
+    impl Kernel for SingularAboveTwo
+    {
+        fn nb_parameters(&self) -> usize
+        {
+            2
+        }
+
+        fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                                x1: &SRowVector<S1>,
+                                                                                x2: &SRowVector<S2>)
+                                                                                -> f64
+        {
+            if self.0.ls.abs() > 2. && x1 != x2
+            {
+                2. * self.0.ampl.abs()
+            }
+            else
+            {
+                self.0.kernel(x1, x2)
+            }
+        }
+
+        fn get_parameters(&self) -> Vec<f64>
+        {
+            self.0.get_parameters()
+        }
+
+        fn set_parameters(&mut self, parameters: &[f64])
+        {
+            self.0.set_parameters(parameters)
+        }
+    }
+
+    #[test]
+    fn restarts_reaching_a_singular_covariance_matrix_are_skipped()
+    {
+        // Outputs alternating between neighbouring inputs keep the first run at short length scales.
+        let training_inputs = DMatrix::from_fn(12, 1, |r, _| r as f64 * 0.5);
+        let training_outputs = DVector::from_fn(12, |r, _| if r % 2 == 0 { 1. } else { -1. });
+        let mut gp = GaussianProcess::new(prior::ZeroPrior {},
+                                          SingularAboveTwo(kernel::SquaredExp::new(0.5, 1.)),
+                                          0.1,
+                                          None,
+                                          training_inputs,
+                                          training_outputs);
+        let config = FitConfig { nb_restarts: 8, seed: 5, record_trace: true, ..FitConfig::default() };
+        let trace = gp.try_fit_parameters_with_config(false, true, &config).expect("singular restarts are skipped");
+
+        let skipped: Vec<&OptimStep> = trace.iter().filter(|step| step.iteration == 0).collect();
+        assert!(!skipped.is_empty(), "no restart was drawn with a length scale above 2");
+        assert!(skipped.iter().all(|step| step.restart > 0 && step.log_likelihood == f64::NEG_INFINITY));
+        assert!(gp.kernel.0.ls.abs() <= 2.);
+    }
+
+    #[test]
+    fn nelder_mead_skips_singular_vertices()
+    {
+        let training_inputs = DMatrix::from_fn(12, 1, |r, _| r as f64 * 0.5);
+        let training_outputs = DVector::from_fn(12, |r, _| if r % 2 == 0 { 1. } else { -1. });
+        // With the amplitude above the noise, the covariance matrix is singular for all length scales above 2.
+        let config =
+            FitConfig { optimizer: Optimizer::NelderMead, fixed_parameters: vec![1, 2], ..FitConfig::default() };
+
+        // The first step of the simplex goes above a length scale of 2.
+        let mut gp = GaussianProcess::new(prior::ZeroPrior {},
+                                          SingularAboveTwo(kernel::SquaredExp::new(1.8, 1.)),
+                                          0.1,
+                                          None,
+                                          training_inputs.clone(),
+                                          training_outputs.clone());
+        gp.nelder_mead_optimize_parameters::<Serial>(&config).expect("singular vertices are skipped");
+        assert!(gp.kernel.0.ls.abs() <= 2.);
+        assert!(gp.objective_value(&config).is_finite());
+
+        // Only a singular initial vertex is an error.
+        let mut gp = GaussianProcess::new(prior::ZeroPrior {},
+                                          SingularAboveTwo(kernel::SquaredExp::new(0.5, 1.)),
+                                          0.1,
+                                          None,
+                                          training_inputs,
+                                          training_outputs);
+        gp.kernel.0.ls = 3.;
+        assert!(gp.nelder_mead_optimize_parameters::<Serial>(&config).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+//! Saving and loading trained processes
+//!
+//! With the `friedrich_serde` feature (enabled by default), a process can be written to a file and read back
+//! in a compact binary format (using [bincode](https://crates.io/crates/bincode)), without being retrained.
+//!
+//! ```rust
+//! # use friedrich::gaussian_process::GaussianProcess;
+//! # use friedrich::{kernel::Gaussian, prior::ConstantPrior};
+//! let training_inputs = vec![vec![0.8], vec![1.2], vec![3.8], vec![4.2]];
+//! let training_outputs = vec![3.0, 4.0, -2.0, -2.0];
+//! let gp = GaussianProcess::default(training_inputs, training_outputs);
+//!
+//! let path = std::env::temp_dir().join("friedrich_persistence_example.gp");
+//! gp.save(&path).unwrap();
+//! // The type of the kernel and prior should be given as they are not stored in the file.
+//! let loaded: GaussianProcess<Gaussian, ConstantPrior> = GaussianProcess::load(&path).unwrap();
+//! # std::fs::remove_file(&path).unwrap();
+//! assert_eq!(loaded.predict(&vec![vec![1.]]), gp.predict(&vec![vec![1.]]));
+//! ```
+
+use super::GaussianProcess;
+use crate::parameters::{kernel::Kernel, prior::Prior};
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+/// Bytes written at the beginning of every file, identifying it as a saved process.
+const MAGIC: &[u8; 9] = b"FRIEDRICH";
+
+/// Version of the format, written after the magic bytes.
+///
+/// It should be incremented whenever the serialized representation of a process changes
+/// such that files written by an older version are rejected (or migrated) rather than misread.
+const FORMAT_VERSION: u32 = 1;
+
+impl<KernelType, PriorType> GaussianProcess<KernelType, PriorType>
+    where KernelType: Kernel + Serialize + DeserializeOwned,
+          PriorType: Prior + Serialize + DeserializeOwned
+{
+    /// Saves the process to a file, in a compact binary format preceded by a version tag.
+    ///
+    /// The process can be read back, without being retrained, with `load`.
+    /// Returns an error if the file cannot be written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()>
+    {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.extend(bincode::serialize(self).map_err(|error| Error::new(ErrorKind::InvalidData, error))?);
+        std::fs::write(path, bytes)
+    }
+
+    /// Loads a process saved with `save`.
+    ///
+    /// The kernel and prior types should be the ones of the saved process.
+    /// Returns an error if the file cannot be read, if it was not written by `save`,
+    /// if it was written by an incompatible version of the library or if it does not describe a process of this type.
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<Self>
+    {
+        let bytes = std::fs::read(path)?;
+        let header_length = MAGIC.len() + 4;
+        if bytes.len() < header_length || !bytes.starts_with(MAGIC)
+        {
+            return Err(Error::new(ErrorKind::InvalidData, "the file does not contain a saved gaussian process"));
+        }
+        let mut version = [0u8; 4];
+        version.copy_from_slice(&bytes[MAGIC.len()..header_length]);
+        let version = u32::from_le_bytes(version);
+        if version != FORMAT_VERSION
+        {
+            let message = format!("the file was saved with format version {} but version {} is expected",
+                                  version, FORMAT_VERSION);
+            return Err(Error::new(ErrorKind::InvalidData, message));
+        }
+        bincode::deserialize(&bytes[header_length..]).map_err(|error| Error::new(ErrorKind::InvalidData, error))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::parameters::{kernel, prior};
+    use nalgebra::{DMatrix, DVector};
+    use std::path::PathBuf;
+
+    /// A path in the temporary directory, unique to the test.
+    fn temporary_path(name: &str) -> PathBuf
+    {
+        std::env::temp_dir().join(format!("friedrich_{}_{}.gp", name, std::process::id()))
+    }
+
+    #[test]
+    fn loaded_process_predicts_as_the_saved_one()
+    {
+        let training_inputs = DMatrix::from_fn(12, 2, |r, c| (r as f64 * 0.7 + c as f64).sin() * 3.);
+        let training_outputs = DVector::from_fn(12, |r, _| training_inputs[(r, 0)] - 0.5 * training_inputs[(r, 1)]);
+        let gp = GaussianProcess::builder(training_inputs.clone(), training_outputs)
+            .set_kernel(kernel::Matern52::default())
+            .set_prior(prior::LinearPrior::default(2))
+            .standardize_outputs()
+            .fit_prior()
+            .train();
+
+        let path = temporary_path("save_load");
+        gp.save(&path).unwrap();
+        let loaded: GaussianProcess<kernel::Matern52, prior::LinearPrior> = GaussianProcess::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let test_inputs = training_inputs.map(|x| x + 0.3);
+        assert_eq!(loaded.predict(&test_inputs), gp.predict(&test_inputs));
+        assert_eq!(loaded.predict_variance(&test_inputs), gp.predict_variance(&test_inputs));
+    }
+
+    #[test]
+    fn files_of_another_version_or_type_are_rejected()
+    {
+        let gp = GaussianProcess::default(vec![vec![0.8], vec![1.2], vec![3.8]], vec![3.0, 4.0, -2.0]);
+        let path = temporary_path("rejected");
+        type DefaultProcess = GaussianProcess<kernel::Gaussian, prior::ConstantPrior>;
+
+        // not a saved process
+        std::fs::write(&path, b"not a model").unwrap();
+        assert_eq!(DefaultProcess::load(&path).err().map(|error| error.kind()), Some(ErrorKind::InvalidData));
+
+        // another version of the format
+        gp.save(&path).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        std::fs::write(&path, bytes).unwrap();
+        let error = DefaultProcess::load(&path).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(error.to_string().contains("format version 2"), "{}", error);
+
+        // another kernel
+        gp.save(&path).unwrap();
+        let result: std::io::Result<GaussianProcess<kernel::SquaredExpArd, prior::ConstantPrior>> =
+            GaussianProcess::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.err().map(|error| error.kind()), Some(ErrorKind::InvalidData));
+        assert_eq!(DefaultProcess::load(&path).err().map(|error| error.kind()), Some(ErrorKind::NotFound));
+    }
+}
@@ -0,0 +1,376 @@
+//! Sparse Gaussian process
+//!
+//! Approximates a gaussian process using `m` inducing points
+//! with the FITC (Fully Independent Training Conditional) approximation,
+//! see [A Unifying View of Sparse Approximate Gaussian Process Regression](https://www.jmlr.org/papers/volume6/quinonero-candela05a/quinonero-candela05a.pdf).
+//!
+//! Training costs O(n*m²) operations (instead of O(n³) for a dense process)
+//! and a prediction O(m²) operations per point,
+//! making it possible to train a model on datasets with tens of thousands of samples.
+//! The training data is not stored, only `m`-sized quantities are kept.
+//!
+//! ```rust
+//! # use friedrich::gaussian_process::SparseGaussianProcess;
+//! # use friedrich::kernel::SquaredExp;
+//! # use friedrich::prior::ZeroPrior;
+//! let training_inputs: Vec<Vec<f64>> = (0..1000).map(|i| vec![i as f64 / 100.]).collect();
+//! let training_outputs: Vec<f64> = training_inputs.iter().map(|x| x[0].sin()).collect();
+//!
+//! // Uses 20 inducing points placed by k-means.
+//! let gp = SparseGaussianProcess::with_kmeans(ZeroPrior {},
+//!                                             SquaredExp::new(1., 1.),
+//!                                             0.1,
+//!                                             None,
+//!                                             training_inputs,
+//!                                             training_outputs,
+//!                                             20);
+//! let prediction = gp.predict(&vec![2.5]);
+//! assert!((prediction - 2.5f64.sin()).abs() < 0.1);
+//! ```
+
+use crate::algebra::make_covariance_matrix;
+use crate::conversion::Input;
+use crate::error::FriedrichError;
+use crate::parameters::{kernel::Kernel, prior::Prior};
+use nalgebra::{Cholesky, DMatrix, DVector, Dynamic};
+
+/// Jitter added to the diagonal of the covariance matrix of the inducing points, relative to its mean diagonal element,
+/// as inducing points that are close to one another would otherwise make it singular.
+const INDUCING_JITTER: f64 = 1e-10;
+
+/// Maximum number of iterations of the k-means algorithm used to place the inducing points.
+const KMEANS_MAX_ITER: usize = 100;
+
+/// A sparse approximation of a gaussian process, built on a set of inducing points (see the module documentation).
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct SparseGaussianProcess<KernelType: Kernel, PriorType: Prior>
+{
+    /// Value to which the process will regress in the absence of information.
+    pub prior: PriorType,
+    /// Kernel used to fit the process on the data.
+    pub kernel: KernelType,
+    /// Amplitude of the noise of the data.
+    pub noise: f64,
+    /// Inducing points, one per row.
+    inducing_points: DMatrix<f64>,
+    /// Cholesky decomposition of the covariance matrix of the inducing points.
+    inducing_cholesky: Cholesky<f64, Dynamic>,
+    /// Cholesky decomposition of `I + V*Λ^-1*V^T` where `V = L^-1 * cov(inducing,train)`
+    /// and Λ is the diagonal matrix of the FITC corrected noise.
+    posterior_cholesky: Cholesky<f64, Dynamic>,
+    /// Weights given to the covariance between the inducing points and an input to compute the mean of the process.
+    weights: DVector<f64>
+}
+
+impl<KernelType: Kernel, PriorType: Prior> SparseGaussianProcess<KernelType, PriorType>
+{
+    /// Trains a new sparse gaussian process on the given data, using the given inducing points.
+    ///
+    /// The parameters of the prior and kernel are not fitted,
+    /// they can be fitted on a subset of the data using a `GaussianProcess`.
+    /// Panics if the parameters or the data are invalid, see `try_new` for a version returning an error instead.
+    pub fn new<T: Input>(prior: PriorType,
+                         kernel: KernelType,
+                         noise: f64,
+                         cholesky_epsilon: Option<f64>,
+                         training_inputs: T,
+                         training_outputs: T::InVector,
+                         inducing_points: T)
+                         -> Self
+    {
+        Self::try_new(prior, kernel, noise, cholesky_epsilon, training_inputs, training_outputs, inducing_points)
+            .unwrap_or_else(|error| panic!("SparseGaussianProcess::new: {}", error))
+    }
+
+    /// Trains a new sparse gaussian process on the given data, placing `nb_inducing_points` inducing points
+    /// on the centroids of the clusters found by k-means on the training inputs.
+    ///
+    /// The k-means algorithm is deterministic (it is initialized with evenly spaced training inputs).
+    /// Panics if the parameters or the data are invalid.
+    pub fn with_kmeans<T: Input>(prior: PriorType,
+                                 kernel: KernelType,
+                                 noise: f64,
+                                 cholesky_epsilon: Option<f64>,
+                                 training_inputs: T,
+                                 training_outputs: T::InVector,
+                                 nb_inducing_points: usize)
+                                 -> Self
+    {
+        let training_inputs = T::into_dmatrix(training_inputs);
+        let training_outputs = T::into_dvector(training_outputs);
+        let inducing_points = kmeans(&training_inputs, nb_inducing_points);
+        Self::try_new(prior, kernel, noise, cholesky_epsilon, training_inputs, training_outputs, inducing_points)
+            .unwrap_or_else(|error| panic!("SparseGaussianProcess::with_kmeans: {}", error))
+    }
+
+    /// Fallible version of `new`, returns an error rather than panicking
+    /// if the noise is invalid, if the dimensions of the data do not match
+    /// or if a covariance matrix is not positive definite.
+    pub fn try_new<T: Input>(prior: PriorType,
+                             kernel: KernelType,
+                             noise: f64,
+                             cholesky_epsilon: Option<f64>,
+                             training_inputs: T,
+                             training_outputs: T::InVector,
+                             inducing_points: T)
+                             -> Result<Self, FriedrichError>
+    {
+        // formula (see Quiñonero-Candela & Rasmussen, A Unifying View of Sparse Approximate GP Regression):
+        // Λ = diag(cov(train,train) - Q) + noise² I
+        // Q = cov(train,inducing) * cov(inducing,inducing)^-1 * cov(inducing,train)
+        // mean = cov(input,inducing) * Σ^-1 * cov(inducing,train) * Λ^-1 * output
+        // Σ = cov(inducing,inducing) + cov(inducing,train) * Λ^-1 * cov(train,inducing)
+
+        if noise.is_nan() || noise < 0.
+        {
+            return Err(FriedrichError::InvalidParameter { name: "noise", value: noise });
+        }
+        let training_inputs = T::into_dmatrix(training_inputs);
+        let training_outputs = T::into_dvector(training_outputs);
+        let inducing_points = T::into_dmatrix(inducing_points);
+        if training_inputs.nrows() != training_outputs.nrows()
+        {
+            return Err(FriedrichError::DimensionMismatch { expected: training_inputs.nrows(),
+                                                           found: training_outputs.nrows() });
+        }
+        if inducing_points.ncols() != training_inputs.ncols()
+        {
+            return Err(FriedrichError::DimensionMismatch { expected: training_inputs.ncols(),
+                                                           found: inducing_points.ncols() });
+        }
+        let training_outputs = training_outputs - prior.prior(&training_inputs);
+
+        // Cholesky decomposition of the covariance of the inducing points, with a small jitter.
+        let mut inducing_covariance = make_covariance_matrix(&inducing_points, &inducing_points, &kernel);
+        let jitter = INDUCING_JITTER * inducing_covariance.diagonal().mean().abs();
+        inducing_covariance.iter_mut().step_by(inducing_points.nrows() + 1).for_each(|d| *d += jitter);
+        let inducing_cholesky = cholesky(inducing_covariance, cholesky_epsilon)?;
+
+        // V = L^-1 * cov(inducing,train)
+        let cross_covariance = make_covariance_matrix(&inducing_points, &training_inputs, &kernel);
+        let v = inducing_cholesky.l()
+                                 .solve_lower_triangular(&cross_covariance)
+                                 .ok_or(FriedrichError::SingularMatrix)?;
+
+        // Λ, the noise corrected by the variance that is not explained by the inducing points.
+        let noise_variance = noise * noise;
+        let lambda = training_inputs.row_iter().zip(v.column_iter()).map(|(row, v_col)| {
+                                                                         // Cannot be negative, up to rounding errors.
+                                                                         let variance = kernel.kernel(&row, &row)
+                                                                                        - v_col.norm_squared();
+                                                                         variance.max(0.) + noise_variance
+                                                                     });
+        let lambda = DVector::<f64>::from_iterator(training_inputs.nrows(), lambda);
+
+        // B = I + V * Λ^-1 * V^T
+        let mut v_scaled = v.clone();
+        for (mut column, l) in v_scaled.column_iter_mut().zip(lambda.iter())
+        {
+            column /= l.sqrt();
+        }
+        let nb_inducing_points = inducing_points.nrows();
+        let b = &v_scaled * v_scaled.transpose() + DMatrix::<f64>::identity(nb_inducing_points, nb_inducing_points);
+        let posterior_cholesky = cholesky(b, cholesky_epsilon)?;
+
+        // weights = L^-T * B^-1 * V * Λ^-1 * output
+        let scaled_outputs = training_outputs.component_div(&lambda);
+        let weights = posterior_cholesky.solve(&(&v * scaled_outputs));
+        let weights =
+            inducing_cholesky.l().tr_solve_lower_triangular(&weights).ok_or(FriedrichError::SingularMatrix)?;
+
+        Ok(SparseGaussianProcess { prior,
+                                   kernel,
+                                   noise,
+                                   inducing_points,
+                                   inducing_cholesky,
+                                   posterior_cholesky,
+                                   weights })
+    }
+
+    /// Returns the inducing points, one per row.
+    pub fn inducing_points(&self) -> &DMatrix<f64>
+    {
+        &self.inducing_points
+    }
+
+    /// Makes a prediction (the mean of the gaussian process) for each row of the input.
+    pub fn predict<T: Input>(&self, inputs: &T) -> T::OutVector
+    {
+        // formula : prior + cov(input,inducing) * weights
+
+        let inputs = T::to_dmatrix(inputs);
+        assert_eq!(inputs.ncols(), self.inducing_points.ncols());
+
+        let cov_inducing_inputs = make_covariance_matrix(&self.inducing_points, &inputs, &self.kernel);
+        let mut prior = self.prior.prior(&inputs);
+        prior.gemm_tr(1f64, &cov_inducing_inputs, &self.weights, 1f64);
+
+        T::from_dvector(&prior)
+    }
+
+    /// Predicts the variance of the gaussian process for each row of the input.
+    pub fn predict_variance<T: Input>(&self, inputs: &T) -> T::OutVector
+    {
+        // formula, diagonal of : cov(input,input) - Q + cov(input,inducing) * Σ^-1 * cov(inducing,input)
+        // Q = cov(input,inducing) * cov(inducing,inducing)^-1 * cov(inducing,input)
+
+        let inputs = T::to_dmatrix(inputs);
+        assert_eq!(inputs.ncols(), self.inducing_points.ncols());
+
+        let cov_inducing_inputs = make_covariance_matrix(&self.inducing_points, &inputs, &self.kernel);
+        let kl = self.inducing_cholesky
+                     .l()
+                     .solve_lower_triangular(&cov_inducing_inputs)
+                     .expect("predict_variance : solve failed");
+        let bkl = self.posterior_cholesky
+                      .l()
+                      .solve_lower_triangular(&kl)
+                      .expect("predict_variance : solve failed");
+
+        let variances = inputs.row_iter()
+                              .zip(kl.column_iter().zip(bkl.column_iter()))
+                              .map(|(row, (kl_col, bkl_col))| {
+                                  self.kernel.kernel(&row, &row) - kl_col.norm_squared() + bkl_col.norm_squared()
+                              });
+        let variances = DVector::<f64>::from_iterator(inputs.nrows(), variances);
+
+        T::from_dvector(&variances)
+    }
+}
+
+/// Computes a Cholesky decomposition, using the `cholesky_epsilon` as a substitute for failing diagonal elements if any.
+fn cholesky(matrix: DMatrix<f64>, cholesky_epsilon: Option<f64>) -> Result<Cholesky<f64, Dynamic>, FriedrichError>
+{
+    let cholesky = match cholesky_epsilon
+    {
+        Some(cholesky_epsilon) => Cholesky::new_with_substitute(matrix, cholesky_epsilon),
+        None => matrix.cholesky()
+    };
+    cholesky.ok_or(FriedrichError::SingularMatrix)
+}
+
+/// Returns `nb_clusters` centroids, one per row, found by the k-means (Lloyd) algorithm on the rows of the inputs.
+///
+/// The centroids are initialized with evenly spaced rows of the inputs,
+/// a centroid that ends up without any input keeps its previous position.
+fn kmeans(inputs: &DMatrix<f64>, nb_clusters: usize) -> DMatrix<f64>
+{
+    let nb_inputs = inputs.nrows();
+    assert!(nb_clusters > 0 && nb_clusters <= nb_inputs,
+            "kmeans: the number of inducing points should be between 1 and the number of training inputs ({}).",
+            nb_inputs);
+
+    let mut centroids = DMatrix::from_fn(nb_clusters, inputs.ncols(), |r, c| inputs[(r * nb_inputs / nb_clusters, c)]);
+    let mut assignments = vec![usize::MAX; nb_inputs];
+    for _ in 0..KMEANS_MAX_ITER
+    {
+        // Assigns each input to its closest centroid.
+        let mut has_changed = false;
+        for (input, assignment) in inputs.row_iter().zip(assignments.iter_mut())
+        {
+            let closest = centroids.row_iter()
+                                   .map(|centroid| (centroid - input).norm_squared())
+                                   .enumerate()
+                                   .min_by(|(_, d1), (_, d2)| d1.total_cmp(d2))
+                                   .map(|(index, _)| index)
+                                   .expect("there is at least one centroid");
+            has_changed |= *assignment != closest;
+            *assignment = closest;
+        }
+        if !has_changed
+        {
+            break;
+        }
+
+        // Moves each centroid to the mean of its inputs.
+        let mut sums = DMatrix::<f64>::zeros(nb_clusters, inputs.ncols());
+        let mut counts = vec![0usize; nb_clusters];
+        for (input, &assignment) in inputs.row_iter().zip(assignments.iter())
+        {
+            let mut sum = sums.row_mut(assignment);
+            sum += input;
+            counts[assignment] += 1;
+        }
+        for (cluster, &count) in counts.iter().enumerate().filter(|(_, &count)| count > 0)
+        {
+            centroids.set_row(cluster, &(sums.row(cluster) / count as f64));
+        }
+    }
+    centroids
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::gaussian_process::GaussianProcess;
+    use crate::parameters::{kernel::SquaredExp, prior::ZeroPrior};
+
+    #[test]
+    fn kmeans_recovers_separated_clusters()
+    {
+        let centers = [[0., 0.], [10., 0.], [0., 10.]];
+        let inputs = DMatrix::from_fn(90, 2, |r, c| centers[r % 3][c] + 0.1 * ((r * 7 + c * 3) as f64).sin());
+        let centroids = kmeans(&inputs, 3);
+        for center in centers
+        {
+            let distance = centroids.row_iter()
+                                    .map(|centroid| (centroid[0] - center[0]).hypot(centroid[1] - center[1]))
+                                    .fold(f64::INFINITY, f64::min);
+            assert!(distance < 0.1, "no centroid near {:?}", center);
+        }
+    }
+
+    #[test]
+    fn using_the_training_inputs_as_inducing_points_gives_the_dense_process()
+    {
+        let training_inputs: Vec<Vec<f64>> = (0..25).map(|i| vec![i as f64 * 0.3]).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| x[0].sin()).collect();
+        let kernel = SquaredExp::new(0.8, 1.2);
+        let sparse = SparseGaussianProcess::new(ZeroPrior {},
+                                                kernel,
+                                                0.1,
+                                                None,
+                                                training_inputs.clone(),
+                                                training_outputs.clone(),
+                                                training_inputs.clone());
+        let dense = GaussianProcess::new(ZeroPrior {}, kernel, 0.1, None, training_inputs, training_outputs);
+
+        let inputs: Vec<Vec<f64>> = (0..15).map(|i| vec![-1. + i as f64 * 0.7]).collect();
+        for (a, b) in sparse.predict(&inputs).iter().zip(dense.predict(&inputs))
+        {
+            assert!((a - b).abs() < 1e-6, "means {} and {}", a, b);
+        }
+        for (a, b) in sparse.predict_variance(&inputs).iter().zip(dense.predict_variance(&inputs))
+        {
+            assert!((a - b).abs() < 1e-6, "variances {} and {}", a, b);
+        }
+    }
+
+    #[test]
+    fn sparse_process_fits_a_large_dataset()
+    {
+        let f = |x: f64| (2. * x).sin() + 0.5 * x;
+        let training_inputs: Vec<Vec<f64>> = (0..3000).map(|i| vec![i as f64 * 0.002]).collect();
+        let training_outputs: Vec<f64> =
+            training_inputs.iter().enumerate().map(|(i, x)| f(x[0]) + 0.05 * (i as f64 * 1.7).sin()).collect();
+        let gp = SparseGaussianProcess::with_kmeans(ZeroPrior {},
+                                                    SquaredExp::new(0.5, 2.),
+                                                    0.05,
+                                                    None,
+                                                    training_inputs,
+                                                    training_outputs,
+                                                    30);
+        assert_eq!(gp.inducing_points().nrows(), 30);
+
+        let inputs: Vec<Vec<f64>> = (0..20).map(|i| vec![0.1 + i as f64 * 0.29]).collect();
+        let variances = gp.predict_variance(&inputs);
+        for ((x, prediction), variance) in inputs.iter().zip(gp.predict(&inputs)).zip(variances)
+        {
+            assert!((prediction - f(x[0])).abs() < 0.05, "prediction {} at {}", prediction, x[0]);
+            assert!((0. ..0.01).contains(&variance), "variance {} at {}", variance, x[0]);
+        }
+        // Far from the data the variance goes back to the amplitude of the kernel.
+        assert!((gp.predict_variance(&vec![100.]) - 2.).abs() < 1e-6);
+    }
+}
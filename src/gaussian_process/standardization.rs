@@ -0,0 +1,113 @@
+//! Standardization of the data
+//!
+//! Affine transformations applied to the data before the process sees it,
+//! such that the parameters of the kernel are fitted on data of a consistent scale.
+
+use nalgebra::{DMatrix, DVector, RowDVector};
+
+/// Standardizes each input dimension to zero mean and unit variance.
+///
+/// The mean and standard deviation are computed on the training inputs,
+/// the same transformation is then applied to all inputs given to the process.
+/// Dimensions with a null standard deviation are only centered.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct InputStandardization
+{
+    /// Mean of each input dimension.
+    mean: RowDVector<f64>,
+    /// Standard deviation of each input dimension.
+    std: RowDVector<f64>
+}
+
+impl InputStandardization
+{
+    /// Computes the mean and standard deviation of each column of the inputs.
+    pub fn fit(inputs: &DMatrix<f64>) -> InputStandardization
+    {
+        let mean = inputs.row_mean();
+        let std = inputs.row_variance().map(|variance| if variance > 0. { variance.sqrt() } else { 1. });
+        InputStandardization { mean, std }
+    }
+
+    /// Returns the mean of each input dimension.
+    pub fn mean(&self) -> &RowDVector<f64>
+    {
+        &self.mean
+    }
+
+    /// Returns the standard deviation of each input dimension (one for constant dimensions).
+    pub fn std(&self) -> &RowDVector<f64>
+    {
+        &self.std
+    }
+
+    /// Standardizes the inputs, one row per sample.
+    pub fn apply(&self, mut inputs: DMatrix<f64>) -> DMatrix<f64>
+    {
+        assert_eq!(inputs.ncols(),
+                   self.mean.ncols(),
+                   "InputStandardization: the inputs are of dimension {} \
+                    but the standardization was fitted on dimension {}.",
+                   inputs.ncols(),
+                   self.mean.ncols());
+        for mut row in inputs.row_iter_mut()
+        {
+            row -= &self.mean;
+            row.component_div_assign(&self.std);
+        }
+        inputs
+    }
+}
+
+/// Standardizes the outputs to zero mean and unit variance.
+///
+/// The mean and standard deviation are computed on the training outputs,
+/// the process is then trained on standardized outputs and its predictions are brought back to the original scale
+/// (means are scaled and shifted while variances are scaled by the square of the standard deviation).
+/// Constant outputs are only centered.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct OutputStandardization
+{
+    /// Mean of the outputs.
+    mean: f64,
+    /// Standard deviation of the outputs.
+    std: f64
+}
+
+impl OutputStandardization
+{
+    /// Computes the mean and standard deviation of the outputs.
+    pub fn fit(outputs: &DVector<f64>) -> OutputStandardization
+    {
+        let mean = outputs.mean();
+        let variance = outputs.variance();
+        let std = if variance > 0. { variance.sqrt() } else { 1. };
+        OutputStandardization { mean, std }
+    }
+
+    /// Returns the mean of the outputs.
+    pub fn mean(&self) -> f64
+    {
+        self.mean
+    }
+
+    /// Returns the standard deviation of the outputs (one for constant outputs).
+    pub fn std(&self) -> f64
+    {
+        self.std
+    }
+
+    /// Standardizes the outputs.
+    pub fn apply(&self, outputs: DVector<f64>) -> DVector<f64>
+    {
+        outputs.map(|output| (output - self.mean) / self.std)
+    }
+
+    /// Brings standardized outputs (such as predicted means) back to the original scale.
+    pub fn restore(&self, outputs: DVector<f64>) -> DVector<f64>
+    {
+        outputs.map(|output| output * self.std + self.mean)
+    }
+}
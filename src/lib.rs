@@ -8,7 +8,8 @@
 //!
 //! All of those properties make them an algorithm of choice to perform regression when data is scarce or when having uncertainty bars on the output is a desirable property.
 //!
-//! However, the `o(n^3)` complexity of the algorithm makes the classical implementation unsuitable for large training datasets.
+//! However, the `o(n^3)` complexity of the algorithm makes the classical implementation unsuitable for large training datasets
+//! (see the `SparseGaussianProcess` for an approximation that scales to large datasets).
 //!
 //! ## Functionalities
 //!
@@ -18,9 +19,11 @@
 //! - Train it on multidimensional data.
 //! - Fit the parameters (kernel, prior and noise) on the training data.
 //! - Add additional samples efficiently (`O(n^2)`) and refit the process.
-//! - Predict the mean, variance and covariance matrix for given inputs.
+//! - Approximate the process with inducing points to train it on large datasets.
+//! - Predict the mean, variance (or standard deviation and confidence intervals) and covariance matrix for given inputs.
 //! - Sample the distribution at a given position.
-//! - Save and load a trained model with [serde](https://serde.rs/).
+//! - Score candidate inputs with acquisition functions (see the `acquisition` module) to perform Bayesian optimization.
+//! - Save and load a trained model with [serde](https://serde.rs/) (or to a file with the `save` and `load` methods).
 //!
 //! ## Inputs
 //!
@@ -36,10 +39,13 @@
 //!
 //! See the [`Input`] trait if you want to add you own input type.
 //!
+pub mod acquisition;
 mod algebra;
 mod conversion;
+mod error;
 pub mod gaussian_process;
 mod parameters;
 pub use algebra::{SMatrix, SRowVector, SVector};
 pub use conversion::Input;
+pub use error::FriedrichError;
 pub use parameters::*;
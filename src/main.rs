@@ -1,13 +1,16 @@
 #![allow(dead_code)]
 
+mod acquisition;
 mod algebra;
 mod conversion;
+mod error;
 mod gaussian_process;
 mod parameters;
 
-use chrono::Duration;
+use std::time::Duration;
 
-use crate::gaussian_process::GaussianProcess;
+use crate::gaussian_process::{CvReport, FitConfig, GaussianProcess, SparseGaussianProcess};
+use crate::parameters::{kernel::SquaredExp, prior::ZeroPrior};
 
 fn main()
 {
@@ -34,7 +37,7 @@ fn main()
         let fit_kernel = true;
         let max_iter = 100;
         let convergence_fraction = 0.05;
-        let max_time = Duration::seconds(3600);
+        let max_time = Duration::from_secs(3600);
         gp.add_samples(&additional_inputs, &additional_outputs);
         gp.fit_parameters(fit_prior, fit_kernel, max_iter, convergence_fraction, max_time);
         println!("model is now updated.");
@@ -66,4 +69,37 @@ fn main()
         let var = gp.predict_variance(&input);
         println!("prediction: {} ± {}", mean, var.sqrt());
     }
+
+    {
+        // Trains a sparse gaussian process, using 10 inducing points, on a larger dataset.
+        let training_inputs: Vec<Vec<f64>> = (0..1000).map(|i| vec![i as f64 / 100.]).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| x[0].sin()).collect();
+        let noise = 0.1;
+        let gp = SparseGaussianProcess::with_kmeans(ZeroPrior {},
+                                                    SquaredExp::default(),
+                                                    noise,
+                                                    None,
+                                                    training_inputs,
+                                                    training_outputs,
+                                                    10);
+
+        // Predicts the mean and variance of a single point.
+        let input = vec![2.5];
+        let mean = gp.predict(&input);
+        let var = gp.predict_variance(&input);
+        println!("sparse prediction: {} ± {}", mean, var.sqrt());
+    }
+
+    {
+        // Estimates the predictive performance of a model with a 5-fold cross-validation.
+        let training_inputs: Vec<Vec<f64>> = (0..20).map(|i| vec![i as f64 * 0.3]).collect();
+        let training_outputs: Vec<f64> = training_inputs.iter().map(|x| x[0].sin()).collect();
+        let gp = GaussianProcess::default(training_inputs.clone(), training_outputs.clone());
+        let report: CvReport =
+            gp.cross_validate(training_inputs, training_outputs, 5, true, true, &FitConfig::default())
+              .expect("the data is valid");
+        println!("cross-validation: mse {} nlpd {}",
+                 report.mean_squared_error,
+                 report.negative_log_predictive_density);
+    }
 }
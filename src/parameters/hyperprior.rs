@@ -0,0 +1,156 @@
+//! Hyperprior
+//!
+//! A hyperprior is a prior distribution on a kernel parameter (or on the noise).
+//! When hyperpriors are given to the optimizer (see the `hyperpriors` field of the `FitConfig`),
+//! it maximizes the objective plus the log density of the hyperpriors (a maximum a posteriori estimate)
+//! which regularizes the fit toward plausible values of the parameters.
+//!
+//! The hyperpriors are evaluated on the absolute value of the parameters as some kernels accept negative parameters
+//! (such as an amplitude whose sign is ignored).
+//!
+//! User-defined hyperpriors should implement the HyperPrior trait.
+
+use std::fmt::Debug;
+
+//---------------------------------------------------------------------------------------
+// TRAIT
+
+/// The HyperPrior trait.
+///
+/// User-defined hyperpriors should implement this trait.
+pub trait HyperPrior: Debug + Send + Sync
+{
+    /// Log density of the distribution at a strictly positive value, up to an additive constant.
+    fn log_density(&self, x: f64) -> f64;
+
+    /// Derivative of the log density with respect to its input.
+    fn log_density_gradient(&self, x: f64) -> f64;
+}
+
+//---------------------------------------------------------------------------------------
+// CLASSICAL HYPERPRIORS
+
+/// The Log-Normal hyperprior.
+///
+/// ln(x) ~ N(μ, σ²)
+///
+/// Its median is exp(μ), making it a natural choice for scale parameters (such as length scales) whose order of magnitude is known.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct LogNormal
+{
+    /// Mean of the logarithm of the parameter.
+    pub mu: f64,
+    /// Standard deviation of the logarithm of the parameter.
+    pub sigma: f64
+}
+
+impl LogNormal
+{
+    /// Constructs a new log-normal hyperprior.
+    pub fn new(mu: f64, sigma: f64) -> LogNormal
+    {
+        assert!(sigma > 0., "LogNormal: the standard deviation should be strictly positive.");
+        LogNormal { mu, sigma }
+    }
+}
+
+impl HyperPrior for LogNormal
+{
+    fn log_density(&self, x: f64) -> f64
+    {
+        let log_x = x.ln();
+        -log_x - (log_x - self.mu).powi(2) / (2. * self.sigma * self.sigma)
+    }
+
+    fn log_density_gradient(&self, x: f64) -> f64
+    {
+        -(1. + (x.ln() - self.mu) / (self.sigma * self.sigma)) / x
+    }
+}
+
+//-----------------------------------------------
+
+/// The Gamma hyperprior.
+///
+/// p(x) ∝ x^(k-1) exp(-θx)
+///
+/// Where k is the shape and θ the rate of the distribution (its mean is k/θ).
+/// With a shape above one, the density vanishes at zero which keeps a parameter away from degenerate small values.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Gamma
+{
+    /// The shape of the distribution.
+    pub shape: f64,
+    /// The rate of the distribution.
+    pub rate: f64
+}
+
+impl Gamma
+{
+    /// Constructs a new gamma hyperprior.
+    pub fn new(shape: f64, rate: f64) -> Gamma
+    {
+        assert!(shape > 0. && rate > 0., "Gamma: the shape and rate should be strictly positive.");
+        Gamma { shape, rate }
+    }
+}
+
+impl HyperPrior for Gamma
+{
+    fn log_density(&self, x: f64) -> f64
+    {
+        (self.shape - 1.) * x.ln() - self.rate * x
+    }
+
+    fn log_density_gradient(&self, x: f64) -> f64
+    {
+        (self.shape - 1.) / x - self.rate
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn assert_gradient_matches_finite_differences<H: HyperPrior>(hyperprior: &H)
+    {
+        for x in [0.05, 0.3, 1., 2.5, 10.]
+        {
+            let h = 1e-6 * x;
+            let numeric = (hyperprior.log_density(x + h) - hyperprior.log_density(x - h)) / (2. * h);
+            let analytic = hyperprior.log_density_gradient(x);
+            assert!((analytic - numeric).abs() <= 1e-5 * (1. + numeric.abs()),
+                    "x {}: analytic gradient {} but finite difference {}",
+                    x,
+                    analytic,
+                    numeric);
+        }
+    }
+
+    #[test]
+    fn log_normal_gradient()
+    {
+        assert_gradient_matches_finite_differences(&LogNormal::new(0., 1.));
+        assert_gradient_matches_finite_differences(&LogNormal::new(-1.5, 0.3));
+    }
+
+    #[test]
+    fn gamma_gradient()
+    {
+        assert_gradient_matches_finite_differences(&Gamma::new(2., 1.));
+        assert_gradient_matches_finite_differences(&Gamma::new(0.5, 3.));
+    }
+
+    #[test]
+    fn modes_are_where_the_gradient_vanishes()
+    {
+        // The mode of a log-normal is exp(μ-σ²) and the mode of a gamma is (k-1)/θ.
+        let log_normal = LogNormal::new(0.5, 0.4);
+        assert!(log_normal.log_density_gradient((0.5f64 - 0.16).exp()).abs() < 1e-12);
+        let gamma = Gamma::new(3., 2.);
+        assert!(gamma.log_density_gradient(1.).abs() < 1e-12);
+    }
+}
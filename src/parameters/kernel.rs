@@ -10,8 +10,11 @@
 //! This implementation is inspired by [rusty-machines'](https://github.com/AtheMathmo/rusty-machine/blob/master/src/learning/toolkit/kernel.rs).
 
 use crate::algebra::{SMatrix, SRowVector, SVector};
-use nalgebra::{storage::Storage, Dynamic, U1};
+use crate::error::FriedrichError;
+use nalgebra::{storage::Storage, DMatrix, DVector, Dynamic, RowDVector, U1};
+use std::fmt;
 use std::ops::{Add, Mul};
+use std::sync::Arc;
 
 //---------------------------------------------------------------------------------------
 // TRAIT
@@ -19,7 +22,14 @@ use std::ops::{Add, Mul};
 /// The Kernel trait.
 ///
 /// If you want to provide a user-defined kernel, you should implement this trait.
-pub trait Kernel: Default
+/// Only the `kernel` function and the accessors to the parameters are required,
+/// the gradient falls back to finite differences (see `finite_difference_gradient`) when it is not provided.
+///
+/// Kernels have to be `Clone` as the finite differences and the combinations of kernels (such as `Additive`) copy them,
+/// kernels written before this requirement only need a `#[derive(Clone)]`.
+/// Kernels only have to be `Sync` with the `rayon` feature, which builds the covariance matrices and predicts large
+/// batches of inputs in parallel.
+pub trait Kernel: Default + Clone
 {
     /// Numbers of parameters (such as bandwidth and amplitude) of the kernel.
     ///
@@ -63,12 +73,19 @@ pub trait Kernel: Default
 
     /// Takes two equal length slices (row vector) and returns a vector containing the value of the gradient for each parameter in an arbitrary order.
     ///
+    /// Optional, defaults to central finite differences of the `kernel` function which cost two evaluations of the kernel per parameter.
+    /// Do implement this function when possible as it makes the fit faster and more precise
+    /// (the `gradient_check` function can then be used to validate your implementation).
+    ///
     /// NOTE: Due to the optimization algorithm, this function might get illegal parameters (ie: negative parameters),
     /// it is the duty of the function implementer to deal with them properly (ie: using the absolute value of the parameter and multiplying its gradient by its original sign).
     fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
                                                                               x1: &SRowVector<S1>,
                                                                               x2: &SRowVector<S2>)
-                                                                              -> Vec<f64>;
+                                                                              -> Vec<f64>
+    {
+        finite_difference_gradient(self, x1, x2)
+    }
 
     /// Returns a vector containing all the parameters of the kernel in the same order as the outputs of the `gradient` function.
     fn get_parameters(&self) -> Vec<f64>;
@@ -76,6 +93,16 @@ pub trait Kernel: Default
     /// Sets all the parameters of the kernel by reading them from a slice where they are in the same order as the outputs of the `gradient` function.
     fn set_parameters(&mut self, parameters: &[f64]);
 
+    /// Optional, returns the lower and upper bounds on the magnitude of each parameter, in the same order as the outputs of the `gradient` function.
+    ///
+    /// The optimizer projects the magnitude of the parameters into these bounds after each step (keeping their signs)
+    /// which prevents it from driving a parameter to degenerate values.
+    /// Defaults to unbounded parameters.
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        vec![(0., f64::INFINITY); self.nb_parameters()]
+    }
+
     /// Optional, function that fits the kernel parameters on the training data using fast heuristics.
     /// This is used as a starting point for gradient descent.
     fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
@@ -83,6 +110,83 @@ pub trait Kernel: Default
                                                                                         _training_outputs: &SVector<SV>)
     {
     }
+
+    /// Optional, checks that the kernel is defined for all the rows of the inputs.
+    ///
+    /// The fallible methods of the gaussian process (such as `try_new`, `try_add_samples` and `try_predict`)
+    /// call it before evaluating the kernel such that they return an error instead of panicking.
+    /// Kernels that are only defined on a part of the input space should implement it.
+    /// Defaults to accepting all inputs.
+    fn validate_inputs<S: Storage<f64, Dynamic, Dynamic>>(&self, _inputs: &SMatrix<S>) -> Result<(), FriedrichError>
+    {
+        Ok(())
+    }
+}
+
+//---------------------------------------------------------------------------------------
+// GRADIENT
+
+/// Computes the gradient of a kernel with respect to its parameters using central finite differences.
+///
+/// The step used for each parameter is proportional to its magnitude (with a minimum of `1e-6`).
+/// This is the default implementation of the `gradient` function of the Kernel trait.
+pub fn finite_difference_gradient<K: Kernel, S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(
+    kernel: &K,
+    x1: &SRowVector<S1>,
+    x2: &SRowVector<S2>)
+    -> Vec<f64>
+{
+    let parameters = kernel.get_parameters();
+    let mut perturbed_parameters = parameters.clone();
+    let mut perturbed_kernel = kernel.clone();
+    parameters.iter()
+              .enumerate()
+              .map(|(p, &value)| {
+                  let h = 1e-6 * value.abs().max(1.);
+                  perturbed_parameters[p] = value + h;
+                  perturbed_kernel.set_parameters(&perturbed_parameters);
+                  let k_plus = perturbed_kernel.kernel(x1, x2);
+                  perturbed_parameters[p] = value - h;
+                  perturbed_kernel.set_parameters(&perturbed_parameters);
+                  let k_minus = perturbed_kernel.kernel(x1, x2);
+                  perturbed_parameters[p] = value;
+                  (k_plus - k_minus) / (2. * h)
+              })
+              .collect()
+}
+
+/// Compares the `gradient` function of a kernel with finite differences (see `finite_difference_gradient`)
+/// on all pairs of rows of the given inputs.
+///
+/// Returns the largest discrepancy found, relative to the magnitude of the numerical gradient
+/// (`|analytic - numeric| / (1 + |numeric|)`).
+/// Values below `1e-5` are expected for a correct implementation of the gradient.
+///
+/// ```rust
+/// # use friedrich::kernel::*;
+/// # use nalgebra::DMatrix;
+/// let inputs = DMatrix::from_row_slice(3, 2, &[0., 1., 0.5, -1., 2., 0.3]);
+/// assert!(gradient_check(&SquaredExp::new(0.8, 1.5), &inputs) < 1e-5);
+/// ```
+pub fn gradient_check<K: Kernel, S: Storage<f64, Dynamic, Dynamic>>(kernel: &K, inputs: &SMatrix<S>) -> f64
+{
+    let mut max_error = 0f64;
+    for x1 in inputs.row_iter()
+    {
+        for x2 in inputs.row_iter()
+        {
+            let analytic = kernel.gradient(&x1, &x2);
+            let numeric = finite_difference_gradient(kernel, &x1, &x2);
+            assert_eq!(analytic.len(),
+                       numeric.len(),
+                       "gradient_check: the gradient has the wrong number of parameters.");
+            for (a, n) in analytic.iter().zip(numeric)
+            {
+                max_error = max_error.max((a - n).abs() / (1. + n.abs()));
+            }
+        }
+    }
+    max_error
 }
 
 //---------------------------------------------------------------------------------------
@@ -118,12 +222,36 @@ fn fit_amplitude_var<S: Storage<f64, Dynamic, U1>>(training_outputs: &SVector<S>
     training_outputs.variance()
 }
 
+/// Bounds on the magnitude of the length scales (see the `parameter_bounds` function of the Kernel trait).
+///
+/// They are wide enough for any reasonable scaling of the inputs,
+/// but prevent the optimizer from driving a length scale to zero or infinity.
+const LENGTH_SCALE_BOUNDS: (f64, f64) = (1e-10, 1e10);
+
+/// Unbounded parameter.
+const UNBOUNDED: (f64, f64) = (0., f64::INFINITY);
+
+/// Bounds on the magnitude of the shape parameters of the Kumaraswamy warp (see `Warp`),
+/// beyond which the warp degenerates into a step function.
+const WARP_BOUNDS: (f64, f64) = (1e-3, 1e3);
+
+/// Bounds on the magnitude of the periods, the periodic kernels being degenerate for a null period.
+const PERIOD_BOUNDS: (f64, f64) = (f64::EPSILON, f64::INFINITY);
+
 //---------------------------------------------------------------------------------------
 // KERNEL COMBINAISON
 
 /// The sum of two kernels.
 ///
-/// This struct should not be directly instantiated but instead is created when we add two kernels together.
+/// This struct should not be directly instantiated but instead is created when we add two kernels together:
+///
+/// ```rust
+/// # use friedrich::kernel::*;
+/// let kernel = KernelArith(SquaredExp::default()) + KernelArith(Matern52::default());
+/// ```
+///
+/// Its parameters are the parameters of the first kernel followed by the parameters of the second kernel.
+/// The sum can only be rescaled if both kernels can be rescaled.
 ///
 /// Note that it will be more efficient to implement the final kernel manually yourself.
 /// However this provides an easy mechanism to test different combinations.
@@ -133,8 +261,10 @@ pub struct KernelSum<T, U>
     where T: Kernel,
           U: Kernel
 {
-    k1: T,
-    k2: U
+    /// The first kernel of the sum.
+    pub k1: T,
+    /// The second kernel of the sum.
+    pub k2: U
 }
 
 /// Computes the sum of the two associated kernels.
@@ -191,6 +321,14 @@ impl<T, U> Kernel for KernelSum<T, U>
         self.k2.set_parameters(&parameters[self.k1.nb_parameters()..]);
     }
 
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        let mut b1 = self.k1.parameter_bounds();
+        let mut b2 = self.k2.parameter_bounds();
+        b1.append(&mut b2);
+        b1
+    }
+
     fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
                                                                                         training_inputs: &SMatrix<SM>,
                                                                                         training_outputs: &SVector<SV>)
@@ -198,6 +336,12 @@ impl<T, U> Kernel for KernelSum<T, U>
         self.k1.heuristic_fit(training_inputs, training_outputs);
         self.k2.heuristic_fit(training_inputs, training_outputs);
     }
+
+    fn validate_inputs<S: Storage<f64, Dynamic, Dynamic>>(&self, inputs: &SMatrix<S>) -> Result<(), FriedrichError>
+    {
+        self.k1.validate_inputs(inputs)?;
+        self.k2.validate_inputs(inputs)
+    }
 }
 
 impl<T: Kernel, U: Kernel> Default for KernelSum<T, U>
@@ -212,7 +356,15 @@ impl<T: Kernel, U: Kernel> Default for KernelSum<T, U>
 
 /// The point-wise product of two kernels.
 ///
-/// This struct should not be directly instantiated but instead is created when we multiply two kernels together.
+/// This struct should not be directly instantiated but instead is created when we multiply two kernels together:
+///
+/// ```rust
+/// # use friedrich::kernel::*;
+/// let kernel = KernelArith(Linear::default()) * KernelArith(Periodic::default());
+/// ```
+///
+/// Its parameters are the parameters of the first kernel followed by the parameters of the second kernel.
+/// The product can be rescaled if any of its kernels can be rescaled.
 ///
 /// Note that it will be more efficient to implement the final kernel manually yourself.
 /// However this provides an easy mechanism to test different combinations.
@@ -222,8 +374,10 @@ pub struct KernelProd<T, U>
     where T: Kernel,
           U: Kernel
 {
-    k1: T,
-    k2: U
+    /// The first kernel of the product.
+    pub k1: T,
+    /// The second kernel of the product.
+    pub k2: U
 }
 
 /// Computes the product of the two associated kernels.
@@ -287,6 +441,14 @@ impl<T, U> Kernel for KernelProd<T, U>
         self.k2.set_parameters(&parameters[self.k1.nb_parameters()..]);
     }
 
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        let mut b1 = self.k1.parameter_bounds();
+        let mut b2 = self.k2.parameter_bounds();
+        b1.append(&mut b2);
+        b1
+    }
+
     fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
                                                                                         training_inputs: &SMatrix<SM>,
                                                                                         training_outputs: &SVector<SV>)
@@ -294,6 +456,12 @@ impl<T, U> Kernel for KernelProd<T, U>
         self.k1.heuristic_fit(training_inputs, training_outputs);
         self.k2.heuristic_fit(training_inputs, training_outputs);
     }
+
+    fn validate_inputs<S: Storage<f64, Dynamic, Dynamic>>(&self, inputs: &SMatrix<S>) -> Result<(), FriedrichError>
+    {
+        self.k1.validate_inputs(inputs)?;
+        self.k2.validate_inputs(inputs)
+    }
 }
 
 impl<T: Kernel, U: Kernel> Default for KernelProd<T, U>
@@ -306,71 +474,65 @@ impl<T: Kernel, U: Kernel> Default for KernelProd<T, U>
     }
 }
 
-/// A wrapper tuple struct used for kernel arithmetic
+/// Wraps a kernel and multiplies it by a learnable amplitude.
+///
+/// k(x,y) = σ² k'(x,y)
+///
+/// Where σ² is the amplitude and k' the inner kernel:
+///
+/// ```rust
+/// # use friedrich::kernel::*;
+/// let kernel = Scaled::new(Polynomial::new(1., 0., 2), 1.);
+/// assert!(kernel.is_scalable());
+/// ```
+///
+/// Its parameters are the parameters of the inner kernel followed by the amplitude.
+/// The wrapped kernel can always be rescaled (by modifying its amplitude)
+/// which lets the optimizer fit the noise without computing its gradient, whatever the inner kernel.
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
-pub struct KernelArith<K: Kernel>(pub K);
-
-impl<T: Kernel, U: Kernel> Add<KernelArith<T>> for KernelArith<U>
+pub struct Scaled<K>
+    where K: Kernel
 {
-    type Output = KernelSum<U, T>;
-
-    fn add(self, ker: KernelArith<T>) -> KernelSum<U, T>
-    {
-        KernelSum { k1: self.0, k2: ker.0 }
-    }
+    /// The inner kernel.
+    pub kernel: K,
+    /// The amplitude by which the inner kernel is multiplied.
+    pub variance: f64
 }
 
-impl<T: Kernel, U: Kernel> Mul<KernelArith<T>> for KernelArith<U>
+impl<K: Kernel> Scaled<K>
 {
-    type Output = KernelProd<U, T>;
-
-    fn mul(self, ker: KernelArith<T>) -> KernelProd<U, T>
+    /// Wraps a kernel, multiplying it by the given amplitude.
+    pub fn new(kernel: K, variance: f64) -> Scaled<K>
     {
-        KernelProd { k1: self.0, k2: ker.0 }
+        Scaled { kernel, variance }
     }
 }
 
-//---------------------------------------------------------------------------------------
-// CLASSICAL KERNELS
-
-/// The Linear Kernel.
+/// The default scaled kernel.
 ///
-/// k(x,y) = x^Ty + c
-#[derive(Clone, Copy, Debug)]
-#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
-pub struct Linear
-{
-    /// Constant term added to inner product.
-    pub c: f64
-}
-
-impl Linear
+/// The defaults are:
+/// - the default inner kernel
+/// - variance = 1
+impl<K: Kernel> Default for Scaled<K>
 {
-    /// Constructs a new Linear Kernel.
-    pub fn new(c: f64) -> Linear
+    fn default() -> Self
     {
-        Linear { c }
+        Scaled { kernel: K::default(), variance: 1f64 }
     }
 }
 
-/// Constructs the default Linear Kernel.
-///
-/// The defaults are:
-/// - c = 0
-impl Default for Linear
+impl<K> Kernel for Scaled<K>
+    where K: Kernel
 {
-    fn default() -> Linear
+    fn nb_parameters(&self) -> usize
     {
-        Linear { c: 0f64 }
+        self.kernel.nb_parameters() + 1
     }
-}
 
-impl Kernel for Linear
-{
-    fn nb_parameters(&self) -> usize
+    fn is_scalable(&self) -> bool
     {
-        1
+        true
     }
 
     fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
@@ -378,74 +540,147 @@ impl Kernel for Linear
                                                                             x2: &SRowVector<S2>)
                                                                             -> f64
     {
-        x1.dot(x2) + self.c
+        self.variance.abs() * self.kernel.kernel(x1, x2)
     }
 
     fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
-                                                                              _x1: &SRowVector<S1>,
-                                                                              _x2: &SRowVector<S2>)
+                                                                              x1: &SRowVector<S1>,
+                                                                              x2: &SRowVector<S2>)
                                                                               -> Vec<f64>
     {
-        let grad_c = 1.;
-        vec![grad_c]
+        let variance = self.variance.abs();
+        let mut gradients: Vec<f64> = self.kernel.gradient(x1, x2).iter().map(|g| g * variance).collect();
+        gradients.push(self.variance.signum() * self.kernel.kernel(x1, x2));
+        gradients
+    }
+
+    fn rescale(&mut self, scale: f64)
+    {
+        self.variance *= scale;
     }
 
     fn get_parameters(&self) -> Vec<f64>
     {
-        vec![self.c]
+        let mut parameters = self.kernel.get_parameters();
+        parameters.push(self.variance);
+        parameters
     }
 
     fn set_parameters(&mut self, parameters: &[f64])
     {
-        self.c = parameters[0];
+        let nb_kernel_parameters = self.kernel.nb_parameters();
+        self.kernel.set_parameters(&parameters[..nb_kernel_parameters]);
+        self.variance = parameters[nb_kernel_parameters];
     }
-}
 
-//-----------------------------------------------
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        let mut bounds = self.kernel.parameter_bounds();
+        bounds.push(UNBOUNDED);
+        bounds
+    }
 
-/// The Polynomial Kernel.
+    /// Fits the inner kernel, the amplitude is reset to one as the inner kernel might already fit an amplitude.
+    fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
+                                                                                        training_inputs: &SMatrix<SM>,
+                                                                                        training_outputs: &SVector<SV>)
+    {
+        self.kernel.heuristic_fit(training_inputs, training_outputs);
+        self.variance = 1f64;
+    }
+
+    fn validate_inputs<S: Storage<f64, Dynamic, Dynamic>>(&self, inputs: &SMatrix<S>) -> Result<(), FriedrichError>
+    {
+        self.kernel.validate_inputs(inputs)
+    }
+}
+
+/// Wraps a kernel such that it only sees a subset of the input columns (its *active dimensions*).
 ///
-/// k(x,y) = (αx^Ty + c)^d
-#[derive(Clone, Copy, Debug)]
+/// k(x,y) = k'(x[dims], y[dims])
+///
+/// Where k' is the inner kernel and `dims` the list of column indices given at construction.
+/// This lets you build kernels that treat the input dimensions differently,
+/// such as a periodic kernel on a time column summed with a squared exponential on the remaining features:
+///
+/// ```rust
+/// # use friedrich::kernel::*;
+/// let kernel = KernelArith(Masked::new(Periodic::default(), vec![0]))
+///              + KernelArith(Masked::new(SquaredExp::default(), vec![1, 2]));
+/// ```
+///
+/// Its parameters and gradients are the ones of the inner kernel, unchanged.
+/// The indices are validated on each evaluation, the kernel panics with an explicit message
+/// if one of them is out of range for the inputs.
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
-pub struct Polynomial
+pub struct Masked<K>
+    where K: Kernel
 {
-    /// Scaling of the inner product.
-    pub alpha: f64,
-    /// Constant added to inner product.
-    pub c: f64,
-    /// The power to raise the sum to.
-    pub d: f64
+    /// The inner kernel.
+    pub kernel: K,
+    /// The indices of the input columns seen by the inner kernel.
+    dimensions: Vec<usize>
 }
 
-impl Polynomial
+impl<K: Kernel> Masked<K>
 {
-    /// Constructs a new Polynomial Kernel.
-    pub fn new(alpha: f64, c: f64, d: f64) -> Polynomial
+    /// Wraps a kernel, restricting it to the given input columns.
+    ///
+    /// Panics if the list of dimensions is empty.
+    pub fn new(kernel: K, dimensions: Vec<usize>) -> Masked<K>
     {
-        Polynomial { alpha, c, d }
+        assert!(!dimensions.is_empty(), "Masked: the kernel should see at least one dimension.");
+        Masked { kernel, dimensions }
+    }
+
+    /// Returns the indices of the input columns seen by the inner kernel.
+    pub fn dimensions(&self) -> &[usize]
+    {
+        &self.dimensions
+    }
+
+    /// Panics with an explicit message if one of the dimensions is out of range for inputs of the given dimension.
+    fn check_dimensions(&self, nb_dimensions: usize)
+    {
+        if let Some(&d) = self.dimensions.iter().find(|&&d| d >= nb_dimensions)
+        {
+            panic!("Masked: dimension {} is out of range for inputs of dimension {}.", d, nb_dimensions);
+        }
+    }
+
+    /// Extracts the active dimensions of a row.
+    fn select<S: Storage<f64, U1, Dynamic>>(&self, x: &SRowVector<S>) -> RowDVector<f64>
+    {
+        self.check_dimensions(x.ncols());
+        RowDVector::from_iterator(self.dimensions.len(), self.dimensions.iter().map(|&d| x[d]))
     }
 }
 
-/// Construct a new polynomial kernel.
+/// The default masked kernel.
 ///
 /// The defaults are:
-/// - alpha = 1
-/// - c = 0
-/// - d = 1
-impl Default for Polynomial
+/// - the default inner kernel
+/// - dimensions = [0]
+impl<K: Kernel> Default for Masked<K>
 {
-    fn default() -> Polynomial
+    fn default() -> Self
     {
-        Polynomial { alpha: 1f64, c: 0f64, d: 1f64 }
+        Masked { kernel: K::default(), dimensions: vec![0] }
     }
 }
 
-impl Kernel for Polynomial
+impl<K> Kernel for Masked<K>
+    where K: Kernel
 {
     fn nb_parameters(&self) -> usize
     {
-        3
+        self.kernel.nb_parameters()
+    }
+
+    fn is_scalable(&self) -> bool
+    {
+        self.kernel.is_scalable()
     }
 
     fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
@@ -453,7 +688,7 @@ impl Kernel for Polynomial
                                                                             x2: &SRowVector<S2>)
                                                                             -> f64
     {
-        (self.alpha * x1.dot(x2) + self.c).powf(self.d)
+        self.kernel.kernel(&self.select(x1), &self.select(x2))
     }
 
     fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
@@ -461,103 +696,155 @@ impl Kernel for Polynomial
                                                                               x2: &SRowVector<S2>)
                                                                               -> Vec<f64>
     {
-        let x = x1.dot(x2);
-        let inner_term = self.alpha * x + self.c;
-
-        let grad_c = self.d * inner_term.powf(self.d - 1.);
-        let grad_alpha = x * grad_c;
-        let grad_d = inner_term.ln() * inner_term.powf(self.d);
+        self.kernel.gradient(&self.select(x1), &self.select(x2))
+    }
 
-        vec![grad_alpha, grad_c, grad_d]
+    fn rescale(&mut self, scale: f64)
+    {
+        self.kernel.rescale(scale);
     }
 
     fn get_parameters(&self) -> Vec<f64>
     {
-        vec![self.alpha, self.c, self.d]
+        self.kernel.get_parameters()
     }
 
     fn set_parameters(&mut self, parameters: &[f64])
     {
-        self.alpha = parameters[0];
-        self.c = parameters[1];
-        self.d = parameters[2];
+        self.kernel.set_parameters(parameters);
     }
-}
 
-//-----------------------------------------------
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        self.kernel.parameter_bounds()
+    }
 
-/// Gaussian kernel.
-///
-/// Equivalent to a squared exponential kernel.
-///
-/// k(x,y) = A exp(-||x-y||² / 2l²)
-///
-/// Where A is the amplitude and l the length scale.
-pub type Gaussian = SquaredExp;
+    /// Fits the inner kernel on the active columns of the training inputs.
+    fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
+                                                                                        training_inputs: &SMatrix<SM>,
+                                                                                        training_outputs: &SVector<SV>)
+    {
+        self.check_dimensions(training_inputs.ncols());
+        let active_inputs = training_inputs.select_columns(self.dimensions.iter());
+        self.kernel.heuristic_fit(&active_inputs, training_outputs);
+    }
 
-/// Squared exponential kernel
+    /// Checks that the dimensions are in range then validates the active columns of the inputs with the inner kernel.
+    fn validate_inputs<S: Storage<f64, Dynamic, Dynamic>>(&self, inputs: &SMatrix<S>) -> Result<(), FriedrichError>
+    {
+        if let Some(&index) = self.dimensions.iter().find(|&&d| d >= inputs.ncols())
+        {
+            return Err(FriedrichError::IndexOutOfBounds { index, len: inputs.ncols() });
+        }
+        self.kernel.validate_inputs(&inputs.select_columns(self.dimensions.iter()))
+    }
+}
+
+/// Applies a one-dimensional kernel independently to each input dimension and sums the results.
 ///
-/// Equivalent to a gaussian kernel.
+/// k(x,y) = Σ_d k_d(x_d, y_d)
 ///
-/// k(x,y) = A exp(-||x-y||² / 2l²)
+/// Where k_d is the kernel of the dimension d.
+/// The resulting process is a sum of one-dimensional functions (as in a generalized additive model)
+/// which extrapolates much better than an isotropic kernel in high dimensions when the underlying function has this structure.
+/// The contribution of each dimension to the prediction can be recovered with the `predict_dimension` method of the gaussian process.
 ///
-/// Where A is the amplitude and l the length scale.
-#[derive(Clone, Copy, Debug)]
+/// ```rust
+/// # use friedrich::kernel::*;
+/// // One squared exponential kernel, with its own parameters, for each of the three dimensions.
+/// let kernel = Additive::with_dimension(SquaredExp::default(), 3);
+/// assert_eq!(kernel.nb_parameters(), 6);
+/// ```
+///
+/// Its parameters are the parameters of each kernel, ordered by dimension.
+/// A single kernel can be given in which case it is shared by all dimensions
+/// (this is the case of the default kernel, use `with_dimension` to fit one kernel per dimension).
+/// The `heuristic_fit` function never changes the number of kernels, and thus of parameters.
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
-pub struct SquaredExp
+pub struct Additive<K>
+    where K: Kernel
 {
-    /// The length scale of the kernel.
-    pub ls: f64,
-    /// The amplitude of the kernel.
-    pub ampl: f64
+    /// The kernels, one per dimension.
+    kernels: Vec<K>
 }
 
-impl SquaredExp
+impl<K: Kernel> Additive<K>
 {
-    /// Construct a new squared exponential kernel (gaussian).
-    pub fn new(ls: f64, ampl: f64) -> SquaredExp
+    /// Constructs a new additive kernel with one kernel per dimension.
+    pub fn new(kernels: Vec<K>) -> Additive<K>
     {
-        SquaredExp { ls, ampl }
+        assert!(!kernels.is_empty(), "Additive: at least one kernel is needed.");
+        Additive { kernels }
+    }
+
+    /// Constructs a new additive kernel using a copy of the given kernel for each of the `nb_dimensions` dimensions.
+    pub fn with_dimension(kernel: K, nb_dimensions: usize) -> Additive<K>
+    {
+        Additive::new(vec![kernel; nb_dimensions])
+    }
+
+    /// Returns the kernels, one per dimension.
+    pub fn kernels(&self) -> &[K]
+    {
+        &self.kernels
+    }
+
+    /// Returns the kernel associated with a given dimension.
+    pub fn kernel_of_dimension(&self, dimension: usize, nb_dimensions: usize) -> &K
+    {
+        if self.kernels.len() == 1
+        {
+            &self.kernels[0]
+        }
+        else
+        {
+            assert_eq!(self.kernels.len(),
+                       nb_dimensions,
+                       "Additive: the kernel has {} kernels but the inputs are of dimension {}.",
+                       self.kernels.len(),
+                       nb_dimensions);
+            &self.kernels[dimension]
+        }
     }
 }
 
-/// Constructs the default Squared Exp kernel.
+/// The default additive kernel.
 ///
 /// The defaults are:
-/// - ls = 1
-/// - ampl = 1
-impl Default for SquaredExp
+/// - a single default kernel (shared by all dimensions)
+impl<K: Kernel> Default for Additive<K>
 {
-    fn default() -> SquaredExp
+    fn default() -> Self
     {
-        SquaredExp { ls: 1f64, ampl: 1f64 }
+        Additive { kernels: vec![K::default()] }
     }
 }
 
-impl Kernel for SquaredExp
+impl<K> Kernel for Additive<K>
+    where K: Kernel
 {
     fn nb_parameters(&self) -> usize
     {
-        2
+        self.kernels.iter().map(|k| k.nb_parameters()).sum()
     }
 
     fn is_scalable(&self) -> bool
     {
-        true
+        self.kernels.iter().all(|k| k.is_scalable())
     }
 
-    /// The squared exponential kernel function.
     fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
                                                                             x1: &SRowVector<S1>,
                                                                             x2: &SRowVector<S2>)
                                                                             -> f64
     {
-        // Sanitize parameters.
-        let ampl = self.ampl.abs();
-        // Computes kernel.
-        let distance_squared = (x1 - x2).norm_squared();
-        let x = -distance_squared / (2f64 * self.ls * self.ls);
-        ampl * x.exp()
+        let nb_dimensions = x1.ncols();
+        (0..nb_dimensions).map(|d| {
+                              self.kernel_of_dimension(d, nb_dimensions)
+                                  .kernel(&x1.columns(d, 1), &x2.columns(d, 1))
+                          })
+                          .sum()
     }
 
     fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
@@ -565,85 +852,206 @@ impl Kernel for SquaredExp
                                                                               x2: &SRowVector<S2>)
                                                                               -> Vec<f64>
     {
-        // Sanitize parameters.
-        let ampl = self.ampl.abs();
-        // Compute gradients.
-        let distance_squared = (x1 - x2).norm_squared();
-        let exponential = (-distance_squared / (2f64 * self.ls * self.ls)).exp();
-        let grad_ls = (distance_squared * ampl * exponential) / self.ls.powi(3);
-        let grad_ampl = self.ampl.signum() * exponential;
-        vec![grad_ls, grad_ampl]
+        let nb_dimensions = x1.ncols();
+        if self.kernels.len() == 1
+        {
+            // The shared kernel accumulates the gradients of all dimensions.
+            let mut gradients = vec![0.; self.nb_parameters()];
+            for d in 0..nb_dimensions
+            {
+                let gradient = self.kernels[0].gradient(&x1.columns(d, 1), &x2.columns(d, 1));
+                gradients.iter_mut().zip(gradient).for_each(|(g, gd)| *g += gd);
+            }
+            gradients
+        }
+        else
+        {
+            (0..nb_dimensions).flat_map(|d| {
+                                  self.kernel_of_dimension(d, nb_dimensions)
+                                      .gradient(&x1.columns(d, 1), &x2.columns(d, 1))
+                              })
+                              .collect()
+        }
     }
 
     fn rescale(&mut self, scale: f64)
     {
-        self.ampl *= scale;
+        self.kernels.iter_mut().for_each(|k| k.rescale(scale));
     }
 
     fn get_parameters(&self) -> Vec<f64>
     {
-        vec![self.ls, self.ampl]
+        self.kernels.iter().flat_map(|k| k.get_parameters()).collect()
     }
 
     fn set_parameters(&mut self, parameters: &[f64])
     {
-        self.ls = parameters[0];
-        self.ampl = parameters[1];
+        let mut start = 0;
+        for kernel in self.kernels.iter_mut()
+        {
+            let end = start + kernel.nb_parameters();
+            kernel.set_parameters(&parameters[start..end]);
+            start = end;
+        }
+    }
+
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        self.kernels.iter().flat_map(|k| k.parameter_bounds()).collect()
     }
 
+    /// Fits each kernel on its own dimension, a kernel shared by all dimensions is fitted on each dimension
+    /// and takes the average of the fitted parameters (the number of parameters is thus left unchanged).
+    /// When possible, the kernels are then rescaled such that the amplitude of their sum matches the data.
     fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
                                                                                         training_inputs: &SMatrix<SM>,
                                                                                         training_outputs: &SVector<SV>)
     {
-        self.ls = fit_bandwidth_mean(training_inputs);
-        self.ampl = fit_amplitude_var(training_outputs);
+        let nb_dimensions = training_inputs.ncols();
+        if self.kernels.len() == 1
+        {
+            heuristic_fit_per_dimension(&mut self.kernels[0], training_inputs, training_outputs);
+        }
+        else
+        {
+            for (d, kernel) in self.kernels.iter_mut().enumerate()
+            {
+                kernel.heuristic_fit(&training_inputs.columns(d, 1), training_outputs);
+            }
+        }
+        if self.is_scalable()
+        {
+            self.rescale(1. / nb_dimensions as f64);
+        }
+    }
+
+    /// Validates each dimension of the inputs with its kernel.
+    fn validate_inputs<S: Storage<f64, Dynamic, Dynamic>>(&self, inputs: &SMatrix<S>) -> Result<(), FriedrichError>
+    {
+        let nb_dimensions = inputs.ncols();
+        (0..nb_dimensions).try_for_each(|d| {
+                              self.kernel_of_dimension(d, nb_dimensions).validate_inputs(&inputs.columns(d, 1))
+                          })
     }
 }
 
-//-----------------------------------------------
+/// Fits a one-dimensional kernel on each dimension of the inputs then gives it the average of the fitted parameters.
+fn heuristic_fit_per_dimension<K: Kernel, SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(
+    kernel: &mut K,
+    training_inputs: &SMatrix<SM>,
+    training_outputs: &SVector<SV>)
+{
+    let nb_dimensions = training_inputs.ncols();
+    let mut parameters = vec![0.; kernel.nb_parameters()];
+    for d in 0..nb_dimensions
+    {
+        let mut fitted_kernel = kernel.clone();
+        fitted_kernel.heuristic_fit(&training_inputs.columns(d, 1), training_outputs);
+        parameters.iter_mut()
+                  .zip(fitted_kernel.get_parameters())
+                  .for_each(|(p, fitted_p)| *p += fitted_p / nb_dimensions as f64);
+    }
+    kernel.set_parameters(&parameters);
+}
 
-/// The Exponential Kernel.
+/// Sums a one-dimensional kernel over all input dimensions (main effects)
+/// and over all pairs of dimensions (interactions).
 ///
-/// k(x,y) = A exp(-||x-y|| / 2l²)
+/// k(x,y) = a1 * Σ_d k'(x_d, y_d) + a2 * Σ_{d<e} k'(x_d, y_d) * k'(x_e, y_e)
 ///
-/// Where A is the amplitude and l is the length scale.
-#[derive(Clone, Copy, Debug)]
+/// Where k' is the base kernel (shared by all dimensions), a1 the amplitude of the main effects
+/// and a2 the amplitude of the two-way interactions.
+/// The relative size of the amplitudes tells how much of the signal is explained by interactions,
+/// the contribution of each order to the prediction can be recovered
+/// with the `predict_order` method of the gaussian process.
+///
+/// ```rust
+/// # use friedrich::kernel::*;
+/// let kernel = Anova::new(SquaredExp::default(), 1., 0.5);
+/// assert_eq!(kernel.nb_parameters(), 4);
+/// ```
+///
+/// Its parameters are the parameters of the base kernel
+/// followed by the amplitudes of the main effects and interactions.
+/// The pairwise products are computed in O(d) (as half the difference between the squared sum and the sum of squares).
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
-pub struct Exponential
+pub struct Anova<K>
+    where K: Kernel
 {
-    /// The length scale of the kernel.
-    pub ls: f64,
-    /// The amplitude of the kernel.
-    pub ampl: f64
+    /// The base kernel, applied to each dimension.
+    pub kernel: K,
+    /// Amplitude of the main effects.
+    pub main_ampl: f64,
+    /// Amplitude of the two-way interactions.
+    pub interaction_ampl: f64
 }
 
-impl Exponential
+impl<K: Kernel> Anova<K>
 {
-    /// Construct a new squared exponential kernel.
-    pub fn new(ls: f64, ampl: f64) -> Exponential
+    /// Constructs a new ANOVA kernel.
+    pub fn new(kernel: K, main_ampl: f64, interaction_ampl: f64) -> Anova<K>
     {
-        Exponential { ls, ampl }
+        Anova { kernel, main_ampl, interaction_ampl }
+    }
+
+    /// Evaluates the base kernel on each dimension.
+    fn dimension_kernels<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                                       x1: &SRowVector<S1>,
+                                                                                       x2: &SRowVector<S2>)
+                                                                                       -> Vec<f64>
+    {
+        (0..x1.ncols()).map(|d| self.kernel.kernel(&x1.columns(d, 1), &x2.columns(d, 1))).collect()
+    }
+
+    /// Returns the part of the kernel due to the given order: 1 for the main effects and 2 for the interactions.
+    ///
+    /// Panics for any other order.
+    pub fn order_kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                                      order: usize,
+                                                                                      x1: &SRowVector<S1>,
+                                                                                      x2: &SRowVector<S2>)
+                                                                                      -> f64
+    {
+        let values = self.dimension_kernels(x1, x2);
+        match order
+        {
+            1 => self.main_ampl.abs() * values.iter().sum::<f64>(),
+            2 => self.interaction_ampl.abs() * pairwise_products_sum(&values),
+            _ => panic!("Anova: order {} is not supported, the orders are 1 (main effects) and 2 (interactions).",
+                        order)
+        }
     }
 }
 
-/// Constructs the default Exponential kernel.
+/// Computes Σ_{d<e} v_d * v_e.
+fn pairwise_products_sum(values: &[f64]) -> f64
+{
+    let sum: f64 = values.iter().sum();
+    let sum_squares: f64 = values.iter().map(|v| v * v).sum();
+    (sum * sum - sum_squares) / 2.
+}
+
+/// The default ANOVA kernel.
 ///
 /// The defaults are:
-/// - ls = 1
-/// - amplitude = 1
-impl Default for Exponential
+/// - the default base kernel
+/// - main_ampl = 1
+/// - interaction_ampl = 1
+impl<K: Kernel> Default for Anova<K>
 {
-    fn default() -> Exponential
+    fn default() -> Self
     {
-        Exponential { ls: 1f64, ampl: 1f64 }
+        Anova { kernel: K::default(), main_ampl: 1., interaction_ampl: 1. }
     }
 }
 
-impl Kernel for Exponential
+impl<K> Kernel for Anova<K>
+    where K: Kernel
 {
     fn nb_parameters(&self) -> usize
     {
-        2
+        self.kernel.nb_parameters() + 2
     }
 
     fn is_scalable(&self) -> bool
@@ -651,18 +1059,13 @@ impl Kernel for Exponential
         true
     }
 
-    /// The squared exponential kernel function.
     fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
                                                                             x1: &SRowVector<S1>,
                                                                             x2: &SRowVector<S2>)
                                                                             -> f64
     {
-        // sanitize parameters
-        let ampl = self.ampl.abs();
-        // compute kernel
-        let distance = (x1 - x2).norm();
-        let x = -distance / (2f64 * self.ls * self.ls);
-        ampl * x.exp()
+        let values = self.dimension_kernels(x1, x2);
+        self.main_ampl.abs() * values.iter().sum::<f64>() + self.interaction_ampl.abs() * pairwise_products_sum(&values)
     }
 
     fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
@@ -671,104 +1074,290 @@ impl Kernel for Exponential
                                                                               -> Vec<f64>
     {
         // Sanitize parameters.
-        let ampl = self.ampl.abs();
+        let main_ampl = self.main_ampl.abs();
+        let interaction_ampl = self.interaction_ampl.abs();
         // Compute gradients.
-        let distance = (x1 - x2).norm();
-        let exponential = (-distance / (2f64 * self.ls * self.ls)).exp();
-        let grad_ls = (distance * ampl * exponential) / self.ls.powi(3);
-        let grad_ampl = self.ampl.signum() * exponential;
-        vec![grad_ls, grad_ampl]
+        let values = self.dimension_kernels(x1, x2);
+        let sum: f64 = values.iter().sum();
+        // d/dp Σ_{d<e} k_d*k_e = Σ_d dk_d/dp * (Σ_e k_e - k_d)
+        let mut gradients = vec![0.; self.kernel.nb_parameters()];
+        for (d, value) in values.iter().enumerate()
+        {
+            let weight = main_ampl + interaction_ampl * (sum - value);
+            let gradient = self.kernel.gradient(&x1.columns(d, 1), &x2.columns(d, 1));
+            gradients.iter_mut().zip(gradient).for_each(|(g, gd)| *g += weight * gd);
+        }
+        gradients.push(self.main_ampl.signum() * sum);
+        gradients.push(self.interaction_ampl.signum() * pairwise_products_sum(&values));
+        gradients
     }
 
     fn rescale(&mut self, scale: f64)
     {
-        self.ampl *= scale;
+        self.main_ampl *= scale;
+        self.interaction_ampl *= scale;
     }
 
     fn get_parameters(&self) -> Vec<f64>
     {
-        vec![self.ls, self.ampl]
+        let mut parameters = self.kernel.get_parameters();
+        parameters.push(self.main_ampl);
+        parameters.push(self.interaction_ampl);
+        parameters
     }
 
     fn set_parameters(&mut self, parameters: &[f64])
     {
-        self.ls = parameters[0];
-        self.ampl = parameters[1];
+        let nb_parameters = self.kernel.nb_parameters();
+        self.kernel.set_parameters(&parameters[..nb_parameters]);
+        self.main_ampl = parameters[nb_parameters];
+        self.interaction_ampl = parameters[nb_parameters + 1];
+    }
+
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        let mut bounds = self.kernel.parameter_bounds();
+        bounds.extend([UNBOUNDED, UNBOUNDED]);
+        bounds
     }
 
+    /// Fits the base kernel on each dimension and gives it the average of the fitted parameters,
+    /// then sets the amplitudes such that each order explains half of the variance of the outputs.
     fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
                                                                                         training_inputs: &SMatrix<SM>,
                                                                                         training_outputs: &SVector<SV>)
     {
-        self.ls = fit_bandwidth_mean(training_inputs);
-        self.ampl = fit_amplitude_var(training_outputs);
+        heuristic_fit_per_dimension(&mut self.kernel, training_inputs, training_outputs);
+        let nb_dimensions = training_inputs.ncols() as f64;
+        let nb_pairs = (nb_dimensions * (nb_dimensions - 1.) / 2.).max(1.);
+        let origin = RowDVector::<f64>::zeros(1);
+        // The variance of the base kernel is null when the outputs are constant.
+        let variance = self.kernel.kernel(&origin, &origin);
+        self.main_ampl = 0.5 / nb_dimensions;
+        self.interaction_ampl = if variance > 0. { 0.5 / (nb_pairs * variance) } else { 0.5 / nb_pairs };
+    }
+
+    /// Validates each dimension of the inputs with the base kernel.
+    fn validate_inputs<S: Storage<f64, Dynamic, Dynamic>>(&self, inputs: &SMatrix<S>) -> Result<(), FriedrichError>
+    {
+        (0..inputs.ncols()).try_for_each(|d| self.kernel.validate_inputs(&inputs.columns(d, 1)))
     }
 }
 
-//-----------------------------------------------
+/// A monotone transformation of an input dimension, used by the `Warped` kernel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum Warp
+{
+    /// Leaves the dimension unchanged.
+    Identity,
+    /// x -> ln(1+x), for non-negative inputs spanning several orders of magnitude
+    /// (extended to negative inputs by symmetry).
+    Log1p,
+    /// x -> sqrt(x), a milder compression of non-negative inputs (extended to negative inputs by symmetry).
+    Sqrt,
+    /// x -> 1 - (1 - x^a)^b, the cumulative distribution function of the Kumaraswamy distribution.
+    ///
+    /// Its shape parameters `a` and `b` are learned during the fit.
+    /// The inputs should be rescaled into [0,1] beforehand, values outside of this range are clamped.
+    Kumaraswamy
+    {
+        /// First shape parameter.
+        a: f64,
+        /// Second shape parameter.
+        b: f64
+    }
+}
 
-/// The Matèrn1 kernel which is 1 differentiable and correspond to a classical Matèrn kernel with nu=3/2.
+impl Warp
+{
+    /// Number of learnable parameters of the warp.
+    fn nb_parameters(&self) -> usize
+    {
+        match self
+        {
+            Warp::Kumaraswamy { .. } => 2,
+            _ => 0
+        }
+    }
+
+    /// Returns the learnable parameters of the warp.
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        match *self
+        {
+            Warp::Kumaraswamy { a, b } => vec![a, b],
+            _ => vec![]
+        }
+    }
+
+    /// Sets the learnable parameters of the warp.
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        if let Warp::Kumaraswamy { a, b } = self
+        {
+            *a = parameters[0];
+            *b = parameters[1];
+        }
+    }
+
+    /// Applies the warp to an input.
+    pub fn warp(&self, x: f64) -> f64
+    {
+        match *self
+        {
+            Warp::Identity => x,
+            Warp::Log1p => x.signum() * x.abs().ln_1p(),
+            Warp::Sqrt => x.signum() * x.abs().sqrt(),
+            Warp::Kumaraswamy { a, b } => 1. - (1. - x.clamp(0., 1.).powf(a.abs())).powf(b.abs())
+        }
+    }
+
+    /// Returns the gradient of the warped input with respect to each learnable parameter of the warp.
+    fn gradient(&self, x: f64) -> Vec<f64>
+    {
+        match *self
+        {
+            // The warp is constant on the boundaries of [0,1].
+            Warp::Kumaraswamy { .. } if x <= 0. || x >= 1. => vec![0., 0.],
+            Warp::Kumaraswamy { a, b } =>
+            {
+                let x_a = x.powf(a.abs());
+                let one_minus_x_a_b = (1. - x_a).powf(b.abs());
+                let grad_a = b.abs() * one_minus_x_a_b / (1. - x_a) * x_a * x.ln();
+                let grad_b = -one_minus_x_a_b * (1. - x_a).ln();
+                vec![a.signum() * grad_a, b.signum() * grad_b]
+            }
+            _ => vec![]
+        }
+    }
+
+    /// Returns the bounds on the magnitude of the learnable parameters of the warp.
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        vec![WARP_BOUNDS; self.nb_parameters()]
+    }
+}
+
+/// Applies a monotone transformation to each input dimension before calling the inner kernel.
 ///
-/// k(x,y) = A (1 + ||x-y||sqrt(3)/l) exp(-||x-y||sqrt(3)/l)
+/// k(x,y) = k'(w(x), w(y))
 ///
-/// Where A is the amplitude and l is the length scale.
-#[derive(Clone, Copy, Debug)]
+/// Where k' is the inner kernel and w applies the warp of each dimension (see `Warp`).
+/// This helps when the inputs live on skewed scales (budgets, populations...) where a difference of one unit
+/// does not mean the same thing for small and large values.
+/// As the warp is part of the kernel, predictions are made directly on the raw inputs:
+///
+/// ```rust
+/// # use friedrich::kernel::*;
+/// // The first dimension is compressed with a logarithm, the second one is left unchanged.
+/// let kernel = Warped::new(SquaredExp::default(), vec![Warp::Log1p, Warp::Identity]);
+/// assert_eq!(kernel.nb_parameters(), 2);
+/// ```
+///
+/// Its parameters are the parameters of the inner kernel
+/// followed by the learnable parameters of the warps (in dimension order).
+/// A single warp can be given in which case it is shared by all dimensions.
+/// The gradient of the warp parameters is computed with the chain rule,
+/// using a central finite difference for the derivative of the inner kernel with respect to its inputs.
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
-pub struct Matern1
+pub struct Warped<K>
+    where K: Kernel
 {
-    /// The length scale of the kernel.
-    pub ls: f64,
-    /// The amplitude of the kernel.
-    pub ampl: f64
+    /// The inner kernel.
+    pub kernel: K,
+    /// The warps, one per dimension.
+    warps: Vec<Warp>
 }
 
-impl Matern1
+impl<K: Kernel> Warped<K>
 {
-    /// Construct a new matèrn1 kernel.
-    pub fn new(ls: f64, ampl: f64) -> Matern1
+    /// Wraps a kernel, warping each input dimension with the associated warp.
+    ///
+    /// Panics if the list of warps is empty.
+    pub fn new(kernel: K, warps: Vec<Warp>) -> Warped<K>
     {
-        Matern1 { ls, ampl }
+        assert!(!warps.is_empty(), "Warped: at least one warp is needed.");
+        Warped { kernel, warps }
+    }
+
+    /// Returns the warps, one per dimension.
+    pub fn warps(&self) -> &[Warp]
+    {
+        &self.warps
+    }
+
+    /// Returns the index of the warp associated with a given dimension.
+    fn warp_index(&self, dimension: usize, nb_dimensions: usize) -> usize
+    {
+        if self.warps.len() == 1
+        {
+            0
+        }
+        else
+        {
+            assert_eq!(self.warps.len(),
+                       nb_dimensions,
+                       "Warped: the kernel has {} warps but the inputs are of dimension {}.",
+                       self.warps.len(),
+                       nb_dimensions);
+            dimension
+        }
+    }
+
+    /// Warps each dimension of a row.
+    fn warp_row<S: Storage<f64, U1, Dynamic>>(&self, x: &SRowVector<S>) -> RowDVector<f64>
+    {
+        let nb_dimensions = x.ncols();
+        RowDVector::from_fn(nb_dimensions, |_, d| self.warps[self.warp_index(d, nb_dimensions)].warp(x[d]))
+    }
+
+    /// Central finite difference of the inner kernel with respect to the dimension `d` of its first input.
+    fn input_derivative(&self, u1: &RowDVector<f64>, u2: &RowDVector<f64>, d: usize) -> f64
+    {
+        let h = 1e-6 * u1[d].abs().max(1.);
+        let mut perturbed = u1.clone();
+        perturbed[d] = u1[d] + h;
+        let k_plus = self.kernel.kernel(&perturbed, u2);
+        perturbed[d] = u1[d] - h;
+        let k_minus = self.kernel.kernel(&perturbed, u2);
+        (k_plus - k_minus) / (2. * h)
     }
 }
 
-/// Constructs the default Matern1 kernel.
+/// The default warped kernel.
 ///
 /// The defaults are:
-/// - ls = 1
-/// - amplitude = 1
-impl Default for Matern1
+/// - the default inner kernel
+/// - a single identity warp (shared by all dimensions)
+impl<K: Kernel> Default for Warped<K>
 {
-    fn default() -> Matern1
+    fn default() -> Self
     {
-        Matern1 { ls: 1f64, ampl: 1f64 }
+        Warped { kernel: K::default(), warps: vec![Warp::Identity] }
     }
 }
 
-impl Kernel for Matern1
+impl<K> Kernel for Warped<K>
+    where K: Kernel
 {
     fn nb_parameters(&self) -> usize
     {
-        2
+        self.kernel.nb_parameters() + self.warps.iter().map(|w| w.nb_parameters()).sum::<usize>()
     }
 
     fn is_scalable(&self) -> bool
     {
-        true
+        self.kernel.is_scalable()
     }
 
-    /// The matèrn1 kernel function.
     fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
                                                                             x1: &SRowVector<S1>,
                                                                             x2: &SRowVector<S2>)
                                                                             -> f64
     {
-        // sanitize parameters
-        let ampl = self.ampl.abs();
-        let l = self.ls.abs();
-        // compute kernel
-        let distance = (x1 - x2).norm();
-        let x = (3f64).sqrt() * distance / l;
-        ampl * (1f64 + x) * (-x).exp()
+        self.kernel.kernel(&self.warp_row(x1), &self.warp_row(x2))
     }
 
     fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
@@ -776,204 +1365,310 @@ impl Kernel for Matern1
                                                                               x2: &SRowVector<S2>)
                                                                               -> Vec<f64>
     {
-        // Sanitize parameters.
-        let ampl = self.ampl.abs();
-        let l = self.ls.abs();
-        // Compute gradient.
-        let distance = (x1 - x2).norm();
-        let x = 3f64.sqrt() * distance / l;
-        let grad_ls = (3. * ampl * distance.powi(2) * (-x).exp()) / (self.ls.powi(3));
-        let grad_ampl = self.ampl.signum() * (1. + x) * (-x).exp();
-        vec![grad_ls, grad_ampl]
+        let (u1, u2) = (self.warp_row(x1), self.warp_row(x2));
+        let mut gradients = self.kernel.gradient(&u1, &u2);
+
+        // Index of the first parameter of each warp.
+        let offsets: Vec<usize> = self.warps
+                                      .iter()
+                                      .scan(gradients.len(), |offset, warp| {
+                                          let start = *offset;
+                                          *offset += warp.nb_parameters();
+                                          Some(start)
+                                      })
+                                      .collect();
+        gradients.resize(self.nb_parameters(), 0.);
+
+        // Chain rule: dk/dp = dk/du1 * du1/dp + dk/du2 * du2/dp
+        let nb_dimensions = x1.ncols();
+        for d in 0..nb_dimensions
+        {
+            let index = self.warp_index(d, nb_dimensions);
+            let warp = &self.warps[index];
+            if warp.nb_parameters() == 0
+            {
+                continue;
+            }
+            let derivative1 = self.input_derivative(&u1, &u2, d);
+            let derivative2 = self.input_derivative(&u2, &u1, d);
+            for (p, (g1, g2)) in warp.gradient(x1[d]).into_iter().zip(warp.gradient(x2[d])).enumerate()
+            {
+                gradients[offsets[index] + p] += derivative1 * g1 + derivative2 * g2;
+            }
+        }
+        gradients
     }
 
     fn rescale(&mut self, scale: f64)
     {
-        self.ampl *= scale;
+        self.kernel.rescale(scale);
     }
 
     fn get_parameters(&self) -> Vec<f64>
     {
-        vec![self.ls, self.ampl]
+        let mut parameters = self.kernel.get_parameters();
+        parameters.extend(self.warps.iter().flat_map(|w| w.get_parameters()));
+        parameters
     }
 
     fn set_parameters(&mut self, parameters: &[f64])
     {
-        self.ls = parameters[0];
-        self.ampl = parameters[1];
+        let mut start = self.kernel.nb_parameters();
+        self.kernel.set_parameters(&parameters[..start]);
+        for warp in self.warps.iter_mut()
+        {
+            let end = start + warp.nb_parameters();
+            warp.set_parameters(&parameters[start..end]);
+            start = end;
+        }
+    }
+
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        let mut bounds = self.kernel.parameter_bounds();
+        bounds.extend(self.warps.iter().flat_map(|w| w.parameter_bounds()));
+        bounds
     }
 
+    /// Fits the inner kernel on the warped training inputs.
     fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
                                                                                         training_inputs: &SMatrix<SM>,
                                                                                         training_outputs: &SVector<SV>)
     {
-        self.ls = fit_bandwidth_mean(training_inputs);
-        self.ampl = fit_amplitude_var(training_outputs);
+        let nb_dimensions = training_inputs.ncols();
+        let warped_inputs = training_inputs.map_with_location(|_, d, x| {
+                                                               self.warps[self.warp_index(d, nb_dimensions)].warp(x)
+                                                           });
+        self.kernel.heuristic_fit(&warped_inputs, training_outputs);
+    }
+
+    /// Validates the warped inputs with the inner kernel.
+    fn validate_inputs<S: Storage<f64, Dynamic, Dynamic>>(&self, inputs: &SMatrix<S>) -> Result<(), FriedrichError>
+    {
+        let nb_dimensions = inputs.ncols();
+        let warped_inputs =
+            inputs.map_with_location(|_, d, x| self.warps[self.warp_index(d, nb_dimensions)].warp(x));
+        self.kernel.validate_inputs(&warped_inputs)
     }
 }
 
-//-----------------------------------------------
+/// Signature of the function wrapped by a `FnKernel`: two input rows followed by the parameters of the kernel.
+pub type KernelFn = dyn Fn(&[f64], &[f64], &[f64]) -> f64 + Send + Sync;
 
-/// The Matèrn2 kernel which is 2 differentiable and correspond to a classical Matèrn kernel with nu=5/2.
+/// Turns a closure into a kernel.
 ///
-/// k(x,y) = A (1 + ||x-y||sqrt(5)/l + ||x-y||²5/3l²) exp(-||x-y||sqrt(5)/l)
+/// k(x,y) = f(x, y, parameters)
 ///
-/// Where A is the amplitude and l is the length scale.
-#[derive(Clone, Copy, Debug)]
-#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
-pub struct Matern2
+/// Where f is a user-provided function taking the two input rows and a slice of parameters.
+/// This is the fastest way to try a custom covariance function without implementing the Kernel trait:
+///
+/// ```rust
+/// # use friedrich::kernel::*;
+/// // A squared exponential kernel with parameters [length scale, amplitude].
+/// let kernel = FnKernel::new(|x, y, p| {
+///                                let distance_squared: f64 = x.iter().zip(y).map(|(a, b)| (a - b) * (a - b)).sum();
+///                                p[1].abs() * (-distance_squared / (2. * p[0] * p[0])).exp()
+///                            },
+///                            vec![1., 1.]);
+/// assert_eq!(kernel.nb_parameters(), 2);
+/// ```
+///
+/// The gradient is computed with central finite differences (see `finite_difference_gradient`) which costs two evaluations of the function per parameter,
+/// on top of a copy of the inputs into slices for each evaluation:
+/// expect the fit to be noticeably slower than with a native kernel (and slightly less precise).
+/// Implement the Kernel trait once the covariance function is settled.
+///
+/// As with the other kernels, the function might get illegal parameters (such as negative parameters) during the fit,
+/// it is its duty to deal with them (for example by using the absolute value of the parameters).
+/// The kernel is not scalable and cannot be serialized.
+#[derive(Clone)]
+pub struct FnKernel
 {
-    /// The length scale of the kernel.
-    pub ls: f64,
-    /// The amplitude of the kernel.
-    pub ampl: f64
+    /// The covariance function.
+    function: Arc<KernelFn>,
+    /// The parameters given to the function.
+    parameters: Vec<f64>
 }
 
-impl Matern2
+impl FnKernel
 {
-    /// Construct a new matèrn2 kernel.
-    pub fn new(ls: f64, ampl: f64) -> Matern2
+    /// Constructs a new kernel from a function and its initial parameters.
+    pub fn new<F>(function: F, parameters: Vec<f64>) -> FnKernel
+        where F: Fn(&[f64], &[f64], &[f64]) -> f64 + Send + Sync + 'static
     {
-        Matern2 { ls, ampl }
+        FnKernel { function: Arc::new(function), parameters }
+    }
+
+    /// Evaluates the function with the given parameters.
+    fn evaluate(&self, x1: &[f64], x2: &[f64], parameters: &[f64]) -> f64
+    {
+        (self.function)(x1, x2, parameters)
     }
 }
 
-/// Constructs the default Matern2 kernel.
+/// The default closure kernel.
 ///
 /// The defaults are:
-/// - ls = 1
-/// - amplitude = 1
-impl Default for Matern2
+/// - a squared exponential function, with parameters [ls, ampl]
+/// - parameters = [1, 1]
+impl Default for FnKernel
 {
-    fn default() -> Matern2
+    fn default() -> FnKernel
     {
-        Matern2 { ls: 1f64, ampl: 1f64 }
+        FnKernel::new(|x1, x2, parameters| {
+                          let distance_squared: f64 = x1.iter().zip(x2).map(|(a, b)| (a - b) * (a - b)).sum();
+                          parameters[1].abs() * (-distance_squared / (2. * parameters[0] * parameters[0])).exp()
+                      },
+                      vec![1., 1.])
     }
 }
 
-impl Kernel for Matern2
+impl fmt::Debug for FnKernel
 {
-    fn nb_parameters(&self) -> usize
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
     {
-        2
+        f.debug_struct("FnKernel").field("parameters", &self.parameters).finish_non_exhaustive()
     }
+}
 
-    fn is_scalable(&self) -> bool
+impl Kernel for FnKernel
+{
+    fn nb_parameters(&self) -> usize
     {
-        true
+        self.parameters.len()
     }
 
-    /// The matèrn2 kernel function.
     fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
                                                                             x1: &SRowVector<S1>,
                                                                             x2: &SRowVector<S2>)
                                                                             -> f64
     {
-        // sanitize parameters
-        let ampl = self.ampl.abs();
-        let l = self.ls.abs();
-        // compute kernel
-        let distance = (x1 - x2).norm();
-        let x = (5f64).sqrt() * distance / l;
-        ampl * (1f64 + x + (5f64 * distance * distance) / (3f64 * l * l)) * (-x).exp()
+        let x1: Vec<f64> = x1.iter().cloned().collect();
+        let x2: Vec<f64> = x2.iter().cloned().collect();
+        self.evaluate(&x1, &x2, &self.parameters)
     }
 
-    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
-                                                                              x1: &SRowVector<S1>,
-                                                                              x2: &SRowVector<S2>)
-                                                                              -> Vec<f64>
+    fn get_parameters(&self) -> Vec<f64>
     {
-        // Sanitize parameters.
-        let ampl = self.ampl.abs();
-        let l = self.ls.abs();
-        // Compute gradient.
-        let distance = (x1 - x2).norm();
-        let x = (5f64).sqrt() * distance / self.ls;
-        let grad_ls = self.ls.signum()
-                      * ampl
-                      * ((2. * l / 3. + 1.)
-                         + distance * 5f64.sqrt() * ((l.powi(2) / 3. + l + 1.) / l.powi(2)))
-                      * (-x).exp();
-        let grad_ampl =
-            self.ampl.signum() * (1f64 + x + (5f64 * distance * distance) / (3f64 * l * l)) * (-x).exp();
-        vec![grad_ls, grad_ampl]
+        self.parameters.clone()
     }
 
-    fn rescale(&mut self, scale: f64)
+    fn set_parameters(&mut self, parameters: &[f64])
     {
-        self.ampl *= scale;
+        let nb_parameters = self.parameters.len();
+        self.parameters.copy_from_slice(&parameters[..nb_parameters]);
     }
+}
 
-    fn get_parameters(&self) -> Vec<f64>
-    {
-        vec![self.ls, self.ampl]
-    }
+/// A wrapper tuple struct used for kernel arithmetic
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct KernelArith<K: Kernel>(pub K);
 
-    fn set_parameters(&mut self, parameters: &[f64])
+impl<T: Kernel, U: Kernel> Add<KernelArith<T>> for KernelArith<U>
+{
+    type Output = KernelSum<U, T>;
+
+    fn add(self, ker: KernelArith<T>) -> KernelSum<U, T>
     {
-        self.ls = parameters[0];
-        self.ampl = parameters[1];
+        KernelSum { k1: self.0, k2: ker.0 }
     }
+}
 
-    fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
-                                                                                        training_inputs: &SMatrix<SM>,
-                                                                                        training_outputs: &SVector<SV>)
+impl<T: Kernel, U: Kernel> Mul<KernelArith<T>> for KernelArith<U>
+{
+    type Output = KernelProd<U, T>;
+
+    fn mul(self, ker: KernelArith<T>) -> KernelProd<U, T>
     {
-        self.ls = fit_bandwidth_mean(training_inputs);
-        self.ampl = fit_amplitude_var(training_outputs);
+        KernelProd { k1: self.0, k2: ker.0 }
     }
 }
 
-//-----------------------------------------------
+//---------------------------------------------------------------------------------------
+// CLASSICAL KERNELS
 
-/// The Hyperbolic Tangent Kernel.
+/// The Linear Kernel.
 ///
-/// ker(x,y) = tanh(αx^Ty + c)
-#[derive(Clone, Copy, Debug)]
+/// k(x,y) = σ_b² + σ_v²(x-c)^T(y-c)
+///
+/// Where σ_b is the bias amplitude, σ_v is the slope amplitude and c is an offset (one value per dimension).
+///
+/// Combined with a stationary kernel, it can model a linear trend directly in the covariance structure
+/// (rather than in the prior).
+/// On its own, with a zero prior, the process is a Bayesian linear regression
+/// (see the `bayesian_linear_regression` example).
+/// Only σ_b and σ_v are parameters, the offset is fixed and never fitted.
+#[derive(Clone, Debug)]
 #[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
-pub struct HyperTan
+pub struct Linear
 {
-    /// The scaling of the inner product.
-    pub alpha: f64,
-    /// The constant to add to the inner product.
-    pub c: f64
+    /// Square root of the bias variance.
+    pub sigma_b: f64,
+    /// Square root of the slope variance.
+    pub sigma_v: f64,
+    /// Offset subtracted from the inputs, one value per dimension (an empty offset is a null offset).
+    pub offset: DVector<f64>
 }
 
-impl HyperTan
+impl Linear
 {
-    /// Constructs a new Hyperbolic Tangent Kernel.
-    pub fn new(alpha: f64, c: f64) -> HyperTan
+    /// Constructs a new Linear Kernel.
+    pub fn new(sigma_b: f64, sigma_v: f64, offset: DVector<f64>) -> Linear
     {
-        HyperTan { alpha, c }
+        Linear { sigma_b, sigma_v, offset }
+    }
+
+    /// Subtracts the offset from the input.
+    fn center<S: Storage<f64, U1, Dynamic>>(&self, x: &SRowVector<S>) -> RowDVector<f64>
+    {
+        if self.offset.is_empty()
+        {
+            return x.clone_owned();
+        }
+        assert_eq!(self.offset.len(),
+                   x.ncols(),
+                   "Linear: the offset has {} elements but the inputs are of dimension {}.",
+                   self.offset.len(),
+                   x.ncols());
+        x.clone_owned() - self.offset.transpose()
     }
 }
 
-/// Constructs a default Hyperbolic Tangent Kernel.
+/// Constructs the default Linear Kernel.
 ///
 /// The defaults are:
-/// - alpha = 1
-/// - c = 0
-impl Default for HyperTan
+/// - sigma_b = 1
+/// - sigma_v = 1
+/// - no offset
+impl Default for Linear
 {
-    fn default() -> HyperTan
+    fn default() -> Linear
     {
-        HyperTan { alpha: 1f64, c: 0f64 }
+        Linear { sigma_b: 1f64, sigma_v: 1f64, offset: DVector::zeros(0) }
     }
 }
 
-impl Kernel for HyperTan
+impl Kernel for Linear
 {
     fn nb_parameters(&self) -> usize
     {
         2
     }
 
+    fn is_scalable(&self) -> bool
+    {
+        true
+    }
+
     fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
                                                                             x1: &SRowVector<S1>,
                                                                             x2: &SRowVector<S2>)
                                                                             -> f64
     {
-        (self.alpha * x1.dot(x2) + self.c).tanh()
+        let x1 = self.center(x1);
+        let x2 = self.center(x2);
+        self.sigma_b * self.sigma_b + self.sigma_v * self.sigma_v * x1.dot(&x2)
     }
 
     fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
@@ -981,60 +1676,89 @@ impl Kernel for HyperTan
                                                                               x2: &SRowVector<S2>)
                                                                               -> Vec<f64>
     {
-        let x = x1.dot(x2);
-        let grad_c = 1. / (self.alpha * x + self.c).cosh().powi(2);
-        let grad_alpha = x * grad_c;
+        let x1 = self.center(x1);
+        let x2 = self.center(x2);
+        let grad_sigma_b = 2. * self.sigma_b;
+        let grad_sigma_v = 2. * self.sigma_v * x1.dot(&x2);
+        vec![grad_sigma_b, grad_sigma_v]
+    }
 
-        vec![grad_alpha, grad_c]
+    /// Both variances are multiplied by `scale`.
+    fn rescale(&mut self, scale: f64)
+    {
+        let scale = scale.sqrt();
+        self.sigma_b *= scale;
+        self.sigma_v *= scale;
     }
 
     fn get_parameters(&self) -> Vec<f64>
     {
-        vec![self.alpha, self.c]
+        vec![self.sigma_b, self.sigma_v]
     }
 
     fn set_parameters(&mut self, parameters: &[f64])
     {
-        self.alpha = parameters[0];
-        self.c = parameters[1];
+        self.sigma_b = parameters[0];
+        self.sigma_v = parameters[1];
     }
 }
 
 //-----------------------------------------------
 
-/// The Multiquadric Kernel.
+/// The Polynomial Kernel.
 ///
-/// k(x,y) = sqrt(||x-y||² + c²)
+/// k(x,y) = (αx^Ty + c)^d
+///
+/// Where the degree d is a fixed positive integer and only α and c are fitted.
 #[derive(Clone, Copy, Debug)]
 #[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
-pub struct Multiquadric
+pub struct Polynomial
 {
-    /// Constant added to square of difference.
-    pub c: f64
+    /// Scaling of the inner product.
+    pub alpha: f64,
+    /// Constant added to inner product.
+    pub c: f64,
+    /// The power to raise the sum to, at least 1.
+    pub d: i32
 }
 
-impl Multiquadric
+impl Polynomial
 {
-    /// Constructs a new Multiquadric Kernel.
-    pub fn new(c: f64) -> Multiquadric
+    /// Constructs a new Polynomial Kernel.
+    ///
+    /// Panics if the degree `d` is not positive (the kernel would not be positive definite).
+    pub fn new(alpha: f64, c: f64, d: i32) -> Polynomial
     {
-        Multiquadric { c }
+        assert!(d > 0, "Polynomial: the degree should be positive but is {}.", d);
+        Polynomial { alpha, c, d }
+    }
+
+    /// Fallible version of `new`, returns an error rather than panicking if the degree `d` is not positive.
+    pub fn try_new(alpha: f64, c: f64, d: i32) -> Result<Polynomial, FriedrichError>
+    {
+        if d <= 0
+        {
+            return Err(FriedrichError::InvalidParameter { name: "d", value: d as f64 });
+        }
+        Ok(Polynomial { alpha, c, d })
     }
 }
 
-/// Constructs a default Multiquadric Kernel.
+/// Construct a new polynomial kernel.
 ///
 /// The defaults are:
+/// - alpha = 1
 /// - c = 0
-impl Default for Multiquadric
+/// - d = 1
+impl Default for Polynomial
 {
-    fn default() -> Multiquadric
+    fn default() -> Polynomial
     {
-        Multiquadric { c: 0f64 }
+        Polynomial { alpha: 1f64, c: 0f64, d: 1 }
     }
 }
 
-impl Kernel for Multiquadric
+impl Kernel for Polynomial
 {
     fn nb_parameters(&self) -> usize
     {
@@ -1046,7 +1770,7 @@ impl Kernel for Multiquadric
                                                                             x2: &SRowVector<S2>)
                                                                             -> f64
     {
-        (x1 - x2).norm_squared().hypot(self.c)
+        (self.alpha * x1.dot(x2) + self.c).powi(self.d)
     }
 
     fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
@@ -1054,72 +1778,101 @@ impl Kernel for Multiquadric
                                                                               x2: &SRowVector<S2>)
                                                                               -> Vec<f64>
     {
-        let grad_c = self.c / (x1 - x2).norm().hypot(self.c);
-        vec![grad_c]
+        let x = x1.dot(x2);
+        let inner_term = self.alpha * x + self.c;
+
+        let grad_c = (self.d as f64) * inner_term.powi(self.d - 1);
+        let grad_alpha = x * grad_c;
+
+        vec![grad_alpha, grad_c]
     }
 
     fn get_parameters(&self) -> Vec<f64>
     {
-        vec![self.c]
+        vec![self.alpha, self.c]
     }
 
     fn set_parameters(&mut self, parameters: &[f64])
     {
+        self.alpha = parameters[0];
         self.c = parameters[1];
     }
 }
 
 //-----------------------------------------------
 
-/// The Rational Quadratic Kernel.
+/// Gaussian kernel.
+///
+/// Equivalent to a squared exponential kernel.
+///
+/// k(x,y) = A exp(-||x-y||² / 2l²)
+///
+/// Where A is the amplitude and l the length scale.
+pub type Gaussian = SquaredExp;
+
+/// Squared exponential kernel
+///
+/// Equivalent to a gaussian kernel.
+///
+/// k(x,y) = A exp(-||x-y||² / 2l²)
 ///
-/// k(x,y) = (1 + ||x-y||² / (2αl²))^-α
+/// Where A is the amplitude and l the length scale.
 #[derive(Clone, Copy, Debug)]
 #[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
-pub struct RationalQuadratic
+pub struct SquaredExp
 {
-    /// Controls inverse power and difference scale.
-    pub alpha: f64,
-    /// Length scale controls scale of difference.
-    pub ls: f64
+    /// The length scale of the kernel.
+    pub ls: f64,
+    /// The amplitude of the kernel.
+    pub ampl: f64
 }
 
-impl RationalQuadratic
+impl SquaredExp
 {
-    /// Constructs a new Rational Quadratic Kernel.
-    pub fn new(alpha: f64, ls: f64) -> RationalQuadratic
+    /// Construct a new squared exponential kernel (gaussian).
+    pub fn new(ls: f64, ampl: f64) -> SquaredExp
     {
-        RationalQuadratic { alpha, ls }
+        SquaredExp { ls, ampl }
     }
 }
 
-/// The default Rational Quadratic Kernel.
+/// Constructs the default Squared Exp kernel.
 ///
 /// The defaults are:
-/// - alpha = 1
 /// - ls = 1
-impl Default for RationalQuadratic
+/// - ampl = 1
+impl Default for SquaredExp
 {
-    fn default() -> RationalQuadratic
+    fn default() -> SquaredExp
     {
-        RationalQuadratic { alpha: 1f64, ls: 1f64 }
+        SquaredExp { ls: 1f64, ampl: 1f64 }
     }
 }
 
-impl Kernel for RationalQuadratic
+impl Kernel for SquaredExp
 {
     fn nb_parameters(&self) -> usize
     {
         2
     }
 
+    fn is_scalable(&self) -> bool
+    {
+        true
+    }
+
+    /// The squared exponential kernel function.
     fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
                                                                             x1: &SRowVector<S1>,
                                                                             x2: &SRowVector<S2>)
                                                                             -> f64
     {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        // Computes kernel.
         let distance_squared = (x1 - x2).norm_squared();
-        (1f64 + distance_squared / (2f64 * self.alpha * self.ls * self.ls)).powf(-self.alpha)
+        let x = -distance_squared / (2f64 * self.ls * self.ls);
+        ampl * x.exp()
     }
 
     fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
@@ -1128,30 +1881,3467 @@ impl Kernel for RationalQuadratic
                                                                               -> Vec<f64>
     {
         // Sanitize parameters.
-        let l = self.ls.abs();
-        // Compute gradient.
+        let ampl = self.ampl.abs();
+        // Compute gradients.
         let distance_squared = (x1 - x2).norm_squared();
-        let grad_alpha =
-            ((distance_squared + 2. * l.powi(2) * self.alpha) / (l.powi(2) * self.alpha)).powf(-self.alpha)
-            * (2f64.powf(self.alpha)
-               * (1.
-                  - ((distance_squared + 2. * l.powi(2) * self.alpha) / (2. * l.powi(2) * self.alpha)).ln())
-               - (l.powi(2) * 2f64.powf(self.alpha + 1.) * self.alpha)
-                 / (distance_squared + 2. * l.powi(2) * self.alpha));
-        let grad_ls = distance_squared
-                      * (distance_squared / (2. * self.alpha * l * l) + 1.).powf(-self.alpha - 1.)
-                      / self.ls.powi(3);
-        vec![grad_alpha, grad_ls]
+        let exponential = (-distance_squared / (2f64 * self.ls * self.ls)).exp();
+        let grad_ls = (distance_squared * ampl * exponential) / self.ls.powi(3);
+        let grad_ampl = self.ampl.signum() * exponential;
+        vec![grad_ls, grad_ampl]
+    }
+
+    fn rescale(&mut self, scale: f64)
+    {
+        self.ampl *= scale;
     }
 
     fn get_parameters(&self) -> Vec<f64>
     {
-        vec![self.alpha, self.ls]
+        vec![self.ls, self.ampl]
     }
 
     fn set_parameters(&mut self, parameters: &[f64])
     {
-        self.alpha = parameters[0];
-        self.ls = parameters[1];
+        self.ls = parameters[0];
+        self.ampl = parameters[1];
+    }
+
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        vec![LENGTH_SCALE_BOUNDS, UNBOUNDED]
+    }
+
+    fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
+                                                                                        training_inputs: &SMatrix<SM>,
+                                                                                        training_outputs: &SVector<SV>)
+    {
+        self.ls = fit_bandwidth_mean(training_inputs);
+        self.ampl = fit_amplitude_var(training_outputs);
+    }
+}
+
+//-----------------------------------------------
+
+/// RBF kernel with automatic relevance determination.
+///
+/// Equivalent to the SquaredExpArd kernel.
+pub type RbfArd = SquaredExpArd;
+
+/// Squared exponential kernel with automatic relevance determination (ARD).
+///
+/// k(x,y) = A exp(-Σ_d (x_d-y_d)² / 2l_d²)
+///
+/// Where A is the amplitude and l_d is the length scale of the dimension d.
+/// Once fitted, the length scales give a ranking of the relevance of each input dimension (a large length scale meaning an irrelevant dimension).
+///
+/// The parameters are ordered as `[l_0, ..., l_(D-1), A]`.
+/// A single length scale can be given in which case it is shared by all dimensions (this is the case of the default kernel, the `heuristic_fit` function then sets one length scale per dimension).
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct SquaredExpArd
+{
+    /// The length scales of the kernel, one per dimension.
+    pub ls: Vec<f64>,
+    /// The amplitude of the kernel.
+    pub ampl: f64
+}
+
+impl SquaredExpArd
+{
+    /// Constructs a new squared exponential kernel with one length scale per dimension.
+    pub fn new(ls: Vec<f64>, ampl: f64) -> SquaredExpArd
+    {
+        assert!(!ls.is_empty(), "SquaredExpArd: at least one length scale is needed.");
+        SquaredExpArd { ls, ampl }
+    }
+
+    /// Constructs a new squared exponential kernel with a unit length scale for each of the `nb_dimensions` dimensions
+    /// and a unit amplitude.
+    ///
+    /// Unlike the default kernel, whose single length scale is shared by all dimensions until a `heuristic_fit`,
+    /// the length scales of this kernel will be fitted independently even when the kernel is used directly.
+    pub fn with_dimension(nb_dimensions: usize) -> SquaredExpArd
+    {
+        SquaredExpArd::new(vec![1f64; nb_dimensions], 1f64)
+    }
+
+    /// Returns the length scales of the kernel, one per dimension.
+    pub fn length_scales(&self) -> &[f64]
+    {
+        &self.ls
+    }
+
+    /// Returns the index of the length scale associated with a given dimension.
+    fn ls_index(&self, dimension: usize, nb_dimensions: usize) -> usize
+    {
+        if self.ls.len() == 1
+        {
+            0
+        }
+        else
+        {
+            assert_eq!(self.ls.len(),
+                       nb_dimensions,
+                       "SquaredExpArd: the kernel has {} length scales but the inputs are of dimension {}.",
+                       self.ls.len(),
+                       nb_dimensions);
+            dimension
+        }
+    }
+
+    /// Computes the weighted squared distance between two inputs.
+    fn weighted_distance_squared<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                                               x1: &SRowVector<S1>,
+                                                                                               x2: &SRowVector<S2>)
+                                                                                               -> f64
+    {
+        let nb_dimensions = x1.ncols();
+        (0..nb_dimensions).map(|d| {
+                              let l = self.ls[self.ls_index(d, nb_dimensions)];
+                              (x1[d] - x2[d]).powi(2) / (l * l)
+                          })
+                          .sum()
+    }
+}
+
+/// Constructs the default SquaredExpArd kernel.
+///
+/// The defaults are:
+/// - ls = [1] (shared by all dimensions)
+/// - ampl = 1
+impl Default for SquaredExpArd
+{
+    fn default() -> SquaredExpArd
+    {
+        SquaredExpArd { ls: vec![1f64], ampl: 1f64 }
+    }
+}
+
+impl Kernel for SquaredExpArd
+{
+    fn nb_parameters(&self) -> usize
+    {
+        self.ls.len() + 1
+    }
+
+    fn is_scalable(&self) -> bool
+    {
+        true
+    }
+
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            x1: &SRowVector<S1>,
+                                                                            x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        // Computes kernel.
+        ampl * (-self.weighted_distance_squared(x1, x2) / 2.).exp()
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              x1: &SRowVector<S1>,
+                                                                              x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        // Compute gradients.
+        let exponential = (-self.weighted_distance_squared(x1, x2) / 2.).exp();
+        let nb_dimensions = x1.ncols();
+        let mut gradients = vec![0.; self.nb_parameters()];
+        for d in 0..nb_dimensions
+        {
+            let i = self.ls_index(d, nb_dimensions);
+            gradients[i] += ampl * exponential * (x1[d] - x2[d]).powi(2) / self.ls[i].powi(3);
+        }
+        gradients[self.ls.len()] = self.ampl.signum() * exponential;
+        gradients
+    }
+
+    fn rescale(&mut self, scale: f64)
+    {
+        self.ampl *= scale;
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        let mut parameters = self.ls.clone();
+        parameters.push(self.ampl);
+        parameters
+    }
+
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        let nb_ls = self.ls.len();
+        self.ls.copy_from_slice(&parameters[..nb_ls]);
+        self.ampl = parameters[nb_ls];
+    }
+
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        let mut bounds = vec![LENGTH_SCALE_BOUNDS; self.ls.len()];
+        bounds.push(UNBOUNDED);
+        bounds
+    }
+
+    /// Sets one length scale per dimension, using the mean distance between points along that dimension.
+    fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
+                                                                                        training_inputs: &SMatrix<SM>,
+                                                                                        training_outputs: &SVector<SV>)
+    {
+        self.ls = (0..training_inputs.ncols()).map(|d| fit_bandwidth_mean(&training_inputs.columns(d, 1)))
+                                              .collect();
+        self.ampl = fit_amplitude_var(training_outputs);
+    }
+}
+
+//-----------------------------------------------
+
+/// Ornstein-Uhlenbeck kernel.
+///
+/// Equivalent to the Exponential kernel (and to a Matèrn kernel with nu=1/2), it is the continuous limit of an AR(1) process.
+///
+/// k(x,y) = A exp(-||x-y|| / l)
+///
+/// Where A is the amplitude and l is the length scale.
+pub type OrnsteinUhlenbeck = Exponential;
+
+/// The Exponential Kernel.
+///
+/// Also known as the Ornstein-Uhlenbeck kernel, it corresponds to a Matèrn kernel with nu=1/2 and models rough, nowhere differentiable, signals.
+///
+/// k(x,y) = A exp(-||x-y|| / l)
+///
+/// Where A is the amplitude and l is the length scale.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Exponential
+{
+    /// The length scale of the kernel.
+    pub ls: f64,
+    /// The amplitude of the kernel.
+    pub ampl: f64
+}
+
+impl Exponential
+{
+    /// Construct a new exponential kernel.
+    pub fn new(ls: f64, ampl: f64) -> Exponential
+    {
+        Exponential { ls, ampl }
+    }
+}
+
+/// Constructs the default Exponential kernel.
+///
+/// The defaults are:
+/// - ls = 1
+/// - amplitude = 1
+impl Default for Exponential
+{
+    fn default() -> Exponential
+    {
+        Exponential { ls: 1f64, ampl: 1f64 }
+    }
+}
+
+impl Kernel for Exponential
+{
+    fn nb_parameters(&self) -> usize
+    {
+        2
+    }
+
+    fn is_scalable(&self) -> bool
+    {
+        true
+    }
+
+    /// The exponential kernel function.
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            x1: &SRowVector<S1>,
+                                                                            x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        // sanitize parameters
+        let ampl = self.ampl.abs();
+        let l = self.ls.abs().max(f64::EPSILON);
+        // compute kernel
+        let distance = (x1 - x2).norm();
+        ampl * (-distance / l).exp()
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              x1: &SRowVector<S1>,
+                                                                              x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        // Sanitize parameters.
+        // The length scale is kept away from zero as the gradient would otherwise be 0*inf.
+        let ampl = self.ampl.abs();
+        let l = self.ls.abs().max(f64::EPSILON);
+        // Compute gradients.
+        // NOTE: The kernel is not differentiable in x when x1=x2 but its parameter gradients are,
+        // the length scale gradient is explicitly zero on the diagonal of the covariance matrix.
+        let distance = (x1 - x2).norm();
+        let exponential = (-distance / l).exp();
+        let grad_ls = self.ls.signum() * (distance * ampl * exponential) / (l * l);
+        let grad_ampl = self.ampl.signum() * exponential;
+        vec![grad_ls, grad_ampl]
+    }
+
+    fn rescale(&mut self, scale: f64)
+    {
+        self.ampl *= scale;
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        vec![self.ls, self.ampl]
+    }
+
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        self.ls = parameters[0];
+        self.ampl = parameters[1];
+    }
+
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        vec![LENGTH_SCALE_BOUNDS, UNBOUNDED]
+    }
+
+    fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
+                                                                                        training_inputs: &SMatrix<SM>,
+                                                                                        training_outputs: &SVector<SV>)
+    {
+        self.ls = fit_bandwidth_mean(training_inputs);
+        self.ampl = fit_amplitude_var(training_outputs);
+    }
+}
+
+//-----------------------------------------------
+
+/// The Gamma-Exponential Kernel.
+///
+/// k(x,y) = A exp(-(||x-y||/l)^γ)
+///
+/// Where A is the amplitude, l is the length scale and γ ∈ (0, 2] the exponent.
+/// This kernel interpolates between the exponential kernel (γ = 1), which models rough functions,
+/// and the squared exponential kernel (γ = 2, up to a rescaling of the length scale), which models smooth functions.
+/// Fitting γ thus lets the data decide on the smoothness of the model.
+///
+/// The kernel is not positive definite for exponents larger than 2,
+/// the magnitude of the exponent is thus bounded to (0, 2] during the fit (see `parameter_bounds`).
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct GammaExponential
+{
+    /// The length scale of the kernel.
+    pub ls: f64,
+    /// The exponent of the kernel.
+    pub gamma: f64,
+    /// The amplitude of the kernel.
+    pub ampl: f64
+}
+
+impl GammaExponential
+{
+    /// Constructs a new gamma-exponential kernel.
+    ///
+    /// Panics if the exponent is not in (0, 2].
+    pub fn new(ls: f64, gamma: f64, ampl: f64) -> GammaExponential
+    {
+        assert!(gamma > 0. && gamma <= 2., "GammaExponential: the exponent should be in (0, 2] but is {}.", gamma);
+        GammaExponential { ls, gamma, ampl }
+    }
+}
+
+/// The default Gamma-Exponential Kernel.
+///
+/// The defaults are:
+/// - ls = 1
+/// - gamma = 1.5
+/// - ampl = 1
+impl Default for GammaExponential
+{
+    fn default() -> GammaExponential
+    {
+        GammaExponential { ls: 1f64, gamma: 1.5f64, ampl: 1f64 }
+    }
+}
+
+impl Kernel for GammaExponential
+{
+    fn nb_parameters(&self) -> usize
+    {
+        3
+    }
+
+    fn is_scalable(&self) -> bool
+    {
+        true
+    }
+
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            x1: &SRowVector<S1>,
+                                                                            x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        let l = self.ls.abs().max(f64::EPSILON);
+        let gamma = self.gamma.abs();
+        // Computes kernel.
+        let distance = (x1 - x2).norm();
+        ampl * (-(distance / l).powf(gamma)).exp()
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              x1: &SRowVector<S1>,
+                                                                              x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        let l = self.ls.abs().max(f64::EPSILON);
+        let gamma = self.gamma.abs();
+        // Compute gradients.
+        let distance = (x1 - x2).norm();
+        if distance == 0.
+        {
+            // (d/l)^γ ln(d/l) goes to zero with the distance.
+            return vec![0., 0., self.ampl.signum()];
+        }
+        let scaled_distance = distance / l;
+        let power = scaled_distance.powf(gamma);
+        let exponential = (-power).exp();
+        let grad_ls = self.ls.signum() * ampl * exponential * gamma * power / l;
+        let grad_gamma = -self.gamma.signum() * ampl * exponential * power * scaled_distance.ln();
+        let grad_ampl = self.ampl.signum() * exponential;
+        vec![grad_ls, grad_gamma, grad_ampl]
+    }
+
+    fn rescale(&mut self, scale: f64)
+    {
+        self.ampl *= scale;
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        vec![self.ls, self.gamma, self.ampl]
+    }
+
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        self.ls = parameters[0];
+        self.gamma = parameters[1];
+        self.ampl = parameters[2];
+    }
+
+    /// The magnitude of the exponent is bounded to (0, 2], where the kernel is positive definite.
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        vec![LENGTH_SCALE_BOUNDS, (f64::EPSILON, 2.), UNBOUNDED]
+    }
+
+    fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
+                                                                                        training_inputs: &SMatrix<SM>,
+                                                                                        training_outputs: &SVector<SV>)
+    {
+        self.ls = fit_bandwidth_mean(training_inputs);
+        self.ampl = fit_amplitude_var(training_outputs);
+    }
+}
+
+//-----------------------------------------------
+
+/// The Piecewise Polynomial Kernel.
+///
+/// k(x,y) = A (1 - r)⁴ (4r + 1) if r < 1, 0 otherwise
+///
+/// Where A is the amplitude, R the support radius and r = ||x-y||/R.
+/// This is the Wendland function ψ(3,1): a twice differentiable kernel with a compact support,
+/// the covariance between two points further apart than the radius is exactly zero leading to sparse covariance matrices.
+///
+/// The kernel is guaranteed to be positive definite for inputs of dimension up to three.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct PiecewisePolynomial
+{
+    /// The support radius of the kernel.
+    pub radius: f64,
+    /// The amplitude of the kernel.
+    pub ampl: f64
+}
+
+impl PiecewisePolynomial
+{
+    /// Constructs a new piecewise polynomial kernel.
+    pub fn new(radius: f64, ampl: f64) -> PiecewisePolynomial
+    {
+        PiecewisePolynomial { radius, ampl }
+    }
+}
+
+/// The default Piecewise Polynomial Kernel.
+///
+/// The defaults are:
+/// - radius = 1
+/// - ampl = 1
+impl Default for PiecewisePolynomial
+{
+    fn default() -> PiecewisePolynomial
+    {
+        PiecewisePolynomial { radius: 1f64, ampl: 1f64 }
+    }
+}
+
+impl Kernel for PiecewisePolynomial
+{
+    fn nb_parameters(&self) -> usize
+    {
+        2
+    }
+
+    fn is_scalable(&self) -> bool
+    {
+        true
+    }
+
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            x1: &SRowVector<S1>,
+                                                                            x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        let radius = self.radius.abs().max(f64::EPSILON);
+        // Computes kernel.
+        let r = (x1 - x2).norm() / radius;
+        if r >= 1.
+        {
+            return 0.;
+        }
+        ampl * (1. - r).powi(4) * (4. * r + 1.)
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              x1: &SRowVector<S1>,
+                                                                              x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        let radius = self.radius.abs().max(f64::EPSILON);
+        // Compute gradients.
+        // Outside of the support, the kernel and all its gradients are null.
+        let r = (x1 - x2).norm() / radius;
+        if r >= 1.
+        {
+            return vec![0., 0.];
+        }
+        let grad_radius = self.radius.signum() * ampl * 20. * r * r * (1. - r).powi(3) / radius;
+        let grad_ampl = self.ampl.signum() * (1. - r).powi(4) * (4. * r + 1.);
+        vec![grad_radius, grad_ampl]
+    }
+
+    fn rescale(&mut self, scale: f64)
+    {
+        self.ampl *= scale;
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        vec![self.radius, self.ampl]
+    }
+
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        self.radius = parameters[0];
+        self.ampl = parameters[1];
+    }
+
+    /// Sets the radius to twice the mean distance between points.
+    fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
+                                                                                        training_inputs: &SMatrix<SM>,
+                                                                                        training_outputs: &SVector<SV>)
+    {
+        self.radius = 2. * fit_bandwidth_mean(training_inputs);
+        self.ampl = fit_amplitude_var(training_outputs);
+    }
+}
+
+//-----------------------------------------------
+
+/// Matèrn 3/2 kernel.
+///
+/// Equivalent to the Matèrn1 kernel.
+///
+/// k(x,y) = A (1 + ||x-y||sqrt(3)/l) exp(-||x-y||sqrt(3)/l)
+///
+/// Where A is the amplitude and l is the length scale.
+pub type Matern32 = Matern1;
+
+/// The Matèrn1 kernel which is 1 differentiable and correspond to a classical Matèrn kernel with nu=3/2.
+///
+/// k(x,y) = A (1 + ||x-y||sqrt(3)/l) exp(-||x-y||sqrt(3)/l)
+///
+/// Where A is the amplitude and l is the length scale.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Matern1
+{
+    /// The length scale of the kernel.
+    pub ls: f64,
+    /// The amplitude of the kernel.
+    pub ampl: f64
+}
+
+impl Matern1
+{
+    /// Construct a new matèrn1 kernel.
+    pub fn new(ls: f64, ampl: f64) -> Matern1
+    {
+        Matern1 { ls, ampl }
+    }
+}
+
+/// Constructs the default Matern1 kernel.
+///
+/// The defaults are:
+/// - ls = 1
+/// - amplitude = 1
+impl Default for Matern1
+{
+    fn default() -> Matern1
+    {
+        Matern1 { ls: 1f64, ampl: 1f64 }
+    }
+}
+
+impl Kernel for Matern1
+{
+    fn nb_parameters(&self) -> usize
+    {
+        2
+    }
+
+    fn is_scalable(&self) -> bool
+    {
+        true
+    }
+
+    /// The matèrn1 kernel function.
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            x1: &SRowVector<S1>,
+                                                                            x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        // sanitize parameters
+        let ampl = self.ampl.abs();
+        let l = self.ls.abs();
+        // compute kernel
+        let distance = (x1 - x2).norm();
+        let x = (3f64).sqrt() * distance / l;
+        ampl * (1f64 + x) * (-x).exp()
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              x1: &SRowVector<S1>,
+                                                                              x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        let l = self.ls.abs();
+        // Compute gradient.
+        let distance = (x1 - x2).norm();
+        let x = 3f64.sqrt() * distance / l;
+        let grad_ls = (3. * ampl * distance.powi(2) * (-x).exp()) / (self.ls.powi(3));
+        let grad_ampl = self.ampl.signum() * (1. + x) * (-x).exp();
+        vec![grad_ls, grad_ampl]
+    }
+
+    fn rescale(&mut self, scale: f64)
+    {
+        self.ampl *= scale;
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        vec![self.ls, self.ampl]
+    }
+
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        self.ls = parameters[0];
+        self.ampl = parameters[1];
+    }
+
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        vec![LENGTH_SCALE_BOUNDS, UNBOUNDED]
+    }
+
+    fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
+                                                                                        training_inputs: &SMatrix<SM>,
+                                                                                        training_outputs: &SVector<SV>)
+    {
+        self.ls = fit_bandwidth_mean(training_inputs);
+        self.ampl = fit_amplitude_var(training_outputs);
+    }
+}
+
+//-----------------------------------------------
+
+/// Matèrn 5/2 kernel.
+///
+/// Equivalent to the Matèrn2 kernel.
+///
+/// k(x,y) = A (1 + ||x-y||sqrt(5)/l + ||x-y||²5/3l²) exp(-||x-y||sqrt(5)/l)
+///
+/// Where A is the amplitude and l is the length scale.
+///
+/// Being scalable, its amplitude is fitted alongside the noise by the optimizer:
+///
+/// ```rust
+/// # use friedrich::gaussian_process::GaussianProcess;
+/// # use friedrich::kernel::Matern52;
+/// # let training_inputs = vec![vec![0.8], vec![1.2], vec![3.8], vec![4.2]];
+/// # let training_outputs = vec![3.0, 4.0, -2.0, -2.0];
+/// let gp = GaussianProcess::builder(training_inputs, training_outputs).set_kernel(Matern52::default())
+///                                                                     .fit_kernel()
+///                                                                     .train();
+/// ```
+pub type Matern52 = Matern2;
+
+/// The Matèrn2 kernel which is 2 differentiable and correspond to a classical Matèrn kernel with nu=5/2.
+///
+/// k(x,y) = A (1 + ||x-y||sqrt(5)/l + ||x-y||²5/3l²) exp(-||x-y||sqrt(5)/l)
+///
+/// Where A is the amplitude and l is the length scale.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Matern2
+{
+    /// The length scale of the kernel.
+    pub ls: f64,
+    /// The amplitude of the kernel.
+    pub ampl: f64
+}
+
+impl Matern2
+{
+    /// Construct a new matèrn2 kernel.
+    pub fn new(ls: f64, ampl: f64) -> Matern2
+    {
+        Matern2 { ls, ampl }
+    }
+}
+
+/// Constructs the default Matern2 kernel.
+///
+/// The defaults are:
+/// - ls = 1
+/// - amplitude = 1
+impl Default for Matern2
+{
+    fn default() -> Matern2
+    {
+        Matern2 { ls: 1f64, ampl: 1f64 }
+    }
+}
+
+impl Kernel for Matern2
+{
+    fn nb_parameters(&self) -> usize
+    {
+        2
+    }
+
+    fn is_scalable(&self) -> bool
+    {
+        true
+    }
+
+    /// The matèrn2 kernel function.
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            x1: &SRowVector<S1>,
+                                                                            x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        // sanitize parameters
+        let ampl = self.ampl.abs();
+        let l = self.ls.abs();
+        // compute kernel
+        let distance = (x1 - x2).norm();
+        let x = (5f64).sqrt() * distance / l;
+        ampl * (1f64 + x + (5f64 * distance * distance) / (3f64 * l * l)) * (-x).exp()
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              x1: &SRowVector<S1>,
+                                                                              x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        let l = self.ls.abs();
+        // Compute gradient.
+        // NOTE: The gradient does not depend on 1/distance and is thus well defined for coincident points.
+        let distance = (x1 - x2).norm();
+        let x = (5f64).sqrt() * distance / l;
+        let grad_ls = self.ls.signum() * ampl * x * x * (1. + x) * (-x).exp() / (3. * l);
+        let grad_ampl = self.ampl.signum() * (1f64 + x + x * x / 3.) * (-x).exp();
+        vec![grad_ls, grad_ampl]
+    }
+
+    fn rescale(&mut self, scale: f64)
+    {
+        self.ampl *= scale;
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        vec![self.ls, self.ampl]
+    }
+
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        self.ls = parameters[0];
+        self.ampl = parameters[1];
+    }
+
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        vec![LENGTH_SCALE_BOUNDS, UNBOUNDED]
+    }
+
+    fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
+                                                                                        training_inputs: &SMatrix<SM>,
+                                                                                        training_outputs: &SVector<SV>)
+    {
+        self.ls = fit_bandwidth_mean(training_inputs);
+        self.ampl = fit_amplitude_var(training_outputs);
+    }
+}
+
+//-----------------------------------------------
+
+/// The Periodic Kernel.
+///
+/// Also known as the ExpSineSquared kernel, it models functions that repeat themselves exactly.
+///
+/// k(x,y) = A exp(-2 sin²(π||x-y||/p) / l²)
+///
+/// Where A is the amplitude, p is the period and l is the length scale.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Periodic
+{
+    /// The length scale of the kernel.
+    pub ls: f64,
+    /// The period of the kernel.
+    pub period: f64,
+    /// The amplitude of the kernel.
+    pub ampl: f64
+}
+
+impl Periodic
+{
+    /// Constructs a new periodic kernel.
+    pub fn new(ls: f64, period: f64, ampl: f64) -> Periodic
+    {
+        Periodic { ls, period, ampl }
+    }
+}
+
+/// Constructs the default Periodic kernel.
+///
+/// The defaults are:
+/// - ls = 1
+/// - period = 1
+/// - amplitude = 1
+impl Default for Periodic
+{
+    fn default() -> Periodic
+    {
+        Periodic { ls: 1f64, period: 1f64, ampl: 1f64 }
+    }
+}
+
+impl Kernel for Periodic
+{
+    fn nb_parameters(&self) -> usize
+    {
+        3
+    }
+
+    fn is_scalable(&self) -> bool
+    {
+        true
+    }
+
+    /// The periodic kernel function.
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            x1: &SRowVector<S1>,
+                                                                            x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        let l = self.ls.abs();
+        let p = self.period.abs();
+        // Computes kernel.
+        let distance = (x1 - x2).norm();
+        let sin = (std::f64::consts::PI * distance / p).sin();
+        ampl * (-2. * sin * sin / (l * l)).exp()
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              x1: &SRowVector<S1>,
+                                                                              x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        let l = self.ls.abs();
+        let p = self.period.abs();
+        // Compute gradients.
+        let distance = (x1 - x2).norm();
+        let angle = std::f64::consts::PI * distance / p;
+        let (sin, cos) = angle.sin_cos();
+        let exponential = (-2. * sin * sin / (l * l)).exp();
+        let grad_ls = self.ls.signum() * ampl * exponential * 4. * sin * sin / l.powi(3);
+        let grad_period = self.period.signum() * ampl * exponential * 4. * sin * cos * angle / (l * l * p);
+        let grad_ampl = self.ampl.signum() * exponential;
+        vec![grad_ls, grad_period, grad_ampl]
+    }
+
+    fn rescale(&mut self, scale: f64)
+    {
+        self.ampl *= scale;
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        vec![self.ls, self.period, self.ampl]
+    }
+
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        self.ls = parameters[0];
+        self.period = parameters[1];
+        self.ampl = parameters[2];
+    }
+
+    /// The magnitude of the period is kept strictly positive as the kernel is degenerate otherwise.
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        vec![LENGTH_SCALE_BOUNDS, PERIOD_BOUNDS, UNBOUNDED]
+    }
+
+    /// Only the amplitude is fitted as the period is usually known and there is no good heuristic to guess it.
+    fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
+                                                                                        _training_inputs: &SMatrix<SM>,
+                                                                                        training_outputs: &SVector<SV>)
+    {
+        self.ampl = fit_amplitude_var(training_outputs);
+    }
+}
+
+//-----------------------------------------------
+
+/// The Locally Periodic Kernel.
+///
+/// The product of a periodic kernel and a squared exponential kernel, it models functions that repeat themselves
+/// with a pattern that slowly changes over time (such as seasonal data).
+///
+/// k(x,y) = A exp(-2 sin²(π||x-y||/p) / l²) exp(-||x-y||² / 2l_d²)
+///
+/// Where A is the amplitude, p is the period, l is the length scale of the periodic component
+/// and l_d is the decay length scale (over which the pattern changes).
+///
+/// The parameters are ordered as `[l, p, l_d, A]`.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct LocallyPeriodic
+{
+    /// The length scale of the periodic component.
+    pub ls: f64,
+    /// The period of the kernel.
+    pub period: f64,
+    /// The length scale of the decay.
+    pub decay_ls: f64,
+    /// The amplitude of the kernel.
+    pub ampl: f64
+}
+
+impl LocallyPeriodic
+{
+    /// Constructs a new locally periodic kernel.
+    pub fn new(ls: f64, period: f64, decay_ls: f64, ampl: f64) -> LocallyPeriodic
+    {
+        LocallyPeriodic { ls, period, decay_ls, ampl }
+    }
+}
+
+/// The default Locally Periodic Kernel.
+///
+/// The defaults are:
+/// - ls = 1
+/// - period = 1
+/// - decay_ls = 10
+/// - ampl = 1
+impl Default for LocallyPeriodic
+{
+    fn default() -> LocallyPeriodic
+    {
+        LocallyPeriodic { ls: 1f64, period: 1f64, decay_ls: 10f64, ampl: 1f64 }
+    }
+}
+
+impl Kernel for LocallyPeriodic
+{
+    fn nb_parameters(&self) -> usize
+    {
+        4
+    }
+
+    fn is_scalable(&self) -> bool
+    {
+        true
+    }
+
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            x1: &SRowVector<S1>,
+                                                                            x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        let l = self.ls.abs();
+        let p = self.period.abs();
+        let l_decay = self.decay_ls.abs();
+        // Computes kernel.
+        let distance = (x1 - x2).norm();
+        let sin = (std::f64::consts::PI * distance / p).sin();
+        let periodic = (-2. * sin * sin / (l * l)).exp();
+        let decay = (-distance * distance / (2. * l_decay * l_decay)).exp();
+        ampl * periodic * decay
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              x1: &SRowVector<S1>,
+                                                                              x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        let l = self.ls.abs();
+        let p = self.period.abs();
+        let l_decay = self.decay_ls.abs();
+        // Compute gradients.
+        let distance = (x1 - x2).norm();
+        let angle = std::f64::consts::PI * distance / p;
+        let (sin, cos) = angle.sin_cos();
+        let periodic = (-2. * sin * sin / (l * l)).exp();
+        let decay = (-distance * distance / (2. * l_decay * l_decay)).exp();
+        let kernel = ampl * periodic * decay;
+        let grad_ls = self.ls.signum() * kernel * 4. * sin * sin / l.powi(3);
+        let grad_period = self.period.signum() * kernel * 4. * sin * cos * angle / (l * l * p);
+        let grad_decay_ls = self.decay_ls.signum() * kernel * distance * distance / l_decay.powi(3);
+        let grad_ampl = self.ampl.signum() * periodic * decay;
+        vec![grad_ls, grad_period, grad_decay_ls, grad_ampl]
+    }
+
+    fn rescale(&mut self, scale: f64)
+    {
+        self.ampl *= scale;
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        vec![self.ls, self.period, self.decay_ls, self.ampl]
+    }
+
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        self.ls = parameters[0];
+        self.period = parameters[1];
+        self.decay_ls = parameters[2];
+        self.ampl = parameters[3];
+    }
+
+    /// The magnitude of the period is kept strictly positive as the kernel is degenerate otherwise.
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        vec![LENGTH_SCALE_BOUNDS, PERIOD_BOUNDS, LENGTH_SCALE_BOUNDS, UNBOUNDED]
+    }
+
+    /// Only the amplitude and decay length scale are fitted as the period is usually known
+    /// and there is no good heuristic to guess it.
+    fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
+                                                                                        training_inputs: &SMatrix<SM>,
+                                                                                        training_outputs: &SVector<SV>)
+    {
+        self.decay_ls = fit_bandwidth_mean(training_inputs);
+        self.ampl = fit_amplitude_var(training_outputs);
+    }
+}
+
+//-----------------------------------------------
+
+/// The Cosine Kernel.
+///
+/// k(x,y) = A cos(2π||x-y||/p)
+///
+/// Where A is the amplitude (the square of the amplitude of the modelled signal) and p is the period.
+/// Unlike the periodic kernel, there is no decay of the correlation between periods,
+/// this kernel models signals that are perfectly sinusoidal.
+///
+/// The covariance matrix of this kernel is of rank two (in one dimension) and thus singular:
+/// it relies on the noise (or the `cholesky_epsilon`) to be positive definite.
+/// The kernel is designed for one dimensional inputs: in higher dimensions it is a function of the euclidean distance
+/// which is not guaranteed to produce a positive semi-definite covariance matrix.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Cosine
+{
+    /// The period of the kernel.
+    pub period: f64,
+    /// The amplitude of the kernel.
+    pub ampl: f64
+}
+
+impl Cosine
+{
+    /// Constructs a new cosine kernel.
+    pub fn new(period: f64, ampl: f64) -> Cosine
+    {
+        Cosine { period, ampl }
+    }
+}
+
+/// The default Cosine Kernel.
+///
+/// The defaults are:
+/// - period = 1
+/// - ampl = 1
+impl Default for Cosine
+{
+    fn default() -> Cosine
+    {
+        Cosine { period: 1f64, ampl: 1f64 }
+    }
+}
+
+impl Kernel for Cosine
+{
+    fn nb_parameters(&self) -> usize
+    {
+        2
+    }
+
+    fn is_scalable(&self) -> bool
+    {
+        true
+    }
+
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            x1: &SRowVector<S1>,
+                                                                            x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        let p = self.period.abs();
+        // Computes kernel.
+        let distance = (x1 - x2).norm();
+        ampl * (2. * std::f64::consts::PI * distance / p).cos()
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              x1: &SRowVector<S1>,
+                                                                              x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        let p = self.period.abs();
+        // Compute gradients.
+        let distance = (x1 - x2).norm();
+        let angle = 2. * std::f64::consts::PI * distance / p;
+        let (sin, cos) = angle.sin_cos();
+        let grad_period = self.period.signum() * ampl * sin * angle / p;
+        let grad_ampl = self.ampl.signum() * cos;
+        vec![grad_period, grad_ampl]
+    }
+
+    fn rescale(&mut self, scale: f64)
+    {
+        self.ampl *= scale;
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        vec![self.period, self.ampl]
+    }
+
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        self.period = parameters[0];
+        self.ampl = parameters[1];
+    }
+
+    /// The magnitude of the period is kept strictly positive as the kernel is degenerate otherwise.
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        vec![PERIOD_BOUNDS, UNBOUNDED]
+    }
+
+    /// Only the amplitude is fitted as the period is usually known and there is no good heuristic to guess it.
+    fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
+                                                                                        _training_inputs: &SMatrix<SM>,
+                                                                                        training_outputs: &SVector<SV>)
+    {
+        self.ampl = fit_amplitude_var(training_outputs);
+    }
+}
+
+//-----------------------------------------------
+
+/// The Hyperbolic Tangent Kernel.
+///
+/// ker(x,y) = tanh(αx^Ty + c)
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct HyperTan
+{
+    /// The scaling of the inner product.
+    pub alpha: f64,
+    /// The constant to add to the inner product.
+    pub c: f64
+}
+
+impl HyperTan
+{
+    /// Constructs a new Hyperbolic Tangent Kernel.
+    pub fn new(alpha: f64, c: f64) -> HyperTan
+    {
+        HyperTan { alpha, c }
+    }
+}
+
+/// Constructs a default Hyperbolic Tangent Kernel.
+///
+/// The defaults are:
+/// - alpha = 1
+/// - c = 0
+impl Default for HyperTan
+{
+    fn default() -> HyperTan
+    {
+        HyperTan { alpha: 1f64, c: 0f64 }
+    }
+}
+
+impl Kernel for HyperTan
+{
+    fn nb_parameters(&self) -> usize
+    {
+        2
+    }
+
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            x1: &SRowVector<S1>,
+                                                                            x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        (self.alpha * x1.dot(x2) + self.c).tanh()
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              x1: &SRowVector<S1>,
+                                                                              x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        let x = x1.dot(x2);
+        let grad_c = 1. / (self.alpha * x + self.c).cosh().powi(2);
+        let grad_alpha = x * grad_c;
+
+        vec![grad_alpha, grad_c]
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        vec![self.alpha, self.c]
+    }
+
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        self.alpha = parameters[0];
+        self.c = parameters[1];
+    }
+}
+
+//-----------------------------------------------
+
+/// The Arcsine Kernel.
+///
+/// Also known as the neural network kernel, it is the covariance of a single hidden layer neural network
+/// with an infinite number of erf activated neurons and gaussian weights.
+///
+/// k(x,y) = (2/π) asin(2 s(x,y) / sqrt((1 + 2 s(x,x)) (1 + 2 s(y,y))))
+///
+/// Where s(x,y) = σ_b² + σ_w² x^Ty, σ_b² is the bias variance and σ_w² the weight variance.
+/// This kernel is non-stationary and models saturating functions (such as step functions) better than distance-based kernels.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct ArcSine
+{
+    /// The variance of the bias.
+    pub bias_variance: f64,
+    /// The variance of the weights.
+    pub weight_variance: f64
+}
+
+impl ArcSine
+{
+    /// Constructs a new arcsine kernel.
+    pub fn new(bias_variance: f64, weight_variance: f64) -> ArcSine
+    {
+        ArcSine { bias_variance, weight_variance }
+    }
+
+    /// Computes the argument of the arcsine, the inner products `[s(x1,x2), s(x1,x1), s(x2,x2)]`
+    /// and the dot products `[x1^Tx2, x1^Tx1, x2^Tx2]` (the derivatives of the inner products with respect to σ_w²).
+    fn ratio<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                           x1: &SRowVector<S1>,
+                                                                           x2: &SRowVector<S2>)
+                                                                           -> (f64, [f64; 3], [f64; 3])
+    {
+        // Sanitize parameters.
+        let bias_variance = self.bias_variance.abs();
+        let weight_variance = self.weight_variance.abs();
+        // Computes the inner products s(x1,x2), s(x1,x1) and s(x2,x2).
+        let dots = [x1.dot(x2), x1.dot(x1), x2.dot(x2)];
+        let s = dots.map(|dot| bias_variance + weight_variance * dot);
+        // The ratio is below one in absolute value (by Cauchy-Schwarz) but rounding might push it over.
+        let ratio = (2. * s[0] / ((1. + 2. * s[1]) * (1. + 2. * s[2])).sqrt()).clamp(-1., 1.);
+        (ratio, s, dots)
+    }
+}
+
+/// The default Arcsine Kernel.
+///
+/// The defaults are:
+/// - bias_variance = 1
+/// - weight_variance = 1
+impl Default for ArcSine
+{
+    fn default() -> ArcSine
+    {
+        ArcSine { bias_variance: 1f64, weight_variance: 1f64 }
+    }
+}
+
+impl Kernel for ArcSine
+{
+    fn nb_parameters(&self) -> usize
+    {
+        2
+    }
+
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            x1: &SRowVector<S1>,
+                                                                            x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        let (ratio, _, _) = self.ratio(x1, x2);
+        std::f64::consts::FRAC_2_PI * ratio.asin()
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              x1: &SRowVector<S1>,
+                                                                              x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        let (ratio, s, dots) = self.ratio(x1, x2);
+        // The derivative of the arcsine diverges when the ratio goes to one, we keep it finite.
+        let grad_asin = std::f64::consts::FRAC_2_PI / (1. - ratio * ratio).max(f64::EPSILON).sqrt();
+        // Derivative of the ratio given the derivatives of s(x1,x2), s(x1,x1) and s(x2,x2).
+        let grad_ratio = |ds: [f64; 3]| {
+            let denominator = ((1. + 2. * s[1]) * (1. + 2. * s[2])).sqrt();
+            2. * ds[0] / denominator - ratio * (ds[1] / (1. + 2. * s[1]) + ds[2] / (1. + 2. * s[2]))
+        };
+        let grad_bias = self.bias_variance.signum() * grad_asin * grad_ratio([1., 1., 1.]);
+        let grad_weight = self.weight_variance.signum() * grad_asin * grad_ratio(dots);
+        vec![grad_bias, grad_weight]
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        vec![self.bias_variance, self.weight_variance]
+    }
+
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        self.bias_variance = parameters[0];
+        self.weight_variance = parameters[1];
+    }
+}
+
+//-----------------------------------------------
+
+/// The Multiquadric Kernel.
+///
+/// k(x,y) = sqrt(||x-y||² + c²)
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Multiquadric
+{
+    /// Constant added to square of difference.
+    pub c: f64
+}
+
+impl Multiquadric
+{
+    /// Constructs a new Multiquadric Kernel.
+    pub fn new(c: f64) -> Multiquadric
+    {
+        Multiquadric { c }
+    }
+}
+
+/// Constructs a default Multiquadric Kernel.
+///
+/// The defaults are:
+/// - c = 0
+impl Default for Multiquadric
+{
+    fn default() -> Multiquadric
+    {
+        Multiquadric { c: 0f64 }
+    }
+}
+
+impl Kernel for Multiquadric
+{
+    fn nb_parameters(&self) -> usize
+    {
+        2
+    }
+
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            x1: &SRowVector<S1>,
+                                                                            x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        (x1 - x2).norm_squared().hypot(self.c)
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              x1: &SRowVector<S1>,
+                                                                              x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        let grad_c = self.c / (x1 - x2).norm().hypot(self.c);
+        vec![grad_c]
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        vec![self.c]
+    }
+
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        self.c = parameters[1];
+    }
+}
+
+//-----------------------------------------------
+
+/// The Rational Quadratic Kernel.
+///
+/// k(x,y) = A (1 + ||x-y||² / (2αl²))^-α
+///
+/// Where A is the amplitude, l is the length scale and α the mixture parameter.
+/// This kernel is a scale mixture of squared exponential kernels and converges to one when α goes to infinity.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct RationalQuadratic
+{
+    /// Controls inverse power and difference scale.
+    pub alpha: f64,
+    /// Length scale controls scale of difference.
+    pub ls: f64,
+    /// The amplitude of the kernel.
+    pub ampl: f64
+}
+
+impl RationalQuadratic
+{
+    /// Constructs a new Rational Quadratic Kernel.
+    pub fn new(alpha: f64, ls: f64, ampl: f64) -> RationalQuadratic
+    {
+        RationalQuadratic { alpha, ls, ampl }
+    }
+}
+
+/// The default Rational Quadratic Kernel.
+///
+/// The defaults are:
+/// - alpha = 1
+/// - ls = 1
+/// - ampl = 1
+impl Default for RationalQuadratic
+{
+    fn default() -> RationalQuadratic
+    {
+        RationalQuadratic { alpha: 1f64, ls: 1f64, ampl: 1f64 }
+    }
+}
+
+impl Kernel for RationalQuadratic
+{
+    fn nb_parameters(&self) -> usize
+    {
+        3
+    }
+
+    fn is_scalable(&self) -> bool
+    {
+        true
+    }
+
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            x1: &SRowVector<S1>,
+                                                                            x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        let alpha = self.alpha.abs();
+        let l = self.ls.abs();
+        // Computes kernel.
+        let distance_squared = (x1 - x2).norm_squared();
+        ampl * (1f64 + distance_squared / (2f64 * alpha * l * l)).powf(-alpha)
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              x1: &SRowVector<S1>,
+                                                                              x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        let alpha = self.alpha.abs();
+        let l = self.ls.abs();
+        // Compute gradient.
+        let distance_squared = (x1 - x2).norm_squared();
+        let base = 1. + distance_squared / (2. * alpha * l * l);
+        let power = base.powf(-alpha);
+        let grad_alpha = self.alpha.signum() * ampl * power * ((base - 1.) / base - base.ln());
+        let grad_ls = self.ls.signum() * ampl * distance_squared * base.powf(-alpha - 1.) / l.powi(3);
+        let grad_ampl = self.ampl.signum() * power;
+        vec![grad_alpha, grad_ls, grad_ampl]
+    }
+
+    fn rescale(&mut self, scale: f64)
+    {
+        self.ampl *= scale;
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        vec![self.alpha, self.ls, self.ampl]
+    }
+
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        self.alpha = parameters[0];
+        self.ls = parameters[1];
+        self.ampl = parameters[2];
+    }
+
+    /// The magnitude of `alpha` is kept strictly positive as the kernel is invalid otherwise.
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        vec![(f64::EPSILON, f64::INFINITY), LENGTH_SCALE_BOUNDS, UNBOUNDED]
+    }
+
+    fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
+                                                                                        training_inputs: &SMatrix<SM>,
+                                                                                        training_outputs: &SVector<SV>)
+    {
+        self.ls = fit_bandwidth_mean(training_inputs);
+        self.ampl = fit_amplitude_var(training_outputs);
+    }
+}
+
+//-----------------------------------------------
+
+/// The White Noise Kernel.
+///
+/// k(x,y) = σ² if x = y, 0 otherwise
+///
+/// Where σ² is the variance of the noise.
+/// Inputs are compared exactly, meaning that a test point identical to a training point will be correlated with it.
+///
+/// This kernel is meant to be used inside a kernel composition (such as `KernelArith(SquaredExp::default()) +
+/// KernelArith(WhiteNoise::default())`) in order to fit a noise term jointly with the other kernel parameters.
+/// Note that the global `noise` of the `GaussianProcess` is added to the diagonal of the covariance matrix
+/// independently of the kernel and is also fitted by the optimizer:
+/// when both are used, only their sum is identifiable and the variance ends up split between them.
+/// Setting the global `noise` to a small value (such as `0.`, relying on the `cholesky_epsilon` for stability)
+/// makes the white noise kernel responsible for all of the noise.
+/// If the kernel is scalable, the optimizer only ever rescales the global `noise` so a null `noise` stays null.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct WhiteNoise
+{
+    /// The variance of the noise.
+    pub variance: f64
+}
+
+impl WhiteNoise
+{
+    /// Constructs a new White Noise Kernel.
+    pub fn new(variance: f64) -> WhiteNoise
+    {
+        WhiteNoise { variance }
+    }
+}
+
+/// The default White Noise Kernel.
+///
+/// The defaults are:
+/// - variance = 0.1
+impl Default for WhiteNoise
+{
+    fn default() -> WhiteNoise
+    {
+        WhiteNoise { variance: 0.1f64 }
+    }
+}
+
+impl Kernel for WhiteNoise
+{
+    fn nb_parameters(&self) -> usize
+    {
+        1
+    }
+
+    fn is_scalable(&self) -> bool
+    {
+        true
+    }
+
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            x1: &SRowVector<S1>,
+                                                                            x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        if x1.iter().eq(x2.iter())
+        {
+            self.variance.abs()
+        }
+        else
+        {
+            0.
+        }
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              x1: &SRowVector<S1>,
+                                                                              x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        if x1.iter().eq(x2.iter())
+        {
+            vec![self.variance.signum()]
+        }
+        else
+        {
+            vec![0.]
+        }
+    }
+
+    fn rescale(&mut self, scale: f64)
+    {
+        self.variance *= scale;
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        vec![self.variance]
+    }
+
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        self.variance = parameters[0];
+    }
+}
+
+//-----------------------------------------------
+
+/// The Constant Kernel.
+///
+/// k(x,y) = c
+///
+/// Where c is a constant.
+/// On its own, this kernel only models a constant offset but, multiplied with another kernel
+/// (such as `KernelArith(Constant::default()) * KernelArith(SquaredExp::default())`), it acts as an amplitude.
+/// Added to another kernel, it acts as a learnable bias: the offset of the data is then fitted with the kernel
+/// (and predictions far away from the data go back to this offset) rather than with the prior.
+///
+/// The resulting covariance matrix is singular and relies on the noise (or the `cholesky_epsilon`) to be positive definite.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Constant
+{
+    /// The value of the kernel.
+    pub c: f64
+}
+
+impl Constant
+{
+    /// Constructs a new Constant Kernel.
+    pub fn new(c: f64) -> Constant
+    {
+        Constant { c }
+    }
+}
+
+/// The default Constant Kernel.
+///
+/// The defaults are:
+/// - c = 1
+impl Default for Constant
+{
+    fn default() -> Constant
+    {
+        Constant { c: 1f64 }
+    }
+}
+
+impl Kernel for Constant
+{
+    fn nb_parameters(&self) -> usize
+    {
+        1
+    }
+
+    fn is_scalable(&self) -> bool
+    {
+        true
+    }
+
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            _x1: &SRowVector<S1>,
+                                                                            _x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        self.c.abs()
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              _x1: &SRowVector<S1>,
+                                                                              _x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        vec![self.c.signum()]
+    }
+
+    fn rescale(&mut self, scale: f64)
+    {
+        self.c *= scale;
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        vec![self.c]
+    }
+
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        self.c = parameters[0];
+    }
+}
+
+//-----------------------------------------------
+
+/// The Brownian Motion Kernel.
+///
+/// k(x,y) = σ² Π_d min(x_d, y_d)
+///
+/// Where σ² is the variance (per unit of input).
+/// In one dimension, this is the covariance of a brownian motion (a continuous random walk) starting at zero in zero:
+/// the process is null at the origin and its variance grows linearly with the input.
+/// In higher dimensions, it is the covariance of a brownian sheet.
+///
+/// This kernel is only defined for non-negative inputs and panics when given a negative input,
+/// the fallible methods of the gaussian process (such as `try_new`) return a `FriedrichError::InvalidInput` instead.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Brownian
+{
+    /// The variance of the kernel.
+    pub variance: f64
+}
+
+impl Brownian
+{
+    /// Constructs a new brownian motion kernel.
+    pub fn new(variance: f64) -> Brownian
+    {
+        Brownian { variance }
+    }
+
+    /// Computes the product of the componentwise minimums of the inputs.
+    fn min_product<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(x1: &SRowVector<S1>,
+                                                                                 x2: &SRowVector<S2>)
+                                                                                 -> f64
+    {
+        x1.iter()
+          .zip(x2.iter())
+          .map(|(&a, &b)| {
+              assert!(a >= 0. && b >= 0.,
+                      "Brownian: the kernel is only defined for non-negative inputs, got {} and {}.",
+                      a,
+                      b);
+              a.min(b)
+          })
+          .product()
+    }
+}
+
+/// The default Brownian Motion Kernel.
+///
+/// The defaults are:
+/// - variance = 1
+impl Default for Brownian
+{
+    fn default() -> Brownian
+    {
+        Brownian { variance: 1f64 }
+    }
+}
+
+impl Kernel for Brownian
+{
+    fn nb_parameters(&self) -> usize
+    {
+        1
+    }
+
+    fn is_scalable(&self) -> bool
+    {
+        true
+    }
+
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            x1: &SRowVector<S1>,
+                                                                            x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        self.variance.abs() * Self::min_product(x1, x2)
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              x1: &SRowVector<S1>,
+                                                                              x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        vec![self.variance.signum() * Self::min_product(x1, x2)]
+    }
+
+    fn rescale(&mut self, scale: f64)
+    {
+        self.variance *= scale;
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        vec![self.variance]
+    }
+
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        self.variance = parameters[0];
+    }
+
+    /// Rejects negative inputs.
+    fn validate_inputs<S: Storage<f64, Dynamic, Dynamic>>(&self, inputs: &SMatrix<S>) -> Result<(), FriedrichError>
+    {
+        for (row, x) in inputs.row_iter().enumerate()
+        {
+            if let Some(&value) = x.iter().find(|&&value| value < 0.)
+            {
+                return Err(FriedrichError::InvalidInput { kernel: "Brownian", row, value });
+            }
+        }
+        Ok(())
+    }
+}
+
+//-----------------------------------------------
+
+/// The Hamming Kernel.
+///
+/// k(x,y) = A exp(-θ h(x,y))
+///
+/// Where A is the amplitude, θ the decay rate and h(x,y) the Hamming distance between the inputs
+/// (the number of coordinates on which they differ).
+/// This kernel is meant for categorical inputs (encoded as numbers), for which Euclidean distances are meaningless:
+/// two inputs are either in the same category, along a given dimension, or not.
+/// Wrap it in a `Masked` kernel to use it on the categorical columns only,
+/// combined with another kernel on the continuous columns.
+///
+/// The parameters are ordered as `[θ, A]`.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Hamming
+{
+    /// The decay rate of the kernel, by mismatching coordinate.
+    pub theta: f64,
+    /// The amplitude of the kernel.
+    pub ampl: f64
+}
+
+impl Hamming
+{
+    /// Constructs a new Hamming kernel.
+    pub fn new(theta: f64, ampl: f64) -> Hamming
+    {
+        Hamming { theta, ampl }
+    }
+
+    /// Counts the number of coordinates on which the inputs differ.
+    fn nb_mismatches<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(x1: &SRowVector<S1>,
+                                                                                   x2: &SRowVector<S2>)
+                                                                                   -> f64
+    {
+        x1.iter().zip(x2.iter()).filter(|(a, b)| a != b).count() as f64
+    }
+}
+
+/// The default Hamming Kernel.
+///
+/// The defaults are:
+/// - theta = 1
+/// - ampl = 1
+impl Default for Hamming
+{
+    fn default() -> Hamming
+    {
+        Hamming { theta: 1f64, ampl: 1f64 }
+    }
+}
+
+impl Kernel for Hamming
+{
+    fn nb_parameters(&self) -> usize
+    {
+        2
+    }
+
+    fn is_scalable(&self) -> bool
+    {
+        true
+    }
+
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            x1: &SRowVector<S1>,
+                                                                            x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        let theta = self.theta.abs();
+        // Computes kernel.
+        ampl * (-theta * Self::nb_mismatches(x1, x2)).exp()
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              x1: &SRowVector<S1>,
+                                                                              x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        // Sanitize parameters.
+        let ampl = self.ampl.abs();
+        let theta = self.theta.abs();
+        // Compute gradients.
+        let nb_mismatches = Self::nb_mismatches(x1, x2);
+        let exponential = (-theta * nb_mismatches).exp();
+        let grad_theta = -self.theta.signum() * ampl * nb_mismatches * exponential;
+        let grad_ampl = self.ampl.signum() * exponential;
+        vec![grad_theta, grad_ampl]
+    }
+
+    fn rescale(&mut self, scale: f64)
+    {
+        self.ampl *= scale;
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        vec![self.theta, self.ampl]
+    }
+
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        self.theta = parameters[0];
+        self.ampl = parameters[1];
+    }
+
+    fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
+                                                                                        _training_inputs: &SMatrix<SM>,
+                                                                                        training_outputs: &SVector<SV>)
+    {
+        self.ampl = fit_amplitude_var(training_outputs);
+    }
+}
+
+//-----------------------------------------------
+
+/// The Spectral Mixture Kernel.
+///
+/// k(x,y) = Σ_q w_q exp(-2π² v_q ||x-y||²) cos(2π μ_q ||x-y||)
+///
+/// Where, for each of the Q components, w_q is the weight, μ_q the mean frequency and v_q the variance of the frequency.
+/// This kernel models the spectral density of the process as a mixture of Q gaussians which lets it approximate any
+/// stationary kernel given enough components (see [Gaussian Process Kernels for Pattern Discovery and Extrapolation](https://arxiv.org/abs/1302.4245)).
+///
+/// The parameters are ordered as `[w_0, μ_0, v_0, ..., w_(Q-1), μ_(Q-1), v_(Q-1)]`.
+/// The kernel is designed for one dimensional inputs: in higher dimensions it is a function of the euclidean distance
+/// which is not guaranteed to produce a positive definite covariance matrix.
+///
+/// The likelihood of this kernel is very multimodal, it is recommended to initialize the mean frequencies over the range
+/// of frequencies of interest (see `with_frequency_range`) and to use random restarts when fitting it.
+/// On one dimensional inputs, `heuristic_fit` (called by the builder before fitting the kernel)
+/// places the components on the main peaks of the empirical spectral density of the outputs.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct SpectralMixture
+{
+    /// The weights of the components.
+    pub weights: Vec<f64>,
+    /// The mean frequencies of the components.
+    pub means: Vec<f64>,
+    /// The variances of the frequencies of the components.
+    pub variances: Vec<f64>
+}
+
+impl SpectralMixture
+{
+    /// Constructs a new spectral mixture kernel with one component per element of the vectors.
+    pub fn new(weights: Vec<f64>, means: Vec<f64>, variances: Vec<f64>) -> SpectralMixture
+    {
+        assert!(!weights.is_empty(), "SpectralMixture: at least one component is needed.");
+        assert!(weights.len() == means.len() && weights.len() == variances.len(),
+                "SpectralMixture: the weights, means and variances should have the same length.");
+        SpectralMixture { weights, means, variances }
+    }
+
+    /// Constructs a new spectral mixture kernel with `nb_components` components
+    /// whose mean frequencies are evenly spread over `[min_frequency, max_frequency]`.
+    ///
+    /// The components have equal weights summing to one
+    /// and a standard deviation of a quarter of the spacing between two mean frequencies.
+    pub fn with_frequency_range(nb_components: usize, min_frequency: f64, max_frequency: f64) -> SpectralMixture
+    {
+        assert!(nb_components > 0, "SpectralMixture: at least one component is needed.");
+        let spacing = (max_frequency - min_frequency) / (nb_components as f64);
+        let weights = vec![1. / (nb_components as f64); nb_components];
+        let means = (0..nb_components).map(|q| min_frequency + (q as f64 + 0.5) * spacing).collect();
+        let variances = vec![(spacing / 4.).powi(2).max(f64::EPSILON); nb_components];
+        SpectralMixture::new(weights, means, variances)
+    }
+
+    /// Returns the number of components of the mixture.
+    pub fn nb_components(&self) -> usize
+    {
+        self.weights.len()
+    }
+}
+
+/// The default Spectral Mixture Kernel.
+///
+/// The defaults are:
+/// - a single component
+/// - weights = [1]
+/// - means = [0.5]
+/// - variances = [0.0625]
+impl Default for SpectralMixture
+{
+    fn default() -> SpectralMixture
+    {
+        SpectralMixture::with_frequency_range(1, 0., 1.)
+    }
+}
+
+impl Kernel for SpectralMixture
+{
+    fn nb_parameters(&self) -> usize
+    {
+        3 * self.nb_components()
+    }
+
+    fn is_scalable(&self) -> bool
+    {
+        true
+    }
+
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            x1: &SRowVector<S1>,
+                                                                            x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        let two_pi = 2. * std::f64::consts::PI;
+        let distance = (x1 - x2).norm();
+        (0..self.nb_components()).map(|q| {
+                                     // Sanitize parameters.
+                                     let w = self.weights[q].abs();
+                                     let v = self.variances[q].abs();
+                                     let exponential = (-0.5 * (two_pi * distance).powi(2) * v).exp();
+                                     w * exponential * (two_pi * distance * self.means[q]).cos()
+                                 })
+                                 .sum()
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              x1: &SRowVector<S1>,
+                                                                              x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        let two_pi = 2. * std::f64::consts::PI;
+        let distance = (x1 - x2).norm();
+        let mut gradients = Vec::with_capacity(self.nb_parameters());
+        for q in 0..self.nb_components()
+        {
+            // Sanitize parameters.
+            let w = self.weights[q].abs();
+            let v = self.variances[q].abs();
+            // Compute gradients.
+            let exponential_exponent = -0.5 * (two_pi * distance).powi(2);
+            let exponential = (exponential_exponent * v).exp();
+            let angle = two_pi * distance * self.means[q];
+            let grad_weight = self.weights[q].signum() * exponential * angle.cos();
+            let grad_mean = -w * exponential * angle.sin() * two_pi * distance;
+            let grad_variance = self.variances[q].signum() * w * exponential * angle.cos() * exponential_exponent;
+            gradients.push(grad_weight);
+            gradients.push(grad_mean);
+            gradients.push(grad_variance);
+        }
+        gradients
+    }
+
+    fn rescale(&mut self, scale: f64)
+    {
+        self.weights.iter_mut().for_each(|w| *w *= scale);
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        (0..self.nb_components()).flat_map(|q| [self.weights[q], self.means[q], self.variances[q]]).collect()
+    }
+
+    fn set_parameters(&mut self, parameters: &[f64])
+    {
+        for q in 0..self.nb_components()
+        {
+            self.weights[q] = parameters[3 * q];
+            self.means[q] = parameters[3 * q + 1];
+            self.variances[q] = parameters[3 * q + 2];
+        }
+    }
+
+    /// The weights and variances are used through their magnitude,
+    /// the variances being kept away from zero where the gradient of a component vanishes.
+    fn parameter_bounds(&self) -> Vec<(f64, f64)>
+    {
+        (0..self.nb_components()).flat_map(|_| [UNBOUNDED, UNBOUNDED, (f64::EPSILON, f64::INFINITY)]).collect()
+    }
+
+    /// On one dimensional inputs, places the components on the highest peaks of the empirical spectral density
+    /// of the outputs, the variance of the outputs being split between the components in proportion to their peaks.
+    ///
+    /// Otherwise (or if there are fewer peaks than components),
+    /// splits the variance of the outputs evenly between the components, keeping their frequencies.
+    fn heuristic_fit<SM: Storage<f64, Dynamic, Dynamic>, SV: Storage<f64, Dynamic, U1>>(&mut self,
+                                                                                        training_inputs: &SMatrix<SM>,
+                                                                                        training_outputs: &SVector<SV>)
+    {
+        let variance = fit_amplitude_var(training_outputs);
+        let weight = variance / (self.nb_components() as f64);
+        self.weights.iter_mut().for_each(|w| *w = weight);
+        if training_inputs.ncols() != 1
+        {
+            return;
+        }
+
+        let inputs: Vec<f64> = training_inputs.column(0).iter().copied().collect();
+        let outputs: Vec<f64> = training_outputs.iter().copied().collect();
+        let (frequencies, powers) = empirical_spectral_density(&inputs, &outputs);
+        // local maxima of the spectral density, highest first
+        let mut peaks: Vec<usize> = (1..powers.len().saturating_sub(1)).filter(|&i| {
+                                                                          powers[i] > powers[i - 1]
+                                                                          && powers[i] >= powers[i + 1]
+                                                                      })
+                                                                      .collect();
+        peaks.sort_unstable_by(|&i, &j| powers[j].total_cmp(&powers[i]));
+        peaks.truncate(self.nb_components());
+        if peaks.len() < self.nb_components()
+        {
+            return;
+        }
+
+        // the width of a peak is of the order of the frequency resolution
+        let resolution = frequencies[1] - frequencies[0];
+        let total_power: f64 = peaks.iter().map(|&i| powers[i]).sum();
+        for (q, &i) in peaks.iter().enumerate()
+        {
+            self.weights[q] = variance * powers[i] / total_power;
+            self.means[q] = frequencies[i];
+            self.variances[q] = resolution * resolution;
+        }
+    }
+}
+
+/// Computes the periodogram of (possibly irregularly sampled) one dimensional data: `|Σ_i y_i exp(-2iπ f x_i)|² / n`
+/// for the centered outputs `y`, on a grid of frequencies.
+///
+/// The grid goes from zero to the Nyquist frequency of the average sampling interval
+/// with a step of a quarter of the frequency resolution `1/range(x)`.
+/// Returns the frequencies and the associated powers.
+fn empirical_spectral_density(inputs: &[f64], outputs: &[f64]) -> (Vec<f64>, Vec<f64>)
+{
+    let nb_samples = inputs.len();
+    let min = inputs.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = inputs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+    if nb_samples < 2 || range <= 0.
+    {
+        return (vec![0.], vec![0.]);
+    }
+    let mean = outputs.iter().sum::<f64>() / (nb_samples as f64);
+
+    let step = 0.25 / range;
+    let nyquist = 0.5 * ((nb_samples - 1) as f64) / range;
+    let nb_frequencies = (nyquist / step).ceil() as usize + 1;
+    let frequencies: Vec<f64> = (0..nb_frequencies).map(|k| k as f64 * step).collect();
+    let powers = frequencies.iter()
+                            .map(|&frequency| {
+                                let angular_frequency = 2. * std::f64::consts::PI * frequency;
+                                let (mut real, mut imaginary) = (0., 0.);
+                                for (&x, &y) in inputs.iter().zip(outputs)
+                                {
+                                    let (sin, cos) = (angular_frequency * x).sin_cos();
+                                    real += (y - mean) * cos;
+                                    imaginary -= (y - mean) * sin;
+                                }
+                                (real * real + imaginary * imaginary) / (nb_samples as f64)
+                            })
+                            .collect();
+    (frequencies, powers)
+}
+
+//-----------------------------------------------
+
+/// The Precomputed Kernel.
+///
+/// k(i,j) = G_ij
+///
+/// Where G is a user-provided Gram matrix (the similarities between all pairs of points, computed externally)
+/// and the inputs are indices (stored as `f64` in a single column) into this matrix.
+/// This lets you do regression on objects, such as strings or graphs, for which you already have a similarity measure.
+///
+/// ```rust
+/// # use friedrich::kernel::*;
+/// # use nalgebra::DMatrix;
+/// let gram = DMatrix::from_row_slice(3, 3, &[1.0, 0.5, 0.1,
+///                                            0.5, 1.0, 0.3,
+///                                            0.1, 0.3, 1.0]);
+/// let kernel = Precomputed::new(gram);
+/// // The inputs are the indices of the points in the Gram matrix.
+/// let inputs = vec![vec![0.], vec![2.]];
+/// ```
+///
+/// The kernel has no parameters, fitting it only fits the noise.
+/// Points outside of the Gram matrix can be predicted
+/// with the `predict_with_similarities` method of the gaussian process,
+/// given their similarities with the points of the Gram matrix
+/// (the prior then has to be constant, see `ConstantValuedPrior`).
+/// The kernel panics with an explicit message if an input is not a valid index.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Precomputed
+{
+    /// The Gram matrix.
+    gram: DMatrix<f64>
+}
+
+impl Precomputed
+{
+    /// Constructs a new precomputed kernel from a symmetric Gram matrix.
+    ///
+    /// Panics if the matrix is not square or not symmetric
+    /// (up to a relative difference of `1e-10` between an element and its transposed element).
+    pub fn new(gram: DMatrix<f64>) -> Precomputed
+    {
+        match Precomputed::try_new(gram)
+        {
+            Ok(kernel) => kernel,
+            Err(error) => panic!("Precomputed: {}.", error)
+        }
+    }
+
+    /// Fallible version of `new`, returns an error rather than panicking if the matrix is not square or not symmetric.
+    pub fn try_new(gram: DMatrix<f64>) -> Result<Precomputed, FriedrichError>
+    {
+        if gram.nrows() != gram.ncols()
+        {
+            return Err(FriedrichError::DimensionMismatch { expected: gram.nrows(), found: gram.ncols() });
+        }
+        for row in 0..gram.nrows()
+        {
+            for column in 0..row
+            {
+                let (a, b) = (gram[(row, column)], gram[(column, row)]);
+                if (a - b).abs() > 1e-10 * a.abs().max(b.abs()) || a.is_nan() || b.is_nan()
+                {
+                    return Err(FriedrichError::NonSymmetricMatrix { row, column });
+                }
+            }
+        }
+        Ok(Precomputed { gram })
+    }
+
+    /// Returns the Gram matrix.
+    pub fn gram(&self) -> &DMatrix<f64>
+    {
+        &self.gram
+    }
+
+    /// Returns the number of points in the Gram matrix.
+    pub fn nb_points(&self) -> usize
+    {
+        self.gram.nrows()
+    }
+
+    /// Converts an input into an index in the Gram matrix, panics with an explicit message if it is not a valid index.
+    fn index<S: Storage<f64, U1, Dynamic>>(&self, x: &SRowVector<S>) -> usize
+    {
+        assert_eq!(x.ncols(), 1, "Precomputed: the inputs should be a single column of indices.");
+        let index = x[0];
+        if index < 0. || index.fract() != 0. || index >= self.nb_points() as f64
+        {
+            panic!("Precomputed: {} is not a valid index for a Gram matrix of {} points.", index, self.nb_points());
+        }
+        index as usize
+    }
+}
+
+/// The default precomputed kernel.
+///
+/// The defaults are:
+/// - an empty Gram matrix
+impl Default for Precomputed
+{
+    fn default() -> Precomputed
+    {
+        Precomputed { gram: DMatrix::zeros(0, 0) }
+    }
+}
+
+impl Kernel for Precomputed
+{
+    fn nb_parameters(&self) -> usize
+    {
+        0
+    }
+
+    fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                            x1: &SRowVector<S1>,
+                                                                            x2: &SRowVector<S2>)
+                                                                            -> f64
+    {
+        self.gram[(self.index(x1), self.index(x2))]
+    }
+
+    fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                              _x1: &SRowVector<S1>,
+                                                                              _x2: &SRowVector<S2>)
+                                                                              -> Vec<f64>
+    {
+        vec![]
+    }
+
+    fn get_parameters(&self) -> Vec<f64>
+    {
+        vec![]
+    }
+
+    fn set_parameters(&mut self, _parameters: &[f64]) {}
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::algebra::{make_cholesky_cov_matrix, make_covariance_matrix, make_gradient_covariance_matrices};
+    use nalgebra::{DMatrix, DVector};
+
+    /// A small set of distinct two-dimensional inputs.
+    fn inputs() -> DMatrix<f64>
+    {
+        DMatrix::from_row_slice(5, 2, &[0.0, 0.0, 0.3, -0.2, 1.1, 0.4, -0.7, 1.5, 2.0, -1.0])
+    }
+
+    /// Compares the analytic gradient of a kernel with a central finite difference approximation.
+    fn assert_gradient_matches_finite_differences<K: Kernel>(kernel: &K)
+    {
+        let inputs = inputs();
+        for x1 in inputs.row_iter()
+        {
+            for x2 in inputs.row_iter()
+            {
+                let gradient = kernel.gradient(&x1, &x2);
+                assert_eq!(gradient.len(), kernel.nb_parameters());
+                let numeric_gradient = finite_difference_gradient(kernel, &x1, &x2);
+                for (p, (&analytic, numeric)) in gradient.iter().zip(numeric_gradient).enumerate()
+                {
+                    assert!((analytic - numeric).abs() <= 1e-5 * (1. + numeric.abs()),
+                            "parameter {}: analytic gradient {} but finite difference {}",
+                            p,
+                            analytic,
+                            numeric);
+                }
+            }
+        }
+    }
+
+    /// Checks that the covariance matrix of distinct inputs admits a Cholesky decomposition.
+    fn assert_positive_definite<K: Kernel>(kernel: &K)
+    {
+        let inputs = inputs();
+        let covariance = make_covariance_matrix(&inputs, &inputs, kernel);
+        assert!(covariance.cholesky().is_some(), "covariance matrix is not positive definite");
+    }
+
+    #[test]
+    fn matern32_is_positive_definite()
+    {
+        assert_positive_definite(&Matern32::new(0.7, 1.3));
+        assert_positive_definite(&Matern32::new(3., 0.2));
+    }
+
+    #[test]
+    fn matern32_gradient()
+    {
+        assert_gradient_matches_finite_differences(&Matern32::new(0.7, 1.3));
+        assert_gradient_matches_finite_differences(&Matern32::new(-1.5, 2.));
+    }
+
+    #[test]
+    fn matern52_is_positive_definite()
+    {
+        assert_positive_definite(&Matern52::new(0.7, 1.3));
+        assert_positive_definite(&Matern52::new(3., 0.2));
+    }
+
+    #[test]
+    fn matern52_gradient()
+    {
+        assert_gradient_matches_finite_differences(&Matern52::new(0.7, 1.3));
+        assert_gradient_matches_finite_differences(&Matern52::new(-1.5, 2.));
+    }
+
+    #[test]
+    fn matern52_gradient_at_coincident_points()
+    {
+        let kernel = Matern52::new(0.7, 1.3);
+        let x = DMatrix::from_row_slice(1, 2, &[0.5, -0.5]);
+        let gradient = kernel.gradient(&x.row(0), &x.row(0));
+        assert_eq!(gradient, vec![0., 1.]);
+    }
+
+    #[test]
+    fn rational_quadratic_gradient()
+    {
+        assert_gradient_matches_finite_differences(&RationalQuadratic::new(0.5, 0.7, 1.3));
+        assert_gradient_matches_finite_differences(&RationalQuadratic::new(3., -1.5, 2.));
+    }
+
+    #[test]
+    fn rational_quadratic_alpha_is_bounded()
+    {
+        let mut kernel = RationalQuadratic::default();
+        assert_eq!(kernel.parameter_bounds()[0], (f64::EPSILON, f64::INFINITY));
+        // a negative alpha is stored as is and evaluated through its magnitude
+        kernel.set_parameters(&[-1.5, 1., 1.]);
+        assert_eq!(kernel.alpha, -1.5);
+        let inputs = inputs();
+        let positive = RationalQuadratic::new(1.5, 1., 1.);
+        assert_eq!(kernel.kernel(&inputs.row(1), &inputs.row(3)), positive.kernel(&inputs.row(1), &inputs.row(3)));
+        assert_gradient_matches_finite_differences(&kernel);
+        assert_positive_definite(&kernel);
+    }
+
+    #[test]
+    fn rational_quadratic_converges_to_squared_exponential()
+    {
+        let rational_quadratic = RationalQuadratic::new(1e8, 0.7, 1.3);
+        let squared_exp = SquaredExp::new(0.7, 1.3);
+        let inputs = inputs();
+        for x1 in inputs.row_iter()
+        {
+            for x2 in inputs.row_iter()
+            {
+                assert!((rational_quadratic.kernel(&x1, &x2) - squared_exp.kernel(&x1, &x2)).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn exponential_gradient()
+    {
+        assert_gradient_matches_finite_differences(&Exponential::new(0.7, 1.3));
+        assert_gradient_matches_finite_differences(&Exponential::new(-1.5, 2.));
+    }
+
+    #[test]
+    fn exponential_gradient_at_coincident_points()
+    {
+        let kernel = Exponential::new(0.7, 1.3);
+        let x = DMatrix::from_row_slice(1, 2, &[0.5, -0.5]);
+        let gradient = kernel.gradient(&x.row(0), &x.row(0));
+        assert_eq!(gradient, vec![0., 1.]);
+    }
+
+    #[test]
+    fn periodic_is_positive_definite()
+    {
+        assert_positive_definite(&Periodic::new(0.7, 5., 1.3));
+    }
+
+    #[test]
+    fn periodic_gradient()
+    {
+        assert_gradient_matches_finite_differences(&Periodic::new(0.7, 1.1, 1.3));
+        assert_gradient_matches_finite_differences(&Periodic::new(-1.5, 0.8, 2.));
+    }
+
+    #[test]
+    fn periodic_period_is_bounded()
+    {
+        let mut kernel = Periodic::default();
+        assert_eq!(kernel.parameter_bounds()[1], (f64::EPSILON, f64::INFINITY));
+        // a negative period is stored as is and evaluated through its magnitude
+        kernel.set_parameters(&[1., -2., 1.]);
+        assert_eq!(kernel.period, -2.);
+        let inputs = inputs();
+        let positive = Periodic::new(1., 2., 1.);
+        assert_eq!(kernel.kernel(&inputs.row(1), &inputs.row(3)), positive.kernel(&inputs.row(1), &inputs.row(3)));
+    }
+
+    #[test]
+    fn kernel_sum_gradient()
+    {
+        let kernel = KernelArith(SquaredExp::new(0.7, 1.3)) + KernelArith(Matern52::new(2., 0.4));
+        assert_eq!(kernel.get_parameters(), vec![0.7, 1.3, 2., 0.4]);
+        assert_gradient_matches_finite_differences(&kernel);
+        assert_positive_definite(&kernel);
+    }
+
+    #[test]
+    fn kernel_sum_is_scalable_only_if_both_kernels_are()
+    {
+        let scalable = KernelArith(SquaredExp::default()) + KernelArith(Matern32::default());
+        assert!(scalable.is_scalable());
+        let non_scalable = KernelArith(SquaredExp::default()) + KernelArith(Polynomial::default());
+        assert!(!non_scalable.is_scalable());
+
+        let mut kernel = scalable;
+        kernel.rescale(2.);
+        assert_eq!(kernel.k1.ampl, 2.);
+        assert_eq!(kernel.k2.ampl, 2.);
+    }
+
+    #[test]
+    fn kernel_product_gradient()
+    {
+        let kernel = KernelArith(SquaredExp::new(0.7, 1.3)) * KernelArith(Matern52::new(2., 0.4));
+        assert_eq!(kernel.get_parameters(), vec![0.7, 1.3, 2., 0.4]);
+        assert_gradient_matches_finite_differences(&kernel);
+        assert_positive_definite(&kernel);
+    }
+
+    #[test]
+    fn nested_kernel_product_parameters_round_trip()
+    {
+        let product = KernelArith(SquaredExp::new(0.7, 1.3)) * KernelArith(Periodic::new(1., 2., 0.5));
+        let mut kernel = KernelArith(product) * KernelArith(Matern32::new(2., 0.4));
+        let parameters = vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7];
+        kernel.set_parameters(&parameters);
+        assert_eq!(kernel.get_parameters(), parameters);
+        assert_eq!(kernel.k1.k2.period, 0.4);
+        assert_gradient_matches_finite_differences(&kernel);
+    }
+
+    #[test]
+    fn products_and_sums_compose()
+    {
+        let linear = Linear::new(0.5, 0.3, DVector::from_element(2, 0.1));
+        let sum = KernelArith(linear) + KernelArith(Periodic::new(1., 2., 0.5));
+        let product_of_sum = KernelArith(sum.clone()) * KernelArith(SquaredExp::new(0.7, 1.3));
+        assert_eq!(product_of_sum.nb_parameters(), 7);
+        assert_gradient_matches_finite_differences(&product_of_sum);
+        assert_positive_definite(&product_of_sum);
+
+        let inputs = inputs();
+        let (x1, x2) = (inputs.row(1), inputs.row(4));
+        let expected = sum.kernel(&x1, &x2) * SquaredExp::new(0.7, 1.3).kernel(&x1, &x2);
+        assert!((product_of_sum.kernel(&x1, &x2) - expected).abs() < 1e-12);
+
+        let scaled_matern = KernelArith(Constant::new(2.)) * KernelArith(Matern32::new(0.6, 1.));
+        let sum_of_products = KernelArith(product_of_sum) + KernelArith(scaled_matern);
+        assert_eq!(sum_of_products.get_parameters().len(), sum_of_products.nb_parameters());
+        assert_gradient_matches_finite_differences(&sum_of_products);
+    }
+
+    #[test]
+    fn scaled_kernel_makes_any_kernel_scalable()
+    {
+        let polynomial = Polynomial::new(0.5, 1., 2);
+        assert!(!polynomial.is_scalable());
+        let mut kernel = Scaled::new(polynomial, 1.5);
+        assert!(kernel.is_scalable());
+        assert_eq!(kernel.get_parameters(), vec![0.5, 1., 1.5]);
+        assert_gradient_matches_finite_differences(&kernel);
+
+        let inputs = inputs();
+        let before = kernel.kernel(&inputs.row(0), &inputs.row(3));
+        kernel.rescale(2.);
+        assert!((kernel.kernel(&inputs.row(0), &inputs.row(3)) - 2. * before).abs() < 1e-12);
+
+        // The amplitude gradient matrix is the unscaled covariance matrix.
+        let gradients = make_gradient_covariance_matrices(&inputs, &kernel);
+        let unscaled = make_covariance_matrix(&inputs, &inputs, &polynomial);
+        assert!((&gradients[2] - unscaled).amax() < 1e-12);
+    }
+
+    #[test]
+    fn ornstein_uhlenbeck_handles_vanishing_length_scale()
+    {
+        let kernel = OrnsteinUhlenbeck::new(0., 1.);
+        let inputs = inputs();
+        for x1 in inputs.row_iter()
+        {
+            for x2 in inputs.row_iter()
+            {
+                assert!(kernel.kernel(&x1, &x2).is_finite());
+                assert!(kernel.gradient(&x1, &x2).iter().all(|g| g.is_finite()));
+            }
+        }
+    }
+
+    #[test]
+    fn squared_exp_ard_gradient()
+    {
+        assert_gradient_matches_finite_differences(&SquaredExpArd::new(vec![0.7, 2.], 1.3));
+        assert_gradient_matches_finite_differences(&SquaredExpArd::new(vec![-1.5], 2.));
+        assert_positive_definite(&SquaredExpArd::new(vec![0.7, 2.], 1.3));
+    }
+
+    #[test]
+    fn squared_exp_ard_with_equal_length_scales_is_squared_exp()
+    {
+        let ard = SquaredExpArd::new(vec![0.7, 0.7], 1.3);
+        let squared_exp = SquaredExp::new(0.7, 1.3);
+        let inputs = inputs();
+        for x1 in inputs.row_iter()
+        {
+            for x2 in inputs.row_iter()
+            {
+                assert!((ard.kernel(&x1, &x2) - squared_exp.kernel(&x1, &x2)).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn squared_exp_ard_heuristic_fit_sets_one_length_scale_per_dimension()
+    {
+        let mut kernel = SquaredExpArd::default();
+        let inputs = inputs();
+        let outputs = nalgebra::DVector::from_column_slice(&[1., 2., 3., 4., 5.]);
+        kernel.heuristic_fit(&inputs, &outputs);
+        assert_eq!(kernel.length_scales().len(), 2);
+        assert_eq!(kernel.nb_parameters(), 3);
+    }
+
+    #[test]
+    fn polynomial_gradient()
+    {
+        assert_gradient_matches_finite_differences(&Polynomial::new(0.7, 1.3, 2));
+        assert_gradient_matches_finite_differences(&Polynomial::new(1.5, -0.5, 3));
+    }
+
+    #[test]
+    #[should_panic(expected = "Polynomial: the degree should be positive but is 0.")]
+    fn polynomial_rejects_a_null_degree()
+    {
+        Polynomial::new(1., 1., 0);
+    }
+
+    #[test]
+    fn polynomial_try_new_rejects_a_negative_degree()
+    {
+        assert_eq!(Polynomial::try_new(1., 1., -2).err(),
+                   Some(FriedrichError::InvalidParameter { name: "d", value: -2. }));
+        assert_eq!(Polynomial::try_new(1., 1., 3).map(|kernel| kernel.d), Ok(3));
+    }
+
+    #[test]
+    fn linear_gradient()
+    {
+        assert_gradient_matches_finite_differences(&Linear::new(0.7, 1.3, DVector::from_column_slice(&[0.2, -0.5])));
+        assert_gradient_matches_finite_differences(&Linear::new(-1.5, 0.4, DVector::zeros(0)));
+    }
+
+    #[test]
+    fn linear_offset_is_fixed_and_applied_per_dimension()
+    {
+        let kernel = Linear::new(0.7, 1.3, DVector::from_column_slice(&[0.2, -0.5]));
+        assert_eq!(kernel.get_parameters(), vec![0.7, 1.3]);
+        let inputs = inputs();
+        let (x1, x2) = (inputs.row(1), inputs.row(2));
+        let expected = 0.7 * 0.7 + 1.3 * 1.3 * ((x1[0] - 0.2) * (x2[0] - 0.2) + (x1[1] + 0.5) * (x2[1] + 0.5));
+        assert!((kernel.kernel(&x1, &x2) - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn linear_rescale_scales_the_kernel()
+    {
+        let mut kernel = Linear::new(0.7, 1.3, DVector::from_element(2, 0.2));
+        let inputs = inputs();
+        let before = kernel.kernel(&inputs.row(1), &inputs.row(2));
+        kernel.rescale(3.);
+        assert!((kernel.kernel(&inputs.row(1), &inputs.row(2)) - 3. * before).abs() < 1e-12);
+    }
+
+    #[test]
+    fn white_noise_only_contributes_on_identical_inputs()
+    {
+        let kernel = WhiteNoise::new(0.3);
+        let inputs = inputs();
+        assert_eq!(kernel.kernel(&inputs.row(1), &inputs.row(1)), 0.3);
+        assert_eq!(kernel.kernel(&inputs.row(1), &inputs.row(2)), 0.);
+
+        let gradients = make_gradient_covariance_matrices(&inputs, &kernel);
+        assert_eq!(gradients.len(), 1);
+        assert_eq!(gradients[0], DMatrix::identity(inputs.nrows(), inputs.nrows()));
+        assert_gradient_matches_finite_differences(&(KernelArith(SquaredExp::new(0.7, 1.3)) + KernelArith(kernel)));
+    }
+
+    #[test]
+    fn constant_acts_as_an_amplitude_in_a_product()
+    {
+        let kernel = KernelArith(Constant::new(2.5)) * KernelArith(SquaredExp::new(0.7, 1.));
+        let inputs = inputs();
+        let expected = 2.5 * SquaredExp::new(0.7, 1.).kernel(&inputs.row(0), &inputs.row(3));
+        assert!((kernel.kernel(&inputs.row(0), &inputs.row(3)) - expected).abs() < 1e-12);
+        assert_gradient_matches_finite_differences(&kernel);
+        assert_gradient_matches_finite_differences(&Constant::new(-0.4));
+    }
+
+    #[test]
+    fn constant_covariance_is_made_positive_definite_by_the_noise()
+    {
+        let inputs = inputs();
+        let kernel = Constant::new(3.);
+        let (cholesky, _) = make_cholesky_cov_matrix(&inputs, &kernel, 0.1, None, None);
+        assert!(cholesky.l().diagonal().iter().all(|d| d.is_finite() && *d > 0.));
+        let (cholesky, _) = make_cholesky_cov_matrix(&inputs, &kernel, 0., Some(1e-6), None);
+        assert!(cholesky.l().diagonal().iter().all(|d| d.is_finite() && *d > 0.));
+    }
+
+    #[test]
+    fn squared_exp_ard_with_dimension_has_one_parameter_per_dimension()
+    {
+        let inputs = inputs();
+        let kernel = SquaredExpArd::with_dimension(inputs.ncols());
+        assert_eq!(kernel.get_parameters(), vec![1., 1., 1.]);
+        assert_eq!(make_gradient_covariance_matrices(&inputs, &kernel).len(), inputs.ncols() + 1);
+        let isotropic = SquaredExp::new(1., 1.).kernel(&inputs.row(0), &inputs.row(2));
+        assert!((kernel.kernel(&inputs.row(0), &inputs.row(2)) - isotropic).abs() < 1e-12);
+    }
+
+    #[test]
+    fn spectral_mixture_gradient()
+    {
+        let kernel = SpectralMixture::new(vec![0.8, -1.2], vec![0.3, 1.1], vec![0.05, 0.2]);
+        assert_eq!(kernel.nb_parameters(), 6);
+        assert_gradient_matches_finite_differences(&kernel);
+        assert_gradient_matches_finite_differences(&SpectralMixture::with_frequency_range(3, 0.1, 2.));
+    }
+
+    #[test]
+    fn spectral_mixture_is_positive_definite_on_one_dimensional_inputs()
+    {
+        let inputs = DMatrix::from_column_slice(6, 1, &[0., 0.4, 1.1, 1.5, 2.3, 3.7]);
+        let kernel = SpectralMixture::with_frequency_range(3, 0.1, 2.);
+        let covariance = make_covariance_matrix(&inputs, &inputs, &kernel);
+        assert!(covariance.cholesky().is_some(), "covariance matrix is not positive definite");
+    }
+
+    #[test]
+    fn spectral_mixture_is_positive_definite_for_random_parameters()
+    {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+        let mut rng = StdRng::seed_from_u64(5);
+        let inputs = DMatrix::from_fn(8, 1, |i, _| i as f64 * 0.45 + 0.1 * (i as f64).sin());
+        for _ in 0..20
+        {
+            let nb_components = rng.gen_range(1..4);
+            let weights = (0..nb_components).map(|_| rng.gen_range(0.1..2.)).collect();
+            let means = (0..nb_components).map(|_| rng.gen_range(0. ..3.)).collect();
+            let variances = (0..nb_components).map(|_| rng.gen_range(0.01..1.)).collect();
+            let kernel = SpectralMixture::new(weights, means, variances);
+            let covariance = make_covariance_matrix(&inputs, &inputs, &kernel);
+            let eigenvalues = covariance.symmetric_eigenvalues();
+            assert!(eigenvalues.min() > -1e-10, "kernel {:?} has eigenvalue {}", kernel, eigenvalues.min());
+        }
+    }
+
+    #[test]
+    fn spectral_mixture_heuristic_fit_finds_the_frequencies_of_the_data()
+    {
+        // irregularly sampled sum of two sinusoids of frequencies 0.8 and 2
+        let inputs = DMatrix::from_fn(120, 1, |i, _| i as f64 * 0.1 + 0.03 * (i as f64 * 1.7).sin());
+        let outputs = inputs.column(0).map(|x| {
+                                           let two_pi = 2. * std::f64::consts::PI;
+                                           (two_pi * 0.8 * x).sin() + 0.5 * (two_pi * 2. * x).sin()
+                                       });
+        let mut kernel = SpectralMixture::with_frequency_range(2, 0., 1.);
+        kernel.heuristic_fit(&inputs, &outputs);
+
+        let resolution = 1. / (inputs.max() - inputs.min());
+        assert!((kernel.means[0] - 0.8).abs() < resolution, "means {:?}", kernel.means);
+        assert!((kernel.means[1] - 2.).abs() < resolution, "means {:?}", kernel.means);
+        // the strongest component gets most of the variance
+        assert!(kernel.weights[0] > 2. * kernel.weights[1], "weights {:?}", kernel.weights);
+        assert!((kernel.weights.iter().sum::<f64>() - outputs.variance()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn spectral_mixture_frequencies_are_spread_over_the_range()
+    {
+        let kernel = SpectralMixture::with_frequency_range(4, 0., 2.);
+        assert_eq!(kernel.nb_components(), 4);
+        assert_eq!(kernel.means, vec![0.25, 0.75, 1.25, 1.75]);
+        assert!((kernel.weights.iter().sum::<f64>() - 1.).abs() < 1e-12);
+        // With a single component and no variance, the kernel is a cosine of the given frequency.
+        let kernel = SpectralMixture::new(vec![1.], vec![0.5], vec![0.]);
+        let x1 = DMatrix::from_element(1, 1, 0.);
+        let x2 = DMatrix::from_element(1, 1, 1.);
+        assert!((kernel.kernel(&x1.row(0), &x2.row(0)) + 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn spectral_mixture_evaluates_the_parameters_it_stores()
+    {
+        let mut kernel = SpectralMixture::with_frequency_range(2, 0.1, 2.);
+        let parameters = vec![-0.7, 0.4, -1e-20, 1.3, 1.5, 0.2];
+        kernel.set_parameters(&parameters);
+        assert_eq!(kernel.get_parameters(), parameters);
+        // the negative weight and variance are evaluated through their magnitude
+        let mut positive = kernel.clone();
+        positive.set_parameters(&parameters.iter().map(|p| p.abs()).collect::<Vec<f64>>());
+        let inputs = DMatrix::from_column_slice(4, 1, &[0., 0.3, 1.2, 2.5]);
+        assert_eq!(make_covariance_matrix(&inputs, &inputs, &kernel),
+                   make_covariance_matrix(&inputs, &inputs, &positive));
+        let bounds = kernel.parameter_bounds();
+        assert_eq!(bounds.len(), kernel.nb_parameters());
+        assert_eq!(bounds[2], (f64::EPSILON, f64::INFINITY));
+    }
+
+    #[test]
+    fn cosine_gradient()
+    {
+        assert_gradient_matches_finite_differences(&Cosine::new(1.7, 0.8));
+        assert_gradient_matches_finite_differences(&Cosine::new(0.6, -1.3));
+    }
+
+    #[test]
+    fn cosine_period_is_bounded()
+    {
+        let mut kernel = Cosine::default();
+        assert_eq!(kernel.parameter_bounds()[0], (f64::EPSILON, f64::INFINITY));
+        // a negative period is stored as is and evaluated through its magnitude
+        kernel.set_parameters(&[-2., 1.]);
+        assert_eq!(kernel.period, -2.);
+        let inputs = inputs();
+        let positive = Cosine::new(2., 1.);
+        assert_eq!(kernel.kernel(&inputs.row(1), &inputs.row(3)), positive.kernel(&inputs.row(1), &inputs.row(3)));
+    }
+
+    #[test]
+    fn cosine_covariance_is_low_rank_but_factorizable_with_noise()
+    {
+        let inputs = DMatrix::from_column_slice(8, 1, &[0., 0.3, 0.9, 1.4, 2.2, 2.5, 3.1, 4.7]);
+        let kernel = Cosine::new(1.3, 2.);
+        // The covariance matrix is of rank two: all but two of its eigenvalues are null.
+        let eigenvalues = make_covariance_matrix(&inputs, &inputs, &kernel).symmetric_eigenvalues();
+        assert_eq!(eigenvalues.iter().filter(|e| e.abs() > 1e-9).count(), 2);
+        // A small noise, or the jitter, is enough to factorize it.
+        let (cholesky, _) = make_cholesky_cov_matrix(&inputs, &kernel, 1e-3, None, None);
+        assert!(cholesky.l().diagonal().iter().all(|d| d.is_finite() && *d > 0.));
+        let (cholesky, _) = make_cholesky_cov_matrix(&inputs, &kernel, 0., Some(1e-6), None);
+        assert!(cholesky.l().diagonal().iter().all(|d| d.is_finite() && *d > 0.));
+    }
+
+    #[test]
+    fn gamma_exponential_gradient()
+    {
+        assert_gradient_matches_finite_differences(&GammaExponential::new(0.7, 1.3, 1.2));
+        assert_gradient_matches_finite_differences(&GammaExponential::new(2., 0.5, -0.8));
+    }
+
+    #[test]
+    fn gamma_exponential_interpolates_between_exponential_and_squared_exponential()
+    {
+        let inputs = inputs();
+        let (x1, x2) = (inputs.row(0), inputs.row(3));
+        let exponential = Exponential::new(0.7, 1.3).kernel(&x1, &x2);
+        assert!((GammaExponential::new(0.7, 1., 1.3).kernel(&x1, &x2) - exponential).abs() < 1e-12);
+        let squared_exp = SquaredExp::new(0.7, 1.3).kernel(&x1, &x2);
+        let l = 0.7 * 2f64.sqrt();
+        assert!((GammaExponential::new(l, 2., 1.3).kernel(&x1, &x2) - squared_exp).abs() < 1e-12);
+        assert_positive_definite(&GammaExponential::new(0.7, 1.5, 1.3));
+    }
+
+    #[test]
+    fn gamma_exponential_exponent_is_bounded()
+    {
+        let kernel = GammaExponential::default();
+        assert_eq!(kernel.parameter_bounds()[1], (f64::EPSILON, 2.));
+        // a negative exponent is evaluated through its magnitude
+        let inputs = inputs();
+        let negative = GammaExponential { gamma: -1.5, ..kernel };
+        assert_eq!(negative.kernel(&inputs.row(1), &inputs.row(3)), kernel.kernel(&inputs.row(1), &inputs.row(3)));
+        assert_gradient_matches_finite_differences(&negative);
+    }
+
+    #[test]
+    #[should_panic(expected = "GammaExponential: the exponent should be in (0, 2]")]
+    fn gamma_exponential_rejects_an_exponent_above_two()
+    {
+        GammaExponential::new(1., 2.5, 1.);
+    }
+
+    #[test]
+    fn piecewise_polynomial_gradient()
+    {
+        // The radius is chosen such that some pairs of inputs are outside of the support.
+        assert_gradient_matches_finite_differences(&PiecewisePolynomial::new(1.3, 0.8));
+        assert_gradient_matches_finite_differences(&PiecewisePolynomial::new(4., -1.2));
+        assert_positive_definite(&PiecewisePolynomial::new(1.3, 0.8));
+    }
+
+    #[test]
+    fn piecewise_polynomial_is_null_outside_of_its_support()
+    {
+        let kernel = PiecewisePolynomial::new(1.3, 0.8);
+        let inputs = inputs();
+        let mut nb_outside = 0;
+        for x1 in inputs.row_iter()
+        {
+            for x2 in inputs.row_iter()
+            {
+                if (x1 - x2).norm() >= 1.3
+                {
+                    nb_outside += 1;
+                    assert_eq!(kernel.kernel(&x1, &x2), 0.);
+                    assert!(kernel.gradient(&x1, &x2).iter().all(|&g| g == 0.));
+                }
+                else
+                {
+                    assert!(kernel.kernel(&x1, &x2) > 0.);
+                }
+            }
+        }
+        assert!(nb_outside > 0);
+        // The covariance matrix is sparse.
+        let covariance = make_covariance_matrix(&inputs, &inputs, &kernel);
+        assert_eq!(covariance.iter().filter(|&&c| c == 0.).count(), nb_outside);
+    }
+
+    #[test]
+    fn arcsine_gradient()
+    {
+        assert_gradient_matches_finite_differences(&ArcSine::new(0.7, 1.3));
+        assert_gradient_matches_finite_differences(&ArcSine::new(-2., 0.4));
+        assert_positive_definite(&ArcSine::new(0.7, 1.3));
+    }
+
+    #[test]
+    fn arcsine_stays_finite_for_large_variances()
+    {
+        // The ratio goes to one on the diagonal when the variances grow.
+        let kernel = ArcSine::new(1e12, 1e12);
+        let inputs = inputs();
+        for x1 in inputs.row_iter()
+        {
+            for x2 in inputs.row_iter()
+            {
+                let k = kernel.kernel(&x1, &x2);
+                assert!(k.is_finite() && k.abs() <= 1.);
+                assert!(kernel.gradient(&x1, &x2).iter().all(|g| g.is_finite()));
+            }
+        }
+        assert!(make_gradient_covariance_matrices(&inputs, &kernel).iter().all(|m| m.iter().all(|g| g.is_finite())));
+    }
+
+    #[test]
+    fn brownian_gradient()
+    {
+        let inputs = inputs().abs();
+        let kernel = Brownian::new(0.7);
+        for x1 in inputs.row_iter()
+        {
+            for x2 in inputs.row_iter()
+            {
+                let expected = (x1[0].min(x2[0])) * (x1[1].min(x2[1]));
+                assert!((kernel.kernel(&x1, &x2) - 0.7 * expected).abs() < 1e-12);
+                assert_eq!(kernel.gradient(&x1, &x2), vec![expected]);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Brownian: the kernel is only defined for non-negative inputs")]
+    fn brownian_rejects_negative_inputs()
+    {
+        let inputs = inputs();
+        Brownian::default().kernel(&inputs.row(0), &inputs.row(1));
+    }
+
+    #[test]
+    fn brownian_validates_its_inputs()
+    {
+        let inputs = inputs();
+        let error = Brownian::default().validate_inputs(&inputs);
+        assert!(matches!(error, Err(FriedrichError::InvalidInput { kernel: "Brownian", .. })));
+        // combinators forward the validation to the kernels they contain
+        let sum = KernelSum { k1: SquaredExp::default(), k2: Brownian::default() };
+        assert_eq!(sum.validate_inputs(&inputs), error);
+        let positive_inputs = inputs.map(f64::abs);
+        assert_eq!(Brownian::default().validate_inputs(&positive_inputs), Ok(()));
+        assert_eq!(sum.validate_inputs(&positive_inputs), Ok(()));
+    }
+
+    #[test]
+    fn locally_periodic_gradient()
+    {
+        assert_gradient_matches_finite_differences(&LocallyPeriodic::new(0.8, 1.3, 2., 1.5));
+        assert_gradient_matches_finite_differences(&LocallyPeriodic::new(-1.2, 0.7, -0.9, -0.6));
+        assert_positive_definite(&LocallyPeriodic::new(0.8, 1.3, 2., 1.5));
+    }
+
+    #[test]
+    fn locally_periodic_period_is_bounded()
+    {
+        let mut kernel = LocallyPeriodic::default();
+        assert_eq!(kernel.parameter_bounds()[1], (f64::EPSILON, f64::INFINITY));
+        // a negative period is stored as is and evaluated through its magnitude
+        kernel.set_parameters(&[1., -2., 10., 1.]);
+        assert_eq!(kernel.period, -2.);
+        let inputs = inputs();
+        let positive = LocallyPeriodic::new(1., 2., 10., 1.);
+        assert_eq!(kernel.kernel(&inputs.row(1), &inputs.row(3)), positive.kernel(&inputs.row(1), &inputs.row(3)));
+    }
+
+    #[test]
+    fn locally_periodic_is_the_product_of_a_periodic_and_a_squared_exponential_kernel()
+    {
+        let kernel = LocallyPeriodic::new(0.8, 1.3, 2., 1.5);
+        let product = KernelArith(Periodic::new(0.8, 1.3, 1.5)) * KernelArith(SquaredExp::new(2., 1.));
+        let inputs = inputs();
+        for x1 in inputs.row_iter()
+        {
+            for x2 in inputs.row_iter()
+            {
+                assert!((kernel.kernel(&x1, &x2) - product.kernel(&x1, &x2)).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn masked_kernel_only_sees_its_dimensions()
+    {
+        let kernel = Masked::new(SquaredExp::new(0.8, 1.5), vec![1]);
+        let inner = SquaredExp::new(0.8, 1.5);
+        let inputs = inputs();
+        for x1 in inputs.row_iter()
+        {
+            for x2 in inputs.row_iter()
+            {
+                let expected = inner.kernel(&x1.columns(1, 1), &x2.columns(1, 1));
+                assert_eq!(kernel.kernel(&x1, &x2), expected);
+                assert_eq!(kernel.gradient(&x1, &x2), inner.gradient(&x1.columns(1, 1), &x2.columns(1, 1)));
+            }
+        }
+        assert_gradient_matches_finite_differences(&kernel);
+    }
+
+    #[test]
+    fn masked_kernels_compose()
+    {
+        let kernel = KernelArith(Masked::new(Periodic::new(1., 1.3, 0.7), vec![0]))
+                     * KernelArith(Masked::new(SquaredExp::new(0.8, 1.5), vec![1]));
+        assert_eq!(kernel.nb_parameters(), 5);
+        assert_gradient_matches_finite_differences(&kernel);
+        assert_positive_definite(&kernel);
+    }
+
+    #[test]
+    #[should_panic(expected = "Masked: dimension 2 is out of range for inputs of dimension 2.")]
+    fn masked_kernel_rejects_out_of_range_dimensions()
+    {
+        let kernel = Masked::new(SquaredExp::default(), vec![0, 2]);
+        let inputs = inputs();
+        kernel.kernel(&inputs.row(0), &inputs.row(1));
+    }
+
+    #[test]
+    fn additive_gradient()
+    {
+        let kernel = Additive::new(vec![SquaredExp::new(0.7, 1.3), SquaredExp::new(1.5, 0.4)]);
+        assert_eq!(kernel.nb_parameters(), 4);
+        assert_gradient_matches_finite_differences(&kernel);
+        assert_positive_definite(&kernel);
+        // A single kernel shared by all dimensions.
+        assert_gradient_matches_finite_differences(&Additive::new(vec![Matern52::new(0.8, 1.1)]));
+    }
+
+    #[test]
+    fn additive_heuristic_fit_keeps_the_number_of_parameters()
+    {
+        let inputs = inputs();
+        let outputs = DVector::from_fn(inputs.nrows(), |i, _| (i as f64).sin());
+
+        // A shared kernel stays shared.
+        let mut kernel = Additive::new(vec![SquaredExp::default()]);
+        kernel.heuristic_fit(&inputs, &outputs);
+        assert_eq!(kernel.kernels().len(), 1);
+        assert_eq!(kernel.nb_parameters(), 2);
+
+        // A kernel per dimension is fitted on its own dimension.
+        let mut kernel = Additive::with_dimension(SquaredExp::default(), inputs.ncols());
+        kernel.heuristic_fit(&inputs, &outputs);
+        assert_eq!(kernel.nb_parameters(), 2 * inputs.ncols());
+    }
+
+    #[test]
+    fn additive_kernel_sums_one_dimensional_kernels()
+    {
+        let kernel = Additive::new(vec![SquaredExp::new(0.7, 1.3), SquaredExp::new(1.5, 0.4)]);
+        let inputs = inputs();
+        for x1 in inputs.row_iter()
+        {
+            for x2 in inputs.row_iter()
+            {
+                let expected = SquaredExp::new(0.7, 1.3).kernel(&x1.columns(0, 1), &x2.columns(0, 1))
+                               + SquaredExp::new(1.5, 0.4).kernel(&x1.columns(1, 1), &x2.columns(1, 1));
+                assert!((kernel.kernel(&x1, &x2) - expected).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn hamming_counts_mismatching_coordinates()
+    {
+        let kernel = Hamming::new(0.7, 1.5);
+        let x = DMatrix::from_row_slice(3, 3, &[1., 2., 0., 1., 2., 0., 3., 0., 1.]);
+        // All coordinates match.
+        assert_eq!(kernel.kernel(&x.row(0), &x.row(1)), 1.5);
+        assert_eq!(kernel.gradient(&x.row(0), &x.row(1)), vec![0., 1.]);
+        // All coordinates differ.
+        assert!((kernel.kernel(&x.row(0), &x.row(2)) - 1.5 * (-0.7f64 * 3.).exp()).abs() < 1e-12);
+        assert_gradient_matches_finite_differences(&kernel);
+        assert_gradient_matches_finite_differences(&Hamming::new(-0.4, -2.));
+        assert_positive_definite(&kernel);
+    }
+
+    #[test]
+    fn hamming_composes_with_continuous_kernels()
+    {
+        // A continuous first column and a categorical second column.
+        let kernel = KernelArith(Masked::new(SquaredExp::new(0.8, 1.), vec![0]))
+                     * KernelArith(Masked::new(Hamming::new(0.5, 1.2), vec![1]));
+        assert_gradient_matches_finite_differences(&kernel);
+        assert_positive_definite(&kernel);
+    }
+
+    #[test]
+    fn fn_kernel_matches_the_native_kernel()
+    {
+        let native = SquaredExp::new(0.7, 1.3);
+        let kernel = FnKernel { parameters: vec![0.7, 1.3], ..FnKernel::default() };
+        let inputs = inputs();
+        for x1 in inputs.row_iter()
+        {
+            for x2 in inputs.row_iter()
+            {
+                assert!((kernel.kernel(&x1, &x2) - native.kernel(&x1, &x2)).abs() < 1e-12);
+                for (numeric, analytic) in kernel.gradient(&x1, &x2).iter().zip(native.gradient(&x1, &x2))
+                {
+                    assert!((numeric - analytic).abs() < 1e-6 * (1. + analytic.abs()));
+                }
+            }
+        }
+        assert_gradient_matches_finite_differences(&kernel);
+    }
+
+    /// A squared exponential kernel relying on the default gradient.
+    #[derive(Clone, Default)]
+    struct GradientFree(SquaredExp);
+
+    impl Kernel for GradientFree
+    {
+        fn nb_parameters(&self) -> usize
+        {
+            2
+        }
+
+        fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                                x1: &SRowVector<S1>,
+                                                                                x2: &SRowVector<S2>)
+                                                                                -> f64
+        {
+            self.0.kernel(x1, x2)
+        }
+
+        fn get_parameters(&self) -> Vec<f64>
+        {
+            self.0.get_parameters()
+        }
+
+        fn set_parameters(&mut self, parameters: &[f64])
+        {
+            self.0.set_parameters(parameters)
+        }
+    }
+
+    /// A squared exponential kernel whose gradient ignores the amplitude.
+    #[derive(Clone, Default)]
+    struct WrongGradient(SquaredExp);
+
+    impl Kernel for WrongGradient
+    {
+        fn nb_parameters(&self) -> usize
+        {
+            2
+        }
+
+        fn kernel<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                                x1: &SRowVector<S1>,
+                                                                                x2: &SRowVector<S2>)
+                                                                                -> f64
+        {
+            self.0.kernel(x1, x2)
+        }
+
+        fn gradient<S1: Storage<f64, U1, Dynamic>, S2: Storage<f64, U1, Dynamic>>(&self,
+                                                                                  x1: &SRowVector<S1>,
+                                                                                  x2: &SRowVector<S2>)
+                                                                                  -> Vec<f64>
+        {
+            SquaredExp::new(self.0.ls, 1.).gradient(x1, x2)
+        }
+
+        fn get_parameters(&self) -> Vec<f64>
+        {
+            self.0.get_parameters()
+        }
+
+        fn set_parameters(&mut self, parameters: &[f64])
+        {
+            self.0.set_parameters(parameters)
+        }
+    }
+
+    #[test]
+    fn default_gradient_matches_the_analytic_gradient()
+    {
+        let native = SquaredExp::new(0.7, -1.3);
+        let kernel = GradientFree(native);
+        let inputs = inputs();
+        for x1 in inputs.row_iter()
+        {
+            for x2 in inputs.row_iter()
+            {
+                for (numeric, analytic) in kernel.gradient(&x1, &x2).iter().zip(native.gradient(&x1, &x2))
+                {
+                    assert!((numeric - analytic).abs() < 1e-6 * (1. + analytic.abs()));
+                }
+            }
+        }
+        let gradients = make_gradient_covariance_matrices(&inputs, &kernel);
+        let native_gradients = make_gradient_covariance_matrices(&inputs, &native);
+        for (gradient, native_gradient) in gradients.iter().zip(native_gradients.iter())
+        {
+            assert!((gradient - native_gradient).amax() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn gradient_check_detects_wrong_gradients()
+    {
+        let inputs = inputs();
+        assert!(gradient_check(&SquaredExp::new(0.7, 1.3), &inputs) < 1e-5);
+        assert!(gradient_check(&(KernelArith(Matern2::default()) * KernelArith(Periodic::default())), &inputs) < 1e-5);
+        assert!(gradient_check(&WrongGradient(SquaredExp::new(0.7, 1.3)), &inputs) > 0.1);
+    }
+
+    #[test]
+    fn combined_kernels_concatenate_the_parameter_bounds()
+    {
+        let kernel = KernelArith(Linear::default()) + KernelArith(Masked::new(SquaredExp::default(), vec![1]));
+        let bounds = kernel.parameter_bounds();
+        assert_eq!(bounds.len(), kernel.nb_parameters());
+        assert_eq!(&bounds[kernel.k1.nb_parameters()..], SquaredExp::default().parameter_bounds().as_slice());
+        assert!(bounds[..kernel.k1.nb_parameters()].iter().all(|&b| b == UNBOUNDED));
+    }
+
+    #[test]
+    fn warped_kernel_applies_the_warps_before_the_inner_kernel()
+    {
+        let kernel = Warped::new(SquaredExp::new(0.8, 1.5), vec![Warp::Log1p, Warp::Sqrt]);
+        let inner = SquaredExp::new(0.8, 1.5);
+        let inputs = inputs();
+        let warps = [Warp::Log1p, Warp::Sqrt];
+        let warped_inputs = inputs.map_with_location(|_, d, x| warps[d].warp(x));
+        for (x1, w1) in inputs.row_iter().zip(warped_inputs.row_iter())
+        {
+            for (x2, w2) in inputs.row_iter().zip(warped_inputs.row_iter())
+            {
+                assert_eq!(kernel.kernel(&x1, &x2), inner.kernel(&w1, &w2));
+                assert_eq!(kernel.gradient(&x1, &x2), inner.gradient(&w1, &w2));
+            }
+        }
+        assert_eq!(kernel.get_parameters(), inner.get_parameters());
+        assert_positive_definite(&kernel);
+    }
+
+    #[test]
+    fn warped_gradient()
+    {
+        // The Kumaraswamy warp is flat outside of (0, 1), the inputs are thus spread inside of it.
+        let inputs = DMatrix::from_row_slice(6, 2, &[0.05, 0.9, 0.2, 0.35, 0.45, 0.6, 0.6, 0.1, 0.8, 0.75, 0.95, 0.5]);
+        // The Kumaraswamy parameters are appended to the parameters of the inner kernel.
+        let kernel = Warped::new(Matern52::new(0.8, 1.1),
+                                 vec![Warp::Kumaraswamy { a: 0.7, b: 1.8 }, Warp::Kumaraswamy { a: 2.1, b: 0.6 }]);
+        assert_eq!(kernel.nb_parameters(), 6);
+        assert_eq!(kernel.get_parameters(), vec![0.8, 1.1, 0.7, 1.8, 2.1, 0.6]);
+        assert!(gradient_check(&kernel, &inputs) < 1e-5);
+        // The warp parameters do influence the kernel.
+        let gradient = kernel.gradient(&inputs.row(0), &inputs.row(3));
+        assert!(gradient[2..].iter().all(|g| g.abs() > 1e-3), "gradient {:?}", gradient);
+        let covariance = make_covariance_matrix(&inputs, &inputs, &kernel);
+        assert!(covariance.cholesky().is_some(), "covariance matrix is not positive definite");
+        // A single warp shared by all dimensions.
+        let kernel = Warped::new(SquaredExp::new(0.5, 1.3), vec![Warp::Kumaraswamy { a: -1.4, b: 0.9 }]);
+        assert_eq!(kernel.nb_parameters(), 4);
+        assert!(gradient_check(&kernel, &inputs) < 1e-5);
+    }
+
+    #[test]
+    fn anova_gradient()
+    {
+        let kernel = Anova::new(SquaredExp::new(0.7, 1.3), 0.8, 0.4);
+        assert_eq!(kernel.nb_parameters(), 4);
+        assert_gradient_matches_finite_differences(&kernel);
+        assert_positive_definite(&kernel);
+        assert_gradient_matches_finite_differences(&Anova::new(Matern52::new(1.2, 0.6), -0.5, 1.7));
+    }
+
+    #[test]
+    fn anova_kernel_sums_main_effects_and_interactions()
+    {
+        let kernel = Anova::new(SquaredExp::new(0.7, 1.3), 0.8, 0.4);
+        let base = SquaredExp::new(0.7, 1.3);
+        let inputs = DMatrix::from_row_slice(3, 3, &[0.0, 0.5, -1.0, 0.3, -0.2, 0.8, 1.1, 0.4, 0.1]);
+        for x1 in inputs.row_iter()
+        {
+            for x2 in inputs.row_iter()
+            {
+                let k: Vec<f64> = (0..3).map(|d| base.kernel(&x1.columns(d, 1), &x2.columns(d, 1))).collect();
+                let main = 0.8 * (k[0] + k[1] + k[2]);
+                let interaction = 0.4 * (k[0] * k[1] + k[0] * k[2] + k[1] * k[2]);
+                assert!((kernel.order_kernel(1, &x1, &x2) - main).abs() < 1e-12);
+                assert!((kernel.order_kernel(2, &x1, &x2) - interaction).abs() < 1e-12);
+                assert!((kernel.kernel(&x1, &x2) - main - interaction).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn anova_heuristic_fit_gives_finite_parameters()
+    {
+        let inputs = DMatrix::from_row_slice(4, 3, &[0.0, 0.5, -1.0, 0.3, -0.2, 0.8, 1.1, 0.4, 0.1, -0.6, 1.3, 0.7]);
+        for outputs in [DVector::from_column_slice(&[1.0, -0.5, 2.0, 0.3]), DVector::from_element(4, 1.5)]
+        {
+            let mut kernel = Anova::new(SquaredExp::default(), 1., 1.);
+            kernel.heuristic_fit(&inputs, &outputs);
+            assert_eq!(kernel.nb_parameters(), 4);
+            assert!(kernel.get_parameters().iter().all(|p| p.is_finite()), "{:?}", kernel.get_parameters());
+        }
+    }
+
+    #[test]
+    fn precomputed_kernel_reads_the_gram_matrix()
+    {
+        let inputs = inputs();
+        let gram = make_covariance_matrix(&inputs, &inputs, &SquaredExp::new(0.8, 1.5));
+        let kernel = Precomputed::new(gram.clone());
+        let indices = DMatrix::from_fn(5, 1, |i, _| i as f64);
+        assert_eq!(make_covariance_matrix(&indices, &indices, &kernel), gram);
+        assert_eq!(kernel.nb_parameters(), 0);
+    }
+
+    #[test]
+    fn precomputed_kernel_rejects_invalid_gram_matrices()
+    {
+        let rectangular = DMatrix::from_element(3, 2, 1.);
+        assert_eq!(Precomputed::try_new(rectangular).unwrap_err(),
+                   FriedrichError::DimensionMismatch { expected: 3, found: 2 });
+        let mut asymmetric = DMatrix::<f64>::identity(3, 3);
+        asymmetric[(2, 1)] = 0.5;
+        assert_eq!(Precomputed::try_new(asymmetric).unwrap_err(),
+                   FriedrichError::NonSymmetricMatrix { row: 2, column: 1 });
+    }
+
+    #[test]
+    #[should_panic(expected = "Precomputed: expected 3 elements but found 2.")]
+    fn precomputed_kernel_panics_on_a_rectangular_gram_matrix()
+    {
+        Precomputed::new(DMatrix::from_element(3, 2, 1.));
+    }
+
+    #[test]
+    #[should_panic(expected = "Precomputed: 3 is not a valid index for a Gram matrix of 3 points.")]
+    fn precomputed_kernel_rejects_invalid_indices()
+    {
+        let kernel = Precomputed::new(DMatrix::identity(3, 3));
+        let indices = DMatrix::from_row_slice(2, 1, &[0., 3.]);
+        kernel.kernel(&indices.row(0), &indices.row(1));
     }
 }
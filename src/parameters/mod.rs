@@ -1,2 +1,3 @@
+pub mod hyperprior;
 pub mod kernel;
 pub mod prior;
@@ -7,8 +7,10 @@
 //! User-defined priors should implement the Prior trait.
 
 use crate::algebra::{SMatrix, SVector};
-use nalgebra::DVector;
+use crate::error::FriedrichError;
+use nalgebra::{DMatrix, DVector};
 use nalgebra::{storage::Storage, Dynamic, U1};
+use std::sync::Arc;
 
 //---------------------------------------------------------------------------------------
 // TRAIT
@@ -30,6 +32,69 @@ pub trait Prior
                                                                                       _training_outputs: &SVector<SV>)
     {
     }
+
+    /// Optional, fallible version of `fit` returning an error rather than panicking if the fit fails.
+    ///
+    /// Defaults to calling `fit`, priors whose fit can fail should implement this function and call it from `fit`.
+    fn try_fit<SM: Storage<f64, Dynamic, Dynamic> + Clone, SV: Storage<f64, Dynamic, U1>>(
+        &mut self,
+        training_inputs: &SMatrix<SM>,
+        training_outputs: &SVector<SV>)
+        -> Result<(), FriedrichError>
+    {
+        self.fit(training_inputs, training_outputs);
+        Ok(())
+    }
+}
+
+/// Priors taking the same value for every input, such as the `ZeroPrior` and the `ConstantPrior`.
+///
+/// They can be used with inputs that carry no information for the prior
+/// (such as the indices of the `Precomputed` kernel, see `predict_with_similarities`).
+pub trait ConstantValuedPrior: Prior
+{
+    /// The value taken by the prior.
+    fn value(&self) -> f64;
+}
+
+/// Solves the least squares problem `features * weights = outputs` using an SVD decomposition,
+/// the singular values below `relative_epsilon` times the largest one being ignored.
+fn least_squares<SV: Storage<f64, Dynamic, U1>>(features: DMatrix<f64>,
+                                                outputs: &SVector<SV>,
+                                                relative_epsilon: f64)
+                                                -> Result<DVector<f64>, FriedrichError>
+{
+    // The SVD decomposition panics on non-finite values.
+    if features.iter().chain(outputs.iter()).any(|x| !x.is_finite())
+    {
+        return Err(FriedrichError::LinearSolveFailure);
+    }
+    let svd = features.svd(true, true);
+    let epsilon = relative_epsilon * svd.singular_values.max();
+    svd.solve(outputs, epsilon).map_err(|_| FriedrichError::LinearSolveFailure)
+}
+
+/// Solves the ridge regression problem `min |features * weights - outputs|² + ridge * |weights[1..]|²`
+/// (the first weight, associated with a constant feature, not being penalized) using `least_squares`.
+///
+/// The penalty is expressed as additional samples: `sqrt(ridge) * weights[i] = 0` for each penalized weight.
+fn ridge_least_squares<SV: Storage<f64, Dynamic, U1>>(features: DMatrix<f64>,
+                                                      outputs: &SVector<SV>,
+                                                      ridge: f64)
+                                                      -> Result<DVector<f64>, FriedrichError>
+{
+    if ridge <= 0.
+    {
+        return least_squares(features, outputs, 1e-12);
+    }
+    let (nb_samples, nb_features) = features.shape();
+    let mut features = features.insert_rows(nb_samples, nb_features - 1, 0.);
+    for i in 1..nb_features
+    {
+        features[(nb_samples + i - 1, i)] = ridge.sqrt();
+    }
+    let outputs = outputs.clone_owned().insert_rows(nb_samples, nb_features - 1, 0.);
+    least_squares(features, &outputs, 1e-12)
 }
 
 //---------------------------------------------------------------------------------------
@@ -55,6 +120,14 @@ impl Prior for ZeroPrior
     }
 }
 
+impl ConstantValuedPrior for ZeroPrior
+{
+    fn value(&self) -> f64
+    {
+        0.
+    }
+}
+
 //-----------------------------------------------
 
 /// The Constant prior.
@@ -98,26 +171,70 @@ impl Prior for ConstantPrior
     }
 }
 
+impl ConstantValuedPrior for ConstantPrior
+{
+    fn value(&self) -> f64
+    {
+        self.c
+    }
+}
+
 //-----------------------------------------------
 
 /// The Linear prior.
 ///
 /// This prior is a linear function which can be fit on the training data.
+/// The fit always succeeds: when the inputs are rank-deficient (duplicated or constant columns,
+/// fewer samples than dimensions) the minimum-norm solution is returned.
+/// An optional ridge penalty (see `with_ridge`) shrinks the weights further.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct LinearPrior
 {
     weights: DVector<f64>,
-    intercept: f64
+    intercept: f64,
+    /// Ridge penalty applied to the weights (but not to the intercept) during the fit.
+    #[cfg_attr(feature = "friedrich_serde", serde(default))]
+    ridge: f64
 }
 
 impl LinearPrior
 {
-    /// Constructs a new linear prior.
-    /// The first row of w is the bias such that `prior = [1|input] * w`
+    /// Constructs a new linear prior such that `prior = input * weights + intercept`.
     pub fn new(weights: DVector<f64>, intercept: f64) -> Self
     {
-        LinearPrior { weights, intercept }
+        LinearPrior { weights, intercept, ridge: 0. }
+    }
+
+    /// Constructs a new linear prior from a combined vector whose first row is the intercept,
+    /// such that `prior = [1|input] * w`.
+    ///
+    /// The intercept is stored apart from the weights, such that computing the prior does not split the vector.
+    pub fn from_combined(w: &DVector<f64>) -> Self
+    {
+        assert!(!w.is_empty(), "LinearPrior: the combined vector should at least contain the intercept.");
+        Self::new(w.rows(1, w.nrows() - 1).into_owned(), w[0])
+    }
+
+    /// Sets the ridge penalty `λ` used during the fit, which then minimizes `|y - Xw - b|² + λ|w|²`.
+    ///
+    /// Panics if the penalty is negative.
+    pub fn with_ridge(self, ridge: f64) -> Self
+    {
+        assert!(ridge >= 0., "LinearPrior: the ridge penalty should be non-negative but is {}.", ridge);
+        LinearPrior { ridge, ..self }
+    }
+
+    /// Returns the weights of the prior (without the intercept).
+    pub fn weights(&self) -> &DVector<f64>
+    {
+        &self.weights
+    }
+
+    /// Returns the intercept of the prior.
+    pub fn intercept(&self) -> f64
+    {
+        self.intercept
     }
 }
 
@@ -125,7 +242,7 @@ impl Prior for LinearPrior
 {
     fn default(input_dimension: usize) -> Self
     {
-        Self { weights: DVector::zeros(input_dimension), intercept: 0f64 }
+        Self::new(DVector::zeros(input_dimension), 0f64)
     }
 
     fn prior<S: Storage<f64, Dynamic, Dynamic>>(&self, input: &SMatrix<S>) -> DVector<f64>
@@ -139,13 +256,23 @@ impl Prior for LinearPrior
     fn fit<SM: Storage<f64, Dynamic, Dynamic> + Clone, SV: Storage<f64, Dynamic, U1>>(&mut self,
                                                                                       training_inputs: &SMatrix<SM>,
                                                                                       training_outputs: &SVector<SV>)
+    {
+        self.try_fit(training_inputs, training_outputs).expect("Linear prior fit : solve failed.");
+    }
+
+    /// Performs a linear fit to set the value of the prior, returns an error if the data contains non-finite values.
+    ///
+    /// The singular values below `1e-12` times the largest one are ignored by the SVD solve
+    /// such that rank-deficient inputs produce the minimum-norm solution.
+    fn try_fit<SM: Storage<f64, Dynamic, Dynamic> + Clone, SV: Storage<f64, Dynamic, U1>>(
+        &mut self,
+        training_inputs: &SMatrix<SM>,
+        training_outputs: &SVector<SV>)
+        -> Result<(), FriedrichError>
     {
         // Solve linear system using an SVD decomposition.
-        let weights = training_inputs.clone()
-                                     .insert_column(0, 1.) // Add constant term for non-zero intercept.
-                                     .svd(true, true)
-                                     .solve(training_outputs, 0.)
-                                     .expect("Linear prior fit : solve failed.");
+        let features = training_inputs.clone_owned().insert_column(0, 1.); // Add constant term for non-zero intercept.
+        let weights = ridge_least_squares(features, training_outputs, self.ridge)?;
 
         // TODO Solve cannot be used with qr and full_piv_lu due to issue 667
         //  (https://github.com/rustsim/nalgebra/issues/667).
@@ -156,5 +283,565 @@ impl Prior for LinearPrior
         // Extracts weights and intercept.
         self.intercept = weights[0];
         self.weights = weights.remove_row(0);
+        Ok(())
+    }
+}
+
+//-----------------------------------------------
+
+/// The Quadratic prior.
+///
+/// This prior is a second-degree polynomial of the inputs, including the cross terms, which can be fit on the training data.
+/// For an input of dimension D, the features are `[1, x_0, ..., x_(D-1), x_0², x_0 x_1, ..., x_(D-1)²]`
+/// (the products `x_i x_j` being ordered with `i <= j`) for a total of `1 + D + D(D+1)/2` weights.
+///
+/// The fit is a least squares fit of the weights, robust to rank-deficient features
+/// (returning the minimum-norm solution) and optionally regularized with a ridge penalty (see `with_ridge`).
+/// The fitted trend can be inspected with `coefficients`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct QuadraticPrior
+{
+    input_dimension: usize,
+    weights: DVector<f64>,
+    /// Ridge penalty applied to all weights but the constant one during the fit.
+    #[cfg_attr(feature = "friedrich_serde", serde(default))]
+    ridge: f64
+}
+
+impl QuadraticPrior
+{
+    /// Constructs a new quadratic prior for inputs of the given dimension.
+    /// The weights are ordered as the features such that `prior = features(input) * weights`.
+    pub fn new(input_dimension: usize, weights: DVector<f64>) -> Self
+    {
+        assert_eq!(weights.len(),
+                   Self::nb_features(input_dimension),
+                   "QuadraticPrior: inputs of dimension {} require {} weights.",
+                   input_dimension,
+                   Self::nb_features(input_dimension));
+        QuadraticPrior { input_dimension, weights, ridge: 0. }
+    }
+
+    /// Sets the ridge penalty `λ` used during the fit, which then minimizes `|y - features * w|² + λ|w[1..]|²`
+    /// (the constant weight not being penalized).
+    ///
+    /// Panics if the penalty is negative.
+    pub fn with_ridge(self, ridge: f64) -> Self
+    {
+        assert!(ridge >= 0., "QuadraticPrior: the ridge penalty should be non-negative but is {}.", ridge);
+        QuadraticPrior { ridge, ..self }
+    }
+
+    /// Returns the weights of the prior.
+    pub fn weights(&self) -> &DVector<f64>
+    {
+        &self.weights
+    }
+
+    /// Returns the coefficients `(c, b, A)` of the prior such that `prior(x) = c + b^T x + x^T A x`,
+    /// `A` being symmetric (the weight of each cross term is split between its two symmetric elements).
+    pub fn coefficients(&self) -> (f64, DVector<f64>, DMatrix<f64>)
+    {
+        let dimension = self.input_dimension;
+        let linear = self.weights.rows(1, dimension).into_owned();
+        let mut quadratic = DMatrix::zeros(dimension, dimension);
+        let mut column = 1 + dimension;
+        for i in 0..dimension
+        {
+            quadratic[(i, i)] = self.weights[column];
+            column += 1;
+            for j in (i + 1)..dimension
+            {
+                quadratic[(i, j)] = self.weights[column] / 2.;
+                quadratic[(j, i)] = self.weights[column] / 2.;
+                column += 1;
+            }
+        }
+        (self.weights[0], linear, quadratic)
+    }
+
+    /// Number of features (and thus of weights) for inputs of the given dimension.
+    fn nb_features(input_dimension: usize) -> usize
+    {
+        1 + input_dimension + (input_dimension * (input_dimension + 1)) / 2
+    }
+
+    /// Checks that the inputs have the dimension of the prior.
+    fn check_dimension<S: Storage<f64, Dynamic, Dynamic>>(&self, inputs: &SMatrix<S>)
+    {
+        assert_eq!(inputs.ncols(),
+                   self.input_dimension,
+                   "QuadraticPrior: the prior was built for inputs of dimension {} but got inputs of dimension {}.",
+                   self.input_dimension,
+                   inputs.ncols());
+    }
+
+    /// Builds the feature (design) matrix, one row per input, one column at a time.
+    fn features<S: Storage<f64, Dynamic, Dynamic>>(&self, inputs: &SMatrix<S>) -> DMatrix<f64>
+    {
+        self.check_dimension(inputs);
+        let mut features = DMatrix::zeros(inputs.nrows(), Self::nb_features(self.input_dimension));
+        features.column_mut(0).fill(1.);
+        features.columns_mut(1, self.input_dimension).copy_from(inputs);
+        let mut column = 1 + self.input_dimension;
+        for i in 0..self.input_dimension
+        {
+            for j in i..self.input_dimension
+            {
+                features.set_column(column, &inputs.column(i).component_mul(&inputs.column(j)));
+                column += 1;
+            }
+        }
+        features
+    }
+}
+
+impl Prior for QuadraticPrior
+{
+    fn default(input_dimension: usize) -> Self
+    {
+        Self::new(input_dimension, DVector::zeros(Self::nb_features(input_dimension)))
+    }
+
+    /// Accumulates the terms of the polynomial one column at a time, without building the feature matrix.
+    fn prior<S: Storage<f64, Dynamic, Dynamic>>(&self, input: &SMatrix<S>) -> DVector<f64>
+    {
+        self.check_dimension(input);
+        let mut result = DVector::from_element(input.nrows(), self.weights[0]);
+        let mut column = 1;
+        for i in 0..self.input_dimension
+        {
+            result.axpy(self.weights[column], &input.column(i), 1.);
+            column += 1;
+        }
+        for i in 0..self.input_dimension
+        {
+            // x_i * (w_ii x_i + w_i(i+1) x_(i+1) + ...)
+            let mut products = DVector::zeros(input.nrows());
+            for j in i..self.input_dimension
+            {
+                products.axpy(self.weights[column], &input.column(j), 1.);
+                column += 1;
+            }
+            result += products.component_mul(&input.column(i));
+        }
+        result
+    }
+
+    /// Performs a least squares fit of the weights.
+    fn fit<SM: Storage<f64, Dynamic, Dynamic> + Clone, SV: Storage<f64, Dynamic, U1>>(&mut self,
+                                                                                      training_inputs: &SMatrix<SM>,
+                                                                                      training_outputs: &SVector<SV>)
+    {
+        self.try_fit(training_inputs, training_outputs).expect("Quadratic prior fit : solve failed.");
+    }
+
+    /// Performs a least squares fit of the weights, returns an error if the linear system cannot be solved.
+    fn try_fit<SM: Storage<f64, Dynamic, Dynamic> + Clone, SV: Storage<f64, Dynamic, U1>>(
+        &mut self,
+        training_inputs: &SMatrix<SM>,
+        training_outputs: &SVector<SV>)
+        -> Result<(), FriedrichError>
+    {
+        // Solve linear system using an SVD decomposition.
+        self.weights = ridge_least_squares(self.features(training_inputs), training_outputs, self.ridge)?;
+        Ok(())
+    }
+}
+
+//-----------------------------------------------
+
+/// The Polynomial prior.
+///
+/// This prior is a polynomial of the inputs of arbitrary degree, including all cross terms, which can be fit on the training data.
+/// The features are all the monomials of degree at most `degree`, ordered by degree then lexicographically
+/// (a monomial being a product `x_i x_j ...` with `i <= j <= ...`): `[1, x_0, ..., x_(D-1), x_0², x_0 x_1, ...]`.
+/// There are `(D+degree)! / (D! degree!)` such monomials for inputs of dimension D.
+///
+/// The default prior is of degree one (a linear function), use `with_degree` to build a prior of higher degree.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "friedrich_serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct PolynomialPrior
+{
+    input_dimension: usize,
+    degree: usize,
+    weights: DVector<f64>
+}
+
+impl PolynomialPrior
+{
+    /// Constructs a new polynomial prior of the given degree for inputs of the given dimension.
+    /// The weights are ordered as the monomials such that `prior = features(input) * weights`.
+    pub fn new(input_dimension: usize, degree: usize, weights: DVector<f64>) -> Self
+    {
+        let nb_features = Self::monomials(input_dimension, degree).len();
+        assert_eq!(weights.len(),
+                   nb_features,
+                   "PolynomialPrior: a polynomial of degree {} on inputs of dimension {} requires {} weights.",
+                   degree,
+                   input_dimension,
+                   nb_features);
+        PolynomialPrior { input_dimension, degree, weights }
+    }
+
+    /// Constructs a polynomial prior of the given degree for inputs of the given dimension, with null weights.
+    pub fn with_degree(input_dimension: usize, degree: usize) -> Self
+    {
+        let nb_features = Self::monomials(input_dimension, degree).len();
+        PolynomialPrior { input_dimension, degree, weights: DVector::zeros(nb_features) }
+    }
+
+    /// Returns the degree of the polynomial.
+    pub fn degree(&self) -> usize
+    {
+        self.degree
+    }
+
+    /// Returns the weights of the prior.
+    pub fn weights(&self) -> &DVector<f64>
+    {
+        &self.weights
+    }
+
+    /// Lists all monomials of degree at most `degree`, each one given as the (non-decreasing) indices of its factors.
+    fn monomials(input_dimension: usize, degree: usize) -> Vec<Vec<usize>>
+    {
+        let mut monomials = vec![Vec::new()];
+        let mut previous_degree = vec![Vec::new()];
+        for _ in 0..degree
+        {
+            let mut current_degree = Vec::new();
+            for monomial in &previous_degree
+            {
+                let first_index = monomial.last().copied().unwrap_or(0);
+                for index in first_index..input_dimension
+                {
+                    let mut extended: Vec<usize> = monomial.clone();
+                    extended.push(index);
+                    current_degree.push(extended);
+                }
+            }
+            monomials.extend(current_degree.iter().cloned());
+            previous_degree = current_degree;
+        }
+        monomials
+    }
+
+    /// Builds the feature (Vandermonde) matrix, one row per input.
+    fn features<S: Storage<f64, Dynamic, Dynamic>>(&self, inputs: &SMatrix<S>) -> DMatrix<f64>
+    {
+        assert_eq!(inputs.ncols(),
+                   self.input_dimension,
+                   "PolynomialPrior: the prior was built for inputs of dimension {} but got inputs of dimension {}.",
+                   self.input_dimension,
+                   inputs.ncols());
+        let monomials = Self::monomials(self.input_dimension, self.degree);
+        assert_eq!(monomials.len(),
+                   self.weights.len(),
+                   "PolynomialPrior: {} features but {} weights.",
+                   monomials.len(),
+                   self.weights.len());
+        DMatrix::from_fn(inputs.nrows(), monomials.len(), |row, column| {
+            monomials[column].iter().map(|&index| inputs[(row, index)]).product()
+        })
+    }
+}
+
+impl Prior for PolynomialPrior
+{
+    fn default(input_dimension: usize) -> Self
+    {
+        Self::with_degree(input_dimension, 1)
+    }
+
+    fn prior<S: Storage<f64, Dynamic, Dynamic>>(&self, input: &SMatrix<S>) -> DVector<f64>
+    {
+        self.features(input) * &self.weights
+    }
+
+    /// Performs a least squares fit of the weights.
+    ///
+    /// As the Vandermonde matrix is often ill-conditioned, the singular values below `1e-12` times the largest one
+    /// are ignored by the SVD solve.
+    fn fit<SM: Storage<f64, Dynamic, Dynamic> + Clone, SV: Storage<f64, Dynamic, U1>>(&mut self,
+                                                                                      training_inputs: &SMatrix<SM>,
+                                                                                      training_outputs: &SVector<SV>)
+    {
+        self.try_fit(training_inputs, training_outputs).expect("Polynomial prior fit : solve failed.");
+    }
+
+    /// Performs a least squares fit of the weights, returns an error if the linear system cannot be solved.
+    fn try_fit<SM: Storage<f64, Dynamic, Dynamic> + Clone, SV: Storage<f64, Dynamic, U1>>(
+        &mut self,
+        training_inputs: &SMatrix<SM>,
+        training_outputs: &SVector<SV>)
+        -> Result<(), FriedrichError>
+    {
+        // Solve linear system using an SVD decomposition.
+        self.weights = least_squares(self.features(training_inputs), training_outputs, 1e-12)?;
+        Ok(())
+    }
+}
+
+//-----------------------------------------------
+
+/// The Function prior.
+///
+/// This prior wraps a user-provided function of a single input (given as a slice),
+/// such as a physical model of the data, the process then only learns the residual around that function.
+/// Fitting the prior does nothing, the function is used as given.
+///
+/// The default prior is the zero function.
+/// ```rust
+/// # use friedrich::gaussian_process::GaussianProcess;
+/// # use friedrich::prior::FnPrior;
+/// # fn main() {
+/// let training_inputs = vec![vec![0.8], vec![1.2], vec![3.8], vec![4.2]];
+/// let training_outputs = vec![4.0, 5.5, 7.0, 9.5];
+/// let gp = GaussianProcess::builder(training_inputs, training_outputs).set_prior(FnPrior::new(|x| 2. * x[0]))
+///                                                                     .train();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct FnPrior
+{
+    function: Arc<PriorFunction>
+}
+
+/// Function of a single input wrapped by a `FnPrior`.
+type PriorFunction = dyn Fn(&[f64]) -> f64 + Send + Sync;
+
+impl FnPrior
+{
+    /// Constructs a new prior from a function of a single input.
+    pub fn new<F: Fn(&[f64]) -> f64 + Send + Sync + 'static>(function: F) -> Self
+    {
+        Self { function: Arc::new(function) }
+    }
+}
+
+impl std::fmt::Debug for FnPrior
+{
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        formatter.write_str("FnPrior")
+    }
+}
+
+impl Prior for FnPrior
+{
+    fn default(_input_dimension: usize) -> Self
+    {
+        Self::new(|_| 0.)
+    }
+
+    fn prior<S: Storage<f64, Dynamic, Dynamic>>(&self, input: &SMatrix<S>) -> DVector<f64>
+    {
+        // rows are not contiguous in memory, they are copied into a buffer
+        let mut row_buffer = Vec::with_capacity(input.ncols());
+        DVector::from_iterator(input.nrows(),
+                               input.row_iter().map(|row| {
+                                                   row_buffer.clear();
+                                                   row_buffer.extend(row.iter());
+                                                   (self.function)(&row_buffer)
+                                               }))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    /// Inputs spread over two dimensions, one per row.
+    fn inputs() -> DMatrix<f64>
+    {
+        DMatrix::from_fn(20, 2, |r, c| ((r * (c + 3)) % 7) as f64 * 0.5 - 1. + c as f64 * 0.1 * r as f64)
+    }
+
+    #[test]
+    fn quadratic_prior_recovers_a_second_degree_polynomial()
+    {
+        let polynomial = |x: f64, y: f64| 1.5 - 2. * x + 0.5 * y + 3. * x * x - x * y + 0.25 * y * y;
+        let inputs = inputs();
+        let outputs = DVector::from_iterator(inputs.nrows(), inputs.row_iter().map(|r| polynomial(r[0], r[1])));
+
+        let mut prior = QuadraticPrior::default(2);
+        prior.fit(&inputs, &outputs);
+        let expected_weights = DVector::from_column_slice(&[1.5, -2., 0.5, 3., -1., 0.25]);
+        assert!((prior.weights() - expected_weights).amax() < 1e-9);
+
+        let test_inputs = DMatrix::from_row_slice(2, 2, &[10., -3., 0.3, 7.]);
+        let expected = DVector::from_column_slice(&[polynomial(10., -3.), polynomial(0.3, 7.)]);
+        assert!((prior.prior(&test_inputs) - expected).amax() < 1e-7);
+    }
+
+    #[test]
+    #[should_panic(expected = "QuadraticPrior: the prior was built for inputs of dimension 2")]
+    fn quadratic_prior_rejects_inputs_of_another_dimension()
+    {
+        let prior = QuadraticPrior::default(2);
+        prior.prior(&DMatrix::zeros(3, 1));
+    }
+
+    #[test]
+    fn polynomial_prior_of_degree_one_matches_the_linear_prior()
+    {
+        let inputs = inputs();
+        let outputs = DVector::from_iterator(inputs.nrows(), inputs.row_iter().map(|r| (r[0] * 3.).sin() + r[1]));
+
+        let mut linear = LinearPrior::default(2);
+        linear.fit(&inputs, &outputs);
+        let mut polynomial = PolynomialPrior::with_degree(2, 1);
+        polynomial.fit(&inputs, &outputs);
+
+        let test_inputs = DMatrix::from_row_slice(3, 2, &[10., -3., 0.3, 7., -1., 0.]);
+        assert!((linear.prior(&test_inputs) - polynomial.prior(&test_inputs)).amax() < 1e-9);
+    }
+
+    #[test]
+    fn polynomial_prior_recovers_a_polynomial_of_higher_degree()
+    {
+        let polynomial = |x: f64, y: f64| 0.5 - x + 2. * x * y * y - 0.3 * x * x * x + y * y * y;
+        let inputs = inputs();
+        let outputs = DVector::from_iterator(inputs.nrows(), inputs.row_iter().map(|r| polynomial(r[0], r[1])));
+
+        let mut prior = PolynomialPrior::with_degree(2, 3);
+        assert_eq!(prior.weights().len(), 10);
+        prior.fit(&inputs, &outputs);
+        let test_inputs = DMatrix::from_row_slice(2, 2, &[1.5, -0.5, 0.3, 2.]);
+        let expected = DVector::from_column_slice(&[polynomial(1.5, -0.5), polynomial(0.3, 2.)]);
+        assert!((prior.prior(&test_inputs) - expected).amax() < 1e-6);
+    }
+
+    #[test]
+    fn polynomial_prior_of_degree_two_matches_the_quadratic_prior_features()
+    {
+        let weights = DVector::from_column_slice(&[1.5, -2., 0.5, 3., -1., 0.25]);
+        let quadratic = QuadraticPrior::new(2, weights.clone());
+        let polynomial = PolynomialPrior::new(2, 2, weights);
+        let inputs = inputs();
+        assert!((quadratic.prior(&inputs) - polynomial.prior(&inputs)).amax() < 1e-12);
+    }
+
+    #[test]
+    fn fit_of_non_finite_data_returns_an_error()
+    {
+        let mut inputs = inputs();
+        let outputs = inputs.column(0).map(|x| 2. * x + 1.);
+        inputs[(3, 1)] = f64::NAN;
+        let mut linear = LinearPrior::default(2);
+        assert_eq!(linear.try_fit(&inputs, &outputs), Err(FriedrichError::LinearSolveFailure));
+        let mut polynomial = PolynomialPrior::with_degree(2, 3);
+        assert_eq!(polynomial.try_fit(&inputs, &outputs), Err(FriedrichError::LinearSolveFailure));
+        // Priors that cannot fail fall back on `fit`.
+        let mut constant = ConstantPrior::default(2);
+        assert_eq!(constant.try_fit(&inputs, &outputs), Ok(()));
+    }
+
+    #[test]
+    fn function_prior_is_applied_to_each_row()
+    {
+        let inputs = inputs();
+        let mut prior = FnPrior::new(|x| x[0] - 2. * x[1]);
+        let expected = DVector::from_iterator(inputs.nrows(), inputs.row_iter().map(|r| r[0] - 2. * r[1]));
+        assert_eq!(prior.prior(&inputs), expected);
+        // fitting does not change the function
+        prior.fit(&inputs, &DVector::zeros(inputs.nrows()));
+        assert_eq!(prior.prior(&inputs), expected);
+        assert_eq!(FnPrior::default(2).prior(&inputs), DVector::zeros(inputs.nrows()));
+    }
+
+    #[test]
+    fn linear_prior_fit_of_rank_deficient_inputs_returns_the_minimum_norm_solution()
+    {
+        // a constant column cannot be told apart from the intercept
+        let inputs = DMatrix::from_fn(10, 2, |r, c| if c == 0 { r as f64 } else { 3. });
+        let outputs = inputs.column(0).map(|x| 2. * x + 1.);
+        let mut prior = LinearPrior::default(2);
+        prior.fit(&inputs, &outputs);
+        assert!((prior.weights()[0] - 2.).abs() < 1e-9);
+        // the offset is split between the intercept and the constant column (b + 3w = 1) with the minimum norm
+        assert!((prior.intercept() - 0.1).abs() < 1e-9, "intercept {}", prior.intercept());
+        assert!((prior.weights()[1] - 0.3).abs() < 1e-9, "weights {}", prior.weights());
+        assert!((prior.prior(&inputs) - &outputs).amax() < 1e-9);
+
+        // fewer samples than dimensions
+        let inputs = DMatrix::from_fn(3, 5, |r, c| ((r + 1) * (c + 2) % 5) as f64);
+        let outputs = DVector::from_column_slice(&[1., -2., 0.5]);
+        let mut prior = LinearPrior::default(5);
+        prior.fit(&inputs, &outputs);
+        assert!(prior.weights().iter().all(|w| w.is_finite()));
+        assert!((prior.prior(&inputs) - &outputs).amax() < 1e-9);
+    }
+
+    #[test]
+    fn linear_prior_ridge_shrinks_the_weights()
+    {
+        let inputs = inputs();
+        let outputs = inputs.column(0).map(|x| 2. * x + 1.) + inputs.column(1);
+        let mut prior = LinearPrior::default(2);
+        prior.fit(&inputs, &outputs);
+        let mut ridge_prior = LinearPrior::default(2).with_ridge(10.);
+        ridge_prior.fit(&inputs, &outputs);
+        assert!(ridge_prior.weights().norm() < prior.weights().norm());
+
+        // the ridge solution is the solution of the regularized normal equations
+        let features = inputs.clone().insert_column(0, 1.);
+        let mut normal_matrix = features.tr_mul(&features);
+        normal_matrix[(1, 1)] += 10.;
+        normal_matrix[(2, 2)] += 10.;
+        let expected = normal_matrix.lu().solve(&features.tr_mul(&outputs)).unwrap();
+        assert!((ridge_prior.intercept() - expected[0]).abs() < 1e-9);
+        assert!((ridge_prior.weights() - expected.rows(1, 2)).amax() < 1e-9);
+    }
+
+    #[test]
+    fn linear_prior_from_combined_vector_matches_the_separate_constructor()
+    {
+        let inputs = inputs();
+        let prior = LinearPrior::from_combined(&DVector::from_column_slice(&[0.5, 2., -1.]));
+        assert_eq!(prior.intercept(), 0.5);
+        assert_eq!(prior.weights(), &DVector::from_column_slice(&[2., -1.]));
+        let expected = LinearPrior::new(DVector::from_column_slice(&[2., -1.]), 0.5).prior(&inputs);
+        assert_eq!(prior.prior(&inputs), expected);
+        let combined = inputs.clone().insert_column(0, 1.) * DVector::from_column_slice(&[0.5, 2., -1.]);
+        assert!((expected - combined).amax() < 1e-12);
+    }
+
+    #[test]
+    fn quadratic_prior_coefficients_describe_the_trend()
+    {
+        let weights = DVector::from_column_slice(&[1.5, -2., 0.5, 3., -1., 0.25]);
+        let prior = QuadraticPrior::new(2, weights);
+        let (c, b, a) = prior.coefficients();
+        assert_eq!(a, a.transpose());
+        let inputs = inputs();
+        for (row, input) in inputs.row_iter().enumerate()
+        {
+            let x = input.transpose();
+            let expected = c + b.dot(&x) + (x.transpose() * &a * &x)[0];
+            assert!((prior.prior(&inputs)[row] - expected).abs() < 1e-12);
+        }
+        // the prior matches the feature matrix without building it
+        assert!((prior.prior(&inputs) - prior.features(&inputs) * prior.weights()).amax() < 1e-12);
+    }
+
+    #[test]
+    fn quadratic_prior_ridge_stabilizes_rank_deficient_fits()
+    {
+        // duplicated input dimensions make the features rank-deficient
+        let inputs = DMatrix::from_fn(15, 2, |r, _| r as f64 * 0.3 - 2.);
+        let outputs = inputs.column(0).map(|x| 1. + x * x);
+        let mut prior = QuadraticPrior::default(2);
+        prior.fit(&inputs, &outputs);
+        assert!((prior.prior(&inputs) - &outputs).amax() < 1e-9);
+        let mut ridge_prior = QuadraticPrior::default(2).with_ridge(1e-3);
+        ridge_prior.fit(&inputs, &outputs);
+        assert!(ridge_prior.weights().iter().all(|w| w.is_finite()));
+        assert!((ridge_prior.prior(&inputs) - &outputs).amax() < 1e-2);
+        let (_, _, a) = ridge_prior.coefficients();
+        assert!((a.sum() - 1.).abs() < 1e-2, "quadratic coefficients {}", a);
     }
 }
@@ -0,0 +1,103 @@
+//! Checks that training and predicting with ndarray arrays gives the same results as the nalgebra path.
+
+use friedrich::gaussian_process::GaussianProcess;
+use friedrich::Input;
+use nalgebra::{DMatrix, DVector};
+use ndarray::{Array1, Array2, ShapeBuilder};
+
+fn training_data() -> (Vec<Vec<f64>>, Vec<f64>)
+{
+    let inputs: Vec<Vec<f64>> = (0..20).map(|i| vec![i as f64 * 0.3, (i as f64 * 0.7).sin()]).collect();
+    let outputs: Vec<f64> = inputs.iter().map(|x| x[0].cos() + 0.5 * x[1]).collect();
+    (inputs, outputs)
+}
+
+fn test_inputs() -> Vec<Vec<f64>>
+{
+    vec![vec![0.5, 0.1], vec![2.2, -0.4], vec![7., 0.9]]
+}
+
+fn to_dmatrix(rows: &[Vec<f64>]) -> DMatrix<f64>
+{
+    DMatrix::from_fn(rows.len(), rows[0].len(), |r, c| rows[r][c])
+}
+
+fn to_array2(rows: &[Vec<f64>], column_major: bool) -> Array2<f64>
+{
+    let shape = (rows.len(), rows[0].len());
+    if column_major
+    {
+        Array2::from_shape_fn(shape.f(), |(r, c)| rows[r][c])
+    }
+    else
+    {
+        Array2::from_shape_fn(shape, |(r, c)| rows[r][c])
+    }
+}
+
+#[test]
+fn ndarray_predictions_match_nalgebra_predictions()
+{
+    let (inputs, outputs) = training_data();
+    let nalgebra_gp = GaussianProcess::default(to_dmatrix(&inputs), DVector::from_vec(outputs.clone()));
+    let expected_means = nalgebra_gp.predict(&to_dmatrix(&test_inputs()));
+    let expected_variances = nalgebra_gp.predict_variance(&to_dmatrix(&test_inputs()));
+
+    for column_major in [false, true]
+    {
+        let ndarray_gp = GaussianProcess::default(to_array2(&inputs, column_major), Array1::from(outputs.clone()));
+        let means = ndarray_gp.predict(&to_array2(&test_inputs(), column_major));
+        let variances = ndarray_gp.predict_variance(&to_array2(&test_inputs(), column_major));
+        for i in 0..test_inputs().len()
+        {
+            assert_eq!(means[i], expected_means[i]);
+            assert_eq!(variances[i], expected_variances[i]);
+        }
+    }
+}
+
+#[test]
+fn non_contiguous_arrays_are_converted()
+{
+    let (inputs, outputs) = training_data();
+    let nalgebra_gp = GaussianProcess::default(to_dmatrix(&inputs), DVector::from_vec(outputs.clone()));
+
+    // Interleaves the test inputs with unused rows then takes a strided view.
+    let padded: Vec<Vec<f64>> = test_inputs().into_iter().flat_map(|row| vec![row, vec![f64::NAN; 2]]).collect();
+    let padded = to_array2(&padded, false);
+    let strided = padded.slice(ndarray::s![..;2, ..]);
+    assert!(strided.as_slice().is_none());
+
+    let ndarray_gp = GaussianProcess::default(to_array2(&inputs, false), Array1::from(outputs));
+    let means = ndarray_gp.predict(&strided);
+    let expected_means = nalgebra_gp.predict(&to_dmatrix(&test_inputs()));
+    for i in 0..test_inputs().len()
+    {
+        assert_eq!(means[i], expected_means[i]);
+    }
+}
+
+#[test]
+fn owned_arrays_are_moved_into_the_model()
+{
+    let (inputs, outputs) = training_data();
+    let nalgebra_gp = GaussianProcess::default(to_dmatrix(&inputs), DVector::from_vec(outputs.clone()));
+    let expected_means = nalgebra_gp.predict(&to_dmatrix(&test_inputs()));
+
+    // Column-major arrays that are a contiguous part of a larger buffer (the first column and output are unused).
+    let padded: Vec<Vec<f64>> = inputs.iter().map(|row| [vec![f64::NAN], row.clone()].concat()).collect();
+    let sliced_inputs = to_array2(&padded, true).slice_move(ndarray::s![.., 1..]);
+    let sliced_outputs = Array1::from([vec![f64::NAN], outputs.clone()].concat()).slice_move(ndarray::s![1..]);
+    assert_eq!(Input::into_dmatrix(sliced_inputs.clone()), to_dmatrix(&inputs));
+
+    for (training_inputs, training_outputs) in
+        [(to_array2(&inputs, true), Array1::from(outputs.clone())), (sliced_inputs, sliced_outputs)]
+    {
+        let ndarray_gp = GaussianProcess::default(training_inputs, training_outputs);
+        let means = ndarray_gp.predict(&to_array2(&test_inputs(), true));
+        for i in 0..test_inputs().len()
+        {
+            assert_eq!(means[i], expected_means[i]);
+        }
+    }
+}